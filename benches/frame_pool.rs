@@ -0,0 +1,102 @@
+//! Compares frame parsing and encoding with and without a `BufferPool`.
+//!
+//! Run with `cargo bench --bench frame_pool`.
+
+use bytes::Bytes;
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use futures_util::{stream, StreamExt};
+
+use connect_rpc::stream::{pool::BufferPool, ConnectFrame};
+
+const FRAMES: usize = 64;
+const CHUNK_SIZE: usize = 1024;
+
+/// Polls a future that never waits (the input streams are in memory).
+fn block_on<F: std::future::Future>(fut: F) -> F::Output {
+    let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+    match std::pin::pin!(fut).poll(&mut cx) {
+        std::task::Poll::Ready(output) => output,
+        std::task::Poll::Pending => unreachable!("in-memory stream pending"),
+    }
+}
+
+fn frame(size: usize) -> ConnectFrame {
+    ConnectFrame {
+        compressed: false,
+        end: false,
+        data: Bytes::from(vec![b'x'; size]),
+    }
+}
+
+/// Returns an encoded stream of frames, split into transport-sized chunks.
+fn chunks(message_size: usize) -> Vec<Bytes> {
+    let mut body = vec![];
+    for _ in 0..FRAMES {
+        body.extend_from_slice(&frame(message_size).encode().unwrap());
+    }
+    let body = Bytes::from(body);
+    (0..body.len())
+        .step_by(CHUNK_SIZE)
+        .map(|start| body.slice(start..(start + CHUNK_SIZE).min(body.len())))
+        .collect()
+}
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    for message_size in [64, 4096] {
+        let input = chunks(message_size);
+        group.throughput(Throughput::Elements(FRAMES as u64));
+        group.bench_function(format!("unpooled/{message_size}"), |b| {
+            b.iter_batched(
+                || {
+                    input
+                        .iter()
+                        .cloned()
+                        .map(Ok::<_, std::io::Error>)
+                        .collect::<Vec<_>>()
+                },
+                |input| block_on(ConnectFrame::bytes_stream(stream::iter(input)).count()),
+                BatchSize::SmallInput,
+            )
+        });
+        let pool = BufferPool::default();
+        group.bench_function(format!("pooled/{message_size}"), |b| {
+            b.iter_batched(
+                || {
+                    input
+                        .iter()
+                        .cloned()
+                        .map(Ok::<_, std::io::Error>)
+                        .collect::<Vec<_>>()
+                },
+                |input| {
+                    block_on(
+                        ConnectFrame::bytes_stream_pooled(stream::iter(input), pool.clone())
+                            .count(),
+                    )
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn encode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encode");
+    for message_size in [64, 4096] {
+        let frame = frame(message_size);
+        group.throughput(Throughput::Elements(1));
+        group.bench_function(format!("unpooled/{message_size}"), |b| {
+            b.iter(|| frame.encode().unwrap())
+        });
+        let pool = BufferPool::default();
+        group.bench_function(format!("pooled/{message_size}"), |b| {
+            b.iter(|| pool.recycle(frame.encode_pooled(&pool).unwrap()))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parse, encode);
+criterion_main!(benches);
@@ -0,0 +1,135 @@
+//! Benchmarks for frame parsing and encoding, metadata conversion, error
+//! JSON, and unary request building.
+//!
+//! Run with `cargo bench --bench hot_paths`.
+
+use std::hint::black_box;
+
+use bytes::Bytes;
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use futures_util::{stream, StreamExt};
+use http::HeaderMap;
+
+use connect_rpc::{
+    metadata::{metadata_from_json, metadata_to_json, Metadata},
+    request::builder::RequestBuilder,
+    response::error::{ConnectCode, ConnectError, ConnectErrorDetail},
+    stream::{ConnectFrame, EndStreamResponse},
+};
+
+/// Polls a future that never waits (the input streams are in memory).
+fn block_on<F: std::future::Future>(fut: F) -> F::Output {
+    let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+    match std::pin::pin!(fut).poll(&mut cx) {
+        std::task::Poll::Ready(output) => output,
+        std::task::Poll::Pending => unreachable!("in-memory stream pending"),
+    }
+}
+
+fn frames(c: &mut Criterion) {
+    let mut group = c.benchmark_group("frames");
+    for message_size in [64, 16 * 1024, 1024 * 1024] {
+        let frame = ConnectFrame {
+            compressed: false,
+            end: false,
+            data: Bytes::from(vec![b'x'; message_size]),
+        };
+        let encoded = frame.encode().unwrap();
+        group.throughput(Throughput::Bytes(encoded.len() as u64));
+
+        group.bench_function(format!("encode/{message_size}"), |b| {
+            b.iter(|| frame.encode().unwrap())
+        });
+        group.bench_function(format!("encode_vectored/{message_size}"), |b| {
+            b.iter(|| frame.encode_vectored().unwrap())
+        });
+        group.bench_function(format!("parse/{message_size}"), |b| {
+            b.iter_batched(
+                || stream::iter([Ok::<_, std::io::Error>(encoded.clone())]),
+                |body| block_on(ConnectFrame::bytes_stream(body).count()),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn sample_metadata() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    for i in 0..8 {
+        headers
+            .append_ascii(format!("x-ascii-{i}"), "some ascii value")
+            .unwrap();
+        headers
+            .append_binary(format!("x-binary-{i}-bin"), [i as u8; 32])
+            .unwrap();
+    }
+    headers
+}
+
+fn metadata(c: &mut Criterion) {
+    let mut group = c.benchmark_group("metadata");
+    let headers = sample_metadata();
+    let json = metadata_to_json(&headers);
+
+    group.bench_function("iter_binary", |b| b.iter(|| headers.iter_binary().count()));
+    group.bench_function("to_json", |b| b.iter(|| metadata_to_json(&headers)));
+    group.bench_function("from_json", |b| {
+        b.iter(|| metadata_from_json(&json).unwrap())
+    });
+    group.bench_function("end_stream_response", |b| {
+        b.iter(|| EndStreamResponse::new(None, &headers))
+    });
+    group.finish();
+}
+
+fn error_json(c: &mut Criterion) {
+    let mut group = c.benchmark_group("error_json");
+    let mut error = ConnectError::new(ConnectCode::Unavailable, "backend unavailable");
+    error.details.push(ConnectErrorDetail::new(
+        "type.googleapis.com/google.rpc.RetryInfo",
+        [0u8; 16],
+    ));
+    let json = serde_json::to_vec(&error).unwrap();
+
+    group.bench_function("encode", |b| b.iter(|| serde_json::to_vec(&error).unwrap()));
+    group.bench_function("decode", |b| {
+        b.iter(|| serde_json::from_slice::<ConnectError>(black_box(&json)).unwrap())
+    });
+    group.finish();
+}
+
+fn unary_request(c: &mut Criterion) {
+    let mut group = c.benchmark_group("unary_request");
+    let base = RequestBuilder::default()
+        .scheme("https")
+        .unwrap()
+        .authority("example.com")
+        .unwrap()
+        .protobuf_rpc("connectrpc.eliza.v1.ElizaService", "Say")
+        .unwrap()
+        .message_codec("proto")
+        .unwrap();
+    let body = Bytes::from_static(b"hello");
+
+    group.bench_function("build", |b| {
+        b.iter(|| base.clone().unary(body.clone()).unwrap())
+    });
+    group.bench_function("build_with_metadata", |b| {
+        b.iter(|| {
+            base.clone()
+                .timeout_ms(1000)
+                .unwrap()
+                .ascii_metadata("x-request-id", "abc123")
+                .unwrap()
+                .binary_metadata("x-trace-bin", [1u8; 16])
+                .unwrap()
+                .unary(body.clone())
+                .unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, frames, metadata, error_json, unary_request);
+criterion_main!(benches);
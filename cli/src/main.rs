@@ -0,0 +1,280 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use anyhow::{bail, Context};
+use clap::Parser;
+use connect_rpc::{
+    capabilities::Capability,
+    client::ConnectClient,
+    request::{builder::RequestBuilder, ConnectRequest, UnaryRequest},
+    response::{
+        error::{ConnectCode, ConnectError},
+        UnaryResponse,
+    },
+    transcode::Transcoder,
+};
+use prost_reflect::DescriptorPool;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+/// Invokes Connect RPCs.
+///
+/// Calls `URL/METHOD` with a JSON request message, printing the result
+/// (response message, metadata, trailers, and error details) as JSON.
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    /// The server base URL, e.g. `https://example.com`.
+    url: Option<String>,
+
+    /// The fully-qualified method, e.g. `acme.foo.v1.FooService/Bar`.
+    method: Option<String>,
+
+    /// The JSON request message, or `@path` to read it from a file.
+    /// Defaults to `{}`.
+    #[arg(short, long)]
+    data: Option<String>,
+
+    /// Request metadata as `key: value`; may be repeated.
+    #[arg(short = 'H', long = "header")]
+    headers: Vec<String>,
+
+    /// The request timeout in milliseconds.
+    #[arg(long)]
+    timeout_ms: Option<u64>,
+
+    /// A `FileDescriptorSet` (e.g. from `buf build -o`) describing the
+    /// method. Messages are then sent as binary protobuf, for servers that
+    /// don't support JSON.
+    #[arg(long)]
+    descriptor_set: Option<PathBuf>,
+
+    /// Read call specifications from stdin as JSON lines, writing a JSON
+    /// result line to stdout for each.
+    #[arg(long)]
+    json_io: bool,
+
+    /// Print a JSON report of the capabilities compiled into this build.
+    #[arg(long)]
+    capabilities: bool,
+}
+
+/// A call specification, read in `--json-io` mode.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CallSpec {
+    /// The server base URL, e.g. `https://example.com`.
+    url: String,
+    /// The fully-qualified method, e.g. `acme.foo.v1.FooService/Bar`.
+    method: String,
+    /// Request metadata; values may be a string or list of strings.
+    #[serde(default)]
+    metadata: BTreeMap<String, OneOrMany>,
+    /// The request timeout in milliseconds.
+    #[serde(default)]
+    timeout_ms: Option<u64>,
+    /// The JSON request message. Defaults to `{}`.
+    #[serde(default)]
+    body: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// A call result, written in `--json-io` mode.
+#[derive(Default, Serialize)]
+struct CallResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    metadata: BTreeMap<String, Vec<String>>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    trailers: BTreeMap<String, Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<ConnectError>,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let capabilities = connect_rpc::capabilities();
+    if args.capabilities {
+        println!("{}", serde_json::to_string_pretty(&capabilities)?);
+        return Ok(());
+    }
+    capabilities.require(Capability::Codec, "json")?;
+    capabilities.require(Capability::Transport, "reqwest")?;
+
+    let client = ConnectClient::builder().build()?;
+    let transcoder = match &args.descriptor_set {
+        Some(path) => {
+            let bytes =
+                std::fs::read(path).with_context(|| format!("reading descriptor set {path:?}"))?;
+            Some(Transcoder::new(DescriptorPool::decode(bytes.as_slice())?))
+        }
+        None => None,
+    };
+    let ctx = CallContext { client, transcoder };
+
+    if args.json_io {
+        return json_io(&ctx).await;
+    }
+    let (Some(url), Some(method)) = (args.url, args.method) else {
+        bail!("no mode given; try URL METHOD or --json-io");
+    };
+    let body = match args.data.as_deref() {
+        Some(data) => {
+            let data = match data.strip_prefix('@') {
+                Some(path) => std::fs::read_to_string(path)
+                    .with_context(|| format!("reading request data {path:?}"))?,
+                None => data.to_string(),
+            };
+            Some(serde_json::from_str(&data).context("invalid request JSON")?)
+        }
+        None => None,
+    };
+    let mut headers: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for header in args.headers {
+        let (key, value) = header
+            .split_once(':')
+            .with_context(|| format!("invalid header {header:?}; expected `key: value`"))?;
+        headers
+            .entry(key.trim().to_string())
+            .or_default()
+            .push(value.trim().to_string());
+    }
+    let metadata = headers
+        .into_iter()
+        .map(|(key, values)| (key, OneOrMany::Many(values)))
+        .collect();
+    let spec = CallSpec {
+        url,
+        method,
+        metadata,
+        timeout_ms: args.timeout_ms,
+        body,
+    };
+    let result = call(&ctx, spec).await;
+    println!("{}", serde_json::to_string_pretty(&result)?);
+    if result.error.is_some() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Shared state for making calls.
+struct CallContext {
+    client: ConnectClient,
+    transcoder: Option<Transcoder>,
+}
+
+async fn json_io(ctx: &CallContext) -> anyhow::Result<()> {
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut stdout = tokio::io::stdout();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let result = match serde_json::from_str::<CallSpec>(&line) {
+            Ok(spec) => call(ctx, spec).await,
+            Err(err) => CallResult {
+                error: Some(ConnectError::new(
+                    ConnectCode::InvalidArgument,
+                    format!("invalid call spec: {err}"),
+                )),
+                ..Default::default()
+            },
+        };
+        let mut out = serde_json::to_vec(&result)?;
+        out.push(b'\n');
+        stdout.write_all(&out).await?;
+        stdout.flush().await?;
+    }
+    Ok(())
+}
+
+async fn call(ctx: &CallContext, spec: CallSpec) -> CallResult {
+    match try_call(ctx, spec).await {
+        Ok(result) => result,
+        Err(err) => {
+            let error = match err.downcast::<connect_rpc::Error>() {
+                Ok(err) => err.into(),
+                Err(err) => ConnectError::new(ConnectCode::Unknown, format!("{err:#}")),
+            };
+            let (metadata, trailers) = metadata_maps(error.headers());
+            CallResult {
+                metadata,
+                trailers,
+                error: Some(error),
+                ..Default::default()
+            }
+        }
+    }
+}
+
+async fn try_call(ctx: &CallContext, spec: CallSpec) -> anyhow::Result<CallResult> {
+    let url = format!(
+        "{}/{}",
+        spec.url.trim_end_matches('/'),
+        spec.method.trim_start_matches('/')
+    );
+    let mut builder = RequestBuilder::default().uri(url)?.message_codec("json")?;
+    if let Some(timeout_ms) = spec.timeout_ms {
+        builder = builder.timeout_ms(timeout_ms)?;
+    }
+    for (key, values) in spec.metadata {
+        let values = match values {
+            OneOrMany::One(value) => vec![value],
+            OneOrMany::Many(values) => values,
+        };
+        for value in values {
+            builder = builder.ascii_metadata(key.as_str(), value)?;
+        }
+    }
+    let body = spec
+        .body
+        .unwrap_or_else(|| serde_json::Value::Object(Default::default()));
+    let req = builder.unary(serde_json::to_vec(&body)?)?;
+    let resp = match &ctx.transcoder {
+        Some(transcoder) => {
+            let path = req.path().to_string();
+            let req = transcoder.request(http::Request::from(req))?;
+            let resp = ctx.client.execute_unary(UnaryRequest::from(req)).await?;
+            UnaryResponse::from(transcoder.response(&path, resp.into())?)
+        }
+        None => ctx.client.execute_unary(req).await?,
+    };
+    let resp = http::Response::from(resp);
+    let body = serde_json::from_slice(resp.body()).context("invalid response JSON")?;
+    let (metadata, trailers) = metadata_maps(resp.headers());
+    Ok(CallResult {
+        body: Some(body),
+        metadata,
+        trailers,
+        error: None,
+    })
+}
+
+type MetadataMap = BTreeMap<String, Vec<String>>;
+
+/// Splits metadata into headers and (`trailer-` prefixed) trailers.
+fn metadata_maps(headers: &http::HeaderMap) -> (MetadataMap, MetadataMap) {
+    let mut metadata = MetadataMap::new();
+    let mut trailers = MetadataMap::new();
+    for (key, val) in headers {
+        let Ok(val) = val.to_str() else {
+            continue;
+        };
+        let (map, key) = match key.as_str().strip_prefix("trailer-") {
+            Some(key) => (&mut trailers, key),
+            None => (&mut metadata, key.as_str()),
+        };
+        map.entry(key.to_string())
+            .or_default()
+            .push(val.to_string());
+    }
+    (metadata, trailers)
+}
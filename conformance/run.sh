@@ -26,5 +26,8 @@ conformance=".work/conformance-${ver}"
     mv connectconformance "../${conformance}"
 )
 
+# Descriptors for JSON codec tests (see src/codec.rs)
+[[ -e gen/conformance.binpb ]] || buf build "buf.build/connectrpc/conformance:v${ver}" -o gen/conformance.binpb
+
 cargo build
 $conformance "$@" --conf conformance.yaml --mode client -- target/debug/connect-rpc-conformance
@@ -0,0 +1,79 @@
+//! Message codecs for conformance tests.
+//!
+//! Test cases carry request messages in binary proto form, so the JSON codec
+//! transcodes them (and responses) with a [`Transcoder`] using descriptors
+//! loaded from a `FileDescriptorSet`, generated by `run.sh` with `buf build`.
+
+use std::sync::OnceLock;
+
+use anyhow::Context;
+use bytes::Bytes;
+use connect_rpc::transcode::Transcoder;
+use prost::Message;
+use prost_reflect::DescriptorPool;
+
+use crate::proto::{self, ClientCompatRequest};
+
+const DESCRIPTORS_ENV: &str = "CONFORMANCE_DESCRIPTORS";
+const DEFAULT_DESCRIPTORS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/gen/conformance.binpb");
+
+pub enum Codec {
+    Proto,
+    Json {
+        transcoder: &'static Transcoder,
+        path: String,
+    },
+}
+
+impl Codec {
+    pub fn new(test: &ClientCompatRequest) -> anyhow::Result<Self> {
+        Ok(match test.codec() {
+            proto::Codec::Unspecified | proto::Codec::Proto => Self::Proto,
+            proto::Codec::Json => Self::Json {
+                transcoder: transcoder()?,
+                path: format!("/{}/{}", test.service(), test.method()),
+            },
+            other => anyhow::bail!("{} not supported", other.as_str_name()),
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Proto => "proto",
+            Self::Json { .. } => "json",
+        }
+    }
+
+    /// Encodes a (binary proto) request message.
+    pub fn encode_request(&self, message: &[u8]) -> anyhow::Result<Bytes> {
+        match self {
+            Self::Proto => Ok(Bytes::copy_from_slice(message)),
+            Self::Json { transcoder, path } => {
+                Ok(transcoder.request_message_to_json(path, message)?)
+            }
+        }
+    }
+
+    /// Decodes a response message.
+    ///
+    /// All conformance response messages have the payload at tag 1, so they
+    /// decode as a [`proto::UnaryResponse`].
+    pub fn decode_response(&self, data: Bytes) -> anyhow::Result<proto::UnaryResponse> {
+        let data = match self {
+            Self::Proto => data,
+            Self::Json { transcoder, path } => transcoder.response_message_to_proto(path, &data)?,
+        };
+        Ok(proto::UnaryResponse::decode(data)?)
+    }
+}
+
+fn transcoder() -> anyhow::Result<&'static Transcoder> {
+    static TRANSCODER: OnceLock<Transcoder> = OnceLock::new();
+    if let Some(transcoder) = TRANSCODER.get() {
+        return Ok(transcoder);
+    }
+    let path = std::env::var(DESCRIPTORS_ENV).unwrap_or_else(|_| DEFAULT_DESCRIPTORS.into());
+    let bytes = std::fs::read(&path).with_context(|| format!("reading descriptors {path:?}"))?;
+    let pool = DescriptorPool::decode(bytes.as_slice())?;
+    Ok(TRANSCODER.get_or_init(|| Transcoder::new(pool)))
+}
@@ -1,29 +1,44 @@
 use std::{
     collections::HashMap,
     io::{ErrorKind, Write},
+    sync::Arc,
+    time::Duration,
 };
 
-use anyhow::{bail, ensure};
+use anyhow::{bail, ensure, Context};
+use bytes::Bytes;
+use codec::Codec;
 use connect_rpc::{
-    metadata::Metadata,
-    request::builder::RequestBuilder,
+    client::{
+        call::{BidiStreamCall, ServerStreamCall},
+        ConnectClient,
+    },
+    compression::{self, Compression},
+    metadata::{split_trailers, Metadata},
+    request::{builder::RequestBuilder, StreamingRequest},
     reqwest::ReqwestClientExt,
     response::{
-        error::{ConnectCode, ConnectError},
-        ConnectResponse,
+        error::{ConnectCode, ConnectError, ConnectResult, IntoConnectResult},
+        ConnectResponse, UnaryResponse, ValidateOpts,
     },
+    stream::ConnectFrame,
 };
+use futures_util::{future::BoxFuture, FutureExt, StreamExt};
+use http::{HeaderMap, HeaderName, HeaderValue};
 use prost::Message;
 use tokio::{io::AsyncReadExt, task::JoinSet};
 use tracing_subscriber::{fmt::format, prelude::*, EnvFilter};
 
+mod codec;
+mod raw;
 mod proto {
     include!("../gen/connectrpc.conformance.v1.rs");
 }
 use proto::{
+    client_compat_request::cancel::CancelTiming,
     client_compat_response::Result as ClientCompatResult, ClientCompatRequest,
     ClientCompatResponse, ClientErrorResult, ClientResponseResult, Error as ResponseError, Header,
-    HttpVersion,
+    HttpVersion, RawHttpRequest, StreamType,
 };
 
 #[tokio::main]
@@ -37,11 +52,17 @@ async fn main() -> anyhow::Result<()> {
         .with(EnvFilter::from_default_env())
         .init();
 
+    let args = Args::parse(std::env::args().skip(1))?;
+
     let mut tasks = JoinSet::new();
-    while let Some(req) = read_request().await? {
+    while let Some(req) = read_request::<ClientCompatRequest>().await? {
+        if !args.should_run(&req.test_name) {
+            tracing::debug!(test_name = req.test_name, "Skipping client test");
+            write_skipped(req.test_name)?;
+            continue;
+        }
         tasks.spawn(handle_client_test(req));
-        // TODO configure parallelism
-        while tasks.len() > 16 {
+        while tasks.len() >= args.parallelism {
             tasks.join_next().await;
         }
     }
@@ -49,6 +70,54 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Command-line arguments, passed after the client command:
+///
+/// ```text
+/// connectconformance --conf conformance.yaml --mode client -- \
+///     target/debug/connect-rpc-conformance --parallelism 4 --run 'Server Stream' --skip Timeout
+/// ```
+///
+/// `--run` and `--skip` may be repeated and match test name substrings.
+struct Args {
+    parallelism: usize,
+    run: Vec<String>,
+    skip: Vec<String>,
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> anyhow::Result<Self> {
+        let mut parsed = Self {
+            parallelism: 16,
+            run: vec![],
+            skip: vec![],
+        };
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .with_context(|| format!("missing value for {arg}"))
+            };
+            match arg.as_str() {
+                "--parallelism" => {
+                    parsed.parallelism = value()?.parse()?;
+                    ensure!(parsed.parallelism > 0, "--parallelism must be positive");
+                }
+                "--run" => parsed.run.push(value()?),
+                "--skip" => parsed.skip.push(value()?),
+                _ => bail!("unknown argument {arg:?}"),
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn should_run(&self, test_name: &str) -> bool {
+        (self.run.is_empty() || self.run.iter().any(|run| test_name.contains(run.as_str())))
+            && !self
+                .skip
+                .iter()
+                .any(|skip| test_name.contains(skip.as_str()))
+    }
+}
+
 async fn handle_client_test(test: ClientCompatRequest) {
     let test_name = test.test_name.clone();
     tracing::debug!(test_name, "Running client test");
@@ -75,94 +144,464 @@ async fn run_client_test(test: ClientCompatRequest) -> anyhow::Result<ClientResp
 
     // Assert supported test features
     ensure!(test.protocol() == proto::Protocol::Connect);
-    ensure!(test.codec() == proto::Codec::Proto);
-    ensure!(test.compression() == proto::Compression::Identity);
     ensure!(test.server_tls_cert.is_empty());
     ensure!(test.client_tls_creds.is_none());
 
-    let client = {
+    let (client, http_version) = {
         let builder = reqwest::Client::builder();
-        let builder = match test.http_version() {
-            HttpVersion::Unspecified => builder,
-            HttpVersion::HttpVersion1 => builder.http1_only(),
-            HttpVersion::HttpVersion2 => builder.http2_prior_knowledge(),
-            HttpVersion::HttpVersion3 => bail!("HTTP3 not supported"),
+        let (builder, version) = match test.http_version() {
+            HttpVersion::Unspecified => (builder, None),
+            HttpVersion::HttpVersion1 => (builder.http1_only(), Some(http::Version::HTTP_11)),
+            HttpVersion::HttpVersion2 => {
+                (builder.http2_prior_knowledge(), Some(http::Version::HTTP_2))
+            }
+            #[cfg(feature = "http3")]
+            HttpVersion::HttpVersion3 => {
+                (builder.http3_prior_knowledge(), Some(http::Version::HTTP_3))
+            }
+            #[cfg(not(feature = "http3"))]
+            HttpVersion::HttpVersion3 => bail!("HTTP3 not supported; enable the http3 feature"),
         };
-        builder.build()?
+        (builder.build()?, version)
     };
+    let connect = ConnectClient::builder()
+        .reqwest_client(client.clone())
+        .build()?;
 
-    let resp_result = {
-        let mut builder = RequestBuilder::default()
-            .scheme("http")?
-            .authority(format!("{}:{}", test.host, test.port))?
-            .protobuf_rpc(test.service(), test.method())?
-            .message_codec("proto")?;
+    let codec = Codec::new(&test)?;
+    let mut builder = RequestBuilder::default()
+        .scheme("http")?
+        .authority(format!("{}:{}", test.host, test.port))?
+        .protobuf_rpc(test.service(), test.method())?
+        .message_codec(codec.name())?;
 
-        if let Some(timeout_ms) = test.timeout_ms {
-            builder = builder.timeout_ms(timeout_ms.into())?;
-        }
+    if let Some(version) = http_version {
+        builder = builder.http_version(version);
+    }
 
-        for header in test.request_headers {
-            for value in header.value {
-                builder = builder.ascii_metadata(&header.name, value)?;
-            }
+    if let Some(timeout_ms) = test.timeout_ms {
+        builder = builder.timeout_ms(timeout_ms.into())?;
+    }
+
+    if let Some(encoding) = content_encoding(&test)? {
+        builder = builder
+            .content_encoding(encoding)?
+            .accept_encoding([encoding])?;
+    }
+
+    for header in &test.request_headers {
+        for value in &header.value {
+            builder = builder.ascii_metadata(&header.name, value.as_str())?;
         }
+    }
+
+    if let Some(raw) = &test.raw_request {
+        return run_raw_test(&client, &connect, &test, &codec, raw).await;
+    }
+
+    match test.stream_type() {
+        StreamType::Unary => run_unary_test(&client, &test, &codec, builder).await,
+        StreamType::Unspecified => bail!("unspecified stream type"),
+        _ => run_stream_test(&connect, &test, &codec, builder).await,
+    }
+}
 
-        let msg = &test.request_messages[0].value;
-        tracing::trace!(msg = %msg.escape_ascii());
-        if test.use_get_http_method {
-            client.execute_unary_get(builder.unary_get(msg)?).await
+async fn run_unary_test(
+    client: &reqwest::Client,
+    test: &ClientCompatRequest,
+    codec: &Codec,
+    builder: RequestBuilder,
+) -> anyhow::Result<ClientResponseResult> {
+    let msg = codec.encode_request(&test.request_messages[0].value)?;
+    tracing::trace!(msg = %msg.escape_ascii());
+    let body = match content_encoding(test)? {
+        Some(encoding) => lookup_compression(encoding)?.compress(&msg)?,
+        None => msg.clone(),
+    };
+    let call = async {
+        let result = if test.use_get_http_method {
+            client.execute_unary_get(builder.unary_get(&msg)?).await
         } else {
-            client.execute_unary(builder.unary(msg.clone())?).await
-        }
+            client.execute_unary(builder.unary(body)?).await
+        };
+        result.into_connect()
+    };
+    // Dropping the call future aborts the in-flight request.
+    let cancel_after = match test.cancel.and_then(|cancel| cancel.cancel_timing) {
+        Some(CancelTiming::AfterCloseSendMs(ms)) => Some(Duration::from_millis(ms.into())),
+        Some(CancelTiming::AfterNumResponses(0)) => Some(Duration::ZERO),
+        Some(timing) => bail!("unsupported cancel timing for unary: {timing:?}"),
+        None => None,
+    };
+    let resp_result = match cancel_after {
+        Some(duration) => match tokio::time::timeout(duration, call).await {
+            Ok(result) => result,
+            Err(_) => return Ok(ConnectCode::Canceled.into()),
+        },
+        None => call.await,
     };
     tracing::trace!(?resp_result);
+    unary_result(codec, resp_result)
+}
 
-    if test.cancel.is_some() {
-        return Ok(ConnectCode::Canceled.into());
-    }
-
+fn unary_result(
+    codec: &Codec,
+    resp_result: ConnectResult<UnaryResponse<Bytes>>,
+) -> anyhow::Result<ClientResponseResult> {
     match resp_result {
         Ok(resp) => {
-            let resp_msg = proto::UnaryResponse::decode(resp.body().as_ref())?;
-            let (response_headers, response_trailers) = headers_and_trailers(resp.metadata());
+            let body = match resp.content_encoding() {
+                Some(encoding) => {
+                    lookup_compression(encoding)?.decompress(resp.body(), usize::MAX)?
+                }
+                None => resp.body().clone(),
+            };
+            let resp_msg = codec.decode_response(body)?;
+            let (headers, trailers) = resp.headers_and_trailers();
             let payloads = vec![resp_msg.payload.unwrap_or_default()];
             Ok(ClientResponseResult {
-                response_headers,
-                response_trailers,
+                response_headers: proto_headers(&headers),
+                response_trailers: proto_headers(&trailers),
                 payloads,
                 ..Default::default()
             })
         }
-        Err(err) => {
-            let connect_error = ConnectError::from(err);
-            let (response_headers, response_trailers) =
-                headers_and_trailers(connect_error.metadata());
-            let code = connect_error.code();
-            let details = connect_error
-                .details
-                .into_iter()
-                .map(|detail| {
-                    Ok(prost_types::Any {
-                        type_url: detail.type_url(),
-                        value: detail.value()?,
-                    })
-                })
-                .collect::<anyhow::Result<_>>()?;
+        Err(connect_error) => {
+            let (headers, trailers) = split_trailers(connect_error.headers());
             Ok(ClientResponseResult {
-                response_headers,
-                response_trailers,
-                error: Some(ResponseError {
-                    code: proto::Code::from(code) as i32,
-                    message: Some(connect_error.message),
-                    details,
-                }),
+                response_headers: proto_headers(&headers),
+                response_trailers: proto_headers(&trailers),
+                error: Some(response_error(connect_error)?),
                 ..Default::default()
             })
         }
     }
 }
 
+/// Sends a test's raw request as-is, reading the response as usual.
+async fn run_raw_test(
+    client: &reqwest::Client,
+    connect: &ConnectClient,
+    test: &ClientCompatRequest,
+    codec: &Codec,
+    raw: &RawHttpRequest,
+) -> anyhow::Result<ClientResponseResult> {
+    let req = raw::http_request(&test.host, test.port, raw)?;
+    if test.stream_type() != StreamType::Unary {
+        let req = StreamingRequest::from(req);
+        let connect = connect.clone();
+        let pending = async move { connect.execute_server_stream(req).await }.boxed();
+        let mut call = StreamCall::server(pending, codec);
+        call.receive_all().await?;
+        return call.into_result(0);
+    }
+    let req = reqwest::Request::try_from(req)?;
+    let resp_result = async {
+        let resp = client.execute(req).await?;
+        let status = resp.status();
+        let headers = resp.headers().clone();
+        let mut http_resp = http::Response::new(resp.bytes().await?);
+        *http_resp.status_mut() = status;
+        *http_resp.headers_mut() = headers;
+        UnaryResponse::from(http_resp).result(&ValidateOpts::default())
+    }
+    .await
+    .into_connect();
+    tracing::trace!(?resp_result);
+    unary_result(codec, resp_result)
+}
+
+async fn run_stream_test(
+    connect: &ConnectClient,
+    test: &ClientCompatRequest,
+    codec: &Codec,
+    builder: RequestBuilder,
+) -> anyhow::Result<ClientResponseResult> {
+    let full_duplex = test.stream_type() == StreamType::FullDuplexBidiStream;
+    let cancel = test.cancel.and_then(|cancel| cancel.cancel_timing);
+
+    let compression = content_encoding(test)?
+        .map(lookup_compression)
+        .transpose()?;
+    let encode = |message: &[u8]| -> anyhow::Result<ConnectFrame> {
+        let mut frame = ConnectFrame {
+            compressed: false,
+            end: false,
+            data: codec.encode_request(message)?,
+        };
+        if let Some(compression) = &compression {
+            frame.data = compression.compress(&frame.data)?;
+            frame.compressed = true;
+        }
+        Ok(frame)
+    };
+
+    let mut num_unsent = 0;
+    let mut call = if test.stream_type() == StreamType::ServerStream {
+        let msg = test
+            .request_messages
+            .first()
+            .context("missing request message")?;
+        let req = builder.streaming(encode(&msg.value)?.encode()?)?;
+        let connect = connect.clone();
+        StreamCall::server(
+            async move { connect.execute_server_stream(req).await }.boxed(),
+            codec,
+        )
+    } else {
+        let mut call = StreamCall::bidi(connect.execute_bidi_stream(builder, 1)?, codec);
+        num_unsent = test.request_messages.len();
+        for msg in &test.request_messages {
+            if call.is_finished() {
+                break;
+            }
+            if test.request_delay_ms > 0 {
+                call.delay(Duration::from_millis(test.request_delay_ms.into()))
+                    .await;
+            }
+            if !call.send(encode(&msg.value)?).await {
+                break;
+            }
+            num_unsent -= 1;
+            if full_duplex {
+                call.receive().await?;
+            }
+        }
+        call
+    };
+
+    match cancel {
+        Some(CancelTiming::BeforeCloseSend(())) => call.cancel(),
+        Some(CancelTiming::AfterCloseSendMs(ms)) => {
+            call.close_send();
+            let duration = Duration::from_millis(ms.into());
+            match tokio::time::timeout(duration, call.receive_all()).await {
+                Ok(result) => result?,
+                Err(_) => call.cancel(),
+            }
+        }
+        Some(CancelTiming::AfterNumResponses(num)) => {
+            call.close_send();
+            while call.payloads.len() < num as usize && call.receive().await? {}
+            call.cancel();
+        }
+        None => {
+            call.close_send();
+            call.receive_all().await?;
+        }
+    }
+    call.into_result(num_unsent)
+}
+
+/// A streaming call whose response is read one message at a time, so that
+/// full-duplex calls can interleave sending and receiving.
+struct StreamCall<'a> {
+    codec: &'a Codec,
+    call: Call,
+    headers: Option<HeaderMap>,
+    payloads: Vec<proto::ConformancePayload>,
+    trailers: HeaderMap,
+    error: Option<ConnectError>,
+}
+
+/// The state of the underlying [`ConnectClient`] call.
+enum Call {
+    /// A server-streaming call awaiting response headers.
+    Pending(BoxFuture<'static, Result<ServerStreamCall, connect_rpc::Error>>),
+    Server(ServerStreamCall),
+    Bidi(BidiStreamCall),
+    Finished,
+}
+
+impl<'a> StreamCall<'a> {
+    fn server(
+        pending: BoxFuture<'static, Result<ServerStreamCall, connect_rpc::Error>>,
+        codec: &'a Codec,
+    ) -> Self {
+        Self::new(Call::Pending(pending), codec)
+    }
+
+    fn bidi(call: BidiStreamCall, codec: &'a Codec) -> Self {
+        Self::new(Call::Bidi(call), codec)
+    }
+
+    fn new(call: Call, codec: &'a Codec) -> Self {
+        Self {
+            codec,
+            call,
+            headers: None,
+            payloads: vec![],
+            trailers: HeaderMap::new(),
+            error: None,
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        matches!(self.call, Call::Finished)
+    }
+
+    /// Sends a request message, returning false if it couldn't be sent
+    /// because the call has finished.
+    async fn send(&mut self, frame: ConnectFrame) -> bool {
+        match &mut self.call {
+            Call::Bidi(call) => call.send(frame).await.is_ok(),
+            _ => false,
+        }
+    }
+
+    fn close_send(&self) {
+        if let Call::Bidi(call) = &self.call {
+            call.close_send();
+        }
+    }
+
+    /// Waits for `duration` while driving the request, so that it is sent
+    /// (and any early response received) in the meantime.
+    async fn delay(&mut self, duration: Duration) {
+        let sleep = tokio::time::sleep(duration);
+        tokio::pin!(sleep);
+        if let Call::Bidi(call) = &mut self.call {
+            let result = tokio::select! {
+                () = &mut sleep => return,
+                result = call.response() => result.err(),
+            };
+            if let Some(err) = result {
+                self.finish(err.into());
+            }
+        }
+        sleep.await;
+    }
+
+    /// Receives the next response message, returning false once the call
+    /// has finished.
+    async fn receive(&mut self) -> anyhow::Result<bool> {
+        if let Call::Pending(pending) = &mut self.call {
+            match pending.await {
+                Ok(call) => self.call = Call::Server(call),
+                Err(err) => return Ok(self.finish(err.into())),
+            }
+        }
+        let call = match &mut self.call {
+            Call::Server(call) => call,
+            Call::Bidi(call) => match call.response().await {
+                Ok(call) => call,
+                Err(err) => return Ok(self.finish(err.into())),
+            },
+            Call::Pending(_) | Call::Finished => return Ok(false),
+        };
+        if self.headers.is_none() {
+            self.headers = Some(call.response().headers_and_trailers().0);
+        }
+        match call.next().await {
+            Some(Ok(data)) => {
+                let resp_msg = self.codec.decode_response(data)?;
+                self.payloads.push(resp_msg.payload.unwrap_or_default());
+                Ok(true)
+            }
+            Some(Err(err)) => {
+                self.trailers = trailer_map(call.trailers())?;
+                Ok(self.finish(err.into()))
+            }
+            None => {
+                self.trailers = trailer_map(call.trailers())?;
+                self.call = Call::Finished;
+                Ok(false)
+            }
+        }
+    }
+
+    async fn receive_all(&mut self) -> anyhow::Result<()> {
+        while self.receive().await? {}
+        Ok(())
+    }
+
+    fn finish(&mut self, err: ConnectError) -> bool {
+        self.call = Call::Finished;
+        if self.headers.is_none() {
+            self.headers = Some(err.headers().clone());
+        }
+        self.error = Some(err);
+        false
+    }
+
+    /// Cancels the call, dropping the request and response bodies.
+    fn cancel(&mut self) {
+        if !self.is_finished() || self.error.is_none() {
+            self.error = Some(ConnectError::new(ConnectCode::Canceled, "canceled"));
+        }
+        self.call = Call::Finished;
+    }
+
+    fn into_result(self, num_unsent_requests: usize) -> anyhow::Result<ClientResponseResult> {
+        Ok(ClientResponseResult {
+            response_headers: self.headers.as_ref().map(proto_headers).unwrap_or_default(),
+            response_trailers: proto_headers(&self.trailers),
+            payloads: self.payloads,
+            error: self.error.map(response_error).transpose()?,
+            num_unsent_requests: num_unsent_requests.try_into()?,
+            ..Default::default()
+        })
+    }
+}
+
+/// Returns end-stream trailers as a [`HeaderMap`], ordered by name so that
+/// results are deterministic.
+fn trailer_map(trailers: Option<&HashMap<String, Vec<String>>>) -> anyhow::Result<HeaderMap> {
+    let mut trailers: Vec<_> = trailers.into_iter().flatten().collect();
+    trailers.sort_by_key(|(name, _)| *name);
+    let mut map = HeaderMap::new();
+    for (name, values) in trailers {
+        let name = HeaderName::try_from(name)?;
+        for value in values {
+            map.append(&name, HeaderValue::try_from(value)?);
+        }
+    }
+    Ok(map)
+}
+
+/// Returns the content encoding for the test's compression, if any.
+///
+/// Only gzip is supported (and declared in `conformance.yaml`).
+fn content_encoding(test: &ClientCompatRequest) -> anyhow::Result<Option<&'static str>> {
+    Ok(match test.compression() {
+        proto::Compression::Unspecified | proto::Compression::Identity => None,
+        proto::Compression::Gzip => Some("gzip"),
+        other => bail!("{} not supported", other.as_str_name()),
+    })
+}
+
+fn lookup_compression(encoding: &str) -> anyhow::Result<Arc<dyn Compression>> {
+    compression::lookup(encoding).with_context(|| format!("unsupported encoding {encoding:?}"))
+}
+
+fn response_error(connect_error: ConnectError) -> anyhow::Result<ResponseError> {
+    let code = connect_error.code();
+    let details = connect_error
+        .details
+        .into_iter()
+        .map(|detail| {
+            Ok(prost_types::Any {
+                type_url: detail.type_url(),
+                value: detail.value()?,
+            })
+        })
+        .collect::<anyhow::Result<_>>()?;
+    Ok(ResponseError {
+        code: proto::Code::from(code) as i32,
+        message: Some(connect_error.message),
+        details,
+    })
+}
+
+fn write_skipped(test_name: String) -> anyhow::Result<()> {
+    write_response(ClientCompatResponse {
+        test_name,
+        result: Some(ClientCompatResult::Error(ClientErrorResult {
+            message: "skipped".into(),
+        })),
+    })
+}
+
 async fn read_request<T: Message + Default>() -> anyhow::Result<Option<T>> {
     let len = match tokio::io::stdin().read_u32().await {
         Ok(len) => len,
@@ -185,27 +624,14 @@ fn write_response(resp: impl Message) -> anyhow::Result<()> {
     Ok(())
 }
 
-fn headers_and_trailers(metadata: &impl Metadata) -> (Vec<Header>, Vec<Header>) {
-    let mut headers: HashMap<&str, Header> = HashMap::new();
-    let mut trailers: HashMap<&str, Header> = HashMap::new();
-    for (key, val) in metadata.iter_ascii() {
-        let map = if key.ends_with("-trailer") {
-            &mut trailers
-        } else {
-            &mut headers
-        };
-        map.entry(key)
-            .or_insert_with(|| Header {
-                name: key.to_string(),
-                ..Default::default()
-            })
-            .value
-            .push(val.to_string());
-    }
-    (
-        headers.into_values().collect(),
-        trailers.into_values().collect(),
-    )
+fn proto_headers(metadata: &impl Metadata) -> Vec<Header> {
+    metadata
+        .iter_entries()
+        .map(|(key, values)| Header {
+            name: key.to_string(),
+            value: values.into_iter().map(ToString::to_string).collect(),
+        })
+        .collect()
 }
 
 impl From<ConnectCode> for ClientResponseResult {
@@ -222,24 +648,7 @@ impl From<ConnectCode> for ClientResponseResult {
 
 impl From<ConnectCode> for proto::Code {
     fn from(code: ConnectCode) -> Self {
-        match code {
-            ConnectCode::Ok => Self::Unspecified,
-            ConnectCode::Canceled => Self::Canceled,
-            ConnectCode::Unknown => Self::Unknown,
-            ConnectCode::InvalidArgument => Self::InvalidArgument,
-            ConnectCode::DeadlineExceeded => Self::DeadlineExceeded,
-            ConnectCode::NotFound => Self::NotFound,
-            ConnectCode::AlreadyExists => Self::AlreadyExists,
-            ConnectCode::PermissionDenied => Self::PermissionDenied,
-            ConnectCode::ResourceExhausted => Self::ResourceExhausted,
-            ConnectCode::FailedPrecondition => Self::FailedPrecondition,
-            ConnectCode::Aborted => Self::Aborted,
-            ConnectCode::OutOfRange => Self::OutOfRange,
-            ConnectCode::Unimplemented => Self::Unimplemented,
-            ConnectCode::Internal => Self::Internal,
-            ConnectCode::Unavailable => Self::Unavailable,
-            ConnectCode::DataLoss => Self::DataLoss,
-            ConnectCode::Unauthenticated => Self::Unauthenticated,
-        }
+        // `ok` (0) maps to `CODE_UNSPECIFIED`; the rest share gRPC numbering.
+        Self::try_from(code.as_grpc_code()).unwrap_or(Self::Unknown)
     }
 }
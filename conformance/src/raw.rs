@@ -0,0 +1,103 @@
+//! Raw HTTP requests for conformance tests.
+//!
+//! Some test cases specify the exact request to send (`raw_request`),
+//! including malformed headers, query params, and envelopes, to exercise the
+//! server's handling of invalid requests.
+
+use anyhow::{bail, Context};
+use bytes::{Bytes, BytesMut};
+use connect_rpc::{base64::Base64Variant, compression, stream::ConnectFrame};
+
+use crate::proto::{
+    self,
+    message_contents::Data,
+    raw_http_request::{Body, EncodedQueryParam},
+    MessageContents, RawHttpRequest,
+};
+
+/// Builds the HTTP request described by a raw request.
+pub fn http_request(
+    host: &str,
+    port: u32,
+    raw: &RawHttpRequest,
+) -> anyhow::Result<http::Request<Bytes>> {
+    let mut query = form_urlencoded::Serializer::new(String::new());
+    for param in &raw.raw_query_params {
+        for value in &param.value {
+            query.append_pair(&param.name, value);
+        }
+    }
+    let mut query = query.finish();
+    for param in &raw.encoded_query_params {
+        if !query.is_empty() {
+            query.push('&');
+        }
+        query.push_str(&encoded_query_param(param)?);
+    }
+    let mut uri = format!("http://{host}:{port}{}", raw.uri);
+    if !query.is_empty() {
+        uri.push(if raw.uri.contains('?') { '&' } else { '?' });
+        uri.push_str(&query);
+    }
+
+    let body = match &raw.body {
+        None => Bytes::new(),
+        Some(Body::Unary(contents)) => message_contents(contents)?,
+        Some(Body::Stream(stream)) => {
+            let mut body = BytesMut::new();
+            for item in &stream.items {
+                let data = item
+                    .payload
+                    .as_ref()
+                    .map(message_contents)
+                    .transpose()?
+                    .unwrap_or_default();
+                let flags = item.flags.try_into().context("invalid envelope flags")?;
+                body.extend_from_slice(&ConnectFrame::encode_raw(flags, item.length, &data));
+            }
+            body.freeze()
+        }
+    };
+
+    let mut builder = http::Request::builder().method(raw.verb.as_str()).uri(uri);
+    for header in &raw.headers {
+        for value in &header.value {
+            builder = builder.header(&header.name, value);
+        }
+    }
+    Ok(builder.body(body)?)
+}
+
+fn encoded_query_param(param: &EncodedQueryParam) -> anyhow::Result<String> {
+    let value = param
+        .value
+        .as_ref()
+        .map(message_contents)
+        .transpose()?
+        .unwrap_or_default();
+    let name = form_urlencoded::byte_serialize(param.name.as_bytes()).collect::<String>();
+    let value = if param.base64_encode {
+        Base64Variant::UrlSafe.encode(&value)
+    } else {
+        form_urlencoded::byte_serialize(&value).collect()
+    };
+    Ok(format!("{name}={value}"))
+}
+
+/// Returns the (possibly compressed) bytes of a message.
+fn message_contents(contents: &MessageContents) -> anyhow::Result<Bytes> {
+    let data: Bytes = match &contents.data {
+        None => Bytes::new(),
+        Some(Data::Binary(data)) => data.clone().into(),
+        Some(Data::Text(text)) => text.clone().into(),
+        Some(Data::BinaryMessage(any)) => any.value.clone().into(),
+    };
+    let encoding = match contents.compression() {
+        proto::Compression::Unspecified | proto::Compression::Identity => return Ok(data),
+        proto::Compression::Gzip => "gzip",
+        other => bail!("{} not supported", other.as_str_name()),
+    };
+    let compression = compression::lookup(encoding)
+        .with_context(|| format!("unsupported encoding {encoding:?}"))?;
+    Ok(compression.compress(&data)?)
+}
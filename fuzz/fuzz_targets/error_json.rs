@@ -0,0 +1,30 @@
+//! Deserializes arbitrary bytes as a unary error response body and as an
+//! end-stream message.
+
+#![no_main]
+
+use bytes::Bytes;
+use connect_rpc::{
+    response::error::ConnectError,
+    stream::{ConnectFrame, EndStreamResponse},
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let resp = http::Response::builder()
+        .status(http::StatusCode::BAD_REQUEST)
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .body(data)
+        .unwrap();
+    let error = ConnectError::from(resp);
+    // Decoded errors must round-trip.
+    let json = serde_json::to_vec(&error).unwrap();
+    let _: ConnectError = serde_json::from_slice(&json).unwrap();
+
+    let frame = ConnectFrame {
+        compressed: false,
+        end: true,
+        data: Bytes::copy_from_slice(data),
+    };
+    let _ = EndStreamResponse::from_frame(&frame);
+});
@@ -0,0 +1,42 @@
+//! Parses arbitrary bytes as an enveloped stream, both fully buffered and
+//! split into chunks, which must agree.
+
+#![no_main]
+
+use bytes::Bytes;
+use connect_rpc::stream::ConnectFrame;
+use futures::{executor::block_on, stream, TryStreamExt};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&chunk_size, body)) = data.split_first() else {
+        return;
+    };
+    let body = Bytes::copy_from_slice(body);
+    let buffered = ConnectFrame::parse_all(body.clone());
+
+    let chunks = body
+        .chunks(usize::from(chunk_size).max(1))
+        .map(|chunk| Ok::<_, std::io::Error>(Bytes::copy_from_slice(chunk)))
+        .collect::<Vec<_>>();
+    let chunked: Result<Vec<_>, _> =
+        block_on(ConnectFrame::bytes_stream(stream::iter(chunks)).try_collect());
+
+    match (buffered, chunked) {
+        (Ok(buffered), Ok(chunked)) => {
+            assert_eq!(buffered.len(), chunked.len());
+            for (a, b) in buffered.iter().zip(&chunked) {
+                assert_eq!(
+                    (a.compressed, a.end, &a.data),
+                    (b.compressed, b.end, &b.data)
+                );
+            }
+        }
+        (Err(_), Err(_)) => {}
+        (buffered, chunked) => panic!(
+            "buffered and chunked parses disagree: {:?} vs {:?}",
+            buffered.map(|frames| frames.len()),
+            chunked.map(|frames| frames.len()),
+        ),
+    }
+});
@@ -0,0 +1,23 @@
+//! Parses arbitrary query strings as unary GET requests.
+
+#![no_main]
+
+use connect_rpc::request::{ConnectRequest, ConnectRequestType};
+use libfuzzer_sys::fuzz_target;
+
+/// Bounds decompression so that compression bombs don't OOM the fuzzer.
+const MAX_MESSAGE_SIZE: usize = 1024 * 1024;
+
+fuzz_target!(|query: &str| {
+    let Ok(req) = http::Request::get(format!("/acme.foo.v1.FooService/Get?{query}")).body(())
+    else {
+        return;
+    };
+    let ConnectRequestType::UnaryGet(req) = ConnectRequestType::from_http(req) else {
+        unreachable!("GET requests are unary GET requests");
+    };
+    let _ = req.validate();
+    let _ = req.message_codec();
+    let _ = req.content_encoding();
+    let _ = req.message_with_limit(MAX_MESSAGE_SIZE);
+});
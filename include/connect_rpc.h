@@ -0,0 +1,44 @@
+/* C ABI for connect-rpc; see src/ffi.rs. */
+
+#ifndef CONNECT_RPC_H
+#define CONNECT_RPC_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct ConnectFfiClient ConnectFfiClient;
+
+typedef struct ConnectBuffer {
+    uint8_t *data;
+    size_t len;
+} ConnectBuffer;
+
+typedef void (*ConnectMessageCallback)(void *user_data, const uint8_t *data, size_t len);
+
+ConnectFfiClient *connect_client_new(void);
+
+void connect_client_free(ConnectFfiClient *client);
+
+int32_t connect_unary_call(const ConnectFfiClient *client, const char *url, const char *codec,
+                           uint64_t timeout_ms, const uint8_t *req_data, size_t req_len,
+                           ConnectBuffer *resp_out, char **error_message_out);
+
+int32_t connect_server_stream_call(const ConnectFfiClient *client, const char *url,
+                                   const char *codec, uint64_t timeout_ms,
+                                   const uint8_t *req_data, size_t req_len,
+                                   ConnectMessageCallback callback, void *user_data,
+                                   char **error_message_out);
+
+void connect_buffer_free(ConnectBuffer buf);
+
+void connect_string_free(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* CONNECT_RPC_H */
@@ -0,0 +1,135 @@
+//! Per-RPC authorization policies for server implementations.
+//!
+//! Routes declare the scopes and roles they require in an [`AuthzPolicies`]
+//! table; an [`Authorizer`] evaluates each request's metadata against the
+//! matching [`Policy`] before the handler runs:
+//!
+//! ```no_run
+//! # use connect_rpc::{authz::{AuthzError, AuthzPolicies, Authorizer, Policy}, response::error::ConnectError};
+//! # struct MyAuthorizer;
+//! # impl Authorizer for MyAuthorizer {
+//! #     fn authorize(&self, _: &str, _: &Policy, _: &http::HeaderMap) -> Result<(), AuthzError> {
+//! #         Ok(())
+//! #     }
+//! # }
+//! # fn example(req: http::Request<()>) -> Result<(), ConnectError> {
+//! let policies = AuthzPolicies::new(MyAuthorizer)
+//!     .route("/acme.foo.v1.FooService/Delete", Policy::new().scope("foo:write"));
+//! policies.check(&req)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{collections::HashMap, sync::Arc};
+
+use http::HeaderMap;
+
+use crate::response::error::{ConnectCode, ConnectError};
+
+/// The scopes and roles required to call an RPC.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Policy {
+    pub scopes: Vec<String>,
+    pub roles: Vec<String>,
+}
+
+impl Policy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a required scope.
+    pub fn scope(mut self, scope: impl Into<String>) -> Self {
+        self.scopes.push(scope.into());
+        self
+    }
+
+    /// Adds a required role.
+    pub fn role(mut self, role: impl Into<String>) -> Self {
+        self.roles.push(role.into());
+        self
+    }
+}
+
+/// The reason an [`Authorizer`] rejected a request.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AuthzError {
+    /// The request carried no valid credentials.
+    Unauthenticated,
+    /// The caller is authenticated but lacks a required scope or role.
+    PermissionDenied(String),
+}
+
+impl From<AuthzError> for ConnectError {
+    fn from(err: AuthzError) -> Self {
+        match err {
+            AuthzError::Unauthenticated => ConnectError::new(
+                ConnectCode::Unauthenticated,
+                "missing or invalid credentials",
+            ),
+            AuthzError::PermissionDenied(reason) => {
+                ConnectError::new(ConnectCode::PermissionDenied, reason)
+            }
+        }
+    }
+}
+
+/// Evaluates request metadata against a [`Policy`].
+pub trait Authorizer: Send + Sync {
+    fn authorize(
+        &self,
+        procedure: &str,
+        policy: &Policy,
+        metadata: &HeaderMap,
+    ) -> Result<(), AuthzError>;
+}
+
+/// A table of per-procedure [`Policy`]s checked by an [`Authorizer`].
+///
+/// Procedures are keyed by request path (e.g. `/acme.foo.v1.FooService/Get`).
+/// Requests to procedures without a registered policy are allowed.
+#[derive(Clone)]
+pub struct AuthzPolicies {
+    authorizer: Arc<dyn Authorizer>,
+    routes: HashMap<String, Policy>,
+}
+
+impl AuthzPolicies {
+    pub fn new(authorizer: impl Authorizer + 'static) -> Self {
+        Self {
+            authorizer: Arc::new(authorizer),
+            routes: Default::default(),
+        }
+    }
+
+    /// Registers the policy for a procedure, replacing any existing one.
+    pub fn route(mut self, procedure: impl Into<String>, policy: Policy) -> Self {
+        self.routes.insert(procedure.into(), policy);
+        self
+    }
+
+    /// Returns the policy registered for a procedure.
+    pub fn policy(&self, procedure: &str) -> Option<&Policy> {
+        self.routes.get(procedure)
+    }
+
+    /// Checks a request against its procedure's policy, returning an
+    /// `unauthenticated` or `permission_denied` error if it is rejected.
+    pub fn check<T>(&self, req: &http::Request<T>) -> Result<(), ConnectError> {
+        let procedure = req.uri().path();
+        let Some(policy) = self.routes.get(procedure) else {
+            return Ok(());
+        };
+        self.authorizer
+            .authorize(procedure, policy, req.headers())
+            .map_err(Into::into)
+    }
+}
+
+impl std::fmt::Debug for AuthzPolicies {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuthzPolicies")
+            .field("routes", &self.routes)
+            .finish_non_exhaustive()
+    }
+}
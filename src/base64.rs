@@ -0,0 +1,107 @@
+//! Base64 configuration.
+//!
+//! The Connect protocol uses base64 to encode binary (`-bin`) metadata values
+//! and unary GET `message` query params. The defaults follow the spec, but
+//! may be changed with [`set_config`] to interoperate with off-spec peers.
+//!
+//! Decoding always accepts both padded and unpadded input.
+
+use std::sync::RwLock;
+
+use base64::{
+    alphabet,
+    engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+    Engine,
+};
+
+use crate::Error;
+
+const fn engine(alphabet: &alphabet::Alphabet, pad: bool) -> GeneralPurpose {
+    GeneralPurpose::new(
+        alphabet,
+        GeneralPurposeConfig::new()
+            .with_encode_padding(pad)
+            .with_decode_padding_mode(DecodePaddingMode::Indifferent),
+    )
+}
+
+const STANDARD: GeneralPurpose = engine(&alphabet::STANDARD, true);
+const STANDARD_NO_PAD: GeneralPurpose = engine(&alphabet::STANDARD, false);
+const URL_SAFE: GeneralPurpose = engine(&alphabet::URL_SAFE, true);
+const URL_SAFE_NO_PAD: GeneralPurpose = engine(&alphabet::URL_SAFE, false);
+
+/// A base64 alphabet and padding variant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Base64Variant {
+    /// Standard alphabet, with padding.
+    Standard,
+    /// Standard alphabet, without padding.
+    StandardNoPad,
+    /// URL-safe alphabet, with padding.
+    UrlSafe,
+    /// URL-safe alphabet, without padding.
+    UrlSafeNoPad,
+}
+
+impl Base64Variant {
+    /// Returns the engine for this variant.
+    pub fn engine(self) -> &'static GeneralPurpose {
+        match self {
+            Self::Standard => &STANDARD,
+            Self::StandardNoPad => &STANDARD_NO_PAD,
+            Self::UrlSafe => &URL_SAFE,
+            Self::UrlSafeNoPad => &URL_SAFE_NO_PAD,
+        }
+    }
+
+    /// Encodes the given bytes.
+    pub fn encode(self, input: impl AsRef<[u8]>) -> String {
+        self.engine().encode(input)
+    }
+
+    /// Decodes the given base64, which may or may not be padded.
+    pub fn decode(self, input: impl AsRef<[u8]>) -> Result<Vec<u8>, Error> {
+        Ok(self.engine().decode(input)?)
+    }
+}
+
+/// Crate-wide base64 configuration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Base64Config {
+    /// The variant used for binary (`-bin`) metadata values.
+    ///
+    /// Defaults to [`Base64Variant::StandardNoPad`].
+    pub metadata: Base64Variant,
+    /// The variant used for unary GET `message` query params.
+    ///
+    /// Defaults to [`Base64Variant::UrlSafeNoPad`].
+    pub get_message: Base64Variant,
+}
+
+impl Base64Config {
+    const DEFAULT: Self = Self {
+        metadata: Base64Variant::StandardNoPad,
+        get_message: Base64Variant::UrlSafeNoPad,
+    };
+}
+
+impl Default for Base64Config {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+static CONFIG: RwLock<Base64Config> = RwLock::new(Base64Config::DEFAULT);
+
+/// Returns the current crate-wide base64 configuration.
+pub fn config() -> Base64Config {
+    *CONFIG.read().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Sets the crate-wide base64 configuration.
+///
+/// This should typically be called once at startup; it affects all
+/// subsequent encoding and decoding.
+pub fn set_config(config: Base64Config) {
+    *CONFIG.write().unwrap_or_else(|err| err.into_inner()) = config;
+}
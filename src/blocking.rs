@@ -0,0 +1,94 @@
+//! A blocking client API, for use outside of async code (e.g. CLI tools and
+//! build scripts).
+
+use bytes::Bytes;
+use futures_util::StreamExt;
+use tokio::runtime::Runtime;
+
+use crate::{
+    client::{call::ServerStreamCall, ConnectClient},
+    request::{StreamingRequest, UnaryGetRequest, UnaryRequest},
+    response::UnaryResponse,
+    Error,
+};
+
+/// A blocking Connect client.
+///
+/// Wraps a [`ConnectClient`] and an internal single-threaded Tokio runtime.
+/// Methods must not be called from within an async runtime.
+#[derive(Debug)]
+pub struct BlockingClient {
+    runtime: Runtime,
+    client: ConnectClient,
+}
+
+impl BlockingClient {
+    /// Returns a new blocking client wrapping a default [`ConnectClient`].
+    pub fn new() -> Result<Self, Error> {
+        Self::from_client(ConnectClient::builder().build()?)
+    }
+
+    /// Returns a new blocking client wrapping the given [`ConnectClient`].
+    pub fn from_client(client: ConnectClient) -> Result<Self, Error> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(Error::RuntimeError)?;
+        Ok(Self { runtime, client })
+    }
+
+    /// Executes a Connect RPC [`UnaryRequest`].
+    pub fn execute_unary(
+        &self,
+        req: UnaryRequest<impl Into<Bytes>>,
+    ) -> Result<UnaryResponse<Bytes>, Error> {
+        self.runtime.block_on(self.client.execute_unary(req))
+    }
+
+    /// Executes a Connect RPC [`UnaryGetRequest`].
+    pub fn execute_unary_get(&self, req: UnaryGetRequest) -> Result<UnaryResponse<Bytes>, Error> {
+        self.runtime.block_on(self.client.execute_unary_get(req))
+    }
+
+    /// Executes a server-streaming Connect RPC.
+    ///
+    /// See [`ConnectClient::execute_server_stream`].
+    pub fn execute_server_stream(
+        &self,
+        req: StreamingRequest<impl Into<Bytes>>,
+    ) -> Result<BlockingServerStream<'_>, Error> {
+        let call = self
+            .runtime
+            .block_on(self.client.execute_server_stream(req))?;
+        Ok(BlockingServerStream {
+            runtime: &self.runtime,
+            call,
+        })
+    }
+}
+
+/// An [`Iterator`] over the response messages of a blocking server-streaming
+/// call.
+///
+/// Dropping the iterator before it is exhausted cancels the call.
+#[derive(Debug)]
+pub struct BlockingServerStream<'a> {
+    runtime: &'a Runtime,
+    call: ServerStreamCall,
+}
+
+impl BlockingServerStream<'_> {
+    /// Returns the underlying [`ServerStreamCall`], e.g. to access headers
+    /// and trailers.
+    pub fn call(&self) -> &ServerStreamCall {
+        &self.call
+    }
+}
+
+impl Iterator for BlockingServerStream<'_> {
+    type Item = Result<Bytes, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.runtime.block_on(self.call.next())
+    }
+}
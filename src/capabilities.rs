@@ -0,0 +1,180 @@
+//! Runtime reporting of compiled-in capabilities.
+//!
+//! Which compressions and transports are available depends on enabled crate
+//! features; [`capabilities`] lets applications check their configuration
+//! against the build at startup:
+//!
+//! ```no_run
+//! # use connect_rpc::capabilities::{Capability, MissingCapability};
+//! # fn example() -> Result<(), MissingCapability> {
+//! connect_rpc::capabilities().require(Capability::Compression, "gzip")?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::compression;
+
+/// A kind of capability.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Capability {
+    Codec,
+    Compression,
+    Protocol,
+    Transport,
+}
+
+impl Capability {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Capability::Codec => "codec",
+            Capability::Compression => "compression",
+            Capability::Protocol => "protocol",
+            Capability::Transport => "transport",
+        }
+    }
+}
+
+/// A report of the capabilities compiled into this build.
+#[derive(Clone, Debug, serde::Serialize)]
+#[non_exhaustive]
+pub struct Capabilities {
+    /// Message codecs with built-in support, e.g. `json`.
+    pub codecs: &'static [&'static str],
+    /// Built-in compressions, in order of preference (see
+    /// [`compression::supported`]).
+    pub compressions: &'static [&'static str],
+    /// Supported protocols and call types.
+    pub protocols: &'static [&'static str],
+    /// Built-in [`Transport`](crate::transport::Transport)s.
+    pub transports: &'static [&'static str],
+    /// Enabled crate features.
+    pub features: &'static [&'static str],
+}
+
+impl Capabilities {
+    /// Returns the names supported for a kind of capability.
+    pub fn names(&self, kind: Capability) -> &'static [&'static str] {
+        match kind {
+            Capability::Codec => self.codecs,
+            Capability::Compression => self.compressions,
+            Capability::Protocol => self.protocols,
+            Capability::Transport => self.transports,
+        }
+    }
+
+    pub fn supports(&self, kind: Capability, name: &str) -> bool {
+        self.names(kind).contains(&name)
+    }
+
+    /// Returns an error naming the crate feature to enable if a capability
+    /// isn't supported by this build.
+    pub fn require(&self, kind: Capability, name: &str) -> Result<(), MissingCapability> {
+        if self.supports(kind, name) {
+            return Ok(());
+        }
+        Err(MissingCapability {
+            kind,
+            name: name.into(),
+            feature: required_feature(kind, name),
+        })
+    }
+}
+
+/// The error returned by [`Capabilities::require`].
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "{} {name:?} is not supported by this build{}",
+    .kind.as_str(),
+    .feature.map(|feature| format!("; enable the `{feature}` feature of connect-rpc")).unwrap_or_default()
+)]
+pub struct MissingCapability {
+    pub kind: Capability,
+    pub name: String,
+    /// The crate feature that provides the capability, if known.
+    pub feature: Option<&'static str>,
+}
+
+fn required_feature(kind: Capability, name: &str) -> Option<&'static str> {
+    match (kind, name) {
+        (Capability::Compression, "gzip") => Some("gzip"),
+        (Capability::Transport, "reqwest") => Some("reqwest"),
+        (Capability::Transport, "reqwest-http3") => Some("http3"),
+        (Capability::Transport, "blocking") => Some("blocking"),
+        (Capability::Transport, "hyper" | "unix") => Some("hyper"),
+        (Capability::Transport, "wasi") => Some("wasi"),
+        (Capability::Transport, "web") => Some("web"),
+        _ => None,
+    }
+}
+
+const FEATURES: &[&str] = &[
+    #[cfg(feature = "reqwest")]
+    "reqwest",
+    #[cfg(feature = "opentelemetry")]
+    "opentelemetry",
+    #[cfg(feature = "tracing")]
+    "tracing",
+    #[cfg(feature = "wasi")]
+    "wasi",
+    #[cfg(feature = "web")]
+    "web",
+    #[cfg(feature = "blocking")]
+    "blocking",
+    #[cfg(feature = "ffi")]
+    "ffi",
+    #[cfg(feature = "gzip")]
+    "gzip",
+    #[cfg(feature = "json-path-errors")]
+    "json-path-errors",
+    #[cfg(feature = "hyper")]
+    "hyper",
+    #[cfg(feature = "metrics")]
+    "metrics",
+    #[cfg(feature = "testing")]
+    "testing",
+    #[cfg(feature = "tonic")]
+    "tonic",
+    #[cfg(feature = "prost")]
+    "prost",
+    #[cfg(feature = "transcoding")]
+    "transcoding",
+    #[cfg(feature = "request-id")]
+    "request-id",
+    #[cfg(feature = "wire-log")]
+    "wire-log",
+    #[cfg(feature = "http3")]
+    "http3",
+    #[cfg(feature = "dynamic-config")]
+    "dynamic-config",
+];
+
+/// Returns a report of the capabilities compiled into this build.
+pub fn capabilities() -> Capabilities {
+    Capabilities {
+        codecs: &["json", "proto"],
+        compressions: compression::supported(),
+        protocols: &[
+            "connect-unary",
+            "connect-unary-get",
+            "connect-server-stream",
+        ],
+        transports: &[
+            "memory",
+            #[cfg(feature = "reqwest")]
+            "reqwest",
+            #[cfg(feature = "http3")]
+            "reqwest-http3",
+            #[cfg(feature = "blocking")]
+            "blocking",
+            #[cfg(feature = "hyper")]
+            "hyper",
+            #[cfg(all(feature = "hyper", unix))]
+            "unix",
+            #[cfg(feature = "wasi")]
+            "wasi",
+            #[cfg(feature = "web")]
+            "web",
+        ],
+        features: FEATURES,
+    }
+}
@@ -0,0 +1,772 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+use futures_util::future::BoxFuture;
+use http::{uri::Authority, HeaderMap};
+use http_body_util::BodyExt;
+
+use crate::{
+    instrument::CallInstrument,
+    interceptor::{vcr::VcrInterceptor, Interceptor, Next},
+    metrics::{MetricsSink, RpcInfo},
+    orca::{LoadReportListener, OrcaLoadReport},
+    request::{
+        builder::RequestBuilder, ConnectRequest, IdempotencyLevel, StreamingRequest,
+        UnaryGetOrPostRequest, UnaryGetRequest, UnaryRequest,
+    },
+    response::{
+        error::{ConnectResult, IntoConnectResult},
+        ConnectResponse, StreamingResponse, UnaryResponse, ValidateOpts,
+    },
+    stream::{sender, ConnectFrame},
+    transport::{buffer_response, full_body, RequestBody, Transport},
+    Error,
+};
+
+pub mod balance;
+pub mod builder;
+pub mod call;
+pub mod connection;
+#[cfg(feature = "dynamic-config")]
+pub mod dynamic;
+pub mod hedging;
+pub mod progress;
+pub mod resilient;
+pub mod resolver;
+
+use builder::ClientBuilder;
+use call::{BidiStreamCall, CallState, ServerStreamCall};
+use hedging::HedgingPolicy;
+use progress::ProgressListener;
+
+/// A Connect RPC client.
+///
+/// Executes requests through a chain of [`Interceptor`]s.
+#[derive(Clone)]
+pub struct ConnectClient {
+    transport: Arc<dyn Transport>,
+    interceptors: Arc<[Arc<dyn Interceptor>]>,
+    vcr: Option<Arc<VcrInterceptor>>,
+    metrics_sink: Option<Arc<dyn MetricsSink>>,
+    signer: Option<Arc<dyn RequestSigner>>,
+    load_report_listener: Option<Arc<dyn LoadReportListener>>,
+    progress_listener: Option<Arc<dyn ProgressListener>>,
+    hedging: Option<HedgingPolicy>,
+    http_get: bool,
+    profiles: Arc<HashMap<String, RequestBuilder>>,
+}
+
+impl ConnectClient {
+    /// Returns a new [`ClientBuilder`].
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::default()
+    }
+
+    /// Returns a [`RequestBuilder`] preconfigured with the named profile
+    /// (see [`ClientBuilder::profile`]).
+    ///
+    /// ```no_run
+    /// # use bytes::Bytes;
+    /// # use connect_rpc::client::ConnectClient;
+    /// # const SERVICE: &str = "acme.foo.v1.FooService";
+    /// # fn example(client: ConnectClient, body: Bytes) -> Result<(), connect_rpc::Error> {
+    /// let req = client.profile("internal")?.protobuf_rpc(SERVICE, "Get")?.unary(body)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn profile(&self, name: &str) -> Result<RequestBuilder, Error> {
+        self.profiles
+            .get(name)
+            .cloned()
+            .ok_or_else(|| Error::invalid_request(format!("unknown profile {name:?}")))
+    }
+
+    /// Executes a Connect RPC [`UnaryRequest`].
+    ///
+    /// With [`ClientBuilder::http_get`], side-effect-free requests are sent
+    /// as GET requests.
+    pub async fn execute_unary(
+        &self,
+        req: UnaryRequest<impl Into<Bytes>>,
+    ) -> Result<UnaryResponse<Bytes>, Error> {
+        let req: UnaryRequest<Bytes> = http::Request::from(req).map(Into::into).into();
+        if self.http_get && req.idempotency_level() == IdempotencyLevel::NoSideEffects {
+            let get = req.to_unary_get()?;
+            if get.url_len() <= req.get_url_max_bytes() {
+                return self.execute_unary_get(get).await;
+            }
+        }
+        let call = UnaryCall::new(&req);
+        self.execute(call, http::Request::from(req)).await
+    }
+
+    /// Executes a Connect RPC [`UnaryRequest`] like [`Self::execute_unary`],
+    /// returning any error as a
+    /// [`ConnectError`](crate::response::error::ConnectError).
+    pub async fn execute_unary_connect(
+        &self,
+        req: UnaryRequest<impl Into<Bytes>>,
+    ) -> ConnectResult<UnaryResponse<Bytes>> {
+        self.execute_unary(req).await.into_connect()
+    }
+
+    /// Executes unary requests concurrently, with at most `concurrency` in
+    /// flight at once, returning their results in request order.
+    ///
+    /// Each call is spawned onto a [`JoinSet`](tokio::task::JoinSet), so this
+    /// must be called from within a Tokio runtime. A call that panics
+    /// resumes the panic here; one canceled by runtime shutdown fails with
+    /// a `canceled` error.
+    #[cfg(feature = "tokio")]
+    pub async fn call_all<T: Into<Bytes> + Send + 'static>(
+        &self,
+        reqs: impl IntoIterator<Item = UnaryRequest<T>>,
+        concurrency: usize,
+    ) -> Vec<Result<UnaryResponse<Bytes>, Error>> {
+        use crate::response::error::{ConnectCode, ConnectError};
+
+        let mut reqs = reqs.into_iter().enumerate();
+        let mut results = Vec::new();
+        let mut calls = tokio::task::JoinSet::new();
+        loop {
+            while calls.len() < concurrency.max(1) {
+                let Some((idx, req)) = reqs.next() else {
+                    break;
+                };
+                results.push(None);
+                let client = self.clone();
+                calls.spawn(async move { (idx, client.execute_unary(req).await) });
+            }
+            match calls.join_next().await {
+                Some(Ok((idx, result))) => results[idx] = Some(result),
+                Some(Err(err)) => {
+                    if let Ok(panic) = err.try_into_panic() {
+                        std::panic::resume_unwind(panic);
+                    }
+                }
+                None => break,
+            }
+        }
+        results
+            .into_iter()
+            .map(|result| {
+                result.unwrap_or_else(|| {
+                    Err(Error::ConnectError(ConnectError::new(
+                        ConnectCode::Canceled,
+                        "call canceled",
+                    )))
+                })
+            })
+            .collect()
+    }
+
+    /// Executes a server-streaming Connect RPC.
+    ///
+    /// The request body must be a single enveloped message (see
+    /// [`ConnectFrame::encode`]). Returns once response headers are received;
+    /// see [`ServerStreamCall`] for reading messages and cancellation.
+    ///
+    /// [`Interceptor`]s are not applied to streaming calls, as they operate
+    /// on buffered bodies; the [`RequestSigner`] and a
+    /// [VCR](ClientBuilder::vcr) are.
+    pub async fn execute_server_stream(
+        &self,
+        req: StreamingRequest<impl Into<Bytes>>,
+    ) -> Result<ServerStreamCall, Error> {
+        let call = StreamCall::new(&req, self);
+        let mut req: http::Request<Bytes> = http::Request::from(req).map(Into::into);
+        if let Some(sink) = &self.metrics_sink {
+            sink.on_request_start(&call.state.rpc, req.body().len());
+        }
+        let req = match &self.signer {
+            Some(signer) => signer.sign(&mut req).map(|()| req),
+            None => Ok(req),
+        };
+        match req {
+            Ok(req) => {
+                let body = req.body().clone();
+                self.start_stream(call, req.map(full_body), Some(body))
+                    .await
+            }
+            Err(err) => {
+                let result = Err(err);
+                call.state.finish(&result);
+                result
+            }
+        }
+    }
+
+    /// Starts a client-streaming or bidi-streaming Connect RPC built with
+    /// `builder`, returning a [`BidiStreamCall`] that owns the sending half
+    /// of the request body.
+    ///
+    /// Frames sent with [`BidiStreamCall::send`] are buffered (up to
+    /// `capacity` at a time) until the transport sends them. The request
+    /// ends with [`BidiStreamCall::close_send`].
+    ///
+    /// ```no_run
+    /// # use bytes::Bytes;
+    /// # use connect_rpc::{client::ConnectClient, request::builder::RequestBuilder, stream::ConnectFrame};
+    /// # use futures_util::StreamExt;
+    /// # async fn example(client: ConnectClient) -> Result<(), connect_rpc::Error> {
+    /// let builder = RequestBuilder::default()
+    ///     .uri("https://example.com/example.v1.EchoService/Echo")?
+    ///     .message_codec("json")?;
+    /// let mut call = client.execute_bidi_stream(builder, 8)?;
+    /// call.send(ConnectFrame {
+    ///     compressed: false,
+    ///     end: false,
+    ///     data: Bytes::from_static(b"{}"),
+    /// })
+    /// .await?;
+    /// call.close_send();
+    /// while let Some(message) = call.response().await?.next().await {
+    ///     println!("{:?}", message?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`Interceptor`]s and the [`RequestSigner`] are not applied, as they
+    /// operate on buffered bodies; a [VCR](ClientBuilder::vcr) is.
+    pub fn execute_bidi_stream(
+        &self,
+        builder: RequestBuilder,
+        capacity: usize,
+    ) -> Result<BidiStreamCall, Error> {
+        let (sender, body) = sender::channel(capacity);
+        let req = builder.streaming(body)?;
+        let call = StreamCall::new(&req, self);
+        if let Some(sink) = &self.metrics_sink {
+            sink.on_request_start(&call.state.rpc, 0);
+        }
+        let req = http::Request::from(req).map(BodyExt::boxed);
+        let client = self.clone();
+        let response = Box::pin(async move { client.start_stream(call, req, None).await });
+        Ok(BidiStreamCall::new(sender, response))
+    }
+
+    /// Sends a streaming request, returning a [`ServerStreamCall`] once
+    /// response headers are received. `body` is the request body, if it's
+    /// buffered.
+    async fn start_stream(
+        &self,
+        call: StreamCall,
+        req: http::Request<RequestBody>,
+        body: Option<Bytes>,
+    ) -> Result<ServerStreamCall, Error> {
+        let StreamCall {
+            validate_opts,
+            authority,
+            state,
+        } = call;
+        let extensions = req.extensions().clone();
+        let result = async {
+            let resp = match &self.vcr {
+                Some(vcr) => vcr.round_trip_stream(req, body, &*self.transport).await?,
+                None => self.transport.round_trip(req).await?,
+            };
+            if !resp.status().is_success() {
+                let http_resp = buffer_response(resp).await?;
+                self.report_load(authority.as_ref(), http_resp.headers());
+                return Err(Error::ConnectError(http_resp.into()));
+            }
+            self.report_load(authority.as_ref(), resp.headers());
+            let (mut parts, body) = resp.into_parts();
+            parts.extensions.extend(extensions);
+            let response = StreamingResponse::from(http::Response::from_parts(parts, ()));
+            response.validate(&validate_opts)?;
+            Ok((response, body))
+        }
+        .await;
+        match result {
+            Ok((response, body)) => {
+                let frames = ConnectFrame::body_stream(body);
+                Ok(ServerStreamCall::new(response, frames, state))
+            }
+            Err(err) => {
+                let result = Err(err);
+                state.finish(&result);
+                result
+            }
+        }
+    }
+
+    /// Executes a Connect RPC [`UnaryGetRequest`].
+    pub async fn execute_unary_get(
+        &self,
+        req: UnaryGetRequest,
+    ) -> Result<UnaryResponse<Bytes>, Error> {
+        let call = UnaryCall::new(&req);
+        self.execute(call, http::Request::from(req).map(|()| Bytes::new()))
+            .await
+    }
+
+    /// Executes a unary RPC built with
+    /// [`RequestBuilder::unary_get_or_post`](crate::request::builder::RequestBuilder::unary_get_or_post).
+    pub async fn execute_unary_get_or_post(
+        &self,
+        req: UnaryGetOrPostRequest<impl Into<Bytes>>,
+    ) -> Result<UnaryResponse<Bytes>, Error> {
+        match req {
+            UnaryGetOrPostRequest::Get(req) => self.execute_unary_get(req).await,
+            UnaryGetOrPostRequest::Post(req) => self.execute_unary(req).await,
+        }
+    }
+
+    async fn execute(
+        &self,
+        call: UnaryCall,
+        req: http::Request<Bytes>,
+    ) -> Result<UnaryResponse<Bytes>, Error> {
+        let UnaryCall {
+            validate_opts,
+            instrument,
+            rpc,
+            authority,
+        } = call;
+        let start = Instant::now();
+        if let Some(sink) = &self.metrics_sink {
+            sink.on_request_start(&rpc, req.body().len());
+        }
+        let result = instrument
+            .run(async {
+                let http_resp = match &self.hedging {
+                    Some(hedging) if hedging.applies_to(&req) => {
+                        hedging.run(req, || self.next()).await?
+                    }
+                    _ => self.next().run(req).await?,
+                };
+                self.report_load(authority.as_ref(), http_resp.headers());
+                UnaryResponse::from(http_resp).result(&validate_opts)
+            })
+            .await;
+        if let Some(sink) = &self.metrics_sink {
+            let latency = start.elapsed();
+            match &result {
+                Ok(resp) => sink.on_response(&rpc, latency, resp.body().len()),
+                Err(err) => {
+                    if let Error::ConflictingHeaders(violation) = err {
+                        sink.on_protocol_violation(&rpc, violation);
+                    }
+                    sink.on_error(&rpc, err.connect_code(), latency);
+                }
+            }
+        }
+        result
+    }
+
+    fn report_load(&self, authority: Option<&Authority>, headers: &HeaderMap) {
+        let (Some(listener), Some(authority)) = (&self.load_report_listener, authority) else {
+            return;
+        };
+        match OrcaLoadReport::from_metadata(headers) {
+            Ok(Some(report)) => listener.on_load_report(authority, &report),
+            Ok(None) => (),
+            Err(err) => tracing::debug!(?err, "Ignoring invalid load report"),
+        }
+    }
+
+    fn next(&self) -> Next<'_> {
+        Next::new(&self.interceptors, self.signer.as_deref(), &*self.transport)
+    }
+}
+
+impl std::fmt::Debug for ConnectClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConnectClient")
+            .field("interceptors", &self.interceptors.len())
+            .field("vcr", &self.vcr.is_some())
+            .field("metrics_sink", &self.metrics_sink.is_some())
+            .field("signer", &self.signer.is_some())
+            .field("load_report_listener", &self.load_report_listener.is_some())
+            .field("progress_listener", &self.progress_listener.is_some())
+            .field("hedging", &self.hedging)
+            .field("http_get", &self.http_get)
+            .field("profiles", &self.profiles.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// Signs finalized requests before they are sent.
+///
+/// The signer is called after all [`Interceptor`]s, with the final method,
+/// URI, headers, and body. It may compute a signature (e.g. HMAC or
+/// SigV4-style) and append signature headers to the request.
+pub trait RequestSigner: Send + Sync {
+    fn sign(&self, req: &mut http::Request<Bytes>) -> Result<(), Error>;
+}
+
+/// Waits for a duration, e.g. between retries.
+///
+/// The client is runtime-agnostic, so this is supplied by the caller;
+/// functions like `tokio::time::sleep` implement it.
+pub trait Sleep: Send + Sync {
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+}
+
+impl<F, Fut> Sleep for F
+where
+    F: Fn(Duration) -> Fut + Send + Sync,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        Box::pin(self(duration))
+    }
+}
+
+/// Returns true if a failed call may succeed if retried: an `unavailable`
+/// error or a transport failure (including a response body that breaks off).
+pub(crate) fn is_transient(err: &Error) -> bool {
+    match err {
+        Error::ConnectError(err) => err.is_retryable(),
+        Error::BodyError(_) | Error::TruncatedFrame { .. } => true,
+        #[cfg(feature = "reqwest")]
+        Error::ReqwestError(_) => true,
+        #[cfg(feature = "hyper")]
+        Error::HyperError(_) => true,
+        #[cfg(feature = "wasi")]
+        Error::WasiHttpError(_) => true,
+        #[cfg(feature = "web")]
+        Error::FetchError(_) => true,
+        _ => false,
+    }
+}
+
+/// Per-call state captured from a streaming request before it is sent.
+struct StreamCall {
+    validate_opts: ValidateOpts,
+    authority: Option<Authority>,
+    state: CallState,
+}
+
+impl StreamCall {
+    fn new(req: &impl ConnectRequest, client: &ConnectClient) -> Self {
+        Self {
+            validate_opts: ValidateOpts::from_request(req),
+            authority: req.authority().cloned(),
+            state: CallState {
+                rpc: RpcInfo::from_request(req),
+                instrument: CallInstrument::new(req),
+                metrics_sink: client.metrics_sink.clone(),
+                progress_listener: client.progress_listener.clone(),
+                start: Instant::now(),
+                response_bytes: 0,
+                messages: 0,
+            },
+        }
+    }
+}
+
+/// Per-call state captured from a request before it is sent.
+struct UnaryCall {
+    validate_opts: ValidateOpts,
+    instrument: CallInstrument,
+    rpc: RpcInfo,
+    authority: Option<Authority>,
+}
+
+impl UnaryCall {
+    fn new(req: &impl ConnectRequest) -> Self {
+        Self {
+            validate_opts: ValidateOpts::from_request(req),
+            instrument: CallInstrument::new(req),
+            rpc: RpcInfo::from_request(req),
+            authority: req.authority().cloned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::{stream, StreamExt};
+    use http::Method;
+
+    use crate::{
+        request::IdempotencyLevel,
+        response::error::ConnectCode,
+        stream::{EndStreamResponse, FramedBody},
+        transport::MemoryTransport,
+    };
+
+    use super::*;
+
+    fn echo_method_client() -> ConnectClient {
+        let transport = MemoryTransport::new(|req: http::Request<Bytes>| async move {
+            let mut resp = http::Response::new(Bytes::from(req.method().to_string()));
+            resp.headers_mut()
+                .insert(http::header::CONTENT_TYPE, "application/proto".try_into()?);
+            Ok(resp)
+        });
+        ConnectClient::builder()
+            .transport(transport)
+            .http_get(true)
+            .build()
+            .unwrap()
+    }
+
+    fn request(get_url_max_bytes: Option<usize>) -> UnaryRequest<Bytes> {
+        let mut builder = RequestBuilder::default()
+            .uri("http://example.com/example.v1.Service/Get")
+            .unwrap()
+            .message_codec("proto")
+            .unwrap()
+            .idempotency_level(IdempotencyLevel::NoSideEffects);
+        if let Some(max_bytes) = get_url_max_bytes {
+            builder = builder.get_url_max_bytes(max_bytes);
+        }
+        builder.unary(Bytes::from_static(b"message")).unwrap()
+    }
+
+    #[tokio::test]
+    async fn http_get_respects_url_limit() {
+        let client = echo_method_client();
+        let resp = client.execute_unary(request(None)).await.unwrap();
+        assert_eq!(resp.body().as_ref(), Method::GET.as_str().as_bytes());
+        let resp = client.execute_unary(request(Some(10))).await.unwrap();
+        assert_eq!(resp.body().as_ref(), Method::POST.as_str().as_bytes());
+    }
+
+    fn frame(data: Bytes) -> ConnectFrame {
+        ConnectFrame {
+            compressed: false,
+            end: false,
+            data,
+        }
+    }
+
+    fn streaming_response(
+        frames: impl futures_util::Stream<Item = Result<ConnectFrame, Error>> + Send + Sync + 'static,
+    ) -> Result<http::Response<crate::transport::ResponseBody>, Error> {
+        let end = EndStreamResponse::default().to_frame()?;
+        let frames = frames.chain(stream::iter([Ok(end)]));
+        let body = FramedBody::new(ConnectFrame::encode_stream(frames));
+        let mut resp = http::Response::new(BodyExt::boxed(body));
+        resp.headers_mut().insert(
+            http::header::CONTENT_TYPE,
+            "application/connect+proto".try_into()?,
+        );
+        Ok(resp)
+    }
+
+    fn streaming_client(transport: MemoryTransport) -> (ConnectClient, RequestBuilder) {
+        let builder = RequestBuilder::default()
+            .uri("http://example.com/example.v1.Service/Stream")
+            .unwrap()
+            .message_codec("proto")
+            .unwrap();
+        let client = ConnectClient::builder()
+            .transport(transport)
+            .build()
+            .unwrap();
+        (client, builder)
+    }
+
+    #[tokio::test]
+    async fn bidi_stream_echoes_while_sending() {
+        let transport = MemoryTransport::default()
+            .streaming_route("/example.v1.Service/Stream", |req| async {
+                streaming_response(ConnectFrame::body_stream(req.into_body()))
+            });
+        let (client, builder) = streaming_client(transport);
+        let mut call = client.execute_bidi_stream(builder, 1).unwrap();
+        for i in 0..3 {
+            let data = Bytes::from(format!("message {i}"));
+            call.send(frame(data.clone())).await.unwrap();
+            let echoed = call.response().await.unwrap().next().await.unwrap();
+            assert_eq!(echoed.unwrap(), data);
+        }
+        call.close_send();
+        let response = call.response().await.unwrap();
+        assert!(response.next().await.is_none());
+        assert!(response.is_finished());
+    }
+
+    #[tokio::test]
+    async fn client_stream_responds_after_close_send() {
+        let transport =
+            MemoryTransport::default().streaming_route("/example.v1.Service/Stream", |req| async {
+                let count = ConnectFrame::body_stream(req.into_body())
+                    .map(|frame| frame.map(|_| 1))
+                    .collect::<Vec<_>>()
+                    .await
+                    .into_iter()
+                    .sum::<Result<usize, _>>()?;
+                let reply = frame(Bytes::from(count.to_string()));
+                streaming_response(stream::iter([Ok(reply)]))
+            });
+        let (client, builder) = streaming_client(transport);
+        // A capacity smaller than the number of frames exercises dispatching
+        // the request while sending.
+        let mut call = client.execute_bidi_stream(builder, 2).unwrap();
+        for _ in 0..10 {
+            call.send(frame(Bytes::from_static(b"message")))
+                .await
+                .unwrap();
+        }
+        call.close_send();
+        let response = call.response().await.unwrap();
+        let reply = response.next().await.unwrap().unwrap();
+        assert_eq!(reply, "10");
+        assert!(response.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn bidi_stream_response_is_cancel_safe() {
+        let transport =
+            MemoryTransport::default().streaming_route("/example.v1.Service/Stream", |req| async {
+                // Responds only once the request stream ends.
+                let frames: Vec<_> = ConnectFrame::body_stream(req.into_body()).collect().await;
+                streaming_response(stream::iter(frames))
+            });
+        let (client, builder) = streaming_client(transport);
+        let mut call = client.execute_bidi_stream(builder, 1).unwrap();
+        call.send(frame(Bytes::from_static(b"message")))
+            .await
+            .unwrap();
+        let waiting = tokio::time::timeout(Duration::from_millis(10), call.response()).await;
+        assert!(waiting.is_err());
+
+        call.close_send();
+        let response = call.response().await.unwrap();
+        assert_eq!(response.next().await.unwrap().unwrap(), "message");
+        assert!(response.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn bidi_stream_reports_response_error() {
+        let transport = MemoryTransport::default();
+        let (client, builder) = streaming_client(transport);
+        let mut call = client.execute_bidi_stream(builder, 1).unwrap();
+        // The first frame fits in the buffer; the second waits for the
+        // (failed) response.
+        call.send(frame(Bytes::new())).await.unwrap();
+        let err = call.send(frame(Bytes::new())).await.unwrap_err();
+        assert!(matches!(err, Error::ConnectError(err) if err.code() == ConnectCode::Canceled));
+        let err = call.response().await.unwrap_err();
+        assert!(
+            matches!(err, Error::ConnectError(err) if err.code() == ConnectCode::Unimplemented)
+        );
+    }
+
+    #[tokio::test]
+    async fn server_stream_reads_ahead() {
+        use crate::stream::read_ahead::ReadAhead;
+
+        let transport =
+            MemoryTransport::default().streaming_route("/example.v1.Service/Stream", |_| async {
+                let messages = (0..5).map(|i| Ok(frame(Bytes::from(i.to_string()))));
+                streaming_response(stream::iter(messages))
+            });
+        let (client, builder) = streaming_client(transport);
+        let req = builder.streaming(Bytes::new()).unwrap();
+        let limits = ReadAhead {
+            max_frames: 2,
+            max_bytes: 1,
+        };
+        let mut call =
+            client
+                .execute_server_stream(req)
+                .await
+                .unwrap()
+                .read_ahead(limits, |reader| {
+                    tokio::spawn(reader);
+                });
+        let mut messages = vec![];
+        while let Some(message) = call.next().await {
+            messages.push(message.unwrap());
+        }
+        assert_eq!(messages, ["0", "1", "2", "3", "4"]);
+        assert!(call.trailers().is_some());
+    }
+
+    #[cfg(feature = "gzip")]
+    #[tokio::test]
+    async fn server_stream_decompresses_frames() {
+        use crate::compression::{Compression, Gzip};
+
+        let transport =
+            MemoryTransport::default().streaming_route("/example.v1.Service/Stream", |_| async {
+                let message = ConnectFrame {
+                    compressed: true,
+                    end: false,
+                    data: Gzip.compress(b"message")?,
+                };
+                let mut end = EndStreamResponse::default().to_frame()?;
+                end.data = Gzip.compress(&end.data)?;
+                end.compressed = true;
+                let frames = stream::iter([Ok(message), Ok(end)]);
+                let body = FramedBody::new(ConnectFrame::encode_stream(frames));
+                let mut resp = http::Response::new(BodyExt::boxed(body));
+                let headers = resp.headers_mut();
+                headers.insert(
+                    http::header::CONTENT_TYPE,
+                    "application/connect+proto".try_into()?,
+                );
+                headers.insert("connect-content-encoding", "gzip".try_into()?);
+                Ok(resp)
+            });
+        let (client, builder) = streaming_client(transport);
+        let req = builder
+            .accept_encoding(["gzip"])
+            .unwrap()
+            .streaming(Bytes::new())
+            .unwrap();
+        let mut call = client.execute_server_stream(req).await.unwrap();
+        assert_eq!(call.next().await.unwrap().unwrap(), "message");
+        assert!(call.next().await.is_none());
+        assert!(call.trailers().is_some());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn call_all_orders_results_and_caps_concurrency() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let transport = MemoryTransport::new({
+            let in_flight = in_flight.clone();
+            let max_in_flight = max_in_flight.clone();
+            move |req: http::Request<Bytes>| {
+                let in_flight = in_flight.clone();
+                let max_in_flight = max_in_flight.clone();
+                async move {
+                    let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_in_flight.fetch_max(current, Ordering::SeqCst);
+                    // Later requests finish first.
+                    let delay = 10 - req.body()[0];
+                    tokio::time::sleep(Duration::from_millis(delay.into())).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    let mut resp = http::Response::new(req.into_body());
+                    resp.headers_mut()
+                        .insert(http::header::CONTENT_TYPE, "application/proto".try_into()?);
+                    Ok(resp)
+                }
+            }
+        });
+        let client = ConnectClient::builder()
+            .transport(transport)
+            .build()
+            .unwrap();
+        let builder = RequestBuilder::default()
+            .uri("http://example.com/example.v1.Service/Get")
+            .unwrap()
+            .message_codec("proto")
+            .unwrap();
+        let reqs = (0..10u8).map(|i| builder.clone().unary(vec![i]).unwrap());
+
+        let results = client.call_all(reqs, 3).await;
+        let bodies: Vec<u8> = results
+            .into_iter()
+            .map(|result| result.unwrap().body()[0])
+            .collect();
+        assert_eq!(bodies, (0..10).collect::<Vec<_>>());
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 3);
+    }
+}
@@ -0,0 +1,771 @@
+//! Client-side load balancing across multiple authorities.
+//!
+//! ```no_run
+//! # use connect_rpc::client::{balance::{LoadBalancer, PickStrategy}, ConnectClient};
+//! # fn example() -> Result<(), connect_rpc::Error> {
+//! let balancer = LoadBalancer::new(["backend-1:8080", "backend-2:8080"])?
+//!     .strategy(PickStrategy::LeastPending);
+//! let client = ConnectClient::builder().load_balancer(balancer).build()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Authorities may instead be discovered with a [`Resolver`], e.g. the
+//! `hyper` feature's `DnsResolver`:
+//!
+//! ```no_run
+//! # #[cfg(feature = "hyper")]
+//! # fn example() {
+//! # use std::time::Duration;
+//! # use connect_rpc::client::{balance::LoadBalancer, resolver::DnsResolver};
+//! let resolver = DnsResolver::new("backend.internal", 8080);
+//! let balancer = LoadBalancer::resolve(resolver, Duration::from_secs(30));
+//! # }
+//! ```
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    sync::{
+        atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use futures_util::future::BoxFuture;
+use http::{
+    uri::{Authority, Scheme},
+    HeaderMap, Uri,
+};
+
+use crate::{
+    clock::{Clock, SystemClock},
+    codes,
+    orca::OrcaLoadReport,
+    response::error::{ConnectCode, ConnectError},
+    transport::{RequestBody, ResponseBody, Transport},
+    Error,
+};
+
+use super::{is_transient, resolver::Resolver};
+
+/// How a [`LoadBalancer`] picks an authority for each request.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PickStrategy {
+    /// Cycles through authorities in order, giving each as many
+    /// consecutive turns as its weight.
+    #[default]
+    RoundRobin,
+    /// Picks an authority at random, in proportion to its weight.
+    Random,
+    /// Picks the authority with the fewest in-flight requests relative to
+    /// its weight (ties go to the first).
+    LeastPending,
+    /// Picks an authority at random, in proportion to its weight times its
+    /// spare capacity
+    /// according to the latest [ORCA load report](crate::orca) in its
+    /// responses: its application utilization, or if unset its CPU
+    /// utilization. Authorities that haven't reported are treated as idle.
+    Utilization,
+}
+
+/// The smallest weight of a [`PickStrategy::Utilization`] authority, so
+/// fully utilized authorities still get (and report from) some requests.
+const MIN_UTILIZATION_WEIGHT: f64 = 0.05;
+
+/// When a [`LoadBalancer`] ejects failing authorities.
+#[derive(Clone, Copy, Debug)]
+pub struct Ejection {
+    /// The number of consecutive failures (`unavailable` responses or
+    /// transport errors) after which an authority is ejected.
+    pub consecutive_failures: u32,
+    /// How long an ejected authority is skipped before being tried again.
+    pub duration: Duration,
+}
+
+impl Default for Ejection {
+    fn default() -> Self {
+        Self {
+            consecutive_failures: 5,
+            duration: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Spreads requests across multiple authorities.
+///
+/// Each request's URI authority is replaced by the picked authority (the
+/// scheme and path are kept), so requests may be built with any authority,
+/// e.g. a logical service name. Authorities that keep failing are ejected for
+/// a while (see [`Ejection`]); if all are ejected, all are used.
+///
+/// Authorities have weight 1 unless a [`Resolver`] returns other weights (see
+/// [`Resolver::resolve_weighted`]); each [`PickStrategy`] applies them.
+///
+/// In-flight requests are counted until response headers are received.
+#[derive(Clone)]
+pub struct LoadBalancer {
+    endpoints: Arc<Mutex<Arc<[Arc<Endpoint>]>>>,
+    resolution: Option<Arc<Resolution>>,
+    strategy: PickStrategy,
+    ejection: Ejection,
+    clock: Arc<dyn Clock>,
+    next: Arc<AtomicUsize>,
+    rng: Arc<Rng>,
+}
+
+/// The state of periodic re-resolution.
+struct Resolution {
+    resolver: Box<dyn Resolver>,
+    interval: Duration,
+    refreshed_at: futures_util::lock::Mutex<Option<Instant>>,
+}
+
+impl LoadBalancer {
+    /// Returns a load balancer over the given authorities.
+    pub fn new<T: TryInto<Authority, Error: Into<Error>>>(
+        authorities: impl IntoIterator<Item = T>,
+    ) -> Result<Self, Error> {
+        let authorities = authorities
+            .into_iter()
+            .map(|authority| Ok((authority.try_into().map_err(Into::into)?, 1)))
+            .collect::<Result<Vec<_>, Error>>()?;
+        if authorities.is_empty() {
+            return Err(Error::invalid_request("no authorities to balance"));
+        }
+        let balancer = Self::empty(None);
+        balancer.set_authorities(authorities);
+        Ok(balancer)
+    }
+
+    /// Returns a load balancer over the authorities returned by `resolver`,
+    /// re-resolved when they are older than `refresh_interval`.
+    ///
+    /// Authorities are first resolved by the first request. If a refresh
+    /// fails (or returns no authorities), the previous authorities are kept
+    /// until the next refresh.
+    pub fn resolve(resolver: impl Resolver + 'static, refresh_interval: Duration) -> Self {
+        Self::empty(Some(Arc::new(Resolution {
+            resolver: Box::new(resolver),
+            interval: refresh_interval,
+            refreshed_at: Default::default(),
+        })))
+    }
+
+    fn empty(resolution: Option<Arc<Resolution>>) -> Self {
+        Self {
+            endpoints: Arc::new(Mutex::new(Arc::new([]))),
+            resolution,
+            strategy: PickStrategy::default(),
+            ejection: Ejection::default(),
+            clock: Arc::new(SystemClock),
+            next: Default::default(),
+            rng: Arc::new(Rng::from_entropy()),
+        }
+    }
+
+    /// Sets the [`PickStrategy`].
+    ///
+    /// Defaults to [`PickStrategy::RoundRobin`].
+    pub fn strategy(mut self, strategy: PickStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Sets the [`Ejection`] policy.
+    pub fn ejection(mut self, ejection: Ejection) -> Self {
+        self.ejection = ejection;
+        self
+    }
+
+    /// Sets the [`Clock`] used for ejection and re-resolution.
+    ///
+    /// Defaults to [`SystemClock`].
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Seeds the random number generator used by [`PickStrategy::Random`],
+    /// e.g. for deterministic picks in tests.
+    ///
+    /// Defaults to a random seed.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = Arc::new(Rng::new(seed));
+        self
+    }
+
+    /// Returns the current authorities.
+    pub fn authorities(&self) -> Vec<Authority> {
+        self.endpoints()
+            .iter()
+            .map(|endpoint| endpoint.authority.clone())
+            .collect()
+    }
+
+    /// Re-resolves authorities if they are stale.
+    ///
+    /// Only one refresh runs at a time; other requests use the current
+    /// authorities meanwhile, unless there are none yet.
+    async fn refresh(&self) -> Result<(), Error> {
+        let Some(resolution) = &self.resolution else {
+            return Ok(());
+        };
+        let has_endpoints = !self.endpoints().is_empty();
+        let mut refreshed_at = if has_endpoints {
+            match resolution.refreshed_at.try_lock() {
+                Some(refreshed_at) => refreshed_at,
+                None => return Ok(()),
+            }
+        } else {
+            resolution.refreshed_at.lock().await
+        };
+        let now = self.clock.now();
+        if refreshed_at.is_some_and(|at| now.saturating_duration_since(at) < resolution.interval) {
+            return Ok(());
+        }
+        let result = resolution
+            .resolver
+            .resolve_weighted()
+            .await
+            .map(|mut authorities| {
+                authorities.retain(|(_, weight)| *weight > 0);
+                authorities
+            });
+        *refreshed_at = Some(self.clock.now());
+        match result {
+            Ok(authorities) if !authorities.is_empty() => {
+                self.set_authorities(authorities);
+                Ok(())
+            }
+            Ok(_) if has_endpoints => {
+                tracing::debug!("Resolver returned no authorities; keeping previous");
+                Ok(())
+            }
+            Err(err) if has_endpoints => {
+                tracing::debug!(?err, "Resolver failed; keeping previous authorities");
+                Ok(())
+            }
+            Ok(_) => Err(Error::ConnectError(ConnectError::new(
+                ConnectCode::Unavailable,
+                "resolver returned no authorities",
+            ))),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Replaces the set of (non-zero weight) authorities, keeping the state
+    /// (in-flight requests, failures) of those that remain. The weights of
+    /// repeated authorities are summed.
+    fn set_authorities(&self, authorities: Vec<(Authority, u32)>) {
+        let mut endpoints = self.endpoints.lock().unwrap();
+        let mut updated: Vec<Arc<Endpoint>> = Vec::with_capacity(authorities.len());
+        for (authority, weight) in authorities {
+            if let Some(endpoint) = updated.iter().find(|e| e.authority == authority) {
+                let weight = endpoint
+                    .weight
+                    .load(Ordering::Relaxed)
+                    .saturating_add(weight);
+                endpoint.weight.store(weight, Ordering::Relaxed);
+                continue;
+            }
+            let endpoint = endpoints
+                .iter()
+                .find(|endpoint| endpoint.authority == authority)
+                .cloned()
+                .unwrap_or_else(|| Arc::new(Endpoint::new(authority)));
+            endpoint.weight.store(weight, Ordering::Relaxed);
+            updated.push(endpoint);
+        }
+        *endpoints = updated.into();
+    }
+
+    fn endpoints(&self) -> Arc<[Arc<Endpoint>]> {
+        self.endpoints.lock().unwrap().clone()
+    }
+
+    fn pick(&self) -> Option<Arc<Endpoint>> {
+        let endpoints = self.endpoints();
+        let now = self.clock.now();
+        let healthy: Vec<&Arc<Endpoint>> = endpoints
+            .iter()
+            .filter(|endpoint| !endpoint.is_ejected(now))
+            .collect();
+        let candidates = if healthy.is_empty() {
+            endpoints.iter().collect()
+        } else {
+            healthy
+        };
+        let picked = match self.strategy {
+            PickStrategy::RoundRobin => {
+                let total: u64 = candidates.iter().map(|endpoint| endpoint.weight()).sum();
+                let next = self.next.fetch_add(1, Ordering::Relaxed) as u64;
+                let mut turn = next % total.max(1);
+                candidates.iter().copied().find(|endpoint| {
+                    match turn.checked_sub(endpoint.weight()) {
+                        Some(rest) => {
+                            turn = rest;
+                            false
+                        }
+                        None => true,
+                    }
+                })
+            }
+            PickStrategy::Random => {
+                self.pick_weighted(&candidates, |endpoint| endpoint.weight() as f64)
+            }
+            PickStrategy::LeastPending => candidates.iter().copied().min_by(|a, b| {
+                let load = |endpoint: &Endpoint| {
+                    endpoint.pending.load(Ordering::Relaxed) as f64 / endpoint.weight() as f64
+                };
+                load(a).total_cmp(&load(b))
+            }),
+            PickStrategy::Utilization => self.pick_weighted(&candidates, |endpoint| {
+                endpoint.weight() as f64 * endpoint.utilization_weight()
+            }),
+        };
+        picked.cloned()
+    }
+
+    /// Picks a candidate at random, in proportion to `weight`.
+    fn pick_weighted<'a>(
+        &self,
+        candidates: &[&'a Arc<Endpoint>],
+        weight: impl Fn(&Endpoint) -> f64,
+    ) -> Option<&'a Arc<Endpoint>> {
+        let weights: Vec<f64> = candidates.iter().map(|endpoint| weight(endpoint)).collect();
+        // 53 random bits give a uniform f64 in [0, 1).
+        let random = (self.rng.next() >> 11) as f64 / (1u64 << 53) as f64;
+        let mut target = random * weights.iter().sum::<f64>();
+        let index = weights
+            .iter()
+            .position(|weight| {
+                target -= weight;
+                target < 0.0
+            })
+            .unwrap_or(candidates.len().saturating_sub(1));
+        candidates.get(index).copied()
+    }
+
+    /// Wraps a transport to send requests to the picked authorities.
+    pub(crate) fn wrap(self, inner: Arc<dyn Transport>) -> BalancedTransport {
+        BalancedTransport {
+            balancer: self,
+            inner,
+        }
+    }
+}
+
+impl std::fmt::Debug for LoadBalancer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LoadBalancer")
+            .field("authorities", &self.authorities())
+            .field("resolve", &self.resolution.is_some())
+            .field("strategy", &self.strategy)
+            .field("ejection", &self.ejection)
+            .finish()
+    }
+}
+
+/// A xorshift64* generator; fast, and random enough to spread picks.
+struct Rng(AtomicU64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // Zero is a fixed point of xorshift.
+        Self(AtomicU64::new(seed.max(1)))
+    }
+
+    fn from_entropy() -> Self {
+        Self::new(RandomState::new().build_hasher().finish())
+    }
+
+    fn next(&self) -> u64 {
+        let step = |mut x: u64| {
+            x ^= x >> 12;
+            x ^= x << 25;
+            x ^= x >> 27;
+            x
+        };
+        let prev = self
+            .0
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |x| Some(step(x)))
+            .unwrap();
+        step(prev).wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+}
+
+struct Endpoint {
+    authority: Authority,
+    pending: AtomicUsize,
+    failures: AtomicU32,
+    ejected_until: Mutex<Option<Instant>>,
+    /// From the latest load report.
+    utilization: Mutex<Option<f64>>,
+    /// The relative share of requests; at least 1.
+    weight: AtomicU32,
+}
+
+impl Endpoint {
+    fn new(authority: Authority) -> Self {
+        Self {
+            authority,
+            pending: Default::default(),
+            failures: Default::default(),
+            ejected_until: Default::default(),
+            utilization: Default::default(),
+            weight: AtomicU32::new(1),
+        }
+    }
+
+    fn weight(&self) -> u64 {
+        self.weight.load(Ordering::Relaxed).max(1).into()
+    }
+
+    fn record_load(&self, headers: &HeaderMap) {
+        match OrcaLoadReport::from_metadata(headers) {
+            Ok(Some(report)) => {
+                let utilization = if report.application_utilization > 0.0 {
+                    report.application_utilization
+                } else {
+                    report.cpu_utilization
+                };
+                *self.utilization.lock().unwrap() = Some(utilization);
+            }
+            Ok(None) => (),
+            Err(err) => {
+                tracing::debug!(?err, authority = %self.authority, "Invalid load report");
+            }
+        }
+    }
+
+    fn utilization_weight(&self) -> f64 {
+        let utilization = self.utilization.lock().unwrap().unwrap_or(0.0);
+        (1.0 - utilization).clamp(MIN_UTILIZATION_WEIGHT, 1.0)
+    }
+
+    fn is_ejected(&self, now: Instant) -> bool {
+        self.ejected_until
+            .lock()
+            .unwrap()
+            .is_some_and(|until| now < until)
+    }
+
+    fn record(&self, success: bool, ejection: &Ejection, now: Instant) {
+        if success {
+            self.failures.store(0, Ordering::Relaxed);
+            return;
+        }
+        let failures = self.failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if failures >= ejection.consecutive_failures {
+            tracing::debug!(authority = %self.authority, failures, "Ejecting authority");
+            self.failures.store(0, Ordering::Relaxed);
+            *self.ejected_until.lock().unwrap() = Some(now + ejection.duration);
+        }
+    }
+}
+
+/// Decrements an endpoint's in-flight count when dropped.
+struct PendingGuard(Arc<Endpoint>);
+
+impl PendingGuard {
+    fn new(endpoint: Arc<Endpoint>) -> Self {
+        endpoint.pending.fetch_add(1, Ordering::Relaxed);
+        Self(endpoint)
+    }
+}
+
+impl Drop for PendingGuard {
+    fn drop(&mut self) {
+        self.0.pending.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// A [`Transport`] that sends each request to an authority picked by a
+/// [`LoadBalancer`].
+pub(crate) struct BalancedTransport {
+    balancer: LoadBalancer,
+    inner: Arc<dyn Transport>,
+}
+
+impl Transport for BalancedTransport {
+    fn round_trip(
+        &self,
+        mut req: http::Request<RequestBody>,
+    ) -> BoxFuture<'_, Result<http::Response<ResponseBody>, Error>> {
+        Box::pin(async move {
+            self.balancer.refresh().await?;
+            let endpoint = self.balancer.pick().ok_or_else(|| {
+                Error::ConnectError(ConnectError::new(
+                    ConnectCode::Unavailable,
+                    "no authorities to balance",
+                ))
+            })?;
+            let mut parts = std::mem::take(req.uri_mut()).into_parts();
+            parts.scheme.get_or_insert(Scheme::HTTP);
+            parts.authority = Some(endpoint.authority.clone());
+            if parts.path_and_query.is_none() {
+                parts.path_and_query = Some("/".try_into()?);
+            }
+            *req.uri_mut() = Uri::from_parts(parts)?;
+
+            let guard = PendingGuard::new(endpoint);
+            let result = self.inner.round_trip(req).await;
+            if let Ok(resp) = &result {
+                guard.0.record_load(resp.headers());
+            }
+            let success = match &result {
+                Ok(resp) => codes::from_http_status(resp.status()) != ConnectCode::Unavailable,
+                Err(err) => !is_transient(err),
+            };
+            guard
+                .0
+                .record(success, &self.balancer.ejection, self.balancer.clock.now());
+            result
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use futures_util::poll;
+    use http::StatusCode;
+
+    use std::collections::HashMap;
+
+    use crate::{
+        clock::ManualClock,
+        metadata::Metadata,
+        transport::{buffer_response, full_body, MemoryTransport},
+    };
+
+    use super::*;
+
+    /// Responds with the request's authority; authorities starting with
+    /// "down" are `unavailable` and those starting with "stuck" never
+    /// respond.
+    fn authority_transport() -> Arc<dyn Transport> {
+        Arc::new(MemoryTransport::new(
+            |req: http::Request<Bytes>| async move {
+                let authority = req.uri().authority().unwrap().to_string();
+                if authority.starts_with("stuck") {
+                    std::future::pending::<()>().await;
+                }
+                let mut resp = http::Response::new(Bytes::from(authority.clone()));
+                if authority.starts_with("down") {
+                    *resp.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+                }
+                Ok(resp)
+            },
+        ))
+    }
+
+    fn request() -> http::Request<RequestBody> {
+        let mut req = http::Request::new(full_body(Bytes::new()));
+        *req.uri_mut() = "http://service/example.v1.Service/Method".parse().unwrap();
+        req
+    }
+
+    /// Sends a request, returning the authority it was sent to.
+    async fn send(transport: &BalancedTransport) -> Result<String, Error> {
+        let resp = transport.round_trip(request()).await?;
+        let resp = buffer_response(resp).await?;
+        Ok(String::from_utf8(resp.body().to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn round_robin_replaces_authority() {
+        let transport = LoadBalancer::new(["a:1", "b:2"])
+            .unwrap()
+            .wrap(authority_transport());
+        let mut picked = vec![];
+        for _ in 0..4 {
+            picked.push(send(&transport).await.unwrap());
+        }
+        assert_eq!(picked, ["a:1", "b:2", "a:1", "b:2"]);
+    }
+
+    #[tokio::test]
+    async fn random_picks_are_seeded() {
+        let picks = |seed| async move {
+            let transport = LoadBalancer::new(["a:1", "b:2", "c:3"])
+                .unwrap()
+                .strategy(PickStrategy::Random)
+                .seed(seed)
+                .wrap(authority_transport());
+            let mut picked = vec![];
+            for _ in 0..30 {
+                picked.push(send(&transport).await.unwrap());
+            }
+            picked
+        };
+        let picked = picks(1).await;
+        assert_eq!(picked, picks(1).await);
+        assert_ne!(picked, picks(2).await);
+        for authority in ["a:1", "b:2", "c:3"] {
+            assert!(picked.iter().any(|picked| picked == authority));
+        }
+    }
+
+    #[tokio::test]
+    async fn least_pending_avoids_busy_authority() {
+        let transport = LoadBalancer::new(["stuck:1", "idle:2"])
+            .unwrap()
+            .strategy(PickStrategy::LeastPending)
+            .wrap(authority_transport());
+        let mut stuck = transport.round_trip(request());
+        assert!(poll!(&mut stuck).is_pending());
+        for _ in 0..2 {
+            assert_eq!(send(&transport).await.unwrap(), "idle:2");
+        }
+        drop(stuck);
+        let picked = transport.balancer.pick().unwrap();
+        assert_eq!(picked.authority, "stuck:1");
+    }
+
+    #[tokio::test]
+    async fn utilization_avoids_loaded_authority() {
+        let report = |utilization: f64| {
+            // `cpu_utilization` is field 1, a double.
+            let mut report = vec![0x09];
+            report.extend_from_slice(&utilization.to_le_bytes());
+            report
+        };
+        let transport = Arc::new(MemoryTransport::new(move |req: http::Request<Bytes>| {
+            let authority = req.uri().authority().unwrap().to_string();
+            let utilization = if authority.starts_with("busy") {
+                0.95
+            } else {
+                0.1
+            };
+            let report = report(utilization);
+            async move {
+                let mut resp = http::Response::new(Bytes::from(authority));
+                resp.headers_mut()
+                    .append_binary(crate::orca::LOAD_REPORT_KEY, report)?;
+                Ok(resp)
+            }
+        }));
+        let transport = LoadBalancer::new(["busy:1", "idle:2"])
+            .unwrap()
+            .strategy(PickStrategy::Utilization)
+            .seed(1)
+            .wrap(transport);
+
+        let reported = |transport: &BalancedTransport| {
+            let endpoints = transport.balancer.endpoints();
+            endpoints
+                .iter()
+                .all(|endpoint| endpoint.utilization.lock().unwrap().is_some())
+        };
+        while !reported(&transport) {
+            send(&transport).await.unwrap();
+        }
+
+        let mut picked = HashMap::<String, usize>::new();
+        for _ in 0..200 {
+            *picked.entry(send(&transport).await.unwrap()).or_default() += 1;
+        }
+        let (busy, idle) = (picked["busy:1"], picked["idle:2"]);
+        assert!(busy > 0 && idle > 5 * busy, "picks: {picked:?}");
+    }
+
+    #[tokio::test]
+    async fn ejects_failing_authority() {
+        let clock = ManualClock::new();
+        let transport = LoadBalancer::new(["down:1", "up:2"])
+            .unwrap()
+            .ejection(Ejection {
+                consecutive_failures: 2,
+                duration: Duration::from_secs(10),
+            })
+            .clock(clock.clone())
+            .wrap(authority_transport());
+        let mut picked = vec![];
+        for _ in 0..6 {
+            picked.push(send(&transport).await.unwrap());
+        }
+        assert_eq!(picked, ["down:1", "up:2", "down:1", "up:2", "up:2", "up:2"]);
+
+        clock.advance(Duration::from_secs(10));
+        let mut picked = vec![];
+        for _ in 0..2 {
+            picked.push(send(&transport).await.unwrap());
+        }
+        assert!(picked.contains(&"down:1".to_string()));
+    }
+
+    #[tokio::test]
+    async fn uses_all_authorities_when_all_ejected() {
+        let transport = LoadBalancer::new(["down:1"])
+            .unwrap()
+            .ejection(Ejection {
+                consecutive_failures: 1,
+                duration: Duration::from_secs(10),
+            })
+            .wrap(authority_transport());
+        for _ in 0..2 {
+            assert_eq!(send(&transport).await.unwrap(), "down:1");
+        }
+    }
+
+    /// Returns its authorities, or fails if they are `None`.
+    #[derive(Clone, Default)]
+    struct TestResolver(Arc<Mutex<Option<Vec<&'static str>>>>);
+
+    impl TestResolver {
+        fn set(&self, authorities: Option<Vec<&'static str>>) {
+            *self.0.lock().unwrap() = authorities;
+        }
+    }
+
+    impl Resolver for TestResolver {
+        fn resolve(&self) -> BoxFuture<'_, Result<Vec<Authority>, Error>> {
+            let authorities = self.0.lock().unwrap().clone();
+            Box::pin(async move {
+                let authorities = authorities.ok_or_else(|| {
+                    Error::ConnectError(ConnectError::new(ConnectCode::Unavailable, "failed"))
+                })?;
+                Ok(authorities
+                    .into_iter()
+                    .map(Authority::from_static)
+                    .collect())
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn resolve_refreshes_stale_authorities() {
+        let clock = ManualClock::new();
+        let resolver = TestResolver::default();
+        let transport = LoadBalancer::resolve(resolver.clone(), Duration::from_secs(10))
+            .clock(clock.clone())
+            .wrap(authority_transport());
+
+        // Requests fail until there are authorities.
+        for authorities in [None, Some(vec![])] {
+            resolver.set(authorities);
+            let err = send(&transport).await.unwrap_err();
+            assert!(
+                matches!(err, Error::ConnectError(err) if err.code() == ConnectCode::Unavailable)
+            );
+            clock.advance(Duration::from_secs(10));
+        }
+
+        resolver.set(Some(vec!["a:1"]));
+        assert_eq!(send(&transport).await.unwrap(), "a:1");
+        resolver.set(Some(vec!["b:2"]));
+        assert_eq!(send(&transport).await.unwrap(), "a:1");
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(send(&transport).await.unwrap(), "b:2");
+
+        // Failed or empty refreshes keep the previous authorities.
+        for authorities in [None, Some(vec![])] {
+            resolver.set(authorities);
+            clock.advance(Duration::from_secs(10));
+            assert_eq!(send(&transport).await.unwrap(), "b:2");
+        }
+    }
+}
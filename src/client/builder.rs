@@ -0,0 +1,254 @@
+use std::{collections::HashMap, sync::Arc};
+
+#[cfg(all(feature = "hyper", unix))]
+use http::uri::Authority;
+
+use crate::{
+    interceptor::{vcr::VcrInterceptor, Interceptor},
+    metrics::MetricsSink,
+    orca::LoadReportListener,
+    request::builder::RequestBuilder,
+    transport::Transport,
+    Error,
+};
+
+use super::{
+    balance::LoadBalancer,
+    connection::ConnectionOptions,
+    hedging::HedgingPolicy,
+    progress::{ProgressListener, ProgressTransport},
+    ConnectClient, RequestSigner,
+};
+
+#[derive(Default)]
+pub struct ClientBuilder {
+    transport: Option<Arc<dyn Transport>>,
+    connection_options: Option<ConnectionOptions>,
+    load_balancer: Option<LoadBalancer>,
+    interceptors: Vec<Arc<dyn Interceptor>>,
+    vcr: Option<Arc<VcrInterceptor>>,
+    metrics_sink: Option<Arc<dyn MetricsSink>>,
+    signer: Option<Arc<dyn RequestSigner>>,
+    load_report_listener: Option<Arc<dyn LoadReportListener>>,
+    progress_listener: Option<Arc<dyn ProgressListener>>,
+    hedging: Option<HedgingPolicy>,
+    http_get: bool,
+    profiles: HashMap<String, RequestBuilder>,
+    #[cfg(feature = "wire-log")]
+    wire_log: bool,
+}
+
+impl ClientBuilder {
+    /// Sets the [`Transport`] used to send requests.
+    ///
+    /// With the `reqwest` feature, defaults to [`reqwest::Client::new`].
+    pub fn transport(mut self, transport: impl Transport + 'static) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Sets [`ConnectionOptions`] (pool, keepalive, and flow control
+    /// settings) for the default transport.
+    ///
+    /// These can't be combined with a transport set with [`Self::transport`];
+    /// use e.g. [`ConnectionOptions::apply_reqwest`] when building it instead.
+    pub fn connection_options(mut self, options: ConnectionOptions) -> Self {
+        self.connection_options = Some(options);
+        self
+    }
+
+    /// Sets the underlying [`reqwest::Client`] as the [`Transport`].
+    #[cfg(feature = "reqwest")]
+    pub fn reqwest_client(self, client: reqwest::Client) -> Self {
+        self.transport(client)
+    }
+
+    /// Sends all requests over the Unix domain socket at `path`, using a
+    /// [`UnixSocketTransport`](crate::hyper::UnixSocketTransport).
+    ///
+    /// Request URIs are rewritten to use `authority` as a pseudo-authority
+    /// (sent as the `Host` header). Calls must be made within a Tokio runtime.
+    #[cfg(all(feature = "hyper", unix))]
+    pub fn unix_socket(self, path: impl AsRef<std::path::Path>, authority: Authority) -> Self {
+        self.transport(crate::hyper::UnixSocketTransport::new(path, authority))
+    }
+
+    /// Spreads requests across multiple authorities with a [`LoadBalancer`].
+    pub fn load_balancer(mut self, balancer: LoadBalancer) -> Self {
+        self.load_balancer = Some(balancer);
+        self
+    }
+
+    /// Appends an [`Interceptor`] to the client's interceptor chain.
+    pub fn interceptor(mut self, interceptor: impl Interceptor + 'static) -> Self {
+        self.interceptors.push(Arc::new(interceptor));
+        self
+    }
+
+    /// Records or replays calls, including streaming calls, with a
+    /// [`VcrInterceptor`].
+    ///
+    /// For unary calls it runs after all other interceptors, so it records
+    /// requests as they're sent.
+    pub fn vcr(mut self, vcr: VcrInterceptor) -> Self {
+        self.vcr = Some(Arc::new(vcr));
+        self
+    }
+
+    /// Sets a [`MetricsSink`] to report per-RPC metrics to.
+    pub fn metrics_sink(mut self, sink: impl MetricsSink + 'static) -> Self {
+        self.metrics_sink = Some(Arc::new(sink));
+        self
+    }
+
+    /// Sets a [`RequestSigner`] to sign each request before it is sent.
+    pub fn signer(mut self, signer: impl RequestSigner + 'static) -> Self {
+        self.signer = Some(Arc::new(signer));
+        self
+    }
+
+    /// Sets a [`LoadReportListener`] to receive ORCA load reports from
+    /// response headers.
+    pub fn load_report_listener(mut self, listener: impl LoadReportListener + 'static) -> Self {
+        self.load_report_listener = Some(Arc::new(listener));
+        self
+    }
+
+    /// Sets a [`ProgressListener`] to receive transfer progress.
+    pub fn progress_listener(mut self, listener: impl ProgressListener + 'static) -> Self {
+        self.progress_listener = Some(Arc::new(listener));
+        self
+    }
+
+    /// Sets a [`HedgingPolicy`] for idempotent unary calls.
+    pub fn hedging(mut self, policy: HedgingPolicy) -> Self {
+        self.hedging = Some(policy);
+        self
+    }
+
+    /// Sends unary requests marked
+    /// [`IdempotencyLevel::NoSideEffects`](crate::request::IdempotencyLevel::NoSideEffects)
+    /// with [`ConnectClient::execute_unary`] as GET requests.
+    ///
+    /// Requests fall back to POST if the GET URL would exceed the request's
+    /// [`get_url_max_bytes`](crate::request::builder::RequestBuilder::get_url_max_bytes).
+    pub fn http_get(mut self, enabled: bool) -> Self {
+        self.http_get = enabled;
+        self
+    }
+
+    /// Adds a named profile: a [`RequestBuilder`] template bundling e.g.
+    /// metadata, auth, and compression settings, selected per call with
+    /// [`ConnectClient::profile`].
+    ///
+    /// ```no_run
+    /// # use connect_rpc::{client::ConnectClient, request::builder::RequestBuilder};
+    /// # fn example(token: &str) -> Result<(), connect_rpc::Error> {
+    /// let internal = RequestBuilder::default()
+    ///     .bearer_auth(token)?
+    ///     .content_encoding("gzip")?;
+    /// let client = ConnectClient::builder()
+    ///     .profile("internal", internal)
+    ///     .profile("anonymous", RequestBuilder::default())
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// A profile added with an existing name replaces it.
+    pub fn profile(mut self, name: impl Into<String>, template: RequestBuilder) -> Self {
+        self.profiles.insert(name.into(), template);
+        self
+    }
+
+    /// Logs requests and responses at the wire level, with a
+    /// [`WireLogTransport`](crate::wire_log::WireLogTransport).
+    #[cfg(feature = "wire-log")]
+    pub fn wire_log(mut self, enabled: bool) -> Self {
+        self.wire_log = enabled;
+        self
+    }
+
+    /// Builds a [`ConnectClient`].
+    ///
+    /// Fails if [connection options](Self::connection_options) are combined
+    /// with a custom [transport](Self::transport), if no transport was set
+    /// without the `reqwest` feature, or if the default transport can't be
+    /// built.
+    pub fn build(self) -> Result<ConnectClient, Error> {
+        let mut transport = match (self.transport, self.connection_options) {
+            (Some(_), Some(_)) => {
+                return Err(Error::InvalidClientConfig(
+                    "connection options can't be applied to a custom transport",
+                ))
+            }
+            (Some(transport), None) => transport,
+            (None, options) => default_transport(&options.unwrap_or_default())?,
+        };
+        #[cfg(feature = "wire-log")]
+        if self.wire_log {
+            transport = Arc::new(crate::wire_log::WireLogTransport::wrap(transport));
+        }
+        if let Some(balancer) = self.load_balancer {
+            transport = Arc::new(balancer.wrap(transport));
+        }
+        if let Some(listener) = &self.progress_listener {
+            transport = Arc::new(ProgressTransport::new(transport, listener.clone()));
+        }
+        let mut interceptors = self.interceptors;
+        if let Some(vcr) = &self.vcr {
+            interceptors.push(vcr.clone());
+        }
+        Ok(ConnectClient {
+            transport,
+            interceptors: interceptors.into(),
+            vcr: self.vcr,
+            metrics_sink: self.metrics_sink,
+            signer: self.signer,
+            load_report_listener: self.load_report_listener,
+            progress_listener: self.progress_listener,
+            hedging: self.hedging,
+            http_get: self.http_get,
+            profiles: Arc::new(self.profiles),
+        })
+    }
+}
+
+#[cfg(feature = "reqwest")]
+fn default_transport(options: &ConnectionOptions) -> Result<Arc<dyn Transport>, Error> {
+    let client = options
+        .apply_reqwest(reqwest::Client::builder())
+        .build()
+        .map_err(Error::ReqwestError)?;
+    Ok(Arc::new(client))
+}
+
+#[cfg(not(feature = "reqwest"))]
+fn default_transport(_options: &ConnectionOptions) -> Result<Arc<dyn Transport>, Error> {
+    Err(Error::InvalidClientConfig(
+        "a transport is required without the `reqwest` feature",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::transport::MemoryTransport;
+
+    use super::*;
+
+    #[test]
+    fn rejects_connection_options_with_custom_transport() {
+        let result = ClientBuilder::default()
+            .transport(MemoryTransport::default())
+            .connection_options(ConnectionOptions::default())
+            .build();
+        assert!(matches!(result, Err(Error::InvalidClientConfig(_))));
+    }
+
+    #[cfg(not(feature = "reqwest"))]
+    #[test]
+    fn requires_transport() {
+        let result = ClientBuilder::default().build();
+        assert!(matches!(result, Err(Error::InvalidClientConfig(_))));
+    }
+}
@@ -0,0 +1,404 @@
+use std::{
+    collections::HashMap,
+    pin::{pin, Pin},
+    sync::Arc,
+    task::{ready, Context, Poll},
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+use futures_util::{
+    future::{select, BoxFuture, Either},
+    FutureExt, Stream, StreamExt,
+};
+use http::HeaderMap;
+
+use crate::{
+    compression::{self, Compression},
+    instrument::CallInstrument,
+    metrics::{MetricsSink, RpcInfo},
+    response::{
+        error::{ConnectCode, ConnectError},
+        ConnectResponse, StreamingResponse,
+    },
+    stream::{
+        read_ahead::{self, ReadAhead, Reader},
+        sender::FrameSender,
+        ConnectFrame, EndStreamResponse, ResponseFrame,
+    },
+    Error,
+};
+
+use super::{progress::ProgressListener, Sleep};
+
+const DEFAULT_MAX_MESSAGE_SIZE: usize = 4 * 1024 * 1024;
+
+type FrameStream = Pin<Box<dyn Stream<Item = Result<ConnectFrame, Error>> + Send>>;
+
+/// A handle to an in-progress server-streaming call.
+///
+/// Yields each response message. The call completes when the end-stream
+/// frame is received, after which [`Self::trailers`] are available.
+///
+/// # Cancellation
+///
+/// Dropping the handle before the call completes cancels the call: the
+/// response body is dropped immediately, which resets the HTTP/2 stream
+/// (`RST_STREAM`) or closes the HTTP/1 connection rather than returning it
+/// to the pool. A `canceled` error is reported to the client's
+/// [`MetricsSink`] and (with the `tracing` feature) the call's span.
+///
+/// To let a call run to completion without holding on to the handle, use
+/// [`Self::detach`].
+pub struct ServerStreamCall {
+    response: StreamingResponse<()>,
+    frames: Option<FrameStream>,
+    trailers: Option<HashMap<String, Vec<String>>>,
+    flatten_limit: Option<usize>,
+    metadata_snapshot: Option<HeaderMap>,
+    idle_timeout: Option<IdleTimeout>,
+    compression: Option<Arc<dyn Compression>>,
+    max_message_size: usize,
+    state: CallState,
+}
+
+struct IdleTimeout {
+    sleep: Arc<dyn Sleep>,
+    timeout: Duration,
+    timer: Option<BoxFuture<'static, ()>>,
+}
+
+impl ServerStreamCall {
+    pub(crate) fn new(
+        response: StreamingResponse<()>,
+        frames: impl Stream<Item = Result<ConnectFrame, Error>> + Send + 'static,
+        state: CallState,
+    ) -> Self {
+        let compression = compression::lookup(response.content_encoding().unwrap_or("identity"));
+        Self {
+            response,
+            frames: Some(Box::pin(frames)),
+            trailers: None,
+            flatten_limit: None,
+            metadata_snapshot: None,
+            idle_timeout: None,
+            compression,
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+            state,
+        }
+    }
+
+    /// Returns the response headers.
+    pub fn response(&self) -> &StreamingResponse<()> {
+        &self.response
+    }
+
+    /// Returns the trailing metadata from the end-stream frame, once the
+    /// call has completed successfully.
+    pub fn trailers(&self) -> Option<&HashMap<String, Vec<String>>> {
+        self.trailers.as_ref()
+    }
+
+    /// Enables flattening of response headers and trailers into a single
+    /// metadata snapshot, available from [`Self::metadata_snapshot`] once the
+    /// end-stream frame is received.
+    ///
+    /// This is for environments that can't access trailing metadata late in
+    /// the stream. If the snapshot would exceed `max_bytes` the call fails
+    /// with a `resource_exhausted` error.
+    pub fn flatten_metadata(mut self, max_bytes: usize) -> Self {
+        self.flatten_limit = Some(max_bytes);
+        self
+    }
+
+    /// Returns the flattened metadata snapshot, if enabled with
+    /// [`Self::flatten_metadata`] and the call has completed.
+    pub fn metadata_snapshot(&self) -> Option<&HeaderMap> {
+        self.metadata_snapshot.as_ref()
+    }
+
+    /// Fails the call with a `deadline_exceeded` error if no frame is
+    /// received for `timeout`, using `sleep` to wait (e.g.
+    /// `tokio::time::sleep`).
+    ///
+    /// This protects against servers that stop sending without closing the
+    /// stream. The timer starts on the first poll and restarts with each
+    /// frame; the response body is dropped when it fires.
+    pub fn idle_timeout(mut self, sleep: impl Sleep + 'static, timeout: Duration) -> Self {
+        self.idle_timeout = Some(IdleTimeout {
+            sleep: Arc::new(sleep),
+            timeout,
+            timer: None,
+        });
+        self
+    }
+
+    /// Sets the maximum size of a decompressed response message; larger
+    /// messages fail the call with a `resource_exhausted` error.
+    ///
+    /// Defaults to 4 MiB.
+    pub fn max_message_size(mut self, max_message_size: usize) -> Self {
+        self.max_message_size = max_message_size;
+        self
+    }
+
+    /// Reads response frames ahead of the consumer, into a buffer bounded by
+    /// `limits`, with a [`Reader`] passed to `spawn` (e.g.
+    /// `|reader| { tokio::spawn(reader); }`).
+    ///
+    /// By default frames are only read as messages are consumed. Once the
+    /// buffer is full the reader stops reading the response body until
+    /// messages are consumed, applying backpressure (e.g. via HTTP/2 flow
+    /// control) to the server. Dropping the handle still cancels the call.
+    pub fn read_ahead(mut self, limits: ReadAhead, spawn: impl FnOnce(Reader)) -> Self {
+        if let Some(frames) = self.frames.take() {
+            let (frames, reader) = read_ahead::buffer(frames, limits);
+            spawn(reader);
+            self.frames = Some(Box::pin(frames));
+        }
+        self
+    }
+
+    /// Returns true if the call has completed (successfully or not).
+    pub fn is_finished(&self) -> bool {
+        self.frames.is_none()
+    }
+
+    /// Detaches the call from this handle, returning a future that drives
+    /// it to completion, discarding any remaining messages.
+    ///
+    /// The future owns the call and may be spawned on any executor for
+    /// intentionally fire-and-forget calls. Dropping the future before it
+    /// completes cancels the call as if the handle itself were dropped.
+    pub async fn detach(mut self) {
+        while self.next().await.is_some() {}
+    }
+
+    fn next_message(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes, Error>>> {
+        let Some(frames) = self.frames.as_mut() else {
+            return Poll::Ready(None);
+        };
+        let next = match frames.poll_next_unpin(cx) {
+            Poll::Ready(next) => {
+                if let Some(idle) = &mut self.idle_timeout {
+                    idle.timer = None;
+                }
+                Some(next)
+            }
+            Poll::Pending => None,
+        };
+        let next = match (next, &mut self.idle_timeout) {
+            (Some(next), _) => {
+                ResponseFrame::from_next(next, self.compression.as_deref(), self.max_message_size)
+            }
+            (None, None) => return Poll::Pending,
+            (None, Some(idle)) => {
+                let timer = idle
+                    .timer
+                    .get_or_insert_with(|| idle.sleep.sleep(idle.timeout));
+                ready!(timer.poll_unpin(cx));
+                Err(Error::ConnectError(ConnectError::new(
+                    ConnectCode::DeadlineExceeded,
+                    format!("no response frame received in {:?}", idle.timeout),
+                )))
+            }
+        };
+        let result = match next {
+            Ok(ResponseFrame::Message(data)) => {
+                self.state.response_bytes += data.len();
+                self.state.messages += 1;
+                if let Some(listener) = &self.state.progress_listener {
+                    listener.on_message(&self.state.rpc, self.state.messages);
+                }
+                return Poll::Ready(Some(Ok(data)));
+            }
+            Ok(ResponseFrame::End(end)) => self.end_stream(end),
+            Err(err) => Err(err),
+        };
+        // Drop the body now that the call is complete.
+        self.frames = None;
+        self.state.finish(&result);
+        Poll::Ready(result.err().map(Err))
+    }
+
+    fn end_stream(&mut self, end: EndStreamResponse) -> Result<(), Error> {
+        if let Some(max_bytes) = self.flatten_limit {
+            let snapshot = end.flatten_metadata(self.response.headers(), max_bytes)?;
+            self.metadata_snapshot = Some(snapshot);
+        }
+        self.trailers = Some(end.metadata);
+        match end.error {
+            Some(err) => Err(Error::ConnectError(err)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Stream for ServerStreamCall {
+    type Item = Result<Bytes, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().next_message(cx)
+    }
+}
+
+impl Drop for ServerStreamCall {
+    fn drop(&mut self) {
+        if self.frames.take().is_some() {
+            self.state.cancel();
+        }
+    }
+}
+
+impl std::fmt::Debug for ServerStreamCall {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServerStreamCall")
+            .field("response", &self.response)
+            .field("trailers", &self.trailers)
+            .field("finished", &self.is_finished())
+            .finish()
+    }
+}
+
+/// A handle to an in-progress client-streaming or bidi-streaming call.
+///
+/// Owns the sending half of the request body; see
+/// [`ConnectClient::execute_bidi_stream`](super::ConnectClient::execute_bidi_stream).
+/// The request is dispatched while frames are sent and while awaiting
+/// [`Self::response`].
+///
+/// Dropping the handle ends the request body and cancels the call.
+pub struct BidiStreamCall {
+    sender: FrameSender,
+    pending: Option<BoxFuture<'static, Result<ServerStreamCall, Error>>>,
+    call: Option<ServerStreamCall>,
+    error: Option<Error>,
+}
+
+impl BidiStreamCall {
+    pub(crate) fn new(
+        sender: FrameSender,
+        response: BoxFuture<'static, Result<ServerStreamCall, Error>>,
+    ) -> Self {
+        Self {
+            sender,
+            pending: Some(response),
+            call: None,
+            error: None,
+        }
+    }
+
+    /// Fails a [`Self::send`] that waits longer than `timeout` for buffer
+    /// space. See [`FrameSender::send_timeout`].
+    pub fn send_timeout(self, sleep: impl Sleep + 'static, timeout: Duration) -> Self {
+        Self {
+            sender: self.sender.send_timeout(sleep, timeout),
+            ..self
+        }
+    }
+
+    /// Sends a request frame, waiting for buffer space if necessary.
+    ///
+    /// If the call fails while waiting, this returns a `canceled` error and
+    /// the call's error is returned by [`Self::response`].
+    pub async fn send(&mut self, frame: ConnectFrame) -> Result<(), Error> {
+        let Some(pending) = &mut self.pending else {
+            if self.error.is_some() {
+                return Err(call_failed());
+            }
+            return self.sender.send(frame).await;
+        };
+        let send = pin!(self.sender.send(frame));
+        match select(send, pending).await {
+            Either::Left((result, _)) => result,
+            Either::Right((response, send)) => {
+                self.pending = None;
+                match response {
+                    Ok(call) => {
+                        self.call = Some(call);
+                        send.await
+                    }
+                    Err(err) => {
+                        self.error = Some(err);
+                        Err(call_failed())
+                    }
+                }
+            }
+        }
+    }
+
+    /// Ends the request stream. See [`FrameSender::close_send`].
+    pub fn close_send(&self) {
+        self.sender.close_send();
+    }
+
+    /// Waits for the response headers, returning the response stream.
+    ///
+    /// For client-streaming calls, [`Self::close_send`] first; the server may
+    /// not respond until the request stream ends.
+    ///
+    /// This is cancel-safe: if the returned future is dropped, the request
+    /// is still in flight and this may be called again.
+    pub async fn response(&mut self) -> Result<&mut ServerStreamCall, Error> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        if let Some(pending) = &mut self.pending {
+            // Only cleared once complete, so this is cancel-safe.
+            let result = pending.await;
+            self.pending = None;
+            self.call = Some(result?);
+        }
+        self.call.as_mut().ok_or_else(call_failed)
+    }
+}
+
+impl std::fmt::Debug for BidiStreamCall {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BidiStreamCall")
+            .field("call", &self.call)
+            .field("failed", &self.error.is_some())
+            .finish()
+    }
+}
+
+fn call_failed() -> Error {
+    Error::ConnectError(ConnectError::new(ConnectCode::Canceled, "call failed"))
+}
+
+/// Per-call reporting state for a streaming call.
+pub(crate) struct CallState {
+    pub(crate) rpc: RpcInfo,
+    pub(crate) instrument: CallInstrument,
+    pub(crate) metrics_sink: Option<Arc<dyn MetricsSink>>,
+    pub(crate) progress_listener: Option<Arc<dyn ProgressListener>>,
+    pub(crate) start: Instant,
+    pub(crate) response_bytes: usize,
+    pub(crate) messages: u64,
+}
+
+impl CallState {
+    pub(crate) fn finish<T>(&self, result: &Result<T, Error>) {
+        let latency = self.start.elapsed();
+        self.instrument.record_result(result, latency);
+        if let Some(sink) = &self.metrics_sink {
+            match result {
+                Ok(_) => sink.on_response(&self.rpc, latency, self.response_bytes),
+                Err(err) => {
+                    if let Error::ConflictingHeaders(violation) = err {
+                        sink.on_protocol_violation(&self.rpc, violation);
+                    }
+                    sink.on_error(&self.rpc, err.connect_code(), latency);
+                }
+            }
+        }
+    }
+
+    fn cancel(&self) {
+        let latency = self.start.elapsed();
+        self.instrument.record_canceled(latency);
+        if let Some(sink) = &self.metrics_sink {
+            sink.on_error(&self.rpc, ConnectCode::Canceled, latency);
+        }
+    }
+}
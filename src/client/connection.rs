@@ -0,0 +1,142 @@
+//! Connection pool and keepalive tuning for the built-in transports.
+//!
+//! Long-lived streams through proxies and load balancers that drop idle
+//! connections need keepalives to stay up (or to fail promptly rather than
+//! hang):
+//!
+//! ```no_run
+//! # use std::time::Duration;
+//! # use connect_rpc::client::{connection::ConnectionOptions, ConnectClient};
+//! let options = ConnectionOptions::default()
+//!     .tcp_keepalive(Duration::from_secs(60))
+//!     .http2_keep_alive_interval(Duration::from_secs(30));
+//! let client = ConnectClient::builder().connection_options(options).build()?;
+//! # Ok::<(), connect_rpc::Error>(())
+//! ```
+
+use std::time::Duration;
+
+/// Connection settings for the built-in transports.
+///
+/// Unset options use the transport's defaults.
+#[derive(Clone, Debug, Default)]
+pub struct ConnectionOptions {
+    pool_idle_timeout: Option<Duration>,
+    pool_max_idle_per_host: Option<usize>,
+    tcp_keepalive: Option<Duration>,
+    http2_keep_alive_interval: Option<Duration>,
+    http2_keep_alive_timeout: Option<Duration>,
+    http2_initial_stream_window_size: Option<u32>,
+    http2_initial_connection_window_size: Option<u32>,
+}
+
+impl ConnectionOptions {
+    /// Sets how long idle pooled connections are kept open.
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the maximum number of idle pooled connections per host.
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// Enables TCP keepalive, sending probes after `idle` without traffic.
+    pub fn tcp_keepalive(mut self, idle: Duration) -> Self {
+        self.tcp_keepalive = Some(idle);
+        self
+    }
+
+    /// Enables HTTP/2 keepalive, sending a `PING` frame at this interval.
+    ///
+    /// A connection whose ping isn't acknowledged within
+    /// [`Self::http2_keep_alive_timeout`] is closed, failing its streams.
+    pub fn http2_keep_alive_interval(mut self, interval: Duration) -> Self {
+        self.http2_keep_alive_interval = Some(interval);
+        self
+    }
+
+    /// Sets how long to wait for an HTTP/2 keepalive ping to be
+    /// acknowledged.
+    pub fn http2_keep_alive_timeout(mut self, timeout: Duration) -> Self {
+        self.http2_keep_alive_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the HTTP/2 initial stream-level flow control window size.
+    pub fn http2_initial_stream_window_size(mut self, size: u32) -> Self {
+        self.http2_initial_stream_window_size = Some(size);
+        self
+    }
+
+    /// Sets the HTTP/2 initial connection-level flow control window size.
+    pub fn http2_initial_connection_window_size(mut self, size: u32) -> Self {
+        self.http2_initial_connection_window_size = Some(size);
+        self
+    }
+
+    /// Applies these options to a [`reqwest::ClientBuilder`], e.g. to build a
+    /// custom reqwest transport.
+    #[cfg(feature = "reqwest")]
+    pub fn apply_reqwest(&self, mut builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+        if let Some(timeout) = self.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(timeout);
+        }
+        if let Some(max) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+        if let Some(idle) = self.tcp_keepalive {
+            builder = builder.tcp_keepalive(idle);
+        }
+        if let Some(interval) = self.http2_keep_alive_interval {
+            builder = builder.http2_keep_alive_interval(interval);
+        }
+        if let Some(timeout) = self.http2_keep_alive_timeout {
+            builder = builder.http2_keep_alive_timeout(timeout);
+        }
+        if let Some(size) = self.http2_initial_stream_window_size {
+            builder = builder.http2_initial_stream_window_size(size);
+        }
+        if let Some(size) = self.http2_initial_connection_window_size {
+            builder = builder.http2_initial_connection_window_size(size);
+        }
+        builder
+    }
+
+    /// Applies these options to a [`hyper_util`] legacy client builder and
+    /// HTTP connector.
+    #[cfg(feature = "hyper")]
+    pub fn apply_hyper(
+        &self,
+        builder: &mut hyper_util::client::legacy::Builder,
+        connector: &mut hyper_util::client::legacy::connect::HttpConnector,
+    ) {
+        use hyper_util::rt::TokioTimer;
+
+        builder.pool_timer(TokioTimer::new());
+        if let Some(timeout) = self.pool_idle_timeout {
+            builder.pool_idle_timeout(timeout);
+        }
+        if let Some(max) = self.pool_max_idle_per_host {
+            builder.pool_max_idle_per_host(max);
+        }
+        connector.set_keepalive(self.tcp_keepalive);
+        if let Some(interval) = self.http2_keep_alive_interval {
+            // Keepalive pings need a timer.
+            builder
+                .timer(TokioTimer::new())
+                .http2_keep_alive_interval(interval);
+        }
+        if let Some(timeout) = self.http2_keep_alive_timeout {
+            builder.http2_keep_alive_timeout(timeout);
+        }
+        if let Some(size) = self.http2_initial_stream_window_size {
+            builder.http2_initial_stream_window_size(size);
+        }
+        if let Some(size) = self.http2_initial_connection_window_size {
+            builder.http2_initial_connection_window_size(size);
+        }
+    }
+}
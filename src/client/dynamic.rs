@@ -0,0 +1,314 @@
+//! Endpoints, retry policies, and load balancing weights from a dynamic
+//! configuration source, polled at runtime.
+//!
+//! A configuration document looks like:
+//!
+//! ```json
+//! {
+//!   "endpoints": [
+//!     {"authority": "10.0.0.1:8080", "weight": 3},
+//!     {"authority": "10.0.0.2:8080"}
+//!   ],
+//!   "serviceConfig": {"methodConfig": [...]}
+//! }
+//! ```
+//!
+//! The source is read each time the balancer refreshes its authorities;
+//! sources aren't watched for changes.
+//!
+//! ```no_run
+//! # use std::time::Duration;
+//! # use connect_rpc::{
+//! #     client::{balance::LoadBalancer, dynamic::{DynamicResolver, FileSource}, ConnectClient},
+//! #     interceptor::service_config::ServiceConfigInterceptor,
+//! # };
+//! let resolver = DynamicResolver::new(FileSource::new("/etc/backend.json"));
+//! let client = ConnectClient::builder()
+//!     .interceptor(ServiceConfigInterceptor::new(resolver.service_config(), tokio::time::sleep))
+//!     .load_balancer(LoadBalancer::resolve(resolver, Duration::from_secs(10)))
+//!     .build()?;
+//! # Ok::<(), connect_rpc::Error>(())
+//! ```
+
+use std::{path::PathBuf, sync::Arc};
+
+use bytes::Bytes;
+use futures_util::future::BoxFuture;
+use http::{uri::Authority, Method, Uri};
+
+use crate::{
+    interceptor::service_config::{ServiceConfig, SharedServiceConfig},
+    response::error::{ConnectCode, ConnectError},
+    transport::{buffer_response, full_body, Transport},
+    Error,
+};
+
+use super::resolver::Resolver;
+
+/// A dynamic configuration document.
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DynamicConfig {
+    #[serde(default)]
+    pub endpoints: Vec<EndpointConfig>,
+    pub service_config: Option<ServiceConfig>,
+}
+
+/// An endpoint and its load balancing weight.
+#[derive(Clone, Debug, serde::Deserialize)]
+pub struct EndpointConfig {
+    pub authority: String,
+    /// The endpoint's relative share of requests; defaults to 1, and 0
+    /// removes the endpoint.
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+impl DynamicConfig {
+    /// Parses a configuration JSON document.
+    pub fn from_json(json: &[u8]) -> Result<Self, Error> {
+        serde_json::from_slice(json)
+            .map_err(|err| Error::invalid_request(format!("invalid dynamic config: {err}")))
+    }
+}
+
+/// A source of [`DynamicConfig`] documents.
+pub trait ConfigSource: Send + Sync {
+    /// Returns the current document.
+    fn fetch(&self) -> BoxFuture<'_, Result<Bytes, Error>>;
+}
+
+/// A [`ConfigSource`] that reads a file, so changes to it are picked up on
+/// the next refresh.
+///
+/// Requires a Tokio runtime.
+#[derive(Clone, Debug)]
+pub struct FileSource(PathBuf);
+
+impl FileSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self(path.into())
+    }
+}
+
+impl ConfigSource for FileSource {
+    fn fetch(&self) -> BoxFuture<'_, Result<Bytes, Error>> {
+        Box::pin(async move {
+            tokio::fs::read(&self.0)
+                .await
+                .map(Into::into)
+                .map_err(|err| {
+                    Error::ConnectError(ConnectError::new(
+                        ConnectCode::Unavailable,
+                        format!("reading {:?} failed: {err}", self.0),
+                    ))
+                })
+        })
+    }
+}
+
+/// A [`ConfigSource`] that polls a URL with `GET` requests.
+pub struct HttpSource {
+    transport: Arc<dyn Transport>,
+    uri: Uri,
+}
+
+impl HttpSource {
+    pub fn new(transport: impl Transport + 'static, uri: Uri) -> Self {
+        Self {
+            transport: Arc::new(transport),
+            uri,
+        }
+    }
+}
+
+impl ConfigSource for HttpSource {
+    fn fetch(&self) -> BoxFuture<'_, Result<Bytes, Error>> {
+        Box::pin(async move {
+            let mut req = http::Request::new(full_body(Bytes::new()));
+            *req.method_mut() = Method::GET;
+            *req.uri_mut() = self.uri.clone();
+            let resp = buffer_response(self.transport.round_trip(req).await?).await?;
+            if !resp.status().is_success() {
+                return Err(Error::ConnectError(ConnectError::new(
+                    ConnectCode::Unavailable,
+                    format!("fetching {} failed: {}", self.uri, resp.status()),
+                )));
+            }
+            Ok(resp.into_body())
+        })
+    }
+}
+
+impl std::fmt::Debug for HttpSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HttpSource")
+            .field("uri", &self.uri)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A [`Resolver`] whose authorities come from a [`ConfigSource`].
+///
+/// Weights are returned by [`Resolver::resolve_weighted`], for a
+/// [`LoadBalancer`](super::balance::LoadBalancer). Each fetched service
+/// config replaces the one shared with [`Self::service_config`]; if a fetch
+/// fails, the previous authorities and service config are kept.
+pub struct DynamicResolver {
+    source: Box<dyn ConfigSource>,
+    service_config: SharedServiceConfig,
+}
+
+impl DynamicResolver {
+    pub fn new(source: impl ConfigSource + 'static) -> Self {
+        Self {
+            source: Box::new(source),
+            service_config: Default::default(),
+        }
+    }
+
+    /// Returns the latest service config, e.g. for a
+    /// [`ServiceConfigInterceptor`](crate::interceptor::service_config::ServiceConfigInterceptor).
+    pub fn service_config(&self) -> SharedServiceConfig {
+        self.service_config.clone()
+    }
+}
+
+impl Resolver for DynamicResolver {
+    fn resolve(&self) -> BoxFuture<'_, Result<Vec<Authority>, Error>> {
+        Box::pin(async move {
+            let authorities = self.resolve_weighted().await?;
+            Ok(authorities
+                .into_iter()
+                .filter(|(_, weight)| *weight > 0)
+                .map(|(authority, _)| authority)
+                .collect())
+        })
+    }
+
+    fn resolve_weighted(&self) -> BoxFuture<'_, Result<Vec<(Authority, u32)>, Error>> {
+        Box::pin(async move {
+            let config = DynamicConfig::from_json(&self.source.fetch().await?)?;
+            let authorities = config
+                .endpoints
+                .iter()
+                .map(|endpoint| Ok((endpoint.authority.parse()?, endpoint.weight)))
+                .collect::<Result<_, Error>>()?;
+            if let Some(service_config) = config.service_config {
+                self.service_config.set(service_config);
+            }
+            Ok(authorities)
+        })
+    }
+}
+
+impl std::fmt::Debug for DynamicResolver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DynamicResolver")
+            .field("service_config", &self.service_config)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Mutex, time::Duration};
+
+    use crate::{
+        client::{balance::LoadBalancer, ConnectClient},
+        request::builder::RequestBuilder,
+        transport::MemoryTransport,
+    };
+
+    use super::*;
+
+    /// Returns its current document.
+    #[derive(Clone, Default)]
+    struct MemorySource(Arc<Mutex<&'static str>>);
+
+    impl ConfigSource for MemorySource {
+        fn fetch(&self) -> BoxFuture<'_, Result<Bytes, Error>> {
+            let json = *self.0.lock().unwrap();
+            Box::pin(async move { Ok(Bytes::from_static(json.as_bytes())) })
+        }
+    }
+
+    #[tokio::test]
+    async fn resolves_weights_and_service_config() {
+        let source = MemorySource::default();
+        *source.0.lock().unwrap() = r#"{
+            "endpoints": [
+                {"authority": "a:1", "weight": 4000000000},
+                {"authority": "b:2", "weight": 0},
+                {"authority": "c:3"}
+            ],
+            "serviceConfig": {"methodConfig": [{"name": [{}], "timeout": "1s"}]}
+        }"#;
+        let resolver = DynamicResolver::new(source.clone());
+        let weighted = resolver.resolve_weighted().await.unwrap();
+        let weighted: Vec<_> = weighted
+            .iter()
+            .map(|(authority, weight)| (authority.as_str(), *weight))
+            .collect();
+        assert_eq!(weighted, [("a:1", 4000000000), ("b:2", 0), ("c:3", 1)]);
+        assert_eq!(resolver.resolve().await.unwrap(), ["a:1", "c:3"]);
+        let service_config = resolver.service_config().get();
+        let method = service_config.method("a.Service", "Get").unwrap();
+        assert_eq!(method.timeout, Some(Duration::from_secs(1)));
+
+        *source.0.lock().unwrap() = r#"{"endpoints": [{"authority": "bad authority"}]}"#;
+        assert!(resolver.resolve_weighted().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn balances_by_weight() {
+        let source = MemorySource::default();
+        *source.0.lock().unwrap() = r#"{
+            "endpoints": [{"authority": "a:1", "weight": 3}, {"authority": "b:2"}]
+        }"#;
+        let transport = MemoryTransport::new(|req: http::Request<Bytes>| async move {
+            let authority = req.uri().authority().unwrap().to_string();
+            let mut resp = http::Response::new(Bytes::from(authority));
+            resp.headers_mut()
+                .insert(http::header::CONTENT_TYPE, "application/proto".try_into()?);
+            Ok(resp)
+        });
+        let balancer = LoadBalancer::resolve(DynamicResolver::new(source), Duration::MAX);
+        let client = ConnectClient::builder()
+            .transport(transport)
+            .load_balancer(balancer)
+            .build()
+            .unwrap();
+        let mut picked = vec![];
+        for _ in 0..8 {
+            let req = RequestBuilder::default()
+                .uri("http://service/a.Service/Get")
+                .unwrap()
+                .message_codec("proto")
+                .unwrap()
+                .unary(Bytes::new())
+                .unwrap();
+            let resp = client.execute_unary(req).await.unwrap();
+            picked.push(String::from_utf8(resp.body().to_vec()).unwrap());
+        }
+        assert_eq!(picked, ["a:1", "a:1", "a:1", "b:2"].repeat(2));
+    }
+
+    #[tokio::test]
+    async fn reads_files() {
+        let path =
+            std::env::temp_dir().join(format!("connect-rpc-dynamic-{}.json", std::process::id()));
+        std::fs::write(&path, r#"{"endpoints": [{"authority": "a:1"}]}"#).unwrap();
+        let resolver = DynamicResolver::new(FileSource::new(&path));
+        let result = resolver.resolve().await;
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(result.unwrap(), ["a:1"]);
+
+        let err = resolver.resolve().await.unwrap_err();
+        assert!(matches!(err, Error::ConnectError(err) if err.code() == ConnectCode::Unavailable));
+    }
+}
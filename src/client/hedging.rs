@@ -0,0 +1,241 @@
+//! Request hedging for idempotent unary calls.
+
+use std::{pin::pin, sync::Arc, time::Duration};
+
+use bytes::Bytes;
+use futures_util::future::{self, Either};
+use http::Method;
+
+use crate::{
+    codes,
+    interceptor::{clone_request, Next},
+    request::IdempotencyLevel,
+    response::error::ConnectCode,
+    Error,
+};
+
+use super::{is_transient, Sleep};
+
+/// Sends a second ("hedged") attempt of an idempotent unary call if the
+/// first hasn't completed within a latency threshold.
+///
+/// Whichever attempt succeeds first wins and the other is canceled. If an
+/// attempt fails with an `unavailable` (or transport) error, the other
+/// attempt is awaited instead; other errors are returned immediately.
+///
+/// Only requests sent as GET or marked [`IdempotencyLevel::NoSideEffects`]
+/// or [`IdempotencyLevel::Idempotent`] are hedged.
+#[derive(Clone)]
+pub struct HedgingPolicy {
+    delay: Duration,
+    sleep: Arc<dyn Sleep>,
+}
+
+impl HedgingPolicy {
+    /// Returns a policy that sends the hedged attempt after `delay`, e.g.
+    /// the call's p95 latency, waiting with `sleep`.
+    pub fn new(delay: Duration, sleep: impl Sleep + 'static) -> Self {
+        Self {
+            delay,
+            sleep: Arc::new(sleep),
+        }
+    }
+
+    pub(crate) fn applies_to(&self, req: &http::Request<Bytes>) -> bool {
+        req.method() == Method::GET
+            || matches!(
+                req.extensions().get(),
+                Some(IdempotencyLevel::NoSideEffects | IdempotencyLevel::Idempotent)
+            )
+    }
+
+    /// Runs the request (through `next`) with a hedged attempt.
+    pub(crate) async fn run<'n>(
+        &self,
+        req: http::Request<Bytes>,
+        next: impl Fn() -> Next<'n>,
+    ) -> Result<http::Response<Bytes>, Error> {
+        let hedge = clone_request(&req);
+        let first = pin!(next().run(req));
+        let second = pin!(async {
+            self.sleep.sleep(self.delay).await;
+            tracing::debug!(delay = ?self.delay, "Sending hedged request");
+            next().run(hedge).await
+        });
+        // Dropping the losing attempt cancels it.
+        match future::select(first, second).await {
+            Either::Left((result, _)) | Either::Right((result, _)) if !is_retryable(&result) => {
+                result
+            }
+            Either::Left((_, second)) => second.await,
+            Either::Right((_, first)) => first.await,
+        }
+    }
+}
+
+impl std::fmt::Debug for HedgingPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HedgingPolicy")
+            .field("delay", &self.delay)
+            .finish_non_exhaustive()
+    }
+}
+
+fn is_retryable(result: &Result<http::Response<Bytes>, Error>) -> bool {
+    match result {
+        Ok(resp) => codes::from_http_status(resp.status()) == ConnectCode::Unavailable,
+        Err(err) => is_transient(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use futures_util::poll;
+
+    use crate::{
+        client::ConnectClient,
+        clock::ManualClock,
+        request::{builder::RequestBuilder, UnaryRequest},
+        response::error::ConnectError,
+        transport::MemoryTransport,
+    };
+
+    use super::*;
+
+    const DELAY: Duration = Duration::from_millis(100);
+
+    /// Returns a hedging client whose handler is passed the attempt number,
+    /// and the number of attempts so far.
+    fn client<F, Fut>(clock: &ManualClock, handler: F) -> (ConnectClient, Arc<AtomicUsize>)
+    where
+        F: Fn(usize) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = http::Response<Bytes>> + Send + 'static,
+    {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let handler = Arc::new(handler);
+        let transport = MemoryTransport::new({
+            let attempts = attempts.clone();
+            move |_| {
+                let attempt = attempts.fetch_add(1, Ordering::SeqCst);
+                let handler = handler.clone();
+                async move { Ok(handler(attempt).await) }
+            }
+        });
+        let client = ConnectClient::builder()
+            .transport(transport)
+            .hedging(HedgingPolicy::new(DELAY, clock.clone()))
+            .build()
+            .unwrap();
+        (client, attempts)
+    }
+
+    fn ok(attempt: usize) -> http::Response<Bytes> {
+        let mut resp = http::Response::new(Bytes::from(attempt.to_string()));
+        resp.headers_mut().insert(
+            http::header::CONTENT_TYPE,
+            "application/proto".try_into().unwrap(),
+        );
+        resp
+    }
+
+    fn error(code: ConnectCode) -> http::Response<Bytes> {
+        ConnectError::new(code, "failed").into()
+    }
+
+    fn request(idempotency_level: IdempotencyLevel) -> UnaryRequest<Bytes> {
+        RequestBuilder::default()
+            .uri("http://example.com/example.v1.Service/Get")
+            .unwrap()
+            .message_codec("proto")
+            .unwrap()
+            .idempotency_level(idempotency_level)
+            .unary(Bytes::from_static(b"message"))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn hedges_slow_attempt() {
+        let clock = ManualClock::new();
+        let (client, attempts) = client(&clock, |attempt| async move {
+            if attempt == 0 {
+                future::pending::<()>().await;
+            }
+            ok(attempt)
+        });
+        let mut call = pin!(client.execute_unary(request(IdempotencyLevel::NoSideEffects)));
+        assert!(poll!(&mut call).is_pending());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+
+        clock.advance(DELAY);
+        let resp = call.await.unwrap();
+        assert_eq!(resp.body().as_ref(), b"1");
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn skips_hedge_for_fast_attempt() {
+        let clock = ManualClock::new();
+        let (client, attempts) = client(&clock, |attempt| async move { ok(attempt) });
+        let resp = client
+            .execute_unary(request(IdempotencyLevel::Idempotent))
+            .await
+            .unwrap();
+        assert_eq!(resp.body().as_ref(), b"0");
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn awaits_hedge_after_unavailable() {
+        let clock = ManualClock::new();
+        let (client, attempts) = client(&clock, |attempt| async move {
+            match attempt {
+                0 => error(ConnectCode::Unavailable),
+                _ => ok(attempt),
+            }
+        });
+        let mut call = pin!(client.execute_unary(request(IdempotencyLevel::NoSideEffects)));
+        assert!(poll!(&mut call).is_pending());
+
+        clock.advance(DELAY);
+        let resp = call.await.unwrap();
+        assert_eq!(resp.body().as_ref(), b"1");
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn returns_other_errors_immediately() {
+        let clock = ManualClock::new();
+        let (client, attempts) = client(&clock, |_| async { error(ConnectCode::InvalidArgument) });
+        let err = client
+            .execute_unary(request(IdempotencyLevel::NoSideEffects))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, Error::ConnectError(err) if err.code() == ConnectCode::InvalidArgument)
+        );
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn applies_only_to_idempotent_requests() {
+        let policy = HedgingPolicy::new(DELAY, ManualClock::new());
+        let post = |level: Option<IdempotencyLevel>| {
+            let mut req = http::Request::new(Bytes::new());
+            *req.method_mut() = Method::POST;
+            if let Some(level) = level {
+                req.extensions_mut().insert(level);
+            }
+            req
+        };
+        assert!(!policy.applies_to(&post(None)));
+        assert!(!policy.applies_to(&post(Some(IdempotencyLevel::IdempotencyUnknown))));
+        assert!(policy.applies_to(&post(Some(IdempotencyLevel::Idempotent))));
+        assert!(policy.applies_to(&post(Some(IdempotencyLevel::NoSideEffects))));
+
+        let mut get = post(None);
+        *get.method_mut() = Method::GET;
+        assert!(policy.applies_to(&get));
+    }
+}
@@ -0,0 +1,109 @@
+//! Transfer progress reporting, e.g. for progress bars in UIs and CLIs.
+//!
+//! ```no_run
+//! # use connect_rpc::{client::{progress::ProgressListener, ConnectClient}, metrics::RpcInfo};
+//! struct Bar;
+//!
+//! impl ProgressListener for Bar {
+//!     fn on_download(&self, rpc: &RpcInfo, received: u64, total: Option<u64>) {
+//!         eprint!("\r{}: {received}/{}", rpc.method, total.unwrap_or(0));
+//!     }
+//! }
+//!
+//! let client = ConnectClient::builder().progress_listener(Bar).build()?;
+//! # Ok::<(), connect_rpc::Error>(())
+//! ```
+
+use std::sync::Arc;
+
+use futures_util::future::BoxFuture;
+use http::header;
+use http_body_util::BodyExt;
+
+use crate::{
+    metrics::RpcInfo,
+    transport::{RequestBody, ResponseBody, Transport},
+    Error,
+};
+
+/// Receives transfer progress for RPCs executed by a
+/// [`ConnectClient`](super::ConnectClient).
+///
+/// Byte counts are of the HTTP body as sent or received (including envelope
+/// prefixes and compression). All methods have no-op default
+/// implementations.
+pub trait ProgressListener: Send + Sync {
+    /// Called as request body data is sent, with the total sent so far.
+    ///
+    /// Unary request bodies are sent as a single chunk, so this is called
+    /// once per attempt, with `sent` equal to the body size.
+    fn on_upload(&self, rpc: &RpcInfo, sent: u64) {
+        let _ = (rpc, sent);
+    }
+
+    /// Called as response body data is received, with the total received so
+    /// far and the expected total (from `content-length`), if known.
+    fn on_download(&self, rpc: &RpcInfo, received: u64, total: Option<u64>) {
+        let _ = (rpc, received, total);
+    }
+
+    /// Called as messages are received on a streaming call, with the number
+    /// received so far.
+    fn on_message(&self, rpc: &RpcInfo, received: u64) {
+        let _ = (rpc, received);
+    }
+}
+
+/// A [`Transport`] that reports body progress to a [`ProgressListener`].
+pub(crate) struct ProgressTransport {
+    inner: Arc<dyn Transport>,
+    listener: Arc<dyn ProgressListener>,
+}
+
+impl ProgressTransport {
+    pub(crate) fn new(inner: Arc<dyn Transport>, listener: Arc<dyn ProgressListener>) -> Self {
+        Self { inner, listener }
+    }
+}
+
+impl Transport for ProgressTransport {
+    fn round_trip(
+        &self,
+        req: http::Request<RequestBody>,
+    ) -> BoxFuture<'_, Result<http::Response<ResponseBody>, Error>> {
+        Box::pin(async move {
+            let rpc = RpcInfo::from_path(req.uri().path());
+            let listener = self.listener.clone();
+            let upload_rpc = rpc.clone();
+            let mut sent = 0;
+            let req = req.map(|body| {
+                body.map_frame(move |frame| {
+                    if let Some(data) = frame.data_ref() {
+                        sent += data.len() as u64;
+                        listener.on_upload(&upload_rpc, sent);
+                    }
+                    frame
+                })
+                .boxed()
+            });
+            let resp = self.inner.round_trip(req).await?;
+
+            let total = resp
+                .headers()
+                .get(header::CONTENT_LENGTH)
+                .and_then(|len| len.to_str().ok()?.parse().ok());
+            let listener = self.listener.clone();
+            let mut received = 0;
+            Ok(resp.map(|body| {
+                body.map_frame(move |frame| {
+                    if let Some(data) = frame.data_ref() {
+                        received += data.len() as u64;
+                        listener.on_download(&rpc, received, total);
+                    }
+                    frame
+                })
+                .boxed()
+            }))
+        })
+    }
+}
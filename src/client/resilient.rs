@@ -0,0 +1,184 @@
+//! Server streams that reconnect after transient failures.
+
+use std::{
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use bytes::Bytes;
+use futures_util::{stream, stream::BoxStream, Stream, StreamExt};
+
+use crate::{request::StreamingRequest, Error};
+
+use super::{call::ServerStreamCall, is_transient, ConnectClient, Sleep};
+
+/// Exponential backoff between reconnect attempts.
+#[derive(Clone, Copy, Debug)]
+pub struct Backoff {
+    /// The delay before the first retry.
+    pub initial: Duration,
+    /// The maximum delay between retries.
+    pub max: Duration,
+    /// The factor by which the delay grows after each retry.
+    pub multiplier: f64,
+    /// The maximum number of consecutive retries; receiving a message resets
+    /// the count.
+    pub max_retries: u32,
+}
+
+impl Backoff {
+    /// Returns the delay before the given (zero-based) retry.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = self.multiplier.powi(retry.try_into().unwrap_or(i32::MAX));
+        Duration::try_from_secs_f64(self.initial.as_secs_f64() * factor)
+            .map_or(self.max, |delay| delay.min(self.max))
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(100),
+            max: Duration::from_secs(10),
+            multiplier: 2.0,
+            max_retries: 5,
+        }
+    }
+}
+
+/// A server-streaming call that is re-established after transient failures.
+///
+/// Each (re)connect calls the resume function with the last message received
+/// (`None` at first), which returns the request to send, e.g. with a cursor
+/// taken from that message so the stream picks up where it left off. Failed
+/// attempts are retried with [`Backoff`] as long as the error is transient:
+/// an `unavailable` [`ConnectError`](crate::response::error::ConnectError),
+/// a transport error, or a response body that breaks off mid-stream.
+///
+/// ```no_run
+/// # use bytes::Bytes;
+/// # use connect_rpc::{
+/// #     client::{resilient::{Backoff, ResilientServerStream}, ConnectClient},
+/// #     request::builder::RequestBuilder,
+/// #     Error,
+/// # };
+/// # fn decode_cursor(msg: &Bytes) -> Result<String, Error> { unimplemented!() }
+/// # fn encode_subscribe(cursor: Option<String>) -> Bytes { unimplemented!() }
+/// # fn example(client: ConnectClient, builder: RequestBuilder) {
+/// let stream = ResilientServerStream::new(client, Backoff::default(), tokio::time::sleep, move |last| {
+///     let cursor = last.map(decode_cursor).transpose()?;
+///     builder.clone().streaming(encode_subscribe(cursor))
+/// });
+/// # }
+/// ```
+pub struct ResilientServerStream(BoxStream<'static, Result<Bytes, Error>>);
+
+impl ResilientServerStream {
+    pub fn new<F>(
+        client: ConnectClient,
+        backoff: Backoff,
+        sleep: impl Sleep + 'static,
+        resume: F,
+    ) -> Self
+    where
+        F: FnMut(Option<&Bytes>) -> Result<StreamingRequest<Bytes>, Error> + Send + 'static,
+    {
+        let state = State {
+            client,
+            backoff,
+            sleep: Arc::new(sleep),
+            resume,
+            call: None,
+            last_message: None,
+            retries: 0,
+            done: false,
+        };
+        Self(
+            stream::unfold(state, |mut state| async move {
+                let item = state.next().await?;
+                Some((item, state))
+            })
+            .boxed(),
+        )
+    }
+}
+
+impl Stream for ResilientServerStream {
+    type Item = Result<Bytes, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.0.poll_next_unpin(cx)
+    }
+}
+
+impl std::fmt::Debug for ResilientServerStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ResilientServerStream")
+            .finish_non_exhaustive()
+    }
+}
+
+struct State<F> {
+    client: ConnectClient,
+    backoff: Backoff,
+    sleep: Arc<dyn Sleep>,
+    resume: F,
+    call: Option<ServerStreamCall>,
+    last_message: Option<Bytes>,
+    retries: u32,
+    done: bool,
+}
+
+impl<F> State<F>
+where
+    F: FnMut(Option<&Bytes>) -> Result<StreamingRequest<Bytes>, Error>,
+{
+    async fn next(&mut self) -> Option<Result<Bytes, Error>> {
+        loop {
+            if self.done {
+                return None;
+            }
+            let err = match &mut self.call {
+                Some(call) => match call.next().await {
+                    Some(Ok(message)) => {
+                        self.retries = 0;
+                        self.last_message = Some(message.clone());
+                        return Some(Ok(message));
+                    }
+                    Some(Err(err)) => err,
+                    None => {
+                        self.done = true;
+                        return None;
+                    }
+                },
+                None => {
+                    let req = match (self.resume)(self.last_message.as_ref()) {
+                        Ok(req) => req,
+                        Err(err) => {
+                            self.done = true;
+                            return Some(Err(err));
+                        }
+                    };
+                    match self.client.execute_server_stream(req).await {
+                        Ok(call) => {
+                            self.call = Some(call);
+                            continue;
+                        }
+                        Err(err) => err,
+                    }
+                }
+            };
+            self.call = None;
+            if !is_transient(&err) || self.retries >= self.backoff.max_retries {
+                self.done = true;
+                return Some(Err(err));
+            }
+            let delay = self.backoff.delay(self.retries);
+            self.retries += 1;
+            tracing::debug!(?err, ?delay, retry = self.retries, "Reconnecting stream");
+            self.sleep.sleep(delay).await;
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! Resolution of service names to the authorities serving them.
+//!
+//! A [`LoadBalancer`](super::balance::LoadBalancer) created with
+//! [`LoadBalancer::resolve`](super::balance::LoadBalancer::resolve) consults
+//! a [`Resolver`] for its authorities, refreshing them periodically.
+
+use futures_util::future::BoxFuture;
+use http::uri::Authority;
+
+#[cfg(feature = "hyper")]
+use crate::response::error::{ConnectCode, ConnectError};
+use crate::Error;
+
+/// Resolves a service to the set of authorities serving it.
+pub trait Resolver: Send + Sync {
+    /// Returns the current authorities.
+    fn resolve(&self) -> BoxFuture<'_, Result<Vec<Authority>, Error>>;
+
+    /// Returns the current authorities with their relative shares of
+    /// requests (weights).
+    ///
+    /// Defaults to the authorities from [`Self::resolve`], each with weight 1.
+    fn resolve_weighted(&self) -> BoxFuture<'_, Result<Vec<(Authority, u32)>, Error>> {
+        Box::pin(async move {
+            let authorities = self.resolve().await?;
+            Ok(authorities
+                .into_iter()
+                .map(|authority| (authority, 1))
+                .collect())
+        })
+    }
+}
+
+/// A [`Resolver`] that always returns the same authorities.
+#[derive(Clone, Debug)]
+pub struct StaticResolver(Vec<Authority>);
+
+impl StaticResolver {
+    pub fn new<T: TryInto<Authority, Error: Into<Error>>>(
+        authorities: impl IntoIterator<Item = T>,
+    ) -> Result<Self, Error> {
+        let authorities = authorities
+            .into_iter()
+            .map(|authority| authority.try_into().map_err(Into::into))
+            .collect::<Result<_, _>>()?;
+        Ok(Self(authorities))
+    }
+}
+
+impl Resolver for StaticResolver {
+    fn resolve(&self) -> BoxFuture<'_, Result<Vec<Authority>, Error>> {
+        Box::pin(async move { Ok(self.0.clone()) })
+    }
+}
+
+/// A [`Resolver`] that looks up the addresses of a host name in DNS.
+///
+/// Each address becomes an `ip:port` authority, so the `Host` header (and
+/// TLS server name) will be the IP address rather than the host name.
+/// Requires a Tokio runtime.
+#[cfg(feature = "hyper")]
+#[derive(Clone, Debug)]
+pub struct DnsResolver {
+    host: String,
+    port: u16,
+}
+
+#[cfg(feature = "hyper")]
+impl DnsResolver {
+    pub fn new(host: impl Into<String>, port: u16) -> Self {
+        Self {
+            host: host.into(),
+            port,
+        }
+    }
+}
+
+#[cfg(feature = "hyper")]
+impl Resolver for DnsResolver {
+    fn resolve(&self) -> BoxFuture<'_, Result<Vec<Authority>, Error>> {
+        Box::pin(async move {
+            let addrs = tokio::net::lookup_host((self.host.as_str(), self.port))
+                .await
+                .map_err(|err| {
+                    Error::ConnectError(ConnectError::new(
+                        ConnectCode::Unavailable,
+                        format!("DNS lookup of {:?} failed: {err}", self.host),
+                    ))
+                })?;
+            let mut authorities = vec![];
+            for addr in addrs {
+                let authority: Authority = addr.to_string().parse()?;
+                if !authorities.contains(&authority) {
+                    authorities.push(authority);
+                }
+            }
+            Ok(authorities)
+        })
+    }
+}
@@ -0,0 +1,109 @@
+//! A source of time for deadline, rate limiting, and ejection logic.
+//!
+//! Components that track time (e.g.
+//! [`RateLimitInterceptor`](crate::interceptor::rate_limit::RateLimitInterceptor)
+//! and [`LoadBalancer`](crate::client::balance::LoadBalancer)) read it from
+//! a [`Clock`], defaulting to [`SystemClock`]. Tests can substitute a
+//! [`ManualClock`], which is also a [`Sleep`] for retry and backoff logic:
+//!
+//! ```no_run
+//! # use std::time::Duration;
+//! # use connect_rpc::{clock::ManualClock, interceptor::rate_limit::RateLimitInterceptor};
+//! let clock = ManualClock::new();
+//! let limiter = RateLimitInterceptor::new(1.0, 1)
+//!     .clock(clock.clone())
+//!     .delay(clock.clone(), Duration::from_secs(5));
+//! // ...
+//! clock.advance(Duration::from_secs(1));
+//! ```
+
+use std::{
+    future::poll_fn,
+    sync::{Arc, Mutex},
+    task::{Poll, Waker},
+    time::{Duration, Instant},
+};
+
+use futures_util::future::BoxFuture;
+
+use crate::client::Sleep;
+
+/// Returns the current time.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+/// A [`Clock`] that reads the system's monotonic clock.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A [`Clock`] (and [`Sleep`]) whose time only moves when advanced, for
+/// deterministic tests.
+///
+/// Clones share the same time.
+#[derive(Clone, Debug)]
+pub struct ManualClock(Arc<Mutex<ManualState>>);
+
+#[derive(Debug)]
+struct ManualState {
+    now: Instant,
+    /// Tasks waiting in [`Sleep::sleep`].
+    sleepers: Vec<Waker>,
+}
+
+impl ManualClock {
+    /// Returns a clock starting at the current time.
+    pub fn new() -> Self {
+        Self(Arc::new(Mutex::new(ManualState {
+            now: Instant::now(),
+            sleepers: vec![],
+        })))
+    }
+
+    /// Moves time forward, completing any sleeps that have elapsed.
+    pub fn advance(&self, duration: Duration) {
+        let sleepers = {
+            let mut state = self.0.lock().unwrap();
+            state.now += duration;
+            std::mem::take(&mut state.sleepers)
+        };
+        for waker in sleepers {
+            waker.wake();
+        }
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.0.lock().unwrap().now
+    }
+}
+
+impl Sleep for ManualClock {
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        let deadline = self.now() + duration;
+        let clock = self.clone();
+        Box::pin(poll_fn(move |cx| {
+            let mut state = clock.0.lock().unwrap();
+            if state.now >= deadline {
+                return Poll::Ready(());
+            }
+            if !state.sleepers.iter().any(|w| w.will_wake(cx.waker())) {
+                state.sleepers.push(cx.waker().clone());
+            }
+            Poll::Pending
+        }))
+    }
+}
@@ -0,0 +1,105 @@
+//! Error code mapping tables.
+//!
+//! The mappings between [`ConnectCode`]s, HTTP statuses, and gRPC status
+//! codes, as specified by the Connect protocol.
+//!
+//! See: https://connectrpc.com/docs/protocol/#error-codes and
+//! https://connectrpc.com/docs/protocol/#http-to-error-code
+
+use http::StatusCode;
+
+use crate::response::error::ConnectCode;
+
+/// The mappings for a single [`ConnectCode`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CodeMapping {
+    pub code: ConnectCode,
+    /// The code's string form, as used in error JSON.
+    pub name: &'static str,
+    /// The numeric gRPC status code.
+    pub grpc_code: u32,
+    /// The HTTP status used for unary error responses with this code.
+    pub http_status: u16,
+}
+
+const fn mapping(
+    code: ConnectCode,
+    name: &'static str,
+    grpc_code: u32,
+    http_status: u16,
+) -> CodeMapping {
+    CodeMapping {
+        code,
+        name,
+        grpc_code,
+        http_status,
+    }
+}
+
+/// All [`ConnectCode`]s and their mappings, ordered by gRPC code.
+pub const CODES: [CodeMapping; 17] = [
+    mapping(ConnectCode::Ok, "ok", 0, 200),
+    mapping(ConnectCode::Canceled, "canceled", 1, 499),
+    mapping(ConnectCode::Unknown, "unknown", 2, 500),
+    mapping(ConnectCode::InvalidArgument, "invalid_argument", 3, 400),
+    mapping(ConnectCode::DeadlineExceeded, "deadline_exceeded", 4, 504),
+    mapping(ConnectCode::NotFound, "not_found", 5, 404),
+    mapping(ConnectCode::AlreadyExists, "already_exists", 6, 409),
+    mapping(ConnectCode::PermissionDenied, "permission_denied", 7, 403),
+    mapping(ConnectCode::ResourceExhausted, "resource_exhausted", 8, 429),
+    mapping(
+        ConnectCode::FailedPrecondition,
+        "failed_precondition",
+        9,
+        400,
+    ),
+    mapping(ConnectCode::Aborted, "aborted", 10, 409),
+    mapping(ConnectCode::OutOfRange, "out_of_range", 11, 400),
+    mapping(ConnectCode::Unimplemented, "unimplemented", 12, 501),
+    mapping(ConnectCode::Internal, "internal", 13, 500),
+    mapping(ConnectCode::Unavailable, "unavailable", 14, 503),
+    mapping(ConnectCode::DataLoss, "data_loss", 15, 500),
+    mapping(ConnectCode::Unauthenticated, "unauthenticated", 16, 401),
+];
+
+/// HTTP statuses of responses without a Connect error body and the codes
+/// they imply. Any other non-200 status implies [`ConnectCode::Unknown`].
+pub const HTTP_STATUS_CODES: [(u16, ConnectCode); 8] = [
+    (400, ConnectCode::Internal),
+    (401, ConnectCode::Unauthenticated),
+    (403, ConnectCode::PermissionDenied),
+    (404, ConnectCode::Unimplemented),
+    (429, ConnectCode::Unavailable),
+    (502, ConnectCode::Unavailable),
+    (503, ConnectCode::Unavailable),
+    (504, ConnectCode::Unavailable),
+];
+
+/// Returns the mappings for the given code.
+pub const fn lookup(code: ConnectCode) -> &'static CodeMapping {
+    &CODES[code as usize]
+}
+
+/// Returns the code with the given string form, e.g. `not_found`.
+pub fn from_name(name: &str) -> Option<ConnectCode> {
+    CODES.iter().find(|m| m.name == name).map(|m| m.code)
+}
+
+/// Returns the code with the given numeric gRPC status code.
+pub fn from_grpc_code(grpc_code: u32) -> Option<ConnectCode> {
+    CODES.get(grpc_code as usize).map(|m| m.code)
+}
+
+/// Returns the HTTP status for a unary error response with the given code.
+pub fn http_status(code: ConnectCode) -> StatusCode {
+    StatusCode::from_u16(lookup(code).http_status).unwrap()
+}
+
+/// Returns the code implied by the HTTP status of a response without a
+/// Connect error body.
+pub fn from_http_status(status: StatusCode) -> ConnectCode {
+    HTTP_STATUS_CODES
+        .iter()
+        .find(|(s, _)| *s == status.as_u16())
+        .map_or(ConnectCode::Unknown, |(_, code)| *code)
+}
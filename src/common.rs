@@ -1,29 +1,30 @@
-use base64::prelude::{Engine, BASE64_STANDARD_NO_PAD};
-use http::{header, HeaderMap, HeaderName, HeaderValue};
+use std::time::Duration;
 
-use crate::Error;
+use http::{header, HeaderMap, HeaderValue};
 
-pub const CONNECT_PROTOCOL_VERSION: HeaderName =
-    HeaderName::from_static("connect-protocol-version");
-pub const PROTOCOL_VERSION_1: HeaderValue = HeaderValue::from_static("1");
+use crate::{
+    base64,
+    response::error::{ConnectCode, ConnectError},
+    Error,
+};
 
-pub const CONNECT_TIMEOUT_MS: HeaderName = HeaderName::from_static("connect-timeout-ms");
+pub use crate::consts::*;
 
-pub const CONNECT_CONTENT_ENCODING: HeaderName =
-    HeaderName::from_static("connect-content-encoding");
-pub const CONNECT_ACCEPT_ENCODING: HeaderName = HeaderName::from_static("connect-accept-encoding");
-pub const CONTENT_ENCODING_IDENTITY: HeaderValue = HeaderValue::from_static("identity");
-
-pub const CONTENT_TYPE_PREFIX: &str = "application/";
-pub const STREAMING_CONTENT_TYPE_PREFIX: &str = "application/connect+";
-pub const STREAMING_CONTENT_SUBTYPE_PREFIX: &str = "connect+";
+/// Returns true if a content encoding is `identity` (no compression),
+/// ignoring case and surrounding whitespace.
+pub fn is_identity_encoding(encoding: impl AsRef<[u8]>) -> bool {
+    encoding
+        .as_ref()
+        .trim_ascii()
+        .eq_ignore_ascii_case(CONTENT_ENCODING_IDENTITY.as_bytes())
+}
 
 pub fn base64_encode(input: impl AsRef<[u8]>) -> String {
-    BASE64_STANDARD_NO_PAD.encode(input)
+    base64::config().metadata.encode(input)
 }
 
 pub fn base64_decode(b64: impl AsRef<[u8]>) -> Result<Vec<u8>, Error> {
-    Ok(BASE64_STANDARD_NO_PAD.decode(b64)?)
+    base64::config().metadata.decode(b64)
 }
 
 pub fn is_valid_http_token(s: &str) -> bool {
@@ -49,16 +50,56 @@ pub fn unary_message_codec(headers: &HeaderMap) -> Result<&str, Error> {
 
 pub fn streaming_message_codec(headers: &HeaderMap) -> Result<&str, Error> {
     content_type(headers)?
-        .strip_prefix(STREAMING_CONTENT_SUBTYPE_PREFIX)
+        .strip_prefix(STREAMING_CONTENT_TYPE_PREFIX)
         .ok_or(Error::invalid_request(
             "streaming content-type must start with 'application/connect+'",
         ))
 }
 
+/// Returns the `connect-timeout-ms` timeout, ignoring invalid values.
+pub fn request_timeout(headers: &HeaderMap) -> Option<Duration> {
+    parse_timeout(headers.get(CONNECT_TIMEOUT_MS)?).ok()
+}
+
+/// Parses a `connect-timeout-ms` value, which must be 1 to 10 ASCII digits.
+pub fn parse_timeout(value: &HeaderValue) -> Result<Duration, Error> {
+    let digits = value.as_bytes();
+    if digits.is_empty() || digits.len() > 10 || !digits.iter().all(u8::is_ascii_digit) {
+        return Err(Error::ConnectError(ConnectError::new(
+            ConnectCode::InvalidArgument,
+            format!("{CONNECT_TIMEOUT_MS} must be 1 to 10 digits; got {value:?}"),
+        )));
+    }
+    let timeout_ms = digits
+        .iter()
+        .fold(0, |ms: u64, digit| ms * 10 + u64::from(digit - b'0'));
+    Ok(Duration::from_millis(timeout_ms))
+}
+
+/// Returns the `content-type` media type, without any parameters.
 fn content_type(headers: &HeaderMap) -> Result<&str, Error> {
-    headers
+    let content_type = headers
         .get(header::CONTENT_TYPE)
         .ok_or(Error::invalid_request("missing content-type"))?
         .to_str()
-        .map_err(|_| Error::invalid_request("invalid content-type"))
+        .map_err(|_| Error::invalid_request("invalid content-type"))?;
+    Ok(content_type
+        .split_once(';')
+        .map_or(content_type, |(media_type, _)| media_type)
+        .trim())
+}
+
+/// Returns the `content-type` parameters, e.g. `("charset", "utf-8")`.
+pub fn content_type_params(headers: &HeaderMap) -> impl Iterator<Item = (&str, &str)> {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|content_type| content_type.split_once(';'))
+        .map(|(_, params)| params)
+        .unwrap_or_default()
+        .split(';')
+        .filter_map(|param| {
+            let (name, value) = param.split_once('=')?;
+            Some((name.trim(), value.trim().trim_matches('"')))
+        })
 }
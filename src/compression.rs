@@ -0,0 +1,214 @@
+//! Message compression.
+//!
+//! See: https://connectrpc.com/docs/protocol/#unary-request
+
+use std::sync::Arc;
+
+use bytes::Bytes;
+
+use crate::{
+    response::error::{ConnectCode, ConnectError},
+    Error,
+};
+
+/// A content coding used to compress messages.
+pub trait Compression: Send + Sync {
+    /// Returns the content coding name, e.g. `gzip`.
+    fn name(&self) -> &'static str;
+
+    /// Compresses the given data.
+    fn compress(&self, data: &[u8]) -> Result<Bytes, Error>;
+
+    /// Decompresses the given data.
+    ///
+    /// Returns a `resource_exhausted` error if the decompressed data would
+    /// exceed `limit` bytes.
+    fn decompress(&self, data: &[u8], limit: usize) -> Result<Bytes, Error>;
+}
+
+/// Returns a built-in [`Compression`] by name, if supported.
+///
+/// Which codings are supported depends on enabled crate features.
+pub fn lookup(name: &str) -> Option<Arc<dyn Compression>> {
+    match name {
+        "identity" => Some(Arc::new(Identity)),
+        #[cfg(feature = "gzip")]
+        "gzip" => Some(Arc::new(Gzip)),
+        _ => None,
+    }
+}
+
+/// Returns the names of all supported built-in [`Compression`]s, in order of
+/// preference.
+pub fn supported() -> &'static [&'static str] {
+    &[
+        #[cfg(feature = "gzip")]
+        "gzip",
+        "identity",
+    ]
+}
+
+/// A parsed accept encoding header, e.g. `gzip;q=0.8, br;q=1.0, *;q=0`.
+///
+/// See: https://www.rfc-editor.org/rfc/rfc9110#name-accept-encoding
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AcceptEncoding(Vec<EncodingWeight>);
+
+/// A content coding (or `*`) and its weight, from 0 to 1.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EncodingWeight {
+    pub encoding: String,
+    pub q: f32,
+}
+
+impl AcceptEncoding {
+    /// Parses comma-separated accept encoding header values. Invalid entries
+    /// are skipped.
+    pub fn parse<'a>(values: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut weights: Vec<EncodingWeight> = values
+            .into_iter()
+            .flat_map(|value| value.split(','))
+            .filter_map(|entry| {
+                let mut parts = entry.split(';').map(str::trim);
+                let encoding = parts.next().filter(|enc| !enc.is_empty())?;
+                let mut q = 1.0;
+                for param in parts {
+                    if let Some((name, value)) = param.split_once('=') {
+                        if name.trim().eq_ignore_ascii_case("q") {
+                            q = value
+                                .trim()
+                                .parse()
+                                .ok()
+                                .filter(|q| (0.0..=1.0).contains(q))?;
+                        }
+                    }
+                }
+                Some(EncodingWeight {
+                    encoding: encoding.to_ascii_lowercase(),
+                    q,
+                })
+            })
+            .collect();
+        // Stable, so equal weights keep their listed order.
+        weights.sort_by(|a, b| b.q.total_cmp(&a.q));
+        Self(weights)
+    }
+
+    /// Returns the entries, by descending weight.
+    pub fn iter(&self) -> impl Iterator<Item = &EncodingWeight> {
+        self.0.iter()
+    }
+
+    /// Returns the weight of an encoding, from its own entry or else a `*`
+    /// entry. `identity` is acceptable unless explicitly excluded.
+    pub fn weight(&self, encoding: &str) -> f32 {
+        let find = |name: &str| {
+            self.0
+                .iter()
+                .find(|weight| weight.encoding.eq_ignore_ascii_case(name))
+                .map(|weight| weight.q)
+        };
+        find(encoding).or_else(|| find("*")).unwrap_or(
+            if encoding.eq_ignore_ascii_case("identity") {
+                1.0
+            } else {
+                0.0
+            },
+        )
+    }
+
+    /// Returns true if the encoding has a nonzero weight.
+    pub fn accepts(&self, encoding: &str) -> bool {
+        self.weight(encoding) > 0.0
+    }
+
+    /// Returns the most preferred of the `supported` encodings (ties going to
+    /// the first), if any is acceptable.
+    pub fn preferred<'a>(&self, supported: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+        supported
+            .into_iter()
+            .map(|encoding| (encoding, self.weight(encoding)))
+            .filter(|(_, q)| *q > 0.0)
+            .fold(
+                None,
+                |best: Option<(&str, f32)>, (encoding, q)| match best {
+                    Some((_, best_q)) if best_q >= q => best,
+                    _ => Some((encoding, q)),
+                },
+            )
+            .map(|(encoding, _)| encoding)
+    }
+}
+
+pub(crate) fn limit_exceeded() -> Error {
+    Error::ConnectError(ConnectError::new(
+        ConnectCode::ResourceExhausted,
+        "message exceeds size limit",
+    ))
+}
+
+/// The `identity` coding (no compression).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Identity;
+
+impl Compression for Identity {
+    fn name(&self) -> &'static str {
+        "identity"
+    }
+
+    fn compress(&self, data: &[u8]) -> Result<Bytes, Error> {
+        Ok(Bytes::copy_from_slice(data))
+    }
+
+    fn decompress(&self, data: &[u8], limit: usize) -> Result<Bytes, Error> {
+        if data.len() > limit {
+            return Err(limit_exceeded());
+        }
+        Ok(Bytes::copy_from_slice(data))
+    }
+}
+
+/// The `gzip` coding.
+#[cfg(feature = "gzip")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Gzip;
+
+#[cfg(feature = "gzip")]
+impl Compression for Gzip {
+    fn name(&self) -> &'static str {
+        "gzip"
+    }
+
+    fn compress(&self, data: &[u8]) -> Result<Bytes, Error> {
+        use std::io::Write;
+
+        let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(data).map_err(compression_error)?;
+        Ok(encoder.finish().map_err(compression_error)?.into())
+    }
+
+    fn decompress(&self, data: &[u8], limit: usize) -> Result<Bytes, Error> {
+        read_limited(flate2::read::GzDecoder::new(data), limit)
+    }
+}
+
+/// Reads a decompressing reader to the end, up to `limit` bytes.
+#[cfg(feature = "gzip")]
+fn read_limited(reader: impl std::io::Read, limit: usize) -> Result<Bytes, Error> {
+    use std::io::Read;
+
+    let mut buf = vec![];
+    reader
+        .take((limit as u64).saturating_add(1))
+        .read_to_end(&mut buf)
+        .map_err(compression_error)?;
+    if buf.len() > limit {
+        return Err(limit_exceeded());
+    }
+    Ok(buf.into())
+}
+
+#[cfg(feature = "gzip")]
+fn compression_error(err: impl Into<crate::BoxError>) -> Error {
+    Error::CompressionError(err.into())
+}
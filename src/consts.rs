@@ -0,0 +1,32 @@
+//! Connect protocol header names and values.
+//!
+//! See: https://connectrpc.com/docs/protocol/
+
+use http::{HeaderName, HeaderValue};
+
+/// The `connect-protocol-version` header, required on unary requests.
+pub const CONNECT_PROTOCOL_VERSION: HeaderName =
+    HeaderName::from_static("connect-protocol-version");
+/// The only defined `connect-protocol-version` value.
+pub const PROTOCOL_VERSION_1: HeaderValue = HeaderValue::from_static("1");
+
+/// The `connect-timeout-ms` header, the call's timeout in milliseconds.
+pub const CONNECT_TIMEOUT_MS: HeaderName = HeaderName::from_static("connect-timeout-ms");
+
+/// The `connect-content-encoding` header, the compression of streaming
+/// messages.
+pub const CONNECT_CONTENT_ENCODING: HeaderName =
+    HeaderName::from_static("connect-content-encoding");
+/// The `connect-accept-encoding` header, the compressions accepted for
+/// streaming messages.
+pub const CONNECT_ACCEPT_ENCODING: HeaderName = HeaderName::from_static("connect-accept-encoding");
+/// The `identity` (uncompressed) content encoding.
+pub const CONTENT_ENCODING_IDENTITY: HeaderValue = HeaderValue::from_static("identity");
+
+/// The unary `content-type` prefix, followed by the message codec.
+pub const CONTENT_TYPE_PREFIX: &str = "application/";
+/// The streaming `content-type` prefix, followed by the message codec.
+pub const STREAMING_CONTENT_TYPE_PREFIX: &str = "application/connect+";
+/// The streaming `content-type` subtype prefix, followed by the message
+/// codec.
+pub const STREAMING_CONTENT_SUBTYPE_PREFIX: &str = "connect+";
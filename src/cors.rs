@@ -0,0 +1,221 @@
+//! CORS support for Connect servers.
+//!
+//! Browsers send a preflight `OPTIONS` request before most Connect RPCs,
+//! since Connect uses non-safelisted headers (e.g. `connect-protocol-version`)
+//! and content types (e.g. `application/proto`). Unary GET requests carry
+//! everything in query params and are usually "simple" requests that skip the
+//! preflight, but their responses still need CORS headers.
+//!
+//! ```no_run
+//! # use connect_rpc::{cors::Cors, Error};
+//! # async fn handle(req: &http::Request<()>) -> http::Response<()> { unimplemented!() }
+//! # async fn example(req: http::Request<()>) -> Result<http::Response<()>, Error> {
+//! let cors = Cors::allow_origins(["https://app.example.com"])?.expose_header("x-request-id")?;
+//! if Cors::is_preflight(&req) {
+//!     return Ok(cors.preflight_response(&req));
+//! }
+//! let mut resp = handle(&req).await;
+//! cors.apply(req.headers(), resp.headers_mut());
+//! # Ok(resp)
+//! # }
+//! ```
+//!
+//! See: https://connectrpc.com/docs/cors/
+
+use std::time::Duration;
+
+use http::{header, HeaderMap, HeaderName, HeaderValue, Method, StatusCode};
+
+use crate::{
+    common::{
+        CONNECT_ACCEPT_ENCODING, CONNECT_CONTENT_ENCODING, CONNECT_PROTOCOL_VERSION,
+        CONNECT_TIMEOUT_MS,
+    },
+    Error,
+};
+
+/// Request headers used by Connect (and gRPC-Web) clients.
+pub const ALLOWED_HEADERS: [HeaderName; 8] = [
+    header::CONTENT_TYPE,
+    CONNECT_PROTOCOL_VERSION,
+    CONNECT_TIMEOUT_MS,
+    CONNECT_CONTENT_ENCODING,
+    CONNECT_ACCEPT_ENCODING,
+    HeaderName::from_static("grpc-timeout"),
+    HeaderName::from_static("x-grpc-web"),
+    HeaderName::from_static("x-user-agent"),
+];
+
+/// Response headers that Connect (and gRPC-Web) clients need to read.
+pub const EXPOSED_HEADERS: [HeaderName; 5] = [
+    header::CONTENT_ENCODING,
+    CONNECT_CONTENT_ENCODING,
+    HeaderName::from_static("grpc-status"),
+    HeaderName::from_static("grpc-message"),
+    HeaderName::from_static("grpc-status-details-bin"),
+];
+
+/// The default `access-control-max-age` for preflight responses.
+pub const DEFAULT_MAX_AGE: Duration = Duration::from_secs(2 * 60 * 60);
+
+/// A CORS policy for a Connect server.
+///
+/// Application metadata headers must be added with [`Self::allow_header`]
+/// and [`Self::expose_header`] (for unary responses, trailers are sent as
+/// `trailer-` prefixed headers, which must be exposed under that name).
+#[derive(Clone, Debug)]
+pub struct Cors {
+    origins: Option<Vec<HeaderValue>>,
+    allowed_headers: Vec<HeaderName>,
+    exposed_headers: Vec<HeaderName>,
+    allow_credentials: bool,
+    max_age: Duration,
+}
+
+impl Cors {
+    /// Returns a policy allowing requests from any origin.
+    pub fn allow_any_origin() -> Self {
+        Self {
+            origins: None,
+            allowed_headers: ALLOWED_HEADERS.to_vec(),
+            exposed_headers: EXPOSED_HEADERS.to_vec(),
+            allow_credentials: false,
+            max_age: DEFAULT_MAX_AGE,
+        }
+    }
+
+    /// Returns a policy allowing requests from the given origins, e.g.
+    /// `https://app.example.com`.
+    pub fn allow_origins<T: TryInto<HeaderValue, Error: Into<Error>>>(
+        origins: impl IntoIterator<Item = T>,
+    ) -> Result<Self, Error> {
+        let origins = origins
+            .into_iter()
+            .map(|origin| origin.try_into().map_err(Into::into))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            origins: Some(origins),
+            ..Self::allow_any_origin()
+        })
+    }
+
+    /// Allows an additional request header, e.g. application metadata.
+    pub fn allow_header(
+        mut self,
+        name: impl TryInto<HeaderName, Error: Into<Error>>,
+    ) -> Result<Self, Error> {
+        self.allowed_headers
+            .push(name.try_into().map_err(Into::into)?);
+        Ok(self)
+    }
+
+    /// Exposes an additional response header, e.g. application metadata.
+    pub fn expose_header(
+        mut self,
+        name: impl TryInto<HeaderName, Error: Into<Error>>,
+    ) -> Result<Self, Error> {
+        self.exposed_headers
+            .push(name.try_into().map_err(Into::into)?);
+        Ok(self)
+    }
+
+    /// Sets whether to allow credentials (cookies, HTTP auth). Responses then
+    /// echo the request origin instead of `*`.
+    pub fn allow_credentials(mut self, allow_credentials: bool) -> Self {
+        self.allow_credentials = allow_credentials;
+        self
+    }
+
+    /// Sets how long browsers may cache preflight responses.
+    ///
+    /// Defaults to [`DEFAULT_MAX_AGE`].
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Returns true if the request is a CORS preflight request.
+    pub fn is_preflight<T>(req: &http::Request<T>) -> bool {
+        req.method() == Method::OPTIONS
+            && req.headers().contains_key(header::ORIGIN)
+            && req
+                .headers()
+                .contains_key(header::ACCESS_CONTROL_REQUEST_METHOD)
+    }
+
+    /// Returns the response to a preflight request.
+    ///
+    /// If the origin isn't allowed, the response has no CORS headers, so the
+    /// browser won't send the actual request.
+    pub fn preflight_response<T>(&self, req: &http::Request<T>) -> http::Response<()> {
+        let mut resp = http::Response::new(());
+        *resp.status_mut() = StatusCode::NO_CONTENT;
+        let headers = resp.headers_mut();
+        headers.insert(
+            header::VARY,
+            HeaderValue::from_static(
+                "origin, access-control-request-method, access-control-request-headers",
+            ),
+        );
+        if !self.allow_origin(req.headers(), headers) {
+            return resp;
+        }
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_METHODS,
+            HeaderValue::from_static("GET, POST"),
+        );
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_HEADERS,
+            join(&self.allowed_headers),
+        );
+        headers.insert(
+            header::ACCESS_CONTROL_MAX_AGE,
+            self.max_age.as_secs().into(),
+        );
+        resp
+    }
+
+    /// Adds CORS headers to the response to an actual (non-preflight)
+    /// request.
+    pub fn apply(&self, req_headers: &HeaderMap, resp_headers: &mut HeaderMap) {
+        resp_headers.append(header::VARY, HeaderValue::from_static("origin"));
+        if !self.allow_origin(req_headers, resp_headers) {
+            return;
+        }
+        resp_headers.insert(
+            header::ACCESS_CONTROL_EXPOSE_HEADERS,
+            join(&self.exposed_headers),
+        );
+    }
+
+    /// Sets `access-control-allow-origin` (and `-credentials`) if the request
+    /// origin is allowed, returning whether it is.
+    fn allow_origin(&self, req_headers: &HeaderMap, resp_headers: &mut HeaderMap) -> bool {
+        let Some(origin) = req_headers.get(header::ORIGIN) else {
+            return false;
+        };
+        let allow_origin = match &self.origins {
+            Some(origins) if !origins.contains(origin) => return false,
+            None if !self.allow_credentials => HeaderValue::from_static("*"),
+            _ => origin.clone(),
+        };
+        resp_headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, allow_origin);
+        if self.allow_credentials {
+            resp_headers.insert(
+                header::ACCESS_CONTROL_ALLOW_CREDENTIALS,
+                HeaderValue::from_static("true"),
+            );
+        }
+        true
+    }
+}
+
+fn join(names: &[HeaderName]) -> HeaderValue {
+    let joined = names
+        .iter()
+        .map(HeaderName::as_str)
+        .collect::<Vec<_>>()
+        .join(", ");
+    // Header names are always valid header values.
+    HeaderValue::try_from(joined).unwrap()
+}
@@ -0,0 +1,232 @@
+//! A C ABI for using [`ConnectClient`] from non-Rust applications.
+//!
+//! To produce a linkable library, depend on this crate with the `ffi`
+//! feature from a `cdylib` or `staticlib` crate and re-export this module
+//! with `pub use connect_rpc::ffi::*;`. A matching C header is provided in
+//! `include/connect_rpc.h`.
+//!
+//! Calls block the calling thread until they complete. Connect codes are
+//! returned as their gRPC numeric values, with `0` meaning success.
+
+use std::{
+    ffi::{c_char, c_void, CStr, CString},
+    ptr,
+};
+
+use bytes::Bytes;
+
+use crate::{
+    blocking::BlockingClient,
+    codes,
+    request::builder::RequestBuilder,
+    response::error::{ConnectCode, ConnectError},
+    stream::ConnectFrame,
+    Error,
+};
+
+/// An opaque Connect client handle.
+pub struct ConnectFfiClient {
+    client: BlockingClient,
+}
+
+/// A byte buffer owned by this library.
+///
+/// Must be freed with [`connect_buffer_free`].
+#[repr(C)]
+pub struct ConnectBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+impl ConnectBuffer {
+    fn new(data: impl Into<Vec<u8>>) -> Self {
+        let data = Box::into_raw(data.into().into_boxed_slice());
+        Self {
+            len: data.len(),
+            data: data.cast(),
+        }
+    }
+}
+
+/// A callback receiving each response message of a streaming call.
+///
+/// The message data is only valid for the duration of the callback.
+pub type ConnectMessageCallback =
+    extern "C" fn(user_data: *mut c_void, data: *const u8, len: usize);
+
+/// Creates a new client. Returns null on failure.
+///
+/// The client must be freed with [`connect_client_free`].
+#[no_mangle]
+pub extern "C" fn connect_client_new() -> *mut ConnectFfiClient {
+    let client = match BlockingClient::new() {
+        Ok(client) => client,
+        Err(err) => {
+            tracing::debug!(?err, "Failed to build client");
+            return ptr::null_mut();
+        }
+    };
+    Box::into_raw(Box::new(ConnectFfiClient { client }))
+}
+
+/// Frees a client created by [`connect_client_new`].
+///
+/// # Safety
+///
+/// `client` must be null or a pointer returned by [`connect_client_new`]
+/// that has not already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn connect_client_free(client: *mut ConnectFfiClient) {
+    if !client.is_null() {
+        drop(Box::from_raw(client));
+    }
+}
+
+/// Executes a unary call.
+///
+/// `url` is the full RPC URL (e.g. `https://example.com/pkg.Service/Method`)
+/// and `codec` the message codec (e.g. `proto`). A `timeout_ms` of `0` means
+/// no timeout.
+///
+/// On success, returns `0` and writes the response message to `resp_out`. On
+/// failure, returns the Connect code and, if `error_message_out` is not null,
+/// writes an error message to it which must be freed with
+/// [`connect_string_free`].
+///
+/// # Safety
+///
+/// `client` must be a valid client pointer; `url` and `codec` must be valid
+/// NUL-terminated strings; `req_data` must point to `req_len` readable bytes
+/// (or be null if `req_len` is `0`); `resp_out` must be valid for writes.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn connect_unary_call(
+    client: *const ConnectFfiClient,
+    url: *const c_char,
+    codec: *const c_char,
+    timeout_ms: u64,
+    req_data: *const u8,
+    req_len: usize,
+    resp_out: *mut ConnectBuffer,
+    error_message_out: *mut *mut c_char,
+) -> i32 {
+    let client = &*client;
+    let result = request_builder(url, codec, timeout_ms).and_then(|builder| {
+        let body = Bytes::copy_from_slice(byte_slice(req_data, req_len));
+        let req = builder.unary(body)?;
+        client.client.execute_unary(req)
+    });
+    match result {
+        Ok(resp) => {
+            resp_out.write(ConnectBuffer::new(resp.body().to_vec()));
+            0
+        }
+        Err(err) => write_error(err.into(), error_message_out),
+    }
+}
+
+/// Executes a server-streaming call, invoking `callback` with each response
+/// message.
+///
+/// Arguments and return values are as for [`connect_unary_call`].
+///
+/// # Safety
+///
+/// See [`connect_unary_call`]. `callback` is called from the calling thread
+/// with the given `user_data`.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn connect_server_stream_call(
+    client: *const ConnectFfiClient,
+    url: *const c_char,
+    codec: *const c_char,
+    timeout_ms: u64,
+    req_data: *const u8,
+    req_len: usize,
+    callback: ConnectMessageCallback,
+    user_data: *mut c_void,
+    error_message_out: *mut *mut c_char,
+) -> i32 {
+    let client = &*client;
+    let result = request_builder(url, codec, timeout_ms).and_then(|builder| {
+        let frame = ConnectFrame {
+            compressed: false,
+            end: false,
+            data: Bytes::copy_from_slice(byte_slice(req_data, req_len)),
+        };
+        let req = builder.streaming(frame.encode()?)?;
+        for msg in client.client.execute_server_stream(req)? {
+            let msg = msg?;
+            callback(user_data, msg.as_ptr(), msg.len());
+        }
+        Ok(())
+    });
+    match result {
+        Ok(()) => 0,
+        Err(err) => write_error(err.into(), error_message_out),
+    }
+}
+
+/// Frees a buffer returned by this library.
+///
+/// # Safety
+///
+/// `buf` must have been returned by this library and not already freed.
+#[no_mangle]
+pub unsafe extern "C" fn connect_buffer_free(buf: ConnectBuffer) {
+    if !buf.data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+            buf.data, buf.len,
+        )));
+    }
+}
+
+/// Frees a string returned by this library.
+///
+/// # Safety
+///
+/// `s` must be null or a string returned by this library that has not
+/// already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn connect_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+unsafe fn request_builder(
+    url: *const c_char,
+    codec: *const c_char,
+    timeout_ms: u64,
+) -> Result<RequestBuilder, Error> {
+    let url = CStr::from_ptr(url)
+        .to_str()
+        .map_err(|_| Error::invalid_request("url not valid utf8"))?;
+    let codec = CStr::from_ptr(codec)
+        .to_str()
+        .map_err(|_| Error::invalid_request("codec not valid utf8"))?;
+    let mut builder = RequestBuilder::default().uri(url)?.message_codec(codec)?;
+    if timeout_ms > 0 {
+        builder = builder.timeout_ms(timeout_ms)?;
+    }
+    Ok(builder)
+}
+
+unsafe fn byte_slice<'a>(data: *const u8, len: usize) -> &'a [u8] {
+    if len == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(data, len)
+    }
+}
+
+unsafe fn write_error(err: ConnectError, error_message_out: *mut *mut c_char) -> i32 {
+    if !error_message_out.is_null() {
+        let message = CString::new(err.to_string().replace('\0', "")).unwrap_or_default();
+        error_message_out.write(message.into_raw());
+    }
+    match err.code() {
+        ConnectCode::Ok => codes::lookup(ConnectCode::Unknown).grpc_code as i32,
+        code => codes::lookup(code).grpc_code as i32,
+    }
+}
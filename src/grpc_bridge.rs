@@ -0,0 +1,301 @@
+//! Translation between the Connect and gRPC protocols.
+//!
+//! These are the building blocks of a Connect-to-gRPC bridge: translate an
+//! inbound Connect request with [`grpc_request`], send it to a gRPC upstream
+//! over HTTP/2 with any client, and translate the upstream response back with
+//! [`connect_response`].
+//!
+//! Connect and gRPC share the same envelope format for streaming messages, so
+//! streaming request bodies are passed through unchanged. Error details
+//! (`grpc-status-details-bin`) are not translated.
+//!
+//! See: https://connectrpc.com/docs/protocol/ and
+//! https://github.com/grpc/grpc/blob/master/doc/PROTOCOL-HTTP2.md
+
+use std::time::Duration;
+
+use bytes::{Bytes, BytesMut};
+use futures_util::{stream, TryStreamExt};
+use http::{header, uri::PathAndQuery, HeaderMap, HeaderName, HeaderValue, Method, StatusCode};
+use http_body::Body;
+use http_body_util::BodyExt;
+
+use crate::{
+    codes,
+    common::{
+        CONNECT_ACCEPT_ENCODING, CONNECT_CONTENT_ENCODING, CONNECT_PROTOCOL_VERSION,
+        CONNECT_TIMEOUT_MS, CONTENT_TYPE_PREFIX, STREAMING_CONTENT_TYPE_PREFIX,
+    },
+    metadata::Metadata,
+    request::{ConnectRequest, ConnectRequestType},
+    response::error::{ConnectCode, ConnectError},
+    stream::{ConnectFrame, EndStreamResponse},
+    BoxError, Error,
+};
+
+const GRPC_CONTENT_TYPE: &str = "application/grpc";
+const GRPC_TIMEOUT: HeaderName = HeaderName::from_static("grpc-timeout");
+const GRPC_ENCODING: HeaderName = HeaderName::from_static("grpc-encoding");
+const GRPC_ACCEPT_ENCODING: HeaderName = HeaderName::from_static("grpc-accept-encoding");
+const GRPC_STATUS: HeaderName = HeaderName::from_static("grpc-status");
+const GRPC_MESSAGE: HeaderName = HeaderName::from_static("grpc-message");
+const GRPC_STATUS_DETAILS: HeaderName = HeaderName::from_static("grpc-status-details-bin");
+
+const TRAILER_PREFIX: &str = "trailer-";
+
+/// Translates a Connect request to a gRPC request.
+///
+/// Unary (POST and GET) messages are enveloped; streaming bodies are passed
+/// through. The request URI is kept, so callers forwarding to a different
+/// upstream should rewrite its authority.
+pub fn grpc_request(req: ConnectRequestType<Bytes>) -> Result<http::Request<Bytes>, Error> {
+    let (parts, body) = match req {
+        ConnectRequestType::Unary(req) => {
+            let headers = grpc_headers(&req)?;
+            let compressed = req
+                .content_encoding()
+                .is_some_and(|encoding| encoding != "identity");
+            let (mut parts, body) = http::Request::from(req).into_parts();
+            parts.headers = headers;
+            let frame = ConnectFrame {
+                compressed,
+                end: false,
+                data: body,
+            };
+            (parts, frame.encode()?)
+        }
+        ConnectRequestType::Streaming(req) => {
+            let headers = grpc_headers(&req)?;
+            let (mut parts, body) = http::Request::from(req).into_parts();
+            parts.headers = headers;
+            (parts, body)
+        }
+        ConnectRequestType::UnaryGet(req) => {
+            let mut headers = grpc_headers(&req)?;
+            // The message is decompressed; send it uncompressed.
+            headers.remove(GRPC_ENCODING);
+            let frame = ConnectFrame {
+                compressed: false,
+                end: false,
+                data: req.message()?.into_owned().into(),
+            };
+            let (mut parts, ()) = http::Request::from(req).into_parts();
+            parts.headers = headers;
+            parts.method = Method::POST;
+            let mut uri = parts.uri.into_parts();
+            uri.path_and_query = uri
+                .path_and_query
+                .map(|path_and_query| PathAndQuery::try_from(path_and_query.path()))
+                .transpose()?;
+            parts.uri = uri.try_into()?;
+            (parts, frame.encode()?)
+        }
+    };
+    Ok(http::Request::from_parts(parts, body))
+}
+
+fn grpc_headers(req: &impl ConnectRequest) -> Result<HeaderMap, Error> {
+    let mut headers: HeaderMap = req
+        .metadata()
+        .iter_raw()
+        .filter(|(key, _)| !is_connect_reserved(key))
+        .map(|(key, val)| Ok((HeaderName::try_from(key)?, HeaderValue::from_bytes(val)?)))
+        .collect::<Result<_, Error>>()?;
+    headers.insert(
+        header::CONTENT_TYPE,
+        format!("{GRPC_CONTENT_TYPE}+{}", req.message_codec()?).try_into()?,
+    );
+    headers.insert(header::TE, HeaderValue::from_static("trailers"));
+    if let Some(timeout) = req.timeout() {
+        headers.insert(GRPC_TIMEOUT, grpc_timeout(timeout).try_into()?);
+    }
+    if let Some(encoding) = req.content_encoding() {
+        if encoding != "identity" {
+            headers.insert(GRPC_ENCODING, encoding.try_into()?);
+        }
+    }
+    let accept_encoding = req.accept_encoding().collect::<Vec<_>>().join(",");
+    if !accept_encoding.is_empty() {
+        headers.insert(GRPC_ACCEPT_ENCODING, accept_encoding.try_into()?);
+    }
+    Ok(headers)
+}
+
+fn is_connect_reserved(key: &str) -> bool {
+    [
+        header::CONTENT_TYPE,
+        header::CONTENT_LENGTH,
+        header::CONTENT_ENCODING,
+        header::ACCEPT_ENCODING,
+        CONNECT_PROTOCOL_VERSION,
+        CONNECT_TIMEOUT_MS,
+        CONNECT_CONTENT_ENCODING,
+        CONNECT_ACCEPT_ENCODING,
+    ]
+    .iter()
+    .any(|name| name == key)
+}
+
+/// Formats a `grpc-timeout`, which allows at most 8 digits.
+fn grpc_timeout(timeout: Duration) -> String {
+    let millis = timeout.as_millis();
+    if millis < 100_000_000 {
+        format!("{millis}m")
+    } else {
+        format!("{}S", timeout.as_secs().min(99_999_999))
+    }
+}
+
+/// Translates a gRPC response to a Connect response.
+///
+/// `streaming` must match the original request. The response body is
+/// buffered, since unary Connect responses carry trailers as headers.
+pub async fn connect_response<B>(
+    resp: http::Response<B>,
+    streaming: bool,
+) -> Result<http::Response<Bytes>, Error>
+where
+    B: Body<Error: Into<BoxError>>,
+{
+    let (parts, body) = resp.into_parts();
+    let collected = body.collect().await.map_err(Error::body)?;
+    let trailers = collected.trailers().cloned().unwrap_or_default();
+    let data = collected.to_bytes();
+
+    let error = if parts.status != StatusCode::OK {
+        Some(ConnectError::new(
+            codes::from_http_status(parts.status),
+            format!("upstream returned HTTP status {}", parts.status),
+        ))
+    } else {
+        grpc_status_error(&parts.headers, &trailers)
+    };
+
+    let codec = parts
+        .headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|content_type| content_type.strip_prefix(GRPC_CONTENT_TYPE))
+        .map(|subtype| subtype.strip_prefix('+').unwrap_or("proto"))
+        .filter(|codec| !codec.is_empty())
+        .unwrap_or("proto")
+        .to_string();
+    let encoding = parts.headers.get(GRPC_ENCODING).cloned();
+    let frames: Vec<ConnectFrame> =
+        ConnectFrame::bytes_stream(stream::iter([Ok::<_, Error>(data)]))
+            .try_collect()
+            .await?;
+
+    let mut headers = without_grpc_reserved(&parts.headers);
+    let trailers = without_grpc_reserved(&trailers);
+    let mut resp = if streaming {
+        let mut body = BytesMut::new();
+        for frame in &frames {
+            body.extend_from_slice(&frame.encode()?);
+        }
+        let end = EndStreamResponse::new(error, &trailers).to_frame()?;
+        body.extend_from_slice(&end.encode()?);
+        headers.insert(
+            header::CONTENT_TYPE,
+            format!("{STREAMING_CONTENT_TYPE_PREFIX}{codec}").try_into()?,
+        );
+        if let Some(encoding) = encoding {
+            headers.insert(CONNECT_CONTENT_ENCODING, encoding);
+        }
+        let mut resp = http::Response::new(body.freeze());
+        *resp.headers_mut() = headers;
+        resp
+    } else {
+        let mut resp = match (error, frames.as_slice()) {
+            (Some(error), _) => unary_error_response(&error)?,
+            (None, [frame]) => {
+                let mut resp = http::Response::new(frame.data.clone());
+                resp.headers_mut().insert(
+                    header::CONTENT_TYPE,
+                    format!("{CONTENT_TYPE_PREFIX}{codec}").try_into()?,
+                );
+                if frame.compressed {
+                    if let Some(encoding) = encoding {
+                        resp.headers_mut()
+                            .insert(header::CONTENT_ENCODING, encoding);
+                    }
+                }
+                resp
+            }
+            (None, frames) => unary_error_response(&ConnectError::new(
+                ConnectCode::Unimplemented,
+                format!("unary response has {} messages", frames.len()),
+            ))?,
+        };
+        resp.headers_mut().extend(headers);
+        for (key, val) in &trailers {
+            let key = HeaderName::try_from(format!("{TRAILER_PREFIX}{key}"))?;
+            resp.headers_mut().append(key, val.clone());
+        }
+        resp
+    };
+    resp.headers_mut().remove(header::CONTENT_LENGTH);
+    Ok(resp)
+}
+
+/// Returns the error described by `grpc-status` and `grpc-message`, which
+/// are in the headers of a "trailers-only" response.
+fn grpc_status_error(headers: &HeaderMap, trailers: &HeaderMap) -> Option<ConnectError> {
+    let status = trailers
+        .get(GRPC_STATUS)
+        .or_else(|| headers.get(GRPC_STATUS));
+    let Some(status) = status else {
+        return Some(ConnectError::new(
+            ConnectCode::Internal,
+            "upstream response missing grpc-status",
+        ));
+    };
+    let code = status
+        .to_str()
+        .ok()
+        .and_then(|status| status.parse().ok())
+        .and_then(ConnectCode::from_i32)
+        .unwrap_or(ConnectCode::Unknown);
+    if code == ConnectCode::Ok {
+        return None;
+    }
+    let message = trailers
+        .get(GRPC_MESSAGE)
+        .or_else(|| headers.get(GRPC_MESSAGE))
+        .map(|message| {
+            percent_encoding::percent_decode(message.as_bytes())
+                .decode_utf8_lossy()
+                .into_owned()
+        })
+        .unwrap_or_default();
+    Some(ConnectError::new(code, message))
+}
+
+fn unary_error_response(error: &ConnectError) -> Result<http::Response<Bytes>, Error> {
+    let body = serde_json::to_vec(error)
+        .map_err(|err| Error::InvalidResponse(format!("invalid error JSON: {err}")))?;
+    let mut resp = http::Response::new(body.into());
+    *resp.status_mut() = codes::http_status(error.code());
+    resp.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    Ok(resp)
+}
+
+fn without_grpc_reserved(headers: &HeaderMap) -> HeaderMap {
+    let mut headers = headers.clone();
+    for name in [
+        header::CONTENT_TYPE,
+        header::CONTENT_LENGTH,
+        header::TE,
+        GRPC_STATUS,
+        GRPC_MESSAGE,
+        GRPC_STATUS_DETAILS,
+        GRPC_ENCODING,
+        GRPC_ACCEPT_ENCODING,
+    ] {
+        headers.remove(name);
+    }
+    headers
+}
@@ -0,0 +1,255 @@
+//! A [`Transport`] backed by a [`hyper_util`] legacy client, for full control
+//! over connectors (e.g. Unix sockets or custom TLS).
+//!
+//! ```no_run
+//! # use connect_rpc::{client::ConnectClient, transport::RequestBody};
+//! # use hyper_util::{client::legacy::connect::HttpConnector, rt::TokioExecutor};
+//! # let connector = HttpConnector::new();
+//! let client = hyper_util::client::legacy::Client::builder(TokioExecutor::new())
+//!     .build::<_, RequestBody>(connector);
+//! let connect_client = ConnectClient::builder().transport(client).build()?;
+//! # Ok::<(), connect_rpc::Error>(())
+//! ```
+//!
+//! Request timeouts are enforced with [`tokio::time`], so calls must be made
+//! within a Tokio runtime with the time driver enabled.
+//!
+//! On Unix, [`UnixSocketTransport`] sends requests over a Unix domain socket,
+//! e.g. to a sidecar or local daemon.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+#[cfg(unix)]
+use std::{path::Path, sync::Arc};
+
+#[cfg(unix)]
+use ::hyper::rt::{Read, ReadBufCursor, Write};
+use futures_util::future::BoxFuture;
+#[cfg(unix)]
+use http::{
+    uri::{Authority, PathAndQuery, Scheme},
+    Uri,
+};
+use http_body::{Body, Frame, SizeHint};
+use http_body_util::BodyExt;
+use hyper_util::client::legacy::{connect::Connect, Client};
+#[cfg(unix)]
+use hyper_util::{
+    client::legacy::connect::{Connected, Connection},
+    rt::{TokioExecutor, TokioIo},
+};
+use tokio::time::{Instant, Sleep};
+#[cfg(unix)]
+use tower_service::Service;
+
+use crate::{
+    common::request_timeout,
+    response::error::{ConnectCode, ConnectError},
+    transport::{RequestBody, ResponseBody, Transport},
+    Error,
+};
+
+impl<C> Transport for Client<C, RequestBody>
+where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    fn round_trip(
+        &self,
+        req: http::Request<RequestBody>,
+    ) -> BoxFuture<'_, Result<http::Response<ResponseBody>, Error>> {
+        let deadline = request_timeout(req.headers()).map(|timeout| Instant::now() + timeout);
+        let resp = self.request(req);
+        Box::pin(async move {
+            let resp = match deadline {
+                Some(deadline) => tokio::time::timeout_at(deadline, resp)
+                    .await
+                    .map_err(|_| timeout_error())??,
+                None => resp.await?,
+            };
+            Ok(resp.map(|body| {
+                let body = body.map_err(Error::body);
+                match deadline {
+                    Some(deadline) => DeadlineBody {
+                        body,
+                        sleep: Box::pin(tokio::time::sleep_until(deadline)),
+                    }
+                    .boxed(),
+                    None => body.boxed(),
+                }
+            }))
+        })
+    }
+}
+
+/// A body that fails with `deadline_exceeded` if not read before a deadline.
+struct DeadlineBody<B> {
+    body: B,
+    sleep: Pin<Box<Sleep>>,
+}
+
+impl<B: Body<Error = Error> + Unpin> Body for DeadlineBody<B> {
+    type Data = B::Data;
+    type Error = Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        if self.sleep.as_mut().poll(cx).is_ready() {
+            return Poll::Ready(Some(Err(timeout_error())));
+        }
+        Pin::new(&mut self.body).poll_frame(cx)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.body.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.body.size_hint()
+    }
+}
+
+fn timeout_error() -> Error {
+    Error::ConnectError(ConnectError::new(
+        ConnectCode::DeadlineExceeded,
+        "request timed out",
+    ))
+}
+
+/// A [`Transport`] that sends all requests over a Unix domain socket.
+///
+/// Request URIs are rewritten to `http://{authority}` (keeping the path and
+/// query), so the `Host` header carries the given pseudo-authority rather
+/// than whatever the request was built with.
+#[cfg(unix)]
+pub struct UnixSocketTransport {
+    client: Client<UnixConnector, RequestBody>,
+    authority: Authority,
+}
+
+#[cfg(unix)]
+impl UnixSocketTransport {
+    pub fn new(path: impl AsRef<Path>, authority: Authority) -> Self {
+        let client = Client::builder(TokioExecutor::new()).build(UnixConnector::new(path));
+        Self { client, authority }
+    }
+}
+
+#[cfg(unix)]
+impl Transport for UnixSocketTransport {
+    fn round_trip(
+        &self,
+        mut req: http::Request<RequestBody>,
+    ) -> BoxFuture<'_, Result<http::Response<ResponseBody>, Error>> {
+        let mut parts = req.uri().clone().into_parts();
+        parts.scheme = Some(Scheme::HTTP);
+        parts.authority = Some(self.authority.clone());
+        if parts.path_and_query.is_none() {
+            parts.path_and_query = Some(PathAndQuery::from_static("/"));
+        }
+        match Uri::from_parts(parts) {
+            Ok(uri) => *req.uri_mut() = uri,
+            Err(err) => return Box::pin(async move { Err(err.into()) }),
+        }
+        self.client.round_trip(req)
+    }
+}
+
+/// A [`hyper_util`] connector that connects to a Unix domain socket,
+/// ignoring the destination URI.
+#[cfg(unix)]
+#[derive(Clone, Debug)]
+pub struct UnixConnector {
+    path: Arc<Path>,
+}
+
+#[cfg(unix)]
+impl UnixConnector {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().into(),
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Service<Uri> for UnixConnector {
+    type Response = UnixStream;
+    type Error = std::io::Error;
+    type Future = BoxFuture<'static, Result<UnixStream, std::io::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _dst: Uri) -> Self::Future {
+        let path = self.path.clone();
+        Box::pin(async move {
+            let stream = tokio::net::UnixStream::connect(&*path).await?;
+            Ok(UnixStream(TokioIo::new(stream)))
+        })
+    }
+}
+
+/// A connection returned by [`UnixConnector`].
+#[cfg(unix)]
+pub struct UnixStream(TokioIo<tokio::net::UnixStream>);
+
+#[cfg(unix)]
+impl Connection for UnixStream {
+    fn connected(&self) -> Connected {
+        Connected::new()
+    }
+}
+
+#[cfg(unix)]
+impl Read for UnixStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: ReadBufCursor<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().0).poll_read(cx, buf)
+    }
+}
+
+#[cfg(unix)]
+impl Write for UnixStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.get_mut().0).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().0).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().0).poll_shutdown(cx)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.0.is_write_vectored()
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[std::io::IoSlice<'_>],
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.get_mut().0).poll_write_vectored(cx, bufs)
+    }
+}
+
+impl From<hyper_util::client::legacy::Error> for Error {
+    fn from(err: hyper_util::client::legacy::Error) -> Self {
+        Self::HyperError(err)
+    }
+}
@@ -0,0 +1,88 @@
+use std::{future::Future, time::Duration};
+
+#[cfg(feature = "tracing")]
+use std::time::Instant;
+
+use crate::{request::ConnectRequest, Error};
+
+/// Instrumentation for a single RPC.
+///
+/// If the `tracing` feature is enabled, the RPC is run in a `tracing` span
+/// recording the request path, codec, and timeout, and (on completion) the
+/// Connect code and latency. Otherwise this is a no-op.
+pub(crate) struct CallInstrument {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+impl CallInstrument {
+    #[cfg(feature = "tracing")]
+    pub(crate) fn new(req: &impl ConnectRequest) -> Self {
+        let span = tracing::info_span!(
+            "connect_rpc",
+            rpc.path = req.path(),
+            rpc.codec = req.message_codec().ok(),
+            rpc.timeout_ms = req.timeout().map(|t| t.as_millis() as u64),
+            rpc.code = tracing::field::Empty,
+            rpc.latency_ms = tracing::field::Empty,
+        );
+        Self { span }
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    pub(crate) fn new(_req: &impl ConnectRequest) -> Self {
+        Self {}
+    }
+
+    #[cfg(feature = "tracing")]
+    pub(crate) async fn run<T>(
+        self,
+        fut: impl Future<Output = Result<T, Error>>,
+    ) -> Result<T, Error> {
+        use tracing::Instrument;
+
+        let start = Instant::now();
+        let result = fut.instrument(self.span.clone()).await;
+        self.record_result(&result, start.elapsed());
+        result
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    pub(crate) async fn run<T>(
+        self,
+        fut: impl Future<Output = Result<T, Error>>,
+    ) -> Result<T, Error> {
+        fut.await
+    }
+
+    /// Records the outcome of a completed RPC.
+    #[cfg(feature = "tracing")]
+    pub(crate) fn record_result<T>(&self, result: &Result<T, Error>, latency: Duration) {
+        let code = match result {
+            Ok(_) => "ok",
+            Err(err) => err.connect_code().as_str(),
+        };
+        self.span.record("rpc.code", code);
+        self.span
+            .record("rpc.latency_ms", latency.as_millis() as u64);
+        match result {
+            Ok(_) => tracing::debug!(parent: &self.span, "RPC completed"),
+            Err(err) => tracing::debug!(parent: &self.span, %err, "RPC failed"),
+        }
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    pub(crate) fn record_result<T>(&self, _result: &Result<T, Error>, _latency: Duration) {}
+
+    /// Records that the RPC was canceled by the caller before completion.
+    #[cfg(feature = "tracing")]
+    pub(crate) fn record_canceled(&self, latency: Duration) {
+        self.span.record("rpc.code", "canceled");
+        self.span
+            .record("rpc.latency_ms", latency.as_millis() as u64);
+        tracing::debug!(parent: &self.span, "RPC canceled");
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    pub(crate) fn record_canceled(&self, _latency: Duration) {}
+}
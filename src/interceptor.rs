@@ -0,0 +1,134 @@
+use std::sync::Arc;
+
+use bytes::Bytes;
+use futures_util::future::BoxFuture;
+
+use crate::{
+    client::RequestSigner,
+    transport::{buffer_response, full_body, Transport},
+    Error,
+};
+
+pub mod auth;
+pub mod cache;
+#[cfg(feature = "opentelemetry")]
+pub mod otel;
+pub mod rate_limit;
+#[cfg(feature = "request-id")]
+pub mod request_id;
+pub mod service_config;
+pub mod vcr;
+
+/// Intercepts RPCs executed by a [`ConnectClient`](crate::client::ConnectClient).
+///
+/// Interceptors are called in the order they were added to the client. Each
+/// interceptor may modify the request, call [`Next::run`] to continue the
+/// chain, and inspect or modify the result.
+pub trait Interceptor: Send + Sync {
+    fn intercept<'a>(
+        &'a self,
+        req: http::Request<Bytes>,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, Result<http::Response<Bytes>, Error>>;
+}
+
+/// The remainder of an interceptor chain.
+///
+/// Clone it to run the rest of the chain more than once, e.g. to retry.
+#[derive(Clone)]
+pub struct Next<'a> {
+    interceptors: &'a [Arc<dyn Interceptor>],
+    signer: Option<&'a dyn RequestSigner>,
+    transport: &'a dyn Transport,
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(
+        interceptors: &'a [Arc<dyn Interceptor>],
+        signer: Option<&'a dyn RequestSigner>,
+        transport: &'a dyn Transport,
+    ) -> Self {
+        Self {
+            interceptors,
+            signer,
+            transport,
+        }
+    }
+
+    /// Runs the rest of the chain, sending the request.
+    pub async fn run(self, mut req: http::Request<Bytes>) -> Result<http::Response<Bytes>, Error> {
+        match self.interceptors.split_first() {
+            Some((interceptor, rest)) => {
+                interceptor
+                    .intercept(req, Next::new(rest, self.signer, self.transport))
+                    .await
+            }
+            None => {
+                if let Some(signer) = self.signer {
+                    signer.sign(&mut req)?;
+                }
+                // Transports needn't preserve extensions, so return the
+                // request's on the response.
+                let extensions = req.extensions().clone();
+                let resp = self.transport.round_trip(req.map(full_body)).await?;
+                let mut resp = buffer_response(resp).await?;
+                resp.extensions_mut().extend(extensions);
+                Ok(resp)
+            }
+        }
+    }
+}
+
+/// Returns a copy of a buffered request, e.g. to send it again.
+pub(crate) fn clone_request(req: &http::Request<Bytes>) -> http::Request<Bytes> {
+    let mut clone = http::Request::new(req.body().clone());
+    *clone.method_mut() = req.method().clone();
+    *clone.uri_mut() = req.uri().clone();
+    *clone.version_mut() = req.version();
+    *clone.headers_mut() = req.headers().clone();
+    *clone.extensions_mut() = req.extensions().clone();
+    clone
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::transport::MemoryTransport;
+
+    use super::*;
+
+    /// Returns a transport that responds with `respond(req, count)`, where
+    /// `count` is the number of requests so far (including this one).
+    pub(crate) fn transport<F>(respond: F) -> MemoryTransport
+    where
+        F: Fn(http::Request<Bytes>, usize) -> Result<http::Response<Bytes>, Error>
+            + Send
+            + Sync
+            + 'static,
+    {
+        let requests = AtomicUsize::new(0);
+        MemoryTransport::new(move |req| {
+            let count = requests.fetch_add(1, Ordering::SeqCst) + 1;
+            std::future::ready(respond(req, count))
+        })
+    }
+
+    /// Returns a request for `path` on `example.com`.
+    pub(crate) fn request(path: &str, body: &'static str) -> http::Request<Bytes> {
+        let mut req = http::Request::new(Bytes::from_static(body.as_bytes()));
+        *req.uri_mut() = format!("http://example.com{path}").parse().unwrap();
+        req
+    }
+
+    /// Sends `req` through `interceptor` to `transport`.
+    pub(crate) async fn call(
+        interceptor: &dyn Interceptor,
+        transport: &MemoryTransport,
+        req: http::Request<Bytes>,
+    ) -> Result<http::Response<Bytes>, Error> {
+        interceptor
+            .intercept(req, Next::new(&[], None, transport))
+            .await
+    }
+}
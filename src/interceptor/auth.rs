@@ -0,0 +1,85 @@
+//! Bearer token authentication.
+
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use futures_util::{future::BoxFuture, lock::Mutex};
+use http::{header, HeaderValue};
+
+use crate::Error;
+
+use super::{Interceptor, Next};
+
+/// Tokens expiring within this margin are refreshed early.
+const EXPIRY_MARGIN: Duration = Duration::from_secs(10);
+
+/// A bearer token returned by a [`TokenSource`].
+#[derive(Clone)]
+pub struct Token {
+    pub access_token: String,
+    /// How long the token is valid for; `None` if it doesn't expire.
+    pub expires_in: Option<Duration>,
+}
+
+impl std::fmt::Debug for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Token")
+            .field("access_token", &"<redacted>")
+            .field("expires_in", &self.expires_in)
+            .finish()
+    }
+}
+
+/// An asynchronous source of bearer tokens.
+pub trait TokenSource: Send + Sync {
+    /// Fetches a fresh token.
+    fn fetch_token(&self) -> BoxFuture<'_, Result<Token, Error>>;
+}
+
+/// An [`Interceptor`] that sets the `authorization` header to a bearer token
+/// from a [`TokenSource`].
+///
+/// Tokens are cached and refreshed before each call if they have expired (or
+/// are about to).
+pub struct BearerAuthInterceptor<S> {
+    source: S,
+    cached: Mutex<Option<(HeaderValue, Option<Instant>)>>,
+}
+
+impl<S: TokenSource> BearerAuthInterceptor<S> {
+    pub fn new(source: S) -> Self {
+        Self {
+            source,
+            cached: Default::default(),
+        }
+    }
+
+    async fn authorization(&self) -> Result<HeaderValue, Error> {
+        let mut cached = self.cached.lock().await;
+        if let Some((value, expires_at)) = &*cached {
+            if expires_at.is_none_or(|at| Instant::now() + EXPIRY_MARGIN < at) {
+                return Ok(value.clone());
+            }
+        }
+        let token = self.source.fetch_token().await?;
+        let expires_at = token.expires_in.map(|ttl| Instant::now() + ttl);
+        let mut value = HeaderValue::try_from(format!("Bearer {}", token.access_token))?;
+        value.set_sensitive(true);
+        *cached = Some((value.clone(), expires_at));
+        Ok(value)
+    }
+}
+
+impl<S: TokenSource> Interceptor for BearerAuthInterceptor<S> {
+    fn intercept<'a>(
+        &'a self,
+        mut req: http::Request<Bytes>,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, Result<http::Response<Bytes>, Error>> {
+        Box::pin(async move {
+            let value = self.authorization().await?;
+            req.headers_mut().insert(header::AUTHORIZATION, value);
+            next.run(req).await
+        })
+    }
+}
@@ -0,0 +1,429 @@
+//! HTTP caching of unary GET responses.
+//!
+//! See: https://connectrpc.com/docs/protocol/#unary-get-request
+
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+use futures_util::future::BoxFuture;
+use http::{header, HeaderMap, HeaderName, HeaderValue, Method, StatusCode, Version};
+
+use crate::Error;
+
+use super::{Interceptor, Next};
+
+/// The default maximum number of cached responses.
+pub const DEFAULT_MAX_ENTRIES: usize = 1024;
+
+/// A cached response.
+struct Entry {
+    status: StatusCode,
+    version: Version,
+    headers: HeaderMap,
+    body: Bytes,
+    /// Request header values named by the response's `Vary` header.
+    vary: Vec<(HeaderName, Option<HeaderValue>)>,
+    stored_at: Instant,
+    directives: CacheControl,
+}
+
+impl Entry {
+    fn new(req_headers: &HeaderMap, resp: &http::Response<Bytes>) -> Option<Self> {
+        let directives = CacheControl::parse(resp.headers());
+        if directives.no_store || directives.private {
+            return None;
+        }
+        // Responses to authorized requests may be for that caller only.
+        //
+        // See: https://www.rfc-editor.org/rfc/rfc9111#section-3.5
+        if req_headers.contains_key(header::AUTHORIZATION)
+            && !(directives.public || directives.must_revalidate || directives.s_maxage.is_some())
+        {
+            return None;
+        }
+        let mut vary = vec![];
+        for value in resp.headers().get_all(header::VARY) {
+            for name in value.to_str().ok()?.split(',') {
+                let name = name.trim();
+                if name == "*" {
+                    return None;
+                }
+                let name = HeaderName::try_from(name).ok()?;
+                let value = req_headers.get(&name).cloned();
+                vary.push((name, value));
+            }
+        }
+        if directives.lifetime().is_none() && !resp.headers().contains_key(header::ETAG) {
+            return None;
+        }
+        Some(Self {
+            status: resp.status(),
+            version: resp.version(),
+            headers: resp.headers().clone(),
+            body: resp.body().clone(),
+            vary,
+            stored_at: Instant::now(),
+            directives,
+        })
+    }
+
+    fn matches(&self, req_headers: &HeaderMap) -> bool {
+        self.vary
+            .iter()
+            .all(|(name, value)| req_headers.get(name) == value.as_ref())
+    }
+
+    fn is_fresh(&self) -> bool {
+        !self.directives.no_cache
+            && self
+                .directives
+                .lifetime()
+                .is_some_and(|lifetime| self.stored_at.elapsed() < lifetime)
+    }
+
+    /// Refreshes this entry from a `304 Not Modified` response.
+    fn revalidate(&mut self, not_modified: &http::Response<Bytes>) {
+        for (name, value) in not_modified.headers() {
+            if name != header::CONTENT_LENGTH {
+                self.headers.insert(name, value.clone());
+            }
+        }
+        self.directives = CacheControl::parse(&self.headers);
+        self.stored_at = Instant::now();
+    }
+
+    fn to_response(&self) -> http::Response<Bytes> {
+        let mut resp = http::Response::new(self.body.clone());
+        *resp.status_mut() = self.status;
+        *resp.version_mut() = self.version;
+        *resp.headers_mut() = self.headers.clone();
+        resp
+    }
+}
+
+/// The `Cache-Control` directives relevant to a shared client cache.
+#[derive(Default)]
+struct CacheControl {
+    max_age: Option<Duration>,
+    s_maxage: Option<Duration>,
+    no_cache: bool,
+    no_store: bool,
+    private: bool,
+    public: bool,
+    must_revalidate: bool,
+}
+
+impl CacheControl {
+    fn parse(headers: &HeaderMap) -> Self {
+        let mut directives = Self::default();
+        let values = headers
+            .get_all(header::CACHE_CONTROL)
+            .into_iter()
+            .filter_map(|value| value.to_str().ok());
+        for directive in values.flat_map(|value| value.split(',')) {
+            let directive = directive.trim().to_ascii_lowercase();
+            let parse_secs =
+                |secs: &str| secs.trim_matches('"').parse().ok().map(Duration::from_secs);
+            match directive.split_once('=') {
+                Some(("max-age", secs)) => directives.max_age = parse_secs(secs),
+                Some(("s-maxage", secs)) => directives.s_maxage = parse_secs(secs),
+                // `private` may name fields; treat it as covering the whole
+                // response.
+                Some(("private", _)) => directives.private = true,
+                None if directive == "no-cache" => directives.no_cache = true,
+                None if directive == "no-store" => directives.no_store = true,
+                None if directive == "private" => directives.private = true,
+                None if directive == "public" => directives.public = true,
+                None if directive == "must-revalidate" => directives.must_revalidate = true,
+                _ => (),
+            }
+        }
+        directives
+    }
+
+    /// Returns how long a response is fresh for, preferring the shared
+    /// cache `s-maxage`.
+    fn lifetime(&self) -> Option<Duration> {
+        self.s_maxage.or(self.max_age)
+    }
+}
+
+/// An [`Interceptor`] that caches unary GET responses, honoring
+/// `Cache-Control` (`max-age`, `s-maxage`, `no-cache`, `no-store`,
+/// `private`, `public`, and `must-revalidate`), `ETag` / `If-None-Match`
+/// revalidation, and `Vary`.
+///
+/// The cache is shared by all calls through a client, so like any shared
+/// cache it doesn't store `private` responses, nor responses to requests
+/// with an `Authorization` header unless they are marked `public`,
+/// `s-maxage`, or `must-revalidate`. Stale responses are never served.
+///
+/// Only successful responses with a `max-age`, `s-maxage`, or `ETag` are
+/// cached. When full, the oldest entry is evicted.
+pub struct CacheInterceptor {
+    entries: Mutex<HashMap<String, Entry>>,
+    max_entries: usize,
+}
+
+impl CacheInterceptor {
+    pub fn new() -> Self {
+        Self::with_max_entries(DEFAULT_MAX_ENTRIES)
+    }
+
+    pub fn with_max_entries(max_entries: usize) -> Self {
+        Self {
+            entries: Default::default(),
+            max_entries,
+        }
+    }
+
+    fn store(&self, key: String, entry: Entry) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= self.max_entries && !entries.contains_key(&key) {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.stored_at)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+        if self.max_entries > 0 {
+            entries.insert(key, entry);
+        }
+    }
+}
+
+impl Default for CacheInterceptor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Interceptor for CacheInterceptor {
+    fn intercept<'a>(
+        &'a self,
+        mut req: http::Request<Bytes>,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, Result<http::Response<Bytes>, Error>> {
+        Box::pin(async move {
+            if req.method() != Method::GET {
+                return next.run(req).await;
+            }
+            let key = req.uri().to_string();
+            let req_headers = req.headers().clone();
+            {
+                let entries = self.entries.lock().unwrap();
+                if let Some(entry) = entries
+                    .get(&key)
+                    .filter(|entry| entry.matches(&req_headers))
+                {
+                    if entry.is_fresh() {
+                        return Ok(entry.to_response());
+                    }
+                    if let Some(etag) = entry.headers.get(header::ETAG) {
+                        req.headers_mut()
+                            .insert(header::IF_NONE_MATCH, etag.clone());
+                    }
+                }
+            }
+
+            let resp = next.run(req).await?;
+            if resp.status() == StatusCode::NOT_MODIFIED {
+                let mut entries = self.entries.lock().unwrap();
+                if let Some(entry) = entries.get_mut(&key) {
+                    entry.revalidate(&resp);
+                    return Ok(entry.to_response());
+                }
+                return Ok(resp);
+            }
+            if resp.status() == StatusCode::OK {
+                if let Some(entry) = Entry::new(&req_headers, &resp) {
+                    self.store(key, entry);
+                }
+            }
+            Ok(resp)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        interceptor::tests::{call, request},
+        transport::MemoryTransport,
+    };
+
+    use super::*;
+
+    /// Returns a transport responding with `headers` and the number of
+    /// requests so far, or `304 Not Modified` if the request has a matching
+    /// `If-None-Match`.
+    fn transport(headers: &'static [(&'static str, &'static str)]) -> MemoryTransport {
+        crate::interceptor::tests::transport(move |req, count| {
+            let etag = headers.iter().find(|(name, _)| *name == "etag");
+            let not_modified = etag.is_some_and(|(_, etag)| {
+                req.headers().get(header::IF_NONE_MATCH) == Some(&HeaderValue::from_static(etag))
+            });
+            let mut resp = http::Response::new(Bytes::from(count.to_string()));
+            if not_modified {
+                *resp.status_mut() = StatusCode::NOT_MODIFIED;
+                *resp.body_mut() = Bytes::new();
+            }
+            for (name, value) in headers {
+                resp.headers_mut()
+                    .insert(*name, HeaderValue::from_static(value));
+            }
+            Ok(resp)
+        })
+    }
+
+    fn get(path: &str) -> http::Request<Bytes> {
+        request(path, "")
+    }
+
+    #[tokio::test]
+    async fn serves_fresh_responses() {
+        let cache = CacheInterceptor::new();
+        let transport = transport(&[("cache-control", "max-age=60")]);
+        for _ in 0..2 {
+            let resp = call(&cache, &transport, get("/a.Service/Get"))
+                .await
+                .unwrap();
+            assert_eq!(resp.body().as_ref(), b"1");
+        }
+
+        let mut post = get("/a.Service/Get");
+        *post.method_mut() = Method::POST;
+        let resp = call(&cache, &transport, post).await.unwrap();
+        assert_eq!(resp.body().as_ref(), b"2");
+    }
+
+    #[tokio::test]
+    async fn revalidates_with_etag() {
+        let cache = CacheInterceptor::new();
+        let transport = transport(&[("etag", "\"v1\""), ("cache-control", "no-cache")]);
+        call(&cache, &transport, get("/a.Service/Get"))
+            .await
+            .unwrap();
+        let resp = call(&cache, &transport, get("/a.Service/Get"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.body().as_ref(), b"1");
+        assert_eq!(resp.headers()[header::ETAG], "\"v1\"");
+    }
+
+    #[tokio::test]
+    async fn skips_no_store_responses() {
+        let cache = CacheInterceptor::new();
+        let transport = transport(&[("cache-control", "max-age=60, no-store")]);
+        call(&cache, &transport, get("/a.Service/Get"))
+            .await
+            .unwrap();
+        let resp = call(&cache, &transport, get("/a.Service/Get"))
+            .await
+            .unwrap();
+        assert_eq!(resp.body().as_ref(), b"2");
+    }
+
+    #[tokio::test]
+    async fn matches_vary_headers() {
+        let cache = CacheInterceptor::new();
+        let transport = transport(&[("cache-control", "max-age=60"), ("vary", "x-tenant")]);
+        let tenant = |tenant: &'static str| {
+            let mut req = get("/a.Service/Get");
+            req.headers_mut()
+                .insert("x-tenant", HeaderValue::from_static(tenant));
+            req
+        };
+        let mut bodies = vec![];
+        for tenant in [tenant("a"), tenant("a"), tenant("b")] {
+            bodies.push(call(&cache, &transport, tenant).await.unwrap().into_body());
+        }
+        assert_eq!(bodies, ["1", "1", "2"]);
+    }
+
+    fn authorized(path: &str, token: &'static str) -> http::Request<Bytes> {
+        let mut req = get(path);
+        req.headers_mut()
+            .insert(header::AUTHORIZATION, HeaderValue::from_static(token));
+        req
+    }
+
+    #[tokio::test]
+    async fn skips_authorized_responses() {
+        let cache = CacheInterceptor::new();
+        let transport = transport(&[("cache-control", "max-age=60")]);
+        let mut bodies = vec![];
+        for token in ["Bearer a", "Bearer b", "Bearer a"] {
+            let req = authorized("/a.Service/Get", token);
+            bodies.push(call(&cache, &transport, req).await.unwrap().into_body());
+        }
+        assert_eq!(bodies, ["1", "2", "3"]);
+    }
+
+    #[tokio::test]
+    async fn skips_private_responses() {
+        let cache = CacheInterceptor::new();
+        let transport = transport(&[("cache-control", "max-age=60, private")]);
+        call(&cache, &transport, get("/a.Service/Get"))
+            .await
+            .unwrap();
+        let resp = call(&cache, &transport, get("/a.Service/Get"))
+            .await
+            .unwrap();
+        assert_eq!(resp.body().as_ref(), b"2");
+    }
+
+    #[tokio::test]
+    async fn stores_public_authorized_responses() {
+        let cache = CacheInterceptor::new();
+        let transport = transport(&[("cache-control", "public, max-age=60")]);
+        call(&cache, &transport, authorized("/a.Service/Get", "Bearer a"))
+            .await
+            .unwrap();
+        let resp = call(&cache, &transport, get("/a.Service/Get"))
+            .await
+            .unwrap();
+        assert_eq!(resp.body().as_ref(), b"1");
+    }
+
+    #[tokio::test]
+    async fn prefers_s_maxage() {
+        let cache = CacheInterceptor::new();
+        let transport = transport(&[("cache-control", "max-age=60, s-maxage=0")]);
+        call(&cache, &transport, get("/a.Service/Get"))
+            .await
+            .unwrap();
+        let resp = call(&cache, &transport, get("/a.Service/Get"))
+            .await
+            .unwrap();
+        assert_eq!(resp.body().as_ref(), b"2");
+    }
+
+    #[tokio::test]
+    async fn evicts_oldest_entry() {
+        let cache = CacheInterceptor::with_max_entries(1);
+        let transport = transport(&[("cache-control", "max-age=60")]);
+        let mut bodies = vec![];
+        for path in [
+            "/a.Service/A",
+            "/a.Service/B",
+            "/a.Service/B",
+            "/a.Service/A",
+        ] {
+            bodies.push(
+                call(&cache, &transport, get(path))
+                    .await
+                    .unwrap()
+                    .into_body(),
+            );
+        }
+        assert_eq!(bodies, ["1", "2", "2", "3"]);
+    }
+}
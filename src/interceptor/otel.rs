@@ -0,0 +1,127 @@
+//! OpenTelemetry trace propagation.
+
+use bytes::Bytes;
+use futures_util::future::BoxFuture;
+use http::{HeaderMap, HeaderName, HeaderValue};
+use opentelemetry::{
+    baggage::BaggageExt,
+    global::{self, BoxedTracer},
+    trace::{FutureExt, SpanKind, Status, TraceContextExt, TraceFlags, Tracer},
+    Context, KeyValue,
+};
+
+use crate::{response::error::ConnectError, Error};
+
+use super::{Interceptor, Next};
+
+const TRACEPARENT: HeaderName = HeaderName::from_static("traceparent");
+const TRACESTATE: HeaderName = HeaderName::from_static("tracestate");
+const BAGGAGE: HeaderName = HeaderName::from_static("baggage");
+
+/// An [`Interceptor`] that records a client span for each RPC and injects
+/// W3C Trace Context (`traceparent`, `tracestate`) and `baggage` headers.
+///
+/// Span attributes follow the OpenTelemetry RPC semantic conventions.
+pub struct OpenTelemetryInterceptor {
+    tracer: BoxedTracer,
+}
+
+impl OpenTelemetryInterceptor {
+    /// Returns a new interceptor recording spans with the given tracer.
+    pub fn new(tracer: BoxedTracer) -> Self {
+        Self { tracer }
+    }
+}
+
+impl Default for OpenTelemetryInterceptor {
+    /// Returns a new interceptor using the global tracer provider.
+    fn default() -> Self {
+        Self::new(global::tracer("connect-rpc"))
+    }
+}
+
+impl Interceptor for OpenTelemetryInterceptor {
+    fn intercept<'a>(
+        &'a self,
+        mut req: http::Request<Bytes>,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, Result<http::Response<Bytes>, Error>> {
+        Box::pin(async move {
+            let path = req.uri().path();
+            let (service, method) = path
+                .trim_start_matches('/')
+                .rsplit_once('/')
+                .map(|(prefix, method)| {
+                    let service = prefix.rsplit_once('/').map_or(prefix, |(_, svc)| svc);
+                    (service.to_string(), method.to_string())
+                })
+                .unwrap_or_default();
+
+            let mut attributes = vec![
+                KeyValue::new("rpc.system", "connect_rpc"),
+                KeyValue::new("rpc.service", service.clone()),
+                KeyValue::new("rpc.method", method.clone()),
+            ];
+            if let Some(host) = req.uri().host() {
+                attributes.push(KeyValue::new("server.address", host.to_string()));
+            }
+            if let Some(port) = req.uri().port_u16() {
+                attributes.push(KeyValue::new("server.port", i64::from(port)));
+            }
+
+            let parent = Context::current();
+            let span = self
+                .tracer
+                .span_builder(format!("{service}/{method}"))
+                .with_kind(SpanKind::Client)
+                .with_attributes(attributes)
+                .start_with_context(&self.tracer, &parent);
+            let cx = parent.with_span(span);
+            inject_context(&cx, req.headers_mut());
+
+            let result = next.run(req).with_context(cx.clone()).await;
+
+            let span = cx.span();
+            let code = match &result {
+                Ok(resp) if resp.status().is_success() => None,
+                Ok(resp) => Some(ConnectError::from(resp.clone()).code()),
+                Err(err) => Some(err.connect_code()),
+            };
+            if let Some(code) = code {
+                span.set_attribute(KeyValue::new("rpc.connect_rpc.error_code", code.as_str()));
+                span.set_status(Status::error(code.as_str()));
+            }
+            span.end();
+            result
+        })
+    }
+}
+
+/// Injects W3C Trace Context and Baggage headers for the given context.
+pub fn inject_context(cx: &Context, headers: &mut HeaderMap) {
+    let span = cx.span();
+    let span_context = span.span_context();
+    if span_context.is_valid() {
+        let traceparent = format!(
+            "00-{:032x}-{:016x}-{:02x}",
+            span_context.trace_id(),
+            span_context.span_id(),
+            span_context.trace_flags() & TraceFlags::SAMPLED
+        );
+        if let Ok(value) = HeaderValue::try_from(traceparent) {
+            headers.insert(TRACEPARENT, value);
+        }
+        let tracestate = span_context.trace_state().header();
+        if !tracestate.is_empty() {
+            if let Ok(value) = HeaderValue::try_from(tracestate) {
+                headers.insert(TRACESTATE, value);
+            }
+        }
+    }
+    let baggage = cx.baggage();
+    if !baggage.is_empty() {
+        if let Ok(value) = HeaderValue::try_from(baggage.to_string()) {
+            headers.insert(BAGGAGE, value);
+        }
+    }
+}
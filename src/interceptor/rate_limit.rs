@@ -0,0 +1,250 @@
+//! Client-side rate limiting.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+use futures_util::future::BoxFuture;
+
+use crate::{
+    client::Sleep,
+    clock::{Clock, SystemClock},
+    response::error::{ConnectCode, ConnectError},
+    Error,
+};
+
+use super::{Interceptor, Next};
+
+/// How calls are grouped into rate limits.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RateLimitKey {
+    /// All calls through the client share one limit.
+    #[default]
+    Client,
+    /// Each method (request path) has its own limit.
+    Method,
+}
+
+/// A token bucket.
+struct Bucket {
+    /// May be negative when calls are waiting for reserved tokens.
+    tokens: f64,
+    updated_at: Instant,
+}
+
+/// An [`Interceptor`] that limits the rate of calls with a token bucket,
+/// refilled at `rate` calls per second up to `burst` calls.
+///
+/// By default, calls over the limit fail locally with `resource_exhausted`;
+/// with [`Self::delay`], they instead wait for a token.
+pub struct RateLimitInterceptor {
+    rate: f64,
+    burst: f64,
+    key: RateLimitKey,
+    delay: Option<(Arc<dyn Sleep>, Duration)>,
+    clock: Arc<dyn Clock>,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimitInterceptor {
+    /// Returns an interceptor allowing `rate` calls per second on average,
+    /// with bursts of up to `burst` calls.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rate` isn't positive and finite.
+    pub fn new(rate: f64, burst: u32) -> Self {
+        assert!(
+            rate.is_finite() && rate > 0.0,
+            "rate must be positive and finite"
+        );
+        Self {
+            rate,
+            burst: burst.max(1).into(),
+            key: RateLimitKey::default(),
+            delay: None,
+            clock: Arc::new(SystemClock),
+            buckets: Default::default(),
+        }
+    }
+
+    /// Sets how calls are grouped into rate limits.
+    ///
+    /// Defaults to [`RateLimitKey::Client`].
+    pub fn key(mut self, key: RateLimitKey) -> Self {
+        self.key = key;
+        self
+    }
+
+    /// Delays calls over the limit (using `sleep`) until a token is
+    /// available, rejecting them only if that would take longer than
+    /// `max_delay`.
+    pub fn delay(mut self, sleep: impl Sleep + 'static, max_delay: Duration) -> Self {
+        self.delay = Some((Arc::new(sleep), max_delay));
+        self
+    }
+
+    /// Sets the [`Clock`] used to refill tokens.
+    ///
+    /// Defaults to [`SystemClock`].
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Takes a token, returning how long to wait for it, or `None` if the
+    /// call should be rejected.
+    fn acquire(&self, key: &str) -> Option<Duration> {
+        let max_delay = self.delay.as_ref().map_or(Duration::ZERO, |(_, max)| *max);
+        let now = self.clock.now();
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: self.burst,
+            updated_at: now,
+        });
+        let elapsed = now.duration_since(bucket.updated_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.burst);
+        bucket.updated_at = now;
+
+        let wait = Duration::from_secs_f64((1.0 - bucket.tokens).max(0.0) / self.rate);
+        if wait > max_delay {
+            return None;
+        }
+        bucket.tokens -= 1.0;
+        Some(wait)
+    }
+}
+
+impl Interceptor for RateLimitInterceptor {
+    fn intercept<'a>(
+        &'a self,
+        req: http::Request<Bytes>,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, Result<http::Response<Bytes>, Error>> {
+        Box::pin(async move {
+            let key = match self.key {
+                RateLimitKey::Client => "",
+                RateLimitKey::Method => req.uri().path(),
+            };
+            let Some(wait) = self.acquire(key) else {
+                return Err(Error::ConnectError(ConnectError::new(
+                    ConnectCode::ResourceExhausted,
+                    "client rate limit exceeded",
+                )));
+            };
+            if let Some((sleep, _)) = self.delay.as_ref().filter(|_| !wait.is_zero()) {
+                tracing::debug!(?wait, "Delaying rate-limited call");
+                sleep.sleep(wait).await;
+            }
+            next.run(req).await
+        })
+    }
+}
+
+impl std::fmt::Debug for RateLimitInterceptor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RateLimitInterceptor")
+            .field("rate", &self.rate)
+            .field("burst", &self.burst)
+            .field("key", &self.key)
+            .field("max_delay", &self.delay.as_ref().map(|(_, max)| max))
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::pin::pin;
+
+    use futures_util::poll;
+
+    use crate::{
+        clock::ManualClock,
+        interceptor::tests::{self, request},
+        transport::MemoryTransport,
+    };
+
+    use super::*;
+
+    fn transport() -> MemoryTransport {
+        tests::transport(|_, _| Ok(http::Response::new(Bytes::new())))
+    }
+
+    async fn call(
+        interceptor: &RateLimitInterceptor,
+        transport: &MemoryTransport,
+        path: &str,
+    ) -> Result<(), ConnectCode> {
+        match tests::call(interceptor, transport, request(path, "")).await {
+            Ok(_) => Ok(()),
+            Err(Error::ConnectError(err)) => Err(err.code()),
+            Err(err) => panic!("unexpected error {err:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_calls_over_limit() {
+        let clock = ManualClock::new();
+        let interceptor = RateLimitInterceptor::new(10.0, 2).clock(clock.clone());
+        let transport = transport();
+        for _ in 0..2 {
+            call(&interceptor, &transport, "/a.Service/A")
+                .await
+                .unwrap();
+        }
+        let result = call(&interceptor, &transport, "/a.Service/A").await;
+        assert_eq!(result, Err(ConnectCode::ResourceExhausted));
+
+        // One token is refilled every 100ms.
+        clock.advance(Duration::from_millis(100));
+        call(&interceptor, &transport, "/a.Service/A")
+            .await
+            .unwrap();
+        assert!(call(&interceptor, &transport, "/a.Service/A")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn limits_each_method() {
+        let clock = ManualClock::new();
+        let interceptor = RateLimitInterceptor::new(10.0, 1)
+            .key(RateLimitKey::Method)
+            .clock(clock);
+        let transport = transport();
+        call(&interceptor, &transport, "/a.Service/A")
+            .await
+            .unwrap();
+        call(&interceptor, &transport, "/a.Service/B")
+            .await
+            .unwrap();
+        assert!(call(&interceptor, &transport, "/a.Service/A")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn delays_calls_over_limit() {
+        let clock = ManualClock::new();
+        let interceptor = RateLimitInterceptor::new(10.0, 1)
+            .delay(clock.clone(), Duration::from_millis(150))
+            .clock(clock.clone());
+        let transport = transport();
+        call(&interceptor, &transport, "/a.Service/A")
+            .await
+            .unwrap();
+
+        let mut delayed = pin!(call(&interceptor, &transport, "/a.Service/A"));
+        assert!(poll!(&mut delayed).is_pending());
+        // The delayed call reserved the next token, so another would wait
+        // 200ms, longer than the maximum delay.
+        let result = call(&interceptor, &transport, "/a.Service/A").await;
+        assert_eq!(result, Err(ConnectCode::ResourceExhausted));
+
+        clock.advance(Duration::from_millis(100));
+        delayed.await.unwrap();
+    }
+}
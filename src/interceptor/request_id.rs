@@ -0,0 +1,65 @@
+//! Request ID injection, for correlating logs across services.
+
+use bytes::Bytes;
+use futures_util::future::BoxFuture;
+use http::{HeaderName, HeaderValue};
+
+use crate::Error;
+
+use super::{Interceptor, Next};
+
+/// The default request ID header.
+pub const DEFAULT_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// An [`Interceptor`] that sets a request ID header (a UUIDv7) on requests
+/// that don't already have one.
+///
+/// The request ID is also set on the response headers (unless the server
+/// returned its own), so it is available from the response
+/// [`metadata`](crate::response::ConnectResponse::metadata) or, for failed
+/// calls, the [`ConnectError`](crate::response::error::ConnectError)
+/// metadata.
+#[derive(Clone, Debug)]
+pub struct RequestIdInterceptor {
+    header: HeaderName,
+}
+
+impl RequestIdInterceptor {
+    /// Returns an interceptor using the [`DEFAULT_HEADER`].
+    pub fn new() -> Self {
+        Self::with_header(DEFAULT_HEADER)
+    }
+
+    pub fn with_header(header: HeaderName) -> Self {
+        Self { header }
+    }
+}
+
+impl Default for RequestIdInterceptor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Interceptor for RequestIdInterceptor {
+    fn intercept<'a>(
+        &'a self,
+        mut req: http::Request<Bytes>,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, Result<http::Response<Bytes>, Error>> {
+        Box::pin(async move {
+            let request_id = req
+                .headers_mut()
+                .entry(&self.header)
+                .or_insert_with(|| {
+                    let id = uuid::Uuid::now_v7().hyphenated().to_string();
+                    // UUIDs are always valid header values.
+                    HeaderValue::try_from(id).unwrap()
+                })
+                .clone();
+            let mut resp = next.run(req).await?;
+            resp.headers_mut().entry(&self.header).or_insert(request_id);
+            Ok(resp)
+        })
+    }
+}
@@ -0,0 +1,501 @@
+//! Per-method call settings from a gRPC-style service config document.
+//!
+//! ```no_run
+//! # use connect_rpc::{
+//! #     client::ConnectClient,
+//! #     interceptor::service_config::{ServiceConfig, ServiceConfigInterceptor},
+//! # };
+//! # fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let config = ServiceConfig::from_json(&std::fs::read_to_string("service_config.json")?)?;
+//! let client = ConnectClient::builder()
+//!     .interceptor(ServiceConfigInterceptor::new(config, tokio::time::sleep))
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! See: https://github.com/grpc/grpc/blob/master/doc/service_config.md
+
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use bytes::Bytes;
+use futures_util::future::BoxFuture;
+use http::HeaderValue;
+use serde::Deserialize;
+
+use crate::{
+    client::{resilient::Backoff, Sleep},
+    codes,
+    common::CONNECT_TIMEOUT_MS,
+    metrics::RpcInfo,
+    response::error::{ConnectCode, ConnectError},
+    Error,
+};
+
+use super::{clone_request, Interceptor, Next};
+
+/// A service config document.
+///
+/// Only `methodConfig` is supported; other fields are ignored.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceConfig {
+    #[serde(default)]
+    pub method_config: Vec<MethodConfig>,
+}
+
+impl ServiceConfig {
+    /// Parses a service config JSON document.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        serde_json::from_str(json)
+            .map_err(|err| Error::invalid_request(format!("invalid service config: {err}")))
+    }
+
+    /// Returns the most specific config for a method: one naming the method,
+    /// else its service, else the default (empty name).
+    pub fn method(&self, service: &str, method: &str) -> Option<&MethodConfig> {
+        let find = |matches: &dyn Fn(&MethodName) -> bool| {
+            self.method_config
+                .iter()
+                .find(|config| config.name.iter().any(matches))
+        };
+        find(&|name| name.service == service && name.method.as_deref() == Some(method))
+            .or_else(|| find(&|name| name.service == service && name.method.is_none()))
+            .or_else(|| find(&|name| name.service.is_empty()))
+    }
+}
+
+/// Settings for the methods matching any of `name`.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MethodConfig {
+    #[serde(default)]
+    pub name: Vec<MethodName>,
+    /// Sent as `connect-timeout-ms`, unless the request sets one.
+    #[serde(default, deserialize_with = "deserialize_duration")]
+    pub timeout: Option<Duration>,
+    #[serde(default, deserialize_with = "deserialize_u64_string")]
+    pub max_request_message_bytes: Option<u64>,
+    #[serde(default, deserialize_with = "deserialize_u64_string")]
+    pub max_response_message_bytes: Option<u64>,
+    pub retry_policy: Option<RetryPolicy>,
+}
+
+/// A service and (optionally) method name.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct MethodName {
+    #[serde(default)]
+    pub service: String,
+    pub method: Option<String>,
+}
+
+/// When and how to retry failed calls.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetryPolicy {
+    /// The maximum number of attempts, including the first.
+    pub max_attempts: u32,
+    #[serde(deserialize_with = "deserialize_required_duration")]
+    pub initial_backoff: Duration,
+    #[serde(deserialize_with = "deserialize_required_duration")]
+    pub max_backoff: Duration,
+    pub backoff_multiplier: f64,
+    /// Codes as gRPC names (e.g. `UNAVAILABLE`) or numbers.
+    #[serde(deserialize_with = "deserialize_codes")]
+    pub retryable_status_codes: Vec<ConnectCode>,
+}
+
+impl RetryPolicy {
+    /// Returns the equivalent [`Backoff`].
+    pub fn backoff(&self) -> Backoff {
+        Backoff {
+            initial: self.initial_backoff,
+            max: self.max_backoff,
+            multiplier: self.backoff_multiplier,
+            max_retries: self.max_attempts.saturating_sub(1),
+        }
+    }
+}
+
+/// A [`ServiceConfig`] that can be replaced at runtime, e.g. from a dynamic
+/// configuration source.
+///
+/// Clones share the same config.
+#[derive(Clone, Debug, Default)]
+pub struct SharedServiceConfig(Arc<RwLock<Arc<ServiceConfig>>>);
+
+impl SharedServiceConfig {
+    pub fn new(config: ServiceConfig) -> Self {
+        Self(Arc::new(RwLock::new(Arc::new(config))))
+    }
+
+    /// Returns the current config.
+    pub fn get(&self) -> Arc<ServiceConfig> {
+        self.0.read().unwrap().clone()
+    }
+
+    /// Replaces the config; calls in progress keep the previous one.
+    pub fn set(&self, config: ServiceConfig) {
+        *self.0.write().unwrap() = Arc::new(config);
+    }
+}
+
+impl From<ServiceConfig> for SharedServiceConfig {
+    fn from(config: ServiceConfig) -> Self {
+        Self::new(config)
+    }
+}
+
+/// An [`Interceptor`] that applies a [`ServiceConfig`] to each call: timeouts,
+/// message size limits, and retries.
+///
+/// Message size limits apply to whole (possibly compressed) bodies. Retries
+/// wait with `sleep` (e.g. `tokio::time::sleep`) between attempts.
+pub struct ServiceConfigInterceptor {
+    config: SharedServiceConfig,
+    sleep: Arc<dyn Sleep>,
+}
+
+impl ServiceConfigInterceptor {
+    pub fn new(config: impl Into<SharedServiceConfig>, sleep: impl Sleep + 'static) -> Self {
+        Self {
+            config: config.into(),
+            sleep: Arc::new(sleep),
+        }
+    }
+}
+
+impl Interceptor for ServiceConfigInterceptor {
+    fn intercept<'a>(
+        &'a self,
+        mut req: http::Request<Bytes>,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, Result<http::Response<Bytes>, Error>> {
+        Box::pin(async move {
+            let rpc = RpcInfo::from_path(req.uri().path());
+            let service_config = self.config.get();
+            let Some(config) = service_config.method(&rpc.service, &rpc.method) else {
+                return next.run(req).await;
+            };
+            if let Some(timeout) = config.timeout {
+                let timeout_ms = timeout.as_millis().clamp(1, 9_999_999_999);
+                req.headers_mut()
+                    .entry(CONNECT_TIMEOUT_MS)
+                    .or_insert_with(|| HeaderValue::from(timeout_ms as u64));
+            }
+            if config
+                .max_request_message_bytes
+                .is_some_and(|max| req.body().len() as u64 > max)
+            {
+                return Err(limit_exceeded("request"));
+            }
+
+            let mut attempt = 0;
+            let resp = loop {
+                let result = next.clone().run(clone_request(&req)).await;
+                let Some(policy) = &config.retry_policy else {
+                    break result?;
+                };
+                let code = match &result {
+                    Ok(resp) if resp.status().is_success() => None,
+                    Ok(resp) => Some(codes::from_http_status(resp.status())),
+                    Err(err) => Some(err.connect_code()),
+                };
+                attempt += 1;
+                let retryable = code.filter(|code| policy.retryable_status_codes.contains(code));
+                if retryable.is_none() || attempt >= policy.max_attempts {
+                    break result?;
+                }
+                let delay = policy.backoff().delay(attempt - 1);
+                tracing::debug!(?code, attempt, ?delay, "Retrying call");
+                self.sleep.sleep(delay).await;
+            };
+            // Error responses carry no message, so aren't limited.
+            if resp.status().is_success()
+                && config
+                    .max_response_message_bytes
+                    .is_some_and(|max| resp.body().len() as u64 > max)
+            {
+                return Err(limit_exceeded("response"));
+            }
+            Ok(resp)
+        })
+    }
+}
+
+impl std::fmt::Debug for ServiceConfigInterceptor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServiceConfigInterceptor")
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+fn limit_exceeded(which: &str) -> Error {
+    Error::ConnectError(ConnectError::new(
+        ConnectCode::ResourceExhausted,
+        format!("{which} message exceeds service config limit"),
+    ))
+}
+
+/// Parses a protobuf JSON duration, e.g. `"1.5s"`.
+fn parse_duration(s: &str) -> Option<Duration> {
+    let secs: f64 = s.strip_suffix('s')?.parse().ok()?;
+    Duration::try_from_secs_f64(secs).ok()
+}
+
+fn deserialize_duration<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Duration>, D::Error> {
+    Option::<String>::deserialize(deserializer)?
+        .map(|s| {
+            parse_duration(&s)
+                .ok_or_else(|| serde::de::Error::custom(format!("invalid duration {s:?}")))
+        })
+        .transpose()
+}
+
+fn deserialize_required_duration<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Duration, D::Error> {
+    deserialize_duration(deserializer)?.ok_or_else(|| serde::de::Error::custom("missing duration"))
+}
+
+/// Deserializes a protobuf JSON `uint64`, which may be a number or string.
+fn deserialize_u64_string<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<u64>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NumberOrString {
+        Number(u64),
+        String(String),
+    }
+    Option::<NumberOrString>::deserialize(deserializer)?
+        .map(|value| match value {
+            NumberOrString::Number(n) => Ok(n),
+            NumberOrString::String(s) => s.parse().map_err(serde::de::Error::custom),
+        })
+        .transpose()
+}
+
+fn deserialize_codes<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<ConnectCode>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NameOrNumber {
+        Name(String),
+        Number(u32),
+    }
+    Vec::<NameOrNumber>::deserialize(deserializer)?
+        .into_iter()
+        .map(|code| {
+            match &code {
+                NameOrNumber::Name(name) => codes::from_name(&name.to_ascii_lowercase()),
+                NameOrNumber::Number(n) => codes::from_grpc_code(*n),
+            }
+            .ok_or_else(|| serde::de::Error::custom("unknown status code"))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use crate::{
+        interceptor::tests::{self, request},
+        transport::MemoryTransport,
+    };
+
+    use super::*;
+
+    const CONFIG: &str = r#"{
+        "methodConfig": [
+            {
+                "name": [{"service": "a.Service", "method": "Get"}],
+                "timeout": "1.5s",
+                "maxRequestMessageBytes": "4",
+                "maxResponseMessageBytes": 8,
+                "retryPolicy": {
+                    "maxAttempts": 3,
+                    "initialBackoff": "0.1s",
+                    "maxBackoff": "1s",
+                    "backoffMultiplier": 2,
+                    "retryableStatusCodes": ["UNAVAILABLE", 8]
+                }
+            },
+            {"name": [{"service": "a.Service"}], "timeout": "2s"},
+            {"name": [{}], "timeout": "3s"}
+        ]
+    }"#;
+
+    #[test]
+    fn matches_most_specific_config() {
+        let config = ServiceConfig::from_json(CONFIG).unwrap();
+        let timeout = |service, method| config.method(service, method).unwrap().timeout;
+        assert_eq!(
+            timeout("a.Service", "Get"),
+            Some(Duration::from_millis(1500))
+        );
+        assert_eq!(timeout("a.Service", "Put"), Some(Duration::from_secs(2)));
+        assert_eq!(timeout("b.Service", "Get"), Some(Duration::from_secs(3)));
+
+        let config = config.method("a.Service", "Get").unwrap();
+        assert_eq!(config.max_request_message_bytes, Some(4));
+        assert_eq!(config.max_response_message_bytes, Some(8));
+        let policy = config.retry_policy.as_ref().unwrap();
+        assert_eq!(
+            policy.retryable_status_codes,
+            [ConnectCode::Unavailable, ConnectCode::ResourceExhausted]
+        );
+        assert_eq!(policy.backoff().max_retries, 2);
+
+        assert!(ServiceConfig::from_json(r#"{"methodConfig": [{"timeout": "1m"}]}"#).is_err());
+    }
+
+    /// Fails with each of `failures` in turn, then responds with the
+    /// request's `connect-timeout-ms` header.
+    fn transport(failures: Vec<ConnectCode>) -> MemoryTransport {
+        let failures = Mutex::new(failures.into_iter());
+        tests::transport(move |req, _| {
+            if let Some(code) = failures.lock().unwrap().next() {
+                return Ok(ConnectError::new(code, "failed").into());
+            }
+            let timeout = req.headers().get(CONNECT_TIMEOUT_MS);
+            let body = timeout.map_or(Bytes::new(), |timeout| {
+                Bytes::copy_from_slice(timeout.as_bytes())
+            });
+            Ok(http::Response::new(body))
+        })
+    }
+
+    /// Returns an interceptor with [`CONFIG`], and the delays it slept.
+    fn interceptor() -> (ServiceConfigInterceptor, Arc<Mutex<Vec<Duration>>>) {
+        let config = ServiceConfig::from_json(CONFIG).unwrap();
+        let delays = Arc::new(Mutex::new(vec![]));
+        let sleep = {
+            let delays = delays.clone();
+            move |delay| {
+                delays.lock().unwrap().push(delay);
+                async {}
+            }
+        };
+        (ServiceConfigInterceptor::new(config, sleep), delays)
+    }
+
+    async fn call(
+        interceptor: &ServiceConfigInterceptor,
+        transport: &MemoryTransport,
+        req: http::Request<Bytes>,
+    ) -> Result<Bytes, ConnectCode> {
+        match tests::call(interceptor, transport, req).await {
+            Ok(resp) if resp.status().is_success() => Ok(resp.into_body()),
+            Ok(resp) => Err(codes::from_http_status(resp.status())),
+            Err(err) => Err(err.connect_code()),
+        }
+    }
+
+    #[tokio::test]
+    async fn sets_timeout_unless_present() {
+        let (interceptor, _) = interceptor();
+        let transport = transport(vec![]);
+        let body = call(&interceptor, &transport, request("/a.Service/Put", "")).await;
+        assert_eq!(body.unwrap(), "2000");
+
+        let mut req = request("/a.Service/Put", "");
+        req.headers_mut()
+            .insert(CONNECT_TIMEOUT_MS, HeaderValue::from_static("10"));
+        let body = call(&interceptor, &transport, req).await;
+        assert_eq!(body.unwrap(), "10");
+    }
+
+    #[tokio::test]
+    async fn enforces_message_limits() {
+        let (interceptor, _) = interceptor();
+        let transport = transport(vec![]);
+        let result = call(&interceptor, &transport, request("/a.Service/Get", "large")).await;
+        assert_eq!(result, Err(ConnectCode::ResourceExhausted));
+
+        // The echoed timeout is the response message.
+        let result = call(&interceptor, &transport, request("/a.Service/Get", "")).await;
+        assert_eq!(result.unwrap(), "1500");
+        let config = ServiceConfig::from_json(
+            r#"{"methodConfig": [{"name": [{}], "maxResponseMessageBytes": 2}]}"#,
+        )
+        .unwrap();
+        let interceptor = ServiceConfigInterceptor::new(config, |_| async {});
+        let mut req = request("/b.Service/Get", "");
+        req.headers_mut()
+            .insert(CONNECT_TIMEOUT_MS, HeaderValue::from_static("3000"));
+        let result = call(&interceptor, &transport, req).await;
+        assert_eq!(result, Err(ConnectCode::ResourceExhausted));
+    }
+
+    #[tokio::test]
+    async fn retries_retryable_codes() {
+        let (interceptor, delays) = interceptor();
+        let failures = vec![ConnectCode::Unavailable, ConnectCode::ResourceExhausted];
+        let result = call(
+            &interceptor,
+            &transport(failures),
+            request("/a.Service/Get", ""),
+        )
+        .await;
+        assert_eq!(result.unwrap(), "1500");
+        assert_eq!(
+            *delays.lock().unwrap(),
+            [Duration::from_millis(100), Duration::from_millis(200)]
+        );
+    }
+
+    #[tokio::test]
+    async fn stops_retrying() {
+        let (interceptor, delays) = interceptor();
+        let failures = vec![ConnectCode::Unavailable; 3];
+        let result = call(
+            &interceptor,
+            &transport(failures),
+            request("/a.Service/Get", ""),
+        )
+        .await;
+        assert_eq!(result, Err(ConnectCode::Unavailable));
+        assert_eq!(delays.lock().unwrap().len(), 2);
+
+        // Other codes aren't retried, nor are methods without a policy.
+        let failures = vec![ConnectCode::PermissionDenied];
+        let result = call(
+            &interceptor,
+            &transport(failures),
+            request("/a.Service/Get", ""),
+        )
+        .await;
+        assert_eq!(result, Err(ConnectCode::PermissionDenied));
+        let failures = vec![ConnectCode::Unavailable];
+        let result = call(
+            &interceptor,
+            &transport(failures),
+            request("/a.Service/Put", ""),
+        )
+        .await;
+        assert_eq!(result, Err(ConnectCode::Unavailable));
+        assert_eq!(delays.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn uses_replaced_config() {
+        let config = SharedServiceConfig::default();
+        let interceptor = ServiceConfigInterceptor::new(config.clone(), |_| async {});
+        let transport = transport(vec![]);
+        let body = call(&interceptor, &transport, request("/a.Service/Get", "")).await;
+        assert_eq!(body.unwrap(), "");
+
+        config.set(ServiceConfig::from_json(CONFIG).unwrap());
+        let body = call(&interceptor, &transport, request("/a.Service/Get", "")).await;
+        assert_eq!(body.unwrap(), "1500");
+    }
+}
@@ -0,0 +1,554 @@
+//! Record/replay ("VCR") of RPCs.
+//!
+//! A [`VcrInterceptor`] in record mode forwards requests and appends each
+//! request/response pair to a cassette file as a JSON line. In replay mode it
+//! serves responses from a cassette without sending anything, so tests can
+//! run deterministically and offline.
+//!
+//! Bodies are recorded verbatim (base64-encoded). Streaming calls, when
+//! recorded with [`ClientBuilder::vcr`], record each envelope frame of the
+//! response (including the end-stream frame) as it's read. Sensitive header
+//! values (e.g. bearer tokens) are not recorded.
+//!
+//! [`ClientBuilder::vcr`]: crate::client::builder::ClientBuilder::vcr
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::Path,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::Poll,
+};
+
+use bytes::{Bytes, BytesMut};
+use futures_util::{future::BoxFuture, stream, Stream, TryStreamExt};
+use http::{HeaderMap, HeaderName, HeaderValue, StatusCode};
+use http_body::Body;
+use http_body_util::{BodyExt, StreamBody};
+
+use crate::{
+    base64::Base64Variant,
+    response::error::{ConnectCode, ConnectError},
+    stream::ConnectFrame,
+    transport::{buffer_response, full_body, RequestBody, ResponseBody, Transport},
+    Error,
+};
+
+use super::{Interceptor, Next};
+
+/// A recorded request/response pair; one line of a cassette file.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+struct Interaction {
+    request: RecordedRequest,
+    response: RecordedResponse,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+struct RecordedRequest {
+    method: String,
+    uri: String,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl RecordedRequest {
+    fn new<B>(req: &http::Request<B>, body: &[u8]) -> Self {
+        Self {
+            method: req.method().to_string(),
+            uri: req.uri().to_string(),
+            headers: record_headers(req.headers()),
+            body: Base64Variant::Standard.encode(body),
+        }
+    }
+
+    /// Requests match on method, URI, and (if `match_body`) body; headers
+    /// may legitimately vary between runs (e.g. timeouts or auth).
+    fn matches(&self, other: &Self, match_body: bool) -> bool {
+        self.method == other.method
+            && self.uri == other.uri
+            && (!match_body || self.body == other.body)
+    }
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+struct RecordedResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: String,
+    /// The envelope frames of a streaming response, in place of `body`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    frames: Option<Vec<RecordedFrame>>,
+}
+
+impl RecordedResponse {
+    fn new(resp: &http::Response<Bytes>) -> Self {
+        Self {
+            status: resp.status().as_u16(),
+            headers: record_headers(resp.headers()),
+            body: Base64Variant::Standard.encode(resp.body()),
+            frames: None,
+        }
+    }
+
+    fn streaming<B>(resp: &http::Response<B>) -> Self {
+        Self {
+            status: resp.status().as_u16(),
+            headers: record_headers(resp.headers()),
+            body: String::new(),
+            frames: Some(vec![]),
+        }
+    }
+
+    fn to_response(&self) -> Result<http::Response<Bytes>, Error> {
+        let status = StatusCode::from_u16(self.status)
+            .map_err(|err| Error::InvalidResponse(format!("invalid recorded status: {err}")))?;
+        let body = Base64Variant::Standard.decode(&self.body)?;
+        let mut resp = http::Response::new(body.into());
+        *resp.status_mut() = status;
+        for (name, value) in &self.headers {
+            resp.headers_mut()
+                .append(HeaderName::try_from(name)?, HeaderValue::try_from(value)?);
+        }
+        Ok(resp)
+    }
+
+    fn frames(&self) -> Result<Option<Vec<ConnectFrame>>, Error> {
+        self.frames
+            .as_ref()
+            .map(|frames| frames.iter().map(RecordedFrame::to_frame).collect())
+            .transpose()
+    }
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+struct RecordedFrame {
+    compressed: bool,
+    end: bool,
+    data: String,
+}
+
+impl RecordedFrame {
+    fn new(frame: &ConnectFrame) -> Self {
+        Self {
+            compressed: frame.compressed,
+            end: frame.end,
+            data: Base64Variant::Standard.encode(&frame.data),
+        }
+    }
+
+    fn to_frame(&self) -> Result<ConnectFrame, Error> {
+        Ok(ConnectFrame {
+            compressed: self.compressed,
+            end: self.end,
+            data: Base64Variant::Standard.decode(&self.data)?.into(),
+        })
+    }
+}
+
+fn record_headers(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .filter(|(_, value)| !value.is_sensitive())
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect()
+}
+
+enum Mode {
+    Record(Arc<Mutex<File>>),
+    Replay(Mutex<Vec<(Interaction, bool)>>),
+}
+
+/// An [`Interceptor`] that records RPCs to, or replays them from, a cassette
+/// file.
+///
+/// In replay mode, each request is answered by the first unused recorded
+/// interaction with the same method, URI, and body; requests with no match
+/// fail with `unimplemented`. The requests of bidi streams are sent while
+/// the response is read, so they match on method and URI alone.
+///
+/// Interceptors only see unary calls; add it with [`ClientBuilder::vcr`] to
+/// record and replay streaming calls too.
+///
+/// [`ClientBuilder::vcr`]: crate::client::builder::ClientBuilder::vcr
+pub struct VcrInterceptor {
+    mode: Mode,
+}
+
+impl VcrInterceptor {
+    /// Returns an interceptor that appends interactions to the cassette at
+    /// `path`, creating it if needed.
+    pub fn record(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            mode: Mode::Record(Arc::new(Mutex::new(file))),
+        })
+    }
+
+    /// Returns an interceptor that replays interactions from the cassette at
+    /// `path`.
+    pub fn replay(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let mut interactions = vec![];
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            interactions.push((serde_json::from_str(&line)?, false));
+        }
+        Ok(Self {
+            mode: Mode::Replay(Mutex::new(interactions)),
+        })
+    }
+
+    fn save(file: &Mutex<File>, interaction: &Interaction) -> Result<(), Error> {
+        let mut line = serde_json::to_vec(interaction).map_err(record_error)?;
+        line.push(b'\n');
+        file.lock().unwrap().write_all(&line).map_err(record_error)
+    }
+
+    fn find(
+        interactions: &Mutex<Vec<(Interaction, bool)>>,
+        req: &RecordedRequest,
+        match_body: bool,
+    ) -> Result<RecordedResponse, Error> {
+        let mut interactions = interactions.lock().unwrap();
+        let Some((interaction, used)) = interactions
+            .iter_mut()
+            .find(|(interaction, used)| !used && interaction.request.matches(req, match_body))
+        else {
+            return Err(Error::ConnectError(ConnectError::new(
+                ConnectCode::Unimplemented,
+                format!("no recorded interaction for {} {}", req.method, req.uri),
+            )));
+        };
+        *used = true;
+        Ok(interaction.response.clone())
+    }
+
+    /// Records or replays a streaming call, sent with `transport` when
+    /// recording. `body` is the request body if it's buffered, i.e. for
+    /// server streams.
+    pub(crate) async fn round_trip_stream(
+        &self,
+        req: http::Request<RequestBody>,
+        body: Option<Bytes>,
+        transport: &dyn Transport,
+    ) -> Result<http::Response<ResponseBody>, Error> {
+        let request = RecordedRequest::new(&req, body.as_deref().unwrap_or_default());
+        match &self.mode {
+            Mode::Record(file) => {
+                // The body of a bidi stream is recorded as it's sent.
+                let sent = body.is_none().then(Arc::<Mutex<BytesMut>>::default);
+                let req = match &sent {
+                    Some(sent) => {
+                        let sent = sent.clone();
+                        req.map(|body| {
+                            body.map_frame(move |frame| {
+                                if let Some(data) = frame.data_ref() {
+                                    sent.lock().unwrap().extend_from_slice(data);
+                                }
+                                frame
+                            })
+                            .boxed()
+                        })
+                    }
+                    None => req,
+                };
+                let resp = transport.round_trip(req).await?;
+                if !resp.status().is_success() {
+                    // Error responses aren't enveloped.
+                    let resp = buffer_response(resp).await?;
+                    let interaction = Interaction {
+                        request,
+                        response: RecordedResponse::new(&resp),
+                    };
+                    Self::save(file, &interaction)?;
+                    return Ok(resp.map(full_body));
+                }
+                let mut recording = StreamRecording {
+                    file: file.clone(),
+                    interaction: Interaction {
+                        request,
+                        response: RecordedResponse::streaming(&resp),
+                    },
+                    sent,
+                };
+                Ok(resp.map(|body| {
+                    let frames = ConnectFrame::body_stream(body).map_ok(move |frame| {
+                        recording.record(&frame);
+                        frame
+                    });
+                    framed_body(frames)
+                }))
+            }
+            Mode::Replay(interactions) => {
+                let response = Self::find(interactions, &request, body.is_some())?;
+                let mut resp = response.to_response()?;
+                *resp.version_mut() = req.version();
+                let Some(frames) = response.frames()? else {
+                    return Ok(resp.map(full_body));
+                };
+                // Accept (and discard) the request while replaying, so
+                // senders of bidi streams don't wait on a full buffer.
+                let mut sent = Some(req.into_body());
+                let mut frames = frames.into_iter();
+                let frames = stream::poll_fn(move |cx| {
+                    while let Some(body) = &mut sent {
+                        match Pin::new(body).poll_frame(cx) {
+                            Poll::Ready(Some(Ok(_))) => (),
+                            Poll::Ready(_) => sent = None,
+                            Poll::Pending => break,
+                        }
+                    }
+                    Poll::Ready(frames.next().map(Ok))
+                });
+                Ok(resp.map(|_| framed_body(frames)))
+            }
+        }
+    }
+}
+
+/// Returns a response body that encodes `frames` as they're polled.
+fn framed_body(
+    frames: impl Stream<Item = Result<ConnectFrame, Error>> + Send + Sync + 'static,
+) -> ResponseBody {
+    StreamBody::new(ConnectFrame::encode_stream(frames).map_ok(http_body::Frame::data)).boxed()
+}
+
+/// A streaming interaction being recorded; saved when the response stream
+/// is dropped, so a stream that isn't read to the end is recorded as far
+/// as it was read.
+struct StreamRecording {
+    file: Arc<Mutex<File>>,
+    interaction: Interaction,
+    /// The request body sent so far, for bidi streams.
+    sent: Option<Arc<Mutex<BytesMut>>>,
+}
+
+impl StreamRecording {
+    fn record(&mut self, frame: &ConnectFrame) {
+        if let Some(frames) = &mut self.interaction.response.frames {
+            frames.push(RecordedFrame::new(frame));
+        }
+    }
+}
+
+impl Drop for StreamRecording {
+    fn drop(&mut self) {
+        if let Some(sent) = &self.sent {
+            self.interaction.request.body =
+                Base64Variant::Standard.encode(&sent.lock().unwrap()[..]);
+        }
+        if let Err(err) = VcrInterceptor::save(&self.file, &self.interaction) {
+            tracing::debug!(%err, "Failed to record streaming interaction");
+        }
+    }
+}
+
+fn record_error(err: impl std::fmt::Display) -> Error {
+    Error::ConnectError(ConnectError::new(
+        ConnectCode::Internal,
+        format!("failed to record interaction: {err}"),
+    ))
+}
+
+impl Interceptor for VcrInterceptor {
+    fn intercept<'a>(
+        &'a self,
+        req: http::Request<Bytes>,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, Result<http::Response<Bytes>, Error>> {
+        Box::pin(async move {
+            let request = RecordedRequest::new(&req, req.body());
+            match &self.mode {
+                Mode::Record(file) => {
+                    let resp = next.run(req).await?;
+                    let interaction = Interaction {
+                        request,
+                        response: RecordedResponse::new(&resp),
+                    };
+                    Self::save(file, &interaction)?;
+                    Ok(resp)
+                }
+                Mode::Replay(interactions) => {
+                    // Replayed responses weren't received over any particular
+                    // HTTP version; use the requested one.
+                    let mut resp = Self::find(interactions, &request, true)?.to_response()?;
+                    *resp.version_mut() = req.version();
+                    Ok(resp)
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use futures_util::TryStreamExt;
+    use http::{header, Method};
+
+    use crate::{
+        client::ConnectClient, interceptor::tests, request::builder::RequestBuilder,
+        stream::EndStreamResponse, transport::MemoryTransport,
+    };
+
+    use super::*;
+
+    /// A cassette path, removed when dropped.
+    struct Cassette(PathBuf);
+
+    impl Cassette {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "connect-rpc-vcr-{}-{name}.jsonl",
+                std::process::id()
+            ));
+            let _ = std::fs::remove_file(&path);
+            Self(path)
+        }
+    }
+
+    impl Drop for Cassette {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    /// Responds with the request body and the number of requests so far.
+    fn counting_transport() -> MemoryTransport {
+        tests::transport(|req, count| {
+            let mut body = req.into_body().to_vec();
+            body.extend_from_slice(format!(" {count}").as_bytes());
+            let mut resp = http::Response::new(body.into());
+            resp.headers_mut()
+                .insert(header::CONTENT_TYPE, "application/proto".try_into()?);
+            Ok(resp)
+        })
+    }
+
+    async fn call(
+        vcr: &VcrInterceptor,
+        transport: &MemoryTransport,
+        body: &'static str,
+    ) -> Result<http::Response<Bytes>, Error> {
+        let mut req = tests::request("/a.Service/Echo", body);
+        *req.method_mut() = Method::POST;
+        let mut token = HeaderValue::from_static("Bearer secret");
+        token.set_sensitive(true);
+        req.headers_mut().insert(header::AUTHORIZATION, token);
+        tests::call(vcr, transport, req).await
+    }
+
+    #[tokio::test]
+    async fn replays_recorded_interactions() {
+        let cassette = Cassette::new("replay");
+        let recorder = VcrInterceptor::record(&cassette.0).unwrap();
+        let transport = counting_transport();
+        for body in ["a", "b", "a"] {
+            call(&recorder, &transport, body).await.unwrap();
+        }
+        let recorded = std::fs::read_to_string(&cassette.0).unwrap();
+        assert_eq!(recorded.lines().count(), 3);
+        assert!(!recorded.contains("secret"));
+
+        // Nothing is sent while replaying.
+        let replayer = VcrInterceptor::replay(&cassette.0).unwrap();
+        let transport = MemoryTransport::default();
+        let mut bodies = vec![];
+        for body in ["b", "a", "a"] {
+            let resp = call(&replayer, &transport, body).await.unwrap();
+            assert_eq!(resp.headers()[header::CONTENT_TYPE], "application/proto");
+            bodies.push(resp.into_body());
+        }
+        assert_eq!(bodies, ["b 2", "a 1", "a 3"]);
+    }
+
+    /// Streams each request message back twice.
+    fn streaming_transport() -> MemoryTransport {
+        MemoryTransport::default().streaming_route("/a.Service/Stream", |req| async move {
+            let request = ConnectFrame::body_stream(req.into_body())
+                .try_next()
+                .await?
+                .unwrap();
+            let frames = [request.data.clone(), request.data]
+                .map(|data| {
+                    Ok(ConnectFrame {
+                        compressed: false,
+                        end: false,
+                        data,
+                    })
+                })
+                .into_iter()
+                .chain([EndStreamResponse::default().to_frame()]);
+            let mut resp = http::Response::new(framed_body(stream::iter(frames)));
+            resp.headers_mut().insert(
+                header::CONTENT_TYPE,
+                "application/connect+proto".try_into()?,
+            );
+            Ok(resp)
+        })
+    }
+
+    async fn stream(client: &ConnectClient, message: &'static str) -> Result<Vec<Bytes>, Error> {
+        let frame = ConnectFrame {
+            compressed: false,
+            end: false,
+            data: Bytes::from_static(message.as_bytes()),
+        };
+        let req = RequestBuilder::default()
+            .uri("http://example.com/a.Service/Stream")?
+            .message_codec("proto")?
+            .streaming(frame.encode()?)?;
+        let call = client.execute_server_stream(req).await?;
+        call.try_collect().await
+    }
+
+    #[tokio::test]
+    async fn replays_recorded_server_streams() {
+        let cassette = Cassette::new("server-stream");
+        let client = ConnectClient::builder()
+            .transport(streaming_transport())
+            .vcr(VcrInterceptor::record(&cassette.0).unwrap())
+            .build()
+            .unwrap();
+        assert_eq!(stream(&client, "a").await.unwrap(), ["a", "a"]);
+        drop(client);
+        let recorded: Interaction =
+            serde_json::from_str(&std::fs::read_to_string(&cassette.0).unwrap()).unwrap();
+        let frames = recorded.response.frames.unwrap();
+        assert_eq!(frames.len(), 3);
+        assert!(frames[2].end);
+
+        // Nothing is sent while replaying.
+        let client = ConnectClient::builder()
+            .transport(MemoryTransport::default())
+            .vcr(VcrInterceptor::replay(&cassette.0).unwrap())
+            .build()
+            .unwrap();
+        assert_eq!(stream(&client, "a").await.unwrap(), ["a", "a"]);
+        let err = stream(&client, "a").await.unwrap_err();
+        assert!(
+            matches!(err, Error::ConnectError(err) if err.code() == ConnectCode::Unimplemented)
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_unrecorded_requests() {
+        let cassette = Cassette::new("unrecorded");
+        let recorder = VcrInterceptor::record(&cassette.0).unwrap();
+        call(&recorder, &counting_transport(), "a").await.unwrap();
+
+        let replayer = VcrInterceptor::replay(&cassette.0).unwrap();
+        let transport = MemoryTransport::default();
+        call(&replayer, &transport, "a").await.unwrap();
+        for body in ["a", "b"] {
+            let err = call(&replayer, &transport, body).await.unwrap_err();
+            assert!(
+                matches!(err, Error::ConnectError(err) if err.code() == ConnectCode::Unimplemented)
+            );
+        }
+    }
+}
@@ -1,13 +1,49 @@
-use response::error::ConnectError;
+use response::error::{ConnectCode, ConnectError};
 
+pub mod authz;
+pub mod base64;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod capabilities;
+pub mod client;
+pub mod clock;
+pub mod codes;
 pub(crate) mod common;
+pub mod compression;
+pub mod consts;
+pub mod cors;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod grpc_bridge;
+#[cfg(feature = "hyper")]
+pub mod hyper;
+pub(crate) mod instrument;
+pub mod interceptor;
 pub mod metadata;
+pub mod metrics;
+pub mod orca;
+pub mod proxy;
 pub mod request;
 pub mod response;
 pub mod stream;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "tonic")]
+pub mod tonic;
+#[cfg(feature = "transcoding")]
+pub mod transcode;
+pub mod transport;
+#[cfg(feature = "wire-log")]
+pub mod wire_log;
 
 #[cfg(feature = "reqwest")]
 pub mod reqwest;
+#[cfg(feature = "wasi")]
+pub mod wasi;
+#[cfg(feature = "web")]
+pub mod web;
+
+pub use capabilities::capabilities;
 
 pub(crate) type BoxError = Box<dyn std::error::Error + Send + Sync>;
 
@@ -15,15 +51,40 @@ pub(crate) type BoxError = Box<dyn std::error::Error + Send + Sync>;
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("base64 decode error: {0}")]
-    Base64DecodeError(#[from] base64::DecodeError),
+    Base64DecodeError(#[from] ::base64::DecodeError),
     #[error("body error: {0}")]
     BodyError(#[source] BoxError),
-    #[error("{0}")]
+    #[error("compression error: {0}")]
+    CompressionError(#[source] BoxError),
+    #[error("conflicting headers: {0}")]
+    ConflictingHeaders(&'static str),
+    /// A frame whose length exceeds what can be sent or buffered.
+    #[error("frame too large: {size} bytes exceeds limit of {limit}")]
+    FrameTooLarge { size: u64, limit: u64 },
+    #[error(transparent)]
     ConnectError(ConnectError),
+    #[error("invalid client config: {0}")]
+    InvalidClientConfig(&'static str),
     #[error("invalid request: {0}")]
     InvalidRequest(String),
+    #[error("invalid JSON message {index}{}: {source}", .path.as_ref().map(|path| format!(" at `{path}`")).unwrap_or_default())]
+    InvalidJsonMessage {
+        index: usize,
+        path: Option<String>,
+        #[source]
+        source: serde_json::Error,
+    },
     #[error("invalid response: {0}")]
     InvalidResponse(String),
+    /// A message that couldn't be decoded with its codec; `index` is the
+    /// message's position in a stream.
+    #[error("invalid {codec} message{}: {source}", .index.map(|index| format!(" {index}")).unwrap_or_default())]
+    MessageDecode {
+        codec: String,
+        index: Option<usize>,
+        #[source]
+        source: BoxError,
+    },
     #[error("invalid metadata: {0}")]
     InvalidMetadata(&'static str),
     #[error("invalid header name: {0}")]
@@ -34,6 +95,10 @@ pub enum Error {
     InvalidUri(#[from] http::uri::InvalidUri),
     #[error("invalid URI: {0}")]
     InvalidUriParts(#[from] http::uri::InvalidUriParts),
+    /// A body that ended partway through a frame, after `received` of the
+    /// `expected` bytes (or 5, if the envelope prefix was incomplete).
+    #[error("truncated frame: stream ended after {received} of {expected} bytes")]
+    TruncatedFrame { expected: u64, received: u64 },
     #[error("unacceptable encoding {0:?}")]
     UnacceptableEncoding(String),
     #[error("unexpected message codec {0:?}")]
@@ -42,14 +107,51 @@ pub enum Error {
     #[cfg(feature = "reqwest")]
     #[error("reqwest error: {0}")]
     ReqwestError(#[source] ::reqwest::Error),
+    #[cfg(feature = "blocking")]
+    #[error("runtime error: {0}")]
+    RuntimeError(#[source] std::io::Error),
+    #[cfg(feature = "hyper")]
+    #[error("hyper error: {0}")]
+    HyperError(#[source] ::hyper_util::client::legacy::Error),
+    #[cfg(feature = "wasi")]
+    #[error("wasi http error: {0}")]
+    WasiHttpError(String),
+    #[cfg(feature = "web")]
+    #[error("fetch error: {0}")]
+    FetchError(String),
+}
+
+impl From<std::convert::Infallible> for Error {
+    fn from(err: std::convert::Infallible) -> Self {
+        match err {}
+    }
 }
 
 impl Error {
     pub(crate) fn body(err: impl Into<BoxError>) -> Self {
-        Self::BodyError(err.into())
+        match err.into().downcast::<Self>() {
+            Ok(err) => *err,
+            Err(err) => Self::BodyError(err),
+        }
     }
 
     pub(crate) fn invalid_request(msg: impl std::fmt::Display) -> Self {
         Self::InvalidRequest(msg.to_string())
     }
+
+    /// Returns the [`ConnectCode`] for this error, as it would be reported
+    /// in a [`ConnectError`].
+    pub fn connect_code(&self) -> ConnectCode {
+        match self {
+            Self::ConnectError(err) => err.code(),
+            Self::ConflictingHeaders(_)
+            | Self::InvalidResponse(_)
+            | Self::MessageDecode { .. }
+            | Self::TruncatedFrame { .. }
+            | Self::UnacceptableEncoding(_)
+            | Self::UnexpectedMessageCodec(_) => ConnectCode::Internal,
+            Self::FrameTooLarge { .. } => ConnectCode::ResourceExhausted,
+            _ => ConnectCode::Unknown,
+        }
+    }
 }
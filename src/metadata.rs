@@ -1,3 +1,9 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fmt,
+    hash::Hash,
+};
+
 use http::{header::AsHeaderName, HeaderMap, HeaderName, HeaderValue};
 
 use crate::{
@@ -7,6 +13,16 @@ use crate::{
 
 const BIN_SUFFIX: &str = "-bin";
 const TRAILER_PREFIX: &str = "trailer-";
+const REDACTED: HeaderValue = HeaderValue::from_static("<redacted>");
+
+/// Metadata keys redacted by [`Redact::redacted`], in addition to all binary
+/// (`-bin`) keys.
+pub const DEFAULT_REDACTED_KEYS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+];
 
 pub trait Metadata {
     fn get_ascii(&self, key: impl AsHeaderName + AsRef<str>) -> Option<&str>;
@@ -20,10 +36,47 @@ pub trait Metadata {
         key: impl AsHeaderName + AsRef<str>,
     ) -> impl Iterator<Item = Vec<u8>> + '_;
 
+    /// Returns all values for an ASCII key joined with `", "`, per HTTP list
+    /// semantics, or `None` if there are no values.
+    fn get_joined_ascii(&self, key: impl AsHeaderName + AsRef<str>) -> Option<String> {
+        let values: Vec<&str> = self.get_all_ascii(key).collect();
+        (!values.is_empty()).then(|| values.join(", "))
+    }
+
     fn iter_ascii(&self) -> impl Iterator<Item = (&str, &str)>;
 
     fn iter_binary(&self) -> impl Iterator<Item = (&str, Vec<u8>)>;
 
+    /// Returns all metadata entries with their raw wire values, including
+    /// values that aren't valid UTF-8 (which [`Self::iter_ascii`] skips).
+    ///
+    /// Binary (`-bin`) values are left base64-encoded, so metadata can be
+    /// forwarded losslessly.
+    fn iter_raw(&self) -> impl Iterator<Item = (&str, &[u8])>;
+
+    /// Returns ASCII metadata grouped by key.
+    ///
+    /// Keys are ordered by first appearance and values preserve their
+    /// original (wire) order.
+    fn group_ascii(&self) -> Vec<(&str, Vec<&str>)> {
+        group_ordered(self.iter_ascii())
+    }
+
+    /// Returns binary metadata grouped by key.
+    ///
+    /// Keys are ordered by first appearance and values preserve their
+    /// original (wire) order.
+    fn group_binary(&self) -> Vec<(&str, Vec<Vec<u8>>)> {
+        group_ordered(self.iter_binary())
+    }
+
+    /// Returns all metadata grouped by key, yielding each key once.
+    ///
+    /// Unlike [`Self::group_ascii`], binary (`-bin`) entries are included,
+    /// with their values left base64-encoded as on the wire. Keys are ordered
+    /// by first appearance and values preserve their original order.
+    fn iter_entries(&self) -> impl Iterator<Item = (&str, Vec<&str>)>;
+
     fn insert_ascii(
         &mut self,
         key: impl TryInto<HeaderName, Error: Into<Error>>,
@@ -100,6 +153,23 @@ impl Metadata for HeaderMap {
         })
     }
 
+    fn iter_raw(&self) -> impl Iterator<Item = (&str, &[u8])> {
+        self.iter().map(|(key, val)| {
+            let key = key.as_str();
+            let key = key.strip_prefix(TRAILER_PREFIX).unwrap_or(key);
+            (key, val.as_bytes())
+        })
+    }
+
+    fn iter_entries(&self) -> impl Iterator<Item = (&str, Vec<&str>)> {
+        let entries = self.iter().filter_map(|(key, val)| {
+            let key = key.as_str();
+            let key = key.strip_prefix(TRAILER_PREFIX).unwrap_or(key);
+            Some((key, val.to_str().ok()?))
+        });
+        group_ordered(entries).into_iter()
+    }
+
     fn insert_ascii(
         &mut self,
         key: impl TryInto<HeaderName, Error: Into<Error>>,
@@ -137,6 +207,21 @@ impl Metadata for HeaderMap {
     }
 }
 
+fn group_ordered<K: Eq + Hash + Copy, V>(iter: impl Iterator<Item = (K, V)>) -> Vec<(K, Vec<V>)> {
+    let mut groups: Vec<(K, Vec<V>)> = vec![];
+    let mut indexes: HashMap<K, usize> = HashMap::new();
+    for (key, val) in iter {
+        match indexes.entry(key) {
+            Entry::Occupied(entry) => groups[*entry.get()].1.push(val),
+            Entry::Vacant(entry) => {
+                entry.insert(groups.len());
+                groups.push((key, vec![val]));
+            }
+        }
+    }
+    groups
+}
+
 fn get_maybe_trailer(
     headers: &HeaderMap,
     key: impl AsHeaderName + AsRef<str>,
@@ -197,3 +282,129 @@ fn ascii_value(value: impl Into<String>) -> Result<HeaderValue, Error> {
 fn binary_value(value: impl AsRef<[u8]>) -> HeaderValue {
     base64_encode(value).try_into().unwrap()
 }
+
+/// Serializes metadata as a JSON object mapping each key to an array of its
+/// values, e.g. to persist, log, or replay it.
+///
+/// Binary (`-bin`) values are base64-encoded, as on the wire. Values that
+/// aren't valid UTF-8 are skipped.
+pub fn metadata_to_json(headers: &HeaderMap) -> serde_json::Value {
+    let mut object = serde_json::Map::new();
+    for key in headers.keys() {
+        let values: Vec<serde_json::Value> = headers
+            .get_all(key)
+            .iter()
+            .filter_map(|val| Some(val.to_str().ok()?.into()))
+            .collect();
+        if !values.is_empty() {
+            object.insert(key.to_string(), values.into());
+        }
+    }
+    object.into()
+}
+
+/// Deserializes metadata serialized by [`metadata_to_json`].
+///
+/// A single value may also be given as a string rather than an array.
+/// Binary (`-bin`) values must be valid base64.
+pub fn metadata_from_json(json: &serde_json::Value) -> Result<HeaderMap, Error> {
+    let object = json
+        .as_object()
+        .ok_or(Error::InvalidMetadata("metadata JSON must be an object"))?;
+    let mut headers = HeaderMap::new();
+    for (key, values) in object {
+        let values = match values {
+            serde_json::Value::Array(values) => values.iter().collect(),
+            value => vec![value],
+        };
+        for value in values {
+            let value = value.as_str().ok_or(Error::InvalidMetadata(
+                "metadata JSON values must be strings",
+            ))?;
+            if key.ends_with(BIN_SUFFIX) {
+                headers.append_binary(key.as_str(), base64_decode(value)?)?;
+            } else {
+                headers.append_ascii(key.as_str(), value)?;
+            }
+        }
+    }
+    Ok(headers)
+}
+
+/// Splits `headers` into leading headers and `trailer-` prefixed trailers
+/// (with the prefix removed), as sent in unary responses.
+pub fn split_trailers(headers: &HeaderMap) -> (HeaderMap, HeaderMap) {
+    let mut leading = HeaderMap::new();
+    let mut trailers = HeaderMap::new();
+    for (key, val) in headers {
+        match key.as_str().strip_prefix(TRAILER_PREFIX) {
+            Some(name) => match HeaderName::try_from(name) {
+                Ok(name) => trailers.append(name, val.clone()),
+                Err(_) => continue,
+            },
+            None => leading.append(key, val.clone()),
+        };
+    }
+    (leading, trailers)
+}
+
+/// Returns a copy of `headers` with the values of the given keys (matched
+/// case-insensitively, including as trailers) and of all binary (`-bin`) keys
+/// replaced with `<redacted>`.
+pub fn redact_metadata(headers: &HeaderMap, keys: &[&str]) -> HeaderMap {
+    let mut redacted = headers.clone();
+    for (key, val) in redacted.iter_mut() {
+        let key = key.as_str();
+        let key = key.strip_prefix(TRAILER_PREFIX).unwrap_or(key);
+        if key.ends_with(BIN_SUFFIX) || keys.iter().any(|k| k.eq_ignore_ascii_case(key)) {
+            *val = REDACTED;
+        }
+    }
+    redacted
+}
+
+/// Types with a [`Debug`](fmt::Debug) representation that hides sensitive
+/// metadata values, for logging.
+///
+/// ```no_run
+/// # use bytes::Bytes;
+/// # use connect_rpc::{metadata::Redact, request::UnaryRequest};
+/// # fn example(req: UnaryRequest<Bytes>) {
+/// tracing::debug!(req = ?req.redacted(), "Sending request");
+/// # }
+/// ```
+pub trait Redact {
+    /// Formats `self` like [`Debug`](fmt::Debug), with metadata redacted as
+    /// by [`redact_metadata`].
+    fn fmt_redacted(&self, keys: &[&str], f: &mut fmt::Formatter<'_>) -> fmt::Result;
+
+    /// Returns a view of `self` whose `Debug` redacts the
+    /// [`DEFAULT_REDACTED_KEYS`].
+    fn redacted(&self) -> Redacted<'_, Self> {
+        self.redacted_keys(DEFAULT_REDACTED_KEYS)
+    }
+
+    /// Returns a view of `self` whose `Debug` redacts the given keys.
+    fn redacted_keys<'a>(&'a self, keys: &'a [&'a str]) -> Redacted<'a, Self> {
+        Redacted { inner: self, keys }
+    }
+}
+
+/// A [`Debug`](fmt::Debug) view of a value with redacted metadata; see
+/// [`Redact`].
+pub struct Redacted<'a, T: ?Sized> {
+    inner: &'a T,
+    keys: &'a [&'a str],
+}
+
+impl<T: Redact + ?Sized> fmt::Debug for Redacted<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt_redacted(self.keys, f)
+    }
+}
+
+impl Redact for HeaderMap {
+    fn fmt_redacted(&self, keys: &[&str], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&redact_metadata(self, keys), f)
+    }
+}
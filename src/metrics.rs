@@ -0,0 +1,116 @@
+use std::time::Duration;
+
+use crate::{request::ConnectRequest, response::error::ConnectCode};
+
+/// Identifies the RPC being reported to a [`MetricsSink`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RpcInfo {
+    /// The fully-qualified service name, e.g. `acme.foo.v1.FooService`.
+    pub service: String,
+    /// The method name, e.g. `Bar`.
+    pub method: String,
+}
+
+impl RpcInfo {
+    pub fn from_request(req: &impl ConnectRequest) -> Self {
+        let (service, method) = req
+            .protobuf_rpc_parts()
+            .map(|(_, service, method)| (service.to_string(), method.to_string()))
+            .unwrap_or_else(|| (String::new(), req.path().to_string()));
+        Self { service, method }
+    }
+
+    /// Returns the info for a request path, e.g. from a raw HTTP request.
+    pub(crate) fn from_path(path: &str) -> Self {
+        let parts = path.rsplit_once('/').and_then(|(prefix, method)| {
+            let (_, service) = prefix.rsplit_once('/')?;
+            Some((service.to_string(), method.to_string()))
+        });
+        let (service, method) = parts.unwrap_or_else(|| (String::new(), path.to_string()));
+        Self { service, method }
+    }
+}
+
+/// A sink for per-RPC metrics.
+///
+/// All methods have no-op default implementations, so implementors only need
+/// to override the events they're interested in.
+pub trait MetricsSink: Send + Sync {
+    /// Called when an RPC starts, with the size of the request body.
+    fn on_request_start(&self, rpc: &RpcInfo, request_bytes: usize) {
+        let _ = (rpc, request_bytes);
+    }
+
+    /// Called when an RPC completes successfully, with the size of the
+    /// response body.
+    fn on_response(&self, rpc: &RpcInfo, latency: Duration, response_bytes: usize) {
+        let _ = (rpc, latency, response_bytes);
+    }
+
+    /// Called when an RPC fails.
+    fn on_error(&self, rpc: &RpcInfo, code: ConnectCode, latency: Duration) {
+        let _ = (rpc, code, latency);
+    }
+
+    /// Called when a peer violates the Connect protocol, e.g. by sending
+    /// conflicting headers. Followed by a call to [`Self::on_error`].
+    fn on_protocol_violation(&self, rpc: &RpcInfo, violation: &str) {
+        let _ = (rpc, violation);
+    }
+}
+
+/// A [`MetricsSink`] that reports to the [`metrics`](::metrics) crate's
+/// global recorder.
+///
+/// Metrics are labeled with `service` and `method` (and `code` for errors):
+/// - `connect_rpc_requests_total` (counter)
+/// - `connect_rpc_request_bytes` (histogram)
+/// - `connect_rpc_response_bytes` (histogram)
+/// - `connect_rpc_errors_total` (counter)
+/// - `connect_rpc_protocol_violations_total` (counter)
+/// - `connect_rpc_duration_seconds` (histogram)
+#[cfg(feature = "metrics")]
+#[derive(Clone, Debug, Default)]
+pub struct GlobalRecorderSink;
+
+#[cfg(feature = "metrics")]
+impl MetricsSink for GlobalRecorderSink {
+    fn on_request_start(&self, rpc: &RpcInfo, request_bytes: usize) {
+        let labels = [
+            ("service", rpc.service.clone()),
+            ("method", rpc.method.clone()),
+        ];
+        ::metrics::counter!("connect_rpc_requests_total", &labels).increment(1);
+        ::metrics::histogram!("connect_rpc_request_bytes", &labels).record(request_bytes as f64);
+    }
+
+    fn on_response(&self, rpc: &RpcInfo, latency: Duration, response_bytes: usize) {
+        let labels = [
+            ("service", rpc.service.clone()),
+            ("method", rpc.method.clone()),
+            ("code", "ok".to_string()),
+        ];
+        ::metrics::histogram!("connect_rpc_duration_seconds", &labels).record(latency);
+        ::metrics::histogram!("connect_rpc_response_bytes", &labels[..2])
+            .record(response_bytes as f64);
+    }
+
+    fn on_error(&self, rpc: &RpcInfo, code: ConnectCode, latency: Duration) {
+        let labels = [
+            ("service", rpc.service.clone()),
+            ("method", rpc.method.clone()),
+            ("code", code.as_str().to_string()),
+        ];
+        ::metrics::histogram!("connect_rpc_duration_seconds", &labels).record(latency);
+        ::metrics::counter!("connect_rpc_errors_total", &labels).increment(1);
+    }
+
+    fn on_protocol_violation(&self, rpc: &RpcInfo, violation: &str) {
+        let labels = [
+            ("service", rpc.service.clone()),
+            ("method", rpc.method.clone()),
+            ("violation", violation.to_string()),
+        ];
+        ::metrics::counter!("connect_rpc_protocol_violations_total", &labels).increment(1);
+    }
+}
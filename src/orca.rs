@@ -0,0 +1,151 @@
+//! ORCA (Open Request Cost Aggregation) backend load reports.
+//!
+//! Backends may report their load in an `endpoint-load-metrics-bin` response
+//! header as a serialized `xds.data.orca.v3.OrcaLoadReport`. Reports can be
+//! observed with a [`LoadReportListener`], and drive a
+//! [`LoadBalancer`](crate::client::balance::LoadBalancer) using
+//! [`PickStrategy::Utilization`](crate::client::balance::PickStrategy::Utilization).
+//!
+//! See: https://github.com/cncf/xds/blob/main/xds/data/orca/v3/orca_load_report.proto
+
+use std::collections::HashMap;
+
+use http::uri::Authority;
+
+use crate::{metadata::Metadata, Error};
+
+/// The metadata key for binary ORCA load reports.
+pub const LOAD_REPORT_KEY: &str = "endpoint-load-metrics-bin";
+
+/// A backend load report.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OrcaLoadReport {
+    /// CPU utilization, normally in `[0, 1]`.
+    pub cpu_utilization: f64,
+    /// Memory utilization, in `[0, 1]`.
+    pub mem_utilization: f64,
+    /// Application-specific utilization, normally in `[0, 1]`.
+    pub application_utilization: f64,
+    /// Total requests per second being served by the backend.
+    pub rps_fractional: f64,
+    /// Total errors per second being served by the backend.
+    pub eps: f64,
+    /// Per-request costs, by name.
+    pub request_cost: HashMap<String, f64>,
+    /// Resource utilizations, by name.
+    pub utilization: HashMap<String, f64>,
+    /// Application-specific metrics, by name.
+    pub named_metrics: HashMap<String, f64>,
+}
+
+impl OrcaLoadReport {
+    /// Extracts a load report from response metadata, if present.
+    pub fn from_metadata(metadata: &impl Metadata) -> Result<Option<Self>, Error> {
+        metadata
+            .get_binary(LOAD_REPORT_KEY)
+            .map(|data| Self::decode(&data))
+            .transpose()
+    }
+
+    /// Decodes a serialized `OrcaLoadReport` message.
+    pub fn decode(mut data: &[u8]) -> Result<Self, Error> {
+        let mut report = Self::default();
+        while !data.is_empty() {
+            let (field, value) = read_field(&mut data)?;
+            match (field, value) {
+                (1, FieldValue::Fixed64(v)) => report.cpu_utilization = f64::from_bits(v),
+                (2, FieldValue::Fixed64(v)) => report.mem_utilization = f64::from_bits(v),
+                // Deprecated integer `rps`; superseded by `rps_fractional`.
+                (3, FieldValue::Varint(v)) if report.rps_fractional == 0.0 => {
+                    report.rps_fractional = v as f64
+                }
+                (4, FieldValue::Bytes(entry)) => insert_entry(&mut report.request_cost, entry)?,
+                (5, FieldValue::Bytes(entry)) => insert_entry(&mut report.utilization, entry)?,
+                (6, FieldValue::Fixed64(v)) => report.rps_fractional = f64::from_bits(v),
+                (7, FieldValue::Fixed64(v)) => report.eps = f64::from_bits(v),
+                (8, FieldValue::Bytes(entry)) => insert_entry(&mut report.named_metrics, entry)?,
+                (9, FieldValue::Fixed64(v)) => report.application_utilization = f64::from_bits(v),
+                _ => (),
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// Receives backend load reports from responses.
+pub trait LoadReportListener: Send + Sync {
+    /// Called with each load report received from `authority`.
+    fn on_load_report(&self, authority: &Authority, report: &OrcaLoadReport);
+}
+
+enum FieldValue<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(&'a [u8]),
+    Fixed32,
+}
+
+fn read_field<'a>(data: &mut &'a [u8]) -> Result<(u64, FieldValue<'a>), Error> {
+    let key = read_varint(data)?;
+    let value = match key & 0b111 {
+        0 => FieldValue::Varint(read_varint(data)?),
+        1 => FieldValue::Fixed64(u64::from_le_bytes(take(data, 8)?.try_into().unwrap())),
+        2 => {
+            let len = read_varint(data)?
+                .try_into()
+                .map_err(|_| invalid_report())?;
+            FieldValue::Bytes(take(data, len)?)
+        }
+        5 => {
+            take(data, 4)?;
+            FieldValue::Fixed32
+        }
+        _ => return Err(invalid_report()),
+    };
+    Ok((key >> 3, value))
+}
+
+fn read_varint(data: &mut &[u8]) -> Result<u64, Error> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = data.split_first().ok_or_else(invalid_report)?;
+        *data = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(invalid_report())
+}
+
+fn take<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8], Error> {
+    if data.len() < len {
+        return Err(invalid_report());
+    }
+    let (taken, rest) = data.split_at(len);
+    *data = rest;
+    Ok(taken)
+}
+
+/// Decodes a `map<string, double>` entry.
+fn insert_entry(map: &mut HashMap<String, f64>, mut entry: &[u8]) -> Result<(), Error> {
+    let mut key = String::new();
+    let mut value = 0.0;
+    while !entry.is_empty() {
+        match read_field(&mut entry)? {
+            (1, FieldValue::Bytes(k)) => {
+                key = std::str::from_utf8(k)
+                    .map_err(|_| invalid_report())?
+                    .to_string()
+            }
+            (2, FieldValue::Fixed64(v)) => value = f64::from_bits(v),
+            _ => (),
+        }
+    }
+    map.insert(key, value);
+    Ok(())
+}
+
+fn invalid_report() -> Error {
+    Error::InvalidMetadata("invalid ORCA load report")
+}
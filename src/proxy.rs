@@ -0,0 +1,134 @@
+//! Forwarding of Connect requests through a reverse proxy.
+//!
+//! [`forward`] prepares an inbound request to be sent to an upstream server;
+//! bodies are passed through untouched, so streams are piped rather than
+//! buffered. To deduct time spent in the proxy from the request timeout,
+//! insert a [`ReceivedAt`] extension when the request arrives:
+//!
+//! ```no_run
+//! # use bytes::Bytes;
+//! # use connect_rpc::{proxy::{self, ReceivedAt}, Error};
+//! # async fn send(req: http::Request<Bytes>) -> Result<http::Response<Bytes>, Error> { unimplemented!() }
+//! # async fn example(mut req: http::Request<Bytes>) -> Result<(), Error> {
+//! req.extensions_mut().insert(ReceivedAt::now());
+//! // ...
+//! let upstream_req = proxy::forward(req, "backend.internal:8080")?;
+//! let resp = proxy::forward_response(send(upstream_req).await?);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::time::{Duration, Instant};
+
+use http::{
+    header,
+    uri::{Authority, Scheme},
+    HeaderMap, HeaderName, Uri,
+};
+
+use crate::{
+    common::CONNECT_TIMEOUT_MS,
+    response::error::{ConnectCode, ConnectError},
+    Error,
+};
+
+/// Headers that apply to a single connection and must not be forwarded.
+///
+/// See: https://www.rfc-editor.org/rfc/rfc9110#section-7.6.1
+const HOP_BY_HOP: [HeaderName; 8] = [
+    header::CONNECTION,
+    HeaderName::from_static("keep-alive"),
+    HeaderName::from_static("proxy-connection"),
+    header::PROXY_AUTHENTICATE,
+    header::PROXY_AUTHORIZATION,
+    header::TRAILER,
+    header::TRANSFER_ENCODING,
+    header::UPGRADE,
+];
+
+/// A request extension recording when a proxy received the request.
+#[derive(Clone, Copy, Debug)]
+pub struct ReceivedAt(pub Instant);
+
+impl ReceivedAt {
+    pub fn now() -> Self {
+        Self(Instant::now())
+    }
+}
+
+/// Prepares an inbound request to be forwarded to `upstream`.
+///
+/// The URI's authority is replaced (the scheme defaults to `http`),
+/// hop-by-hop headers are removed, and Connect metadata is preserved. If the
+/// request has a [`ReceivedAt`] extension, `connect-timeout-ms` is reduced by
+/// the time elapsed since; a timeout that has already expired fails with
+/// `deadline_exceeded`.
+pub fn forward<B>(
+    mut req: http::Request<B>,
+    upstream: impl TryInto<Authority, Error: Into<Error>>,
+) -> Result<http::Request<B>, Error> {
+    let mut uri = std::mem::take(req.uri_mut()).into_parts();
+    uri.authority = Some(upstream.try_into().map_err(Into::into)?);
+    uri.scheme.get_or_insert(Scheme::HTTP);
+    if uri.path_and_query.is_none() {
+        uri.path_and_query = Some("/".try_into()?);
+    }
+    *req.uri_mut() = Uri::from_parts(uri)?;
+
+    strip_hop_by_hop(req.headers_mut());
+    // `te: trailers` is hop-by-hop but signals trailer support end-to-end.
+    if req
+        .headers()
+        .get(header::TE)
+        .is_some_and(|te| te.as_bytes() != b"trailers")
+    {
+        req.headers_mut().remove(header::TE);
+    }
+    req.headers_mut().remove(header::HOST);
+
+    if let Some(ReceivedAt(received_at)) = req.extensions().get().copied() {
+        decrement_timeout(req.headers_mut(), received_at.elapsed())?;
+    }
+    Ok(req)
+}
+
+/// Prepares an upstream response to be returned to the client, removing
+/// hop-by-hop headers.
+pub fn forward_response<B>(mut resp: http::Response<B>) -> http::Response<B> {
+    strip_hop_by_hop(resp.headers_mut());
+    resp.headers_mut().remove(header::TE);
+    resp
+}
+
+/// Removes hop-by-hop headers, including those named by `connection`.
+pub(crate) fn strip_hop_by_hop(headers: &mut HeaderMap) {
+    let named: Vec<HeaderName> = headers
+        .get_all(header::CONNECTION)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|name| HeaderName::try_from(name.trim()).ok())
+        .collect();
+    for name in HOP_BY_HOP.iter().chain(&named) {
+        headers.remove(name);
+    }
+}
+
+fn decrement_timeout(headers: &mut HeaderMap, elapsed: Duration) -> Result<(), Error> {
+    let Some(timeout_ms) = headers
+        .get(CONNECT_TIMEOUT_MS)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u128>().ok())
+    else {
+        return Ok(());
+    };
+    let remaining_ms = timeout_ms.saturating_sub(elapsed.as_millis());
+    if remaining_ms == 0 {
+        return Err(Error::ConnectError(ConnectError::new(
+            ConnectCode::DeadlineExceeded,
+            "timeout expired before forwarding",
+        )));
+    }
+    headers.insert(CONNECT_TIMEOUT_MS, remaining_ms.to_string().try_into()?);
+    Ok(())
+}
@@ -1,23 +1,27 @@
-use std::{borrow::Cow, collections::HashMap, time::Duration};
+use std::{borrow::Cow, collections::HashMap, fmt, time::Duration};
 
-use base64::prelude::{Engine, BASE64_URL_SAFE_NO_PAD};
 use http::{
     header,
     uri::{Authority, Scheme},
-    HeaderMap, Method, Uri,
+    HeaderMap, Method, Uri, Version,
 };
 
 use crate::{
+    base64,
     common::{
+        content_type_params, is_identity_encoding, parse_timeout, request_timeout,
         streaming_message_codec, unary_message_codec, CONNECT_ACCEPT_ENCODING,
         CONNECT_CONTENT_ENCODING, CONNECT_PROTOCOL_VERSION, CONNECT_TIMEOUT_MS, PROTOCOL_VERSION_1,
         STREAMING_CONTENT_TYPE_PREFIX,
     },
-    metadata::Metadata,
+    compression::{self, AcceptEncoding},
+    metadata::{self, redact_metadata, Metadata, Redact},
+    response::error::{ConnectCode, ConnectError},
     Error,
 };
 
 pub mod builder;
+pub mod lint;
 
 /// A Connect request.
 pub trait ConnectRequest {
@@ -33,6 +37,11 @@ pub trait ConnectRequest {
     /// Returns the URI path.
     fn path(&self) -> &str;
 
+    /// Returns the HTTP version required with
+    /// [`RequestBuilder::http_version`](builder::RequestBuilder::http_version),
+    /// if any.
+    fn http_version(&self) -> Option<Version>;
+
     /// Splits a protobuf RPC request path into routing prefix, service name,
     /// and method name.
     ///
@@ -46,6 +55,10 @@ pub trait ConnectRequest {
     /// Returns the message codec.
     fn message_codec(&self) -> Result<&str, Error>;
 
+    /// Returns the `content-type` parameters (e.g. `charset`), which are
+    /// ignored when determining the message codec.
+    fn content_type_params(&self) -> impl Iterator<Item = (&str, &str)>;
+
     /// Returns the timeout.
     fn timeout(&self) -> Option<Duration>;
 
@@ -55,11 +68,42 @@ pub trait ConnectRequest {
     /// Returns the accept encoding(s).
     fn accept_encoding(&self) -> impl Iterator<Item = &str>;
 
+    /// Returns the parsed accept encoding(s), with weights.
+    fn accept_encoding_parsed(&self) -> AcceptEncoding {
+        AcceptEncoding::parse(self.accept_encoding())
+    }
+
     /// Returns the metadata.
     fn metadata(&self) -> &impl Metadata;
 
     /// Validates the request.
-    fn validate(&self) -> Result<(), Error>;
+    fn validate(&self) -> Result<(), Error> {
+        self.validate_with(&RequestValidateOpts::default())
+    }
+
+    /// Validates the request with the given options.
+    fn validate_with(&self, opts: &RequestValidateOpts) -> Result<(), Error>;
+
+    /// Returns the timeout for a server to enforce.
+    ///
+    /// Unlike [`Self::timeout`], which ignores invalid values, a
+    /// `connect-timeout-ms` that isn't 1 to 10 digits fails with
+    /// `invalid_argument`. The timeout (or its absence) is clamped to
+    /// [`RequestValidateOpts::max_timeout`].
+    fn timeout_with(&self, opts: &RequestValidateOpts) -> Result<Option<Duration>, Error>;
+}
+
+/// Options for [`ConnectRequest::validate_with`].
+#[derive(Clone, Debug, Default)]
+pub struct RequestValidateOpts {
+    /// Rejects requests without a `connect-protocol-version` header (or
+    /// `connect=v1` query param for GET requests) with `invalid_argument`.
+    ///
+    /// Equivalent to connect-go's `WithRequireConnectProtocolHeader`.
+    pub require_protocol_version: bool,
+    /// The maximum timeout returned by [`ConnectRequest::timeout_with`],
+    /// including for requests without a timeout.
+    pub max_timeout: Option<Duration>,
 }
 
 /// Connect request types.
@@ -91,6 +135,8 @@ trait HttpConnectRequest {
 
     fn http_headers(&self) -> &HeaderMap;
 
+    fn http_required_version(&self) -> Option<Version>;
+
     fn http_message_codec(&self) -> Result<&str, Error>;
 
     fn http_connect_protocol_version(&self) -> Option<&str> {
@@ -109,16 +155,48 @@ trait HttpConnectRequest {
             .filter_map(|val| val.to_str().ok())
     }
 
-    fn http_validate(&self) -> Result<(), Error>
+    /// The error message for a missing protocol version.
+    fn http_missing_protocol_version(&self) -> &'static str {
+        r#"missing required header: set Connect-Protocol-Version to "1""#
+    }
+
+    fn http_validate(&self, opts: &RequestValidateOpts) -> Result<(), Error>
     where
         Self: Sized,
     {
-        validate_request(self)
+        validate_request(self, opts)
     }
 }
 
-fn validate_request(req: &impl HttpConnectRequest) -> Result<(), Error> {
+/// The HTTP version required with
+/// [`RequestBuilder::http_version`](builder::RequestBuilder::http_version),
+/// stored in request extensions.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct RequiredHttpVersion(pub(crate) Version);
+
+/// The GET URL limit set with
+/// [`RequestBuilder::get_url_max_bytes`](builder::RequestBuilder::get_url_max_bytes),
+/// stored in request extensions.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct GetUrlMaxBytes(pub(crate) usize);
+
+fn required_version(extensions: &http::Extensions) -> Option<Version> {
+    extensions
+        .get::<RequiredHttpVersion>()
+        .map(|required| required.0)
+}
+
+fn validate_request(
+    req: &impl HttpConnectRequest,
+    opts: &RequestValidateOpts,
+) -> Result<(), Error> {
     match req.http_connect_protocol_version() {
+        None if opts.require_protocol_version => {
+            return Err(Error::ConnectError(ConnectError::new(
+                ConnectCode::InvalidArgument,
+                req.http_missing_protocol_version(),
+            )));
+        }
         None => (),
         Some(ver) if ver == PROTOCOL_VERSION_1 => (),
         Some(ver) => {
@@ -128,6 +206,9 @@ fn validate_request(req: &impl HttpConnectRequest) -> Result<(), Error> {
         }
     }
     let _ = req.http_message_codec()?;
+    if let Some(timeout) = req.http_headers().get(CONNECT_TIMEOUT_MS) {
+        parse_timeout(timeout)?;
+    }
     Ok(())
 }
 
@@ -148,19 +229,20 @@ impl<T: HttpConnectRequest> ConnectRequest for T {
         self.http_uri().path()
     }
 
+    fn http_version(&self) -> Option<Version> {
+        self.http_required_version()
+    }
+
     fn message_codec(&self) -> Result<&str, Error> {
         self.http_message_codec()
     }
 
+    fn content_type_params(&self) -> impl Iterator<Item = (&str, &str)> {
+        content_type_params(self.http_headers())
+    }
+
     fn timeout(&self) -> Option<Duration> {
-        let timeout_ms: u64 = self
-            .http_headers()
-            .get(CONNECT_TIMEOUT_MS)?
-            .to_str()
-            .ok()?
-            .parse()
-            .ok()?;
-        Some(Duration::from_millis(timeout_ms))
+        request_timeout(self.http_headers())
     }
 
     fn content_encoding(&self) -> Option<&str> {
@@ -175,14 +257,128 @@ impl<T: HttpConnectRequest> ConnectRequest for T {
         self.http_headers()
     }
 
-    fn validate(&self) -> Result<(), Error> {
-        self.http_validate()
+    fn validate_with(&self, opts: &RequestValidateOpts) -> Result<(), Error> {
+        self.http_validate(opts)
     }
+
+    fn timeout_with(&self, opts: &RequestValidateOpts) -> Result<Option<Duration>, Error> {
+        let timeout = self
+            .http_headers()
+            .get(CONNECT_TIMEOUT_MS)
+            .map(parse_timeout)
+            .transpose()?;
+        Ok(match (timeout, opts.max_timeout) {
+            (Some(timeout), Some(max)) => Some(timeout.min(max)),
+            (timeout, max) => timeout.or(max),
+        })
+    }
+}
+
+/// An RPC's idempotency level, mirroring protobuf's
+/// `MethodOptions.IdempotencyLevel`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IdempotencyLevel {
+    #[default]
+    IdempotencyUnknown,
+    /// The RPC has no side effects; it may be sent as a GET request.
+    NoSideEffects,
+    /// The RPC may be safely retried.
+    Idempotent,
 }
 
 /// A Connect unary request.
 pub struct UnaryRequest<T>(http::Request<T>);
 
+impl<T> UnaryRequest<T> {
+    /// Returns the idempotency level set by
+    /// [`RequestBuilder::idempotency_level`](builder::RequestBuilder::idempotency_level).
+    pub fn idempotency_level(&self) -> IdempotencyLevel {
+        self.0.extensions().get().copied().unwrap_or_default()
+    }
+
+    /// Returns the maximum URL length for sending this request as a GET
+    /// request, set with
+    /// [`RequestBuilder::get_url_max_bytes`](builder::RequestBuilder::get_url_max_bytes).
+    pub fn get_url_max_bytes(&self) -> usize {
+        self.0
+            .extensions()
+            .get::<GetUrlMaxBytes>()
+            .map_or(builder::DEFAULT_GET_URL_MAX_BYTES, |max| max.0)
+    }
+
+    /// Returns the per-call extensions, e.g. set with
+    /// [`RequestBuilder::extension`](builder::RequestBuilder::extension).
+    pub fn extensions(&self) -> &http::Extensions {
+        self.0.extensions()
+    }
+
+    /// Returns a mutable reference to the per-call extensions.
+    pub fn extensions_mut(&mut self) -> &mut http::Extensions {
+        self.0.extensions_mut()
+    }
+
+    /// Returns a mutable reference to the headers, including metadata.
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        self.0.headers_mut()
+    }
+
+    /// Returns a mutable reference to the metadata.
+    pub fn metadata_mut(&mut self) -> &mut impl Metadata {
+        self.0.headers_mut()
+    }
+
+    /// Returns a mutable reference to the URI.
+    pub fn uri_mut(&mut self) -> &mut Uri {
+        self.0.uri_mut()
+    }
+
+    /// Returns the body.
+    pub fn body(&self) -> &T {
+        self.0.body()
+    }
+
+    /// Returns a mutable reference to the body.
+    pub fn body_mut(&mut self) -> &mut T {
+        self.0.body_mut()
+    }
+}
+
+impl<T: AsRef<[u8]>> UnaryRequest<T> {
+    /// Returns the equivalent [`UnaryGetRequest`].
+    pub fn to_unary_get(&self) -> Result<UnaryGetRequest, Error> {
+        let query = builder::unary_get_query(
+            self.0.body().as_ref(),
+            self.message_codec()?,
+            self.content_encoding(),
+        );
+        let mut parts = self.0.uri().clone().into_parts();
+        parts.path_and_query = Some(format!("{}?{query}", self.0.uri().path()).try_into()?);
+        let mut req = http::Request::new(());
+        *req.method_mut() = Method::GET;
+        *req.uri_mut() = Uri::from_parts(parts)?;
+        *req.version_mut() = self.0.version();
+        *req.headers_mut() = self.0.headers().clone();
+        for name in [
+            header::CONTENT_TYPE,
+            header::CONTENT_ENCODING,
+            header::CONTENT_LENGTH,
+        ] {
+            req.headers_mut().remove(name);
+        }
+        *req.extensions_mut() = self.0.extensions().clone();
+        Ok(req.into())
+    }
+
+    /// Renders an equivalent curl invocation, e.g. for bug reports.
+    ///
+    /// The body is written (base64-decoded) to a `body.bin` file referenced
+    /// by the curl command. Sensitive metadata is redacted as by
+    /// [`Redact::redacted`].
+    pub fn to_curl(&self) -> String {
+        curl_command(&self.0, Some(self.0.body().as_ref()))
+    }
+}
+
 impl<T> HttpConnectRequest for UnaryRequest<T> {
     fn http_uri(&self) -> &Uri {
         self.0.uri()
@@ -192,6 +388,10 @@ impl<T> HttpConnectRequest for UnaryRequest<T> {
         self.0.headers()
     }
 
+    fn http_required_version(&self) -> Option<Version> {
+        required_version(self.0.extensions())
+    }
+
     fn http_message_codec(&self) -> Result<&str, Error> {
         unary_message_codec(self.http_headers())
     }
@@ -219,6 +419,44 @@ impl<T> From<UnaryRequest<T>> for http::Request<T> {
 /// A Connect streaming request.
 pub struct StreamingRequest<T>(http::Request<T>);
 
+impl<T> StreamingRequest<T> {
+    /// Returns the per-call extensions, e.g. set with
+    /// [`RequestBuilder::extension`](builder::RequestBuilder::extension).
+    pub fn extensions(&self) -> &http::Extensions {
+        self.0.extensions()
+    }
+
+    /// Returns a mutable reference to the per-call extensions.
+    pub fn extensions_mut(&mut self) -> &mut http::Extensions {
+        self.0.extensions_mut()
+    }
+
+    /// Returns a mutable reference to the headers, including metadata.
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        self.0.headers_mut()
+    }
+
+    /// Returns a mutable reference to the metadata.
+    pub fn metadata_mut(&mut self) -> &mut impl Metadata {
+        self.0.headers_mut()
+    }
+
+    /// Returns a mutable reference to the URI.
+    pub fn uri_mut(&mut self) -> &mut Uri {
+        self.0.uri_mut()
+    }
+
+    /// Returns the body.
+    pub fn body(&self) -> &T {
+        self.0.body()
+    }
+
+    /// Returns a mutable reference to the body.
+    pub fn body_mut(&mut self) -> &mut T {
+        self.0.body_mut()
+    }
+}
+
 impl<T> HttpConnectRequest for StreamingRequest<T> {
     fn http_uri(&self) -> &Uri {
         self.0.uri()
@@ -228,6 +466,10 @@ impl<T> HttpConnectRequest for StreamingRequest<T> {
         self.0.headers()
     }
 
+    fn http_required_version(&self) -> Option<Version> {
+        required_version(self.0.extensions())
+    }
+
     fn http_message_codec(&self) -> Result<&str, Error> {
         streaming_message_codec(self.http_headers())
     }
@@ -259,6 +501,14 @@ impl<T> From<StreamingRequest<T>> for http::Request<T> {
     }
 }
 
+/// A unary request sent as either GET or POST.
+///
+/// See [`RequestBuilder::unary_get_or_post`](builder::RequestBuilder::unary_get_or_post).
+pub enum UnaryGetOrPostRequest<T> {
+    Get(UnaryGetRequest),
+    Post(UnaryRequest<T>),
+}
+
 /// A Connect unary GET request.
 pub struct UnaryGetRequest {
     inner: http::Request<()>,
@@ -266,14 +516,75 @@ pub struct UnaryGetRequest {
 }
 
 impl UnaryGetRequest {
-    pub fn message(&self) -> Result<Cow<[u8]>, Error> {
+    /// Returns the length of the request URL in bytes.
+    pub(crate) fn url_len(&self) -> usize {
+        self.inner.uri().to_string().len()
+    }
+
+    /// Returns the per-call extensions, e.g. set with
+    /// [`RequestBuilder::extension`](builder::RequestBuilder::extension).
+    pub fn extensions(&self) -> &http::Extensions {
+        self.inner.extensions()
+    }
+
+    /// Returns a mutable reference to the per-call extensions.
+    pub fn extensions_mut(&mut self) -> &mut http::Extensions {
+        self.inner.extensions_mut()
+    }
+
+    /// Returns a mutable reference to the headers, including metadata.
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        self.inner.headers_mut()
+    }
+
+    /// Returns a mutable reference to the metadata.
+    pub fn metadata_mut(&mut self) -> &mut impl Metadata {
+        self.inner.headers_mut()
+    }
+
+    /// Replaces the URI, re-parsing its query (which carries the message).
+    pub fn set_uri(&mut self, uri: Uri) {
+        self.query = parse_query(&uri);
+        *self.inner.uri_mut() = uri;
+    }
+
+    /// Returns the decoded (and decompressed) message.
+    ///
+    /// See [`Self::message_with_limit`] to bound the decompressed size.
+    pub fn message(&self) -> Result<Cow<'_, [u8]>, Error> {
+        self.message_with_limit(usize::MAX)
+    }
+
+    /// Returns the decoded message, decompressing it per the `compression`
+    /// query param.
+    ///
+    /// Returns a `resource_exhausted` error if the decompressed message would
+    /// exceed `limit` bytes.
+    pub fn message_with_limit(&self, limit: usize) -> Result<Cow<'_, [u8]>, Error> {
+        let message = self.encoded_message()?;
+        match self.content_encoding() {
+            Some(name) if !is_identity_encoding(name) => {
+                let coding = compression::lookup(name)
+                    .ok_or_else(|| Error::UnacceptableEncoding(name.into()))?;
+                Ok(coding.decompress(&message, limit)?.to_vec().into())
+            }
+            _ => {
+                if message.len() > limit {
+                    return Err(compression::limit_exceeded());
+                }
+                Ok(message)
+            }
+        }
+    }
+
+    fn encoded_message(&self) -> Result<Cow<'_, [u8]>, Error> {
         let message = self
             .query
             .get("message")
             .ok_or(Error::invalid_request("missing message"))?;
         let is_b64 = self.query.get("base64").map(|s| s.as_str()) == Some("1");
         if is_b64 {
-            Ok(BASE64_URL_SAFE_NO_PAD.decode(message)?.into())
+            Ok(base64::config().get_message.decode(message)?.into())
         } else {
             Ok(
                 match percent_encoding::percent_decode_str(message)
@@ -288,6 +599,15 @@ impl UnaryGetRequest {
     }
 }
 
+impl UnaryGetRequest {
+    /// Renders an equivalent curl invocation, e.g. for bug reports.
+    ///
+    /// Sensitive metadata is redacted as by [`Redact::redacted`].
+    pub fn to_curl(&self) -> String {
+        curl_command(&self.inner, None)
+    }
+}
+
 impl HttpConnectRequest for UnaryGetRequest {
     fn http_uri(&self) -> &Uri {
         self.inner.uri()
@@ -297,6 +617,10 @@ impl HttpConnectRequest for UnaryGetRequest {
         self.inner.headers()
     }
 
+    fn http_required_version(&self) -> Option<Version> {
+        required_version(self.inner.extensions())
+    }
+
     fn http_message_codec(&self) -> Result<&str, Error> {
         self.query
             .get("encoding")
@@ -309,14 +633,18 @@ impl HttpConnectRequest for UnaryGetRequest {
     }
 
     fn http_content_encoding(&self) -> Option<&str> {
-        self.query.get("encoding").map(|s| s.as_str())
+        self.query.get("compression").map(|s| s.as_str())
     }
 
-    fn http_validate(&self) -> Result<(), Error>
+    fn http_missing_protocol_version(&self) -> &'static str {
+        r#"missing required query parameter: set connect to "v1""#
+    }
+
+    fn http_validate(&self, opts: &RequestValidateOpts) -> Result<(), Error>
     where
         Self: Sized,
     {
-        validate_request(self)?;
+        validate_request(self, opts)?;
         if !self.query.contains_key("message") {
             return Err(Error::invalid_request("missing 'message' param"));
         }
@@ -326,16 +654,80 @@ impl HttpConnectRequest for UnaryGetRequest {
 
 impl From<http::Request<()>> for UnaryGetRequest {
     fn from(req: http::Request<()>) -> Self {
-        let query: HashMap<_, _> =
-            form_urlencoded::parse(req.uri().query().unwrap_or_default().as_bytes())
-                .map(|(k, v)| (k.to_string(), v.to_string()))
-                .collect();
+        let query = parse_query(req.uri());
         Self { inner: req, query }
     }
 }
 
+fn parse_query(uri: &Uri) -> HashMap<String, String> {
+    form_urlencoded::parse(uri.query().unwrap_or_default().as_bytes())
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
 impl From<UnaryGetRequest> for http::Request<()> {
     fn from(req: UnaryGetRequest) -> Self {
         req.inner
     }
 }
+
+impl<T> Redact for UnaryRequest<T> {
+    fn fmt_redacted(&self, keys: &[&str], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_redacted_request("UnaryRequest", &self.0, keys, f)
+    }
+}
+
+impl<T> Redact for StreamingRequest<T> {
+    fn fmt_redacted(&self, keys: &[&str], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_redacted_request("StreamingRequest", &self.0, keys, f)
+    }
+}
+
+impl Redact for UnaryGetRequest {
+    fn fmt_redacted(&self, keys: &[&str], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_redacted_request("UnaryGetRequest", &self.inner, keys, f)
+    }
+}
+
+fn fmt_redacted_request<T>(
+    name: &str,
+    req: &http::Request<T>,
+    keys: &[&str],
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    f.debug_struct(name)
+        .field("method", req.method())
+        .field("uri", req.uri())
+        .field("version", &req.version())
+        .field("headers", &redact_metadata(req.headers(), keys))
+        .finish_non_exhaustive()
+}
+
+fn curl_command<T>(req: &http::Request<T>, body: Option<&[u8]>) -> String {
+    let mut command = String::new();
+    if let Some(body) = body {
+        command.push_str(&format!(
+            "echo {} | base64 -d > body.bin\n",
+            shell_quote(&base64::Base64Variant::Standard.encode(body)),
+        ));
+    }
+    command.push_str(&format!(
+        "curl -X {} {}",
+        req.method(),
+        shell_quote(&req.uri().to_string())
+    ));
+    let headers = redact_metadata(req.headers(), metadata::DEFAULT_REDACTED_KEYS);
+    for (key, val) in &headers {
+        let header = format!("{key}: {}", String::from_utf8_lossy(val.as_bytes()));
+        command.push_str(&format!(" \\\n  -H {}", shell_quote(&header)));
+    }
+    if body.is_some() {
+        command.push_str(" \\\n  --data-binary @body.bin");
+    }
+    command
+}
+
+/// Quotes a string for POSIX shells.
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
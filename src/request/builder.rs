@@ -1,32 +1,51 @@
 use http::{
     header,
     uri::{Authority, Parts, PathAndQuery, Scheme},
-    HeaderMap, HeaderName, HeaderValue, Method, Request, Uri,
+    HeaderMap, HeaderName, HeaderValue, Method, Request, Uri, Version,
 };
 
-use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL_SAFE, Engine};
+use base64::{engine::general_purpose::STANDARD as BASE64_STANDARD, Engine};
+use bytes::Bytes;
+use futures_util::{Stream, StreamExt};
 
 use crate::{
     common::{
-        is_valid_http_token, CONNECT_ACCEPT_ENCODING, CONNECT_CONTENT_ENCODING,
-        CONNECT_PROTOCOL_VERSION, CONNECT_TIMEOUT_MS, CONTENT_TYPE_PREFIX, PROTOCOL_VERSION_1,
+        is_identity_encoding, is_valid_http_token, CONNECT_ACCEPT_ENCODING,
+        CONNECT_CONTENT_ENCODING, CONNECT_PROTOCOL_VERSION, CONNECT_TIMEOUT_MS,
+        CONTENT_TYPE_PREFIX, PROTOCOL_VERSION_1, STREAMING_CONTENT_TYPE_PREFIX,
     },
+    compression,
     metadata::Metadata,
+    stream::{ConnectFrame, FramedBody},
     Error,
 };
 
-use super::{StreamingRequest, UnaryGetRequest, UnaryRequest};
+use super::{
+    GetUrlMaxBytes, IdempotencyLevel, RequiredHttpVersion, StreamingRequest, UnaryGetOrPostRequest,
+    UnaryGetRequest, UnaryRequest,
+};
+
+/// The default `user-agent`, unless overridden with [`RequestBuilder::header`].
+pub const DEFAULT_USER_AGENT: &str = concat!("connect-rpc-rust/", env!("CARGO_PKG_VERSION"));
+
+/// The default maximum URL length for [`RequestBuilder::unary_get_or_post`].
+pub const DEFAULT_GET_URL_MAX_BYTES: usize = 8 * 1024;
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct RequestBuilder {
     scheme: Option<Scheme>,
     authority: Option<Authority>,
     path: Option<String>,
     metadata: HeaderMap,
+    headers: HeaderMap,
     message_codec: Option<String>,
     timeout_ms: Option<HeaderValue>,
     content_encoding: Option<String>,
     accept_encoding: Vec<HeaderValue>,
+    get_url_max_bytes: Option<usize>,
+    idempotency_level: Option<IdempotencyLevel>,
+    http_version: Option<Version>,
+    extensions: http::Extensions,
 }
 
 impl RequestBuilder {
@@ -140,6 +159,64 @@ impl RequestBuilder {
         Ok(self)
     }
 
+    /// Sets a standard HTTP header (e.g. `user-agent` or `x-forwarded-for`),
+    /// replacing any existing values.
+    ///
+    /// Unlike metadata, the value is sent as-is.
+    pub fn header(
+        mut self,
+        key: impl TryInto<HeaderName, Error: Into<Error>>,
+        val: impl TryInto<HeaderValue, Error: Into<Error>>,
+    ) -> Result<Self, Error> {
+        let key = key.try_into().map_err(Into::into)?;
+        let val = val.try_into().map_err(Into::into)?;
+        self.headers.insert(key, val);
+        Ok(self)
+    }
+
+    /// Sets the `authorization` header to a bearer token.
+    pub fn bearer_auth(self, token: impl std::fmt::Display) -> Result<Self, Error> {
+        self.authorization(format!("Bearer {token}"))
+    }
+
+    /// Sets the `authorization` header for HTTP Basic authentication.
+    pub fn basic_auth(
+        self,
+        username: impl std::fmt::Display,
+        password: impl std::fmt::Display,
+    ) -> Result<Self, Error> {
+        let credentials = BASE64_STANDARD.encode(format!("{username}:{password}"));
+        self.authorization(format!("Basic {credentials}"))
+    }
+
+    fn authorization(mut self, value: String) -> Result<Self, Error> {
+        let mut value: HeaderValue = value.try_into()?;
+        value.set_sensitive(true);
+        self.metadata.insert(header::AUTHORIZATION, value);
+        Ok(self)
+    }
+
+    /// Sets an API key header, e.g. `x-api-key`.
+    ///
+    /// Replaces any existing values for the header.
+    pub fn api_key(
+        mut self,
+        key: impl TryInto<HeaderName, Error: Into<Error>>,
+        val: impl Into<String>,
+    ) -> Result<Self, Error> {
+        let key = key.try_into().map_err(Into::into)?;
+        if key.as_str().starts_with("connect-") {
+            return Err(Error::invalid_request(
+                "API key header may not use the reserved 'connect-' prefix",
+            ));
+        }
+        self.metadata.insert_ascii(key.clone(), val)?;
+        if let Some(value) = self.metadata.get_mut(key) {
+            value.set_sensitive(true);
+        }
+        Ok(self)
+    }
+
     /// Sets the message codec for this request.
     ///
     /// Typical codecs are 'json' and 'proto', corresponding to the
@@ -175,12 +252,16 @@ impl RequestBuilder {
     }
 
     /// Sets the request content encoding (e.g. compression).
+    ///
+    /// `identity` (the default) clears any previous encoding; no header is
+    /// sent for it.
     pub fn content_encoding(mut self, content_encoding: impl Into<String>) -> Result<Self, Error> {
         let content_encoding = content_encoding.into();
         if !is_valid_http_token(&content_encoding) {
             return Err(Error::invalid_request("invalid content encoding"));
         }
-        self.content_encoding = Some(content_encoding);
+        self.content_encoding =
+            Some(content_encoding).filter(|encoding| !is_identity_encoding(encoding));
         Ok(self)
     }
 
@@ -196,11 +277,58 @@ impl RequestBuilder {
         Ok(self)
     }
 
+    /// Sets the maximum URL length for [`Self::unary_get_or_post`] and for
+    /// [`ConnectClient`](crate::client::ConnectClient) calls sent as GET
+    /// requests.
+    ///
+    /// Defaults to [`DEFAULT_GET_URL_MAX_BYTES`].
+    pub fn get_url_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.get_url_max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Sets the RPC's idempotency level, e.g. from the method's
+    /// `idempotency_level` option.
+    ///
+    /// See [`ClientBuilder::http_get`](crate::client::builder::ClientBuilder::http_get).
+    pub fn idempotency_level(mut self, level: IdempotencyLevel) -> Self {
+        self.idempotency_level = Some(level);
+        self
+    }
+
+    /// Sets the HTTP version to send the request with, e.g.
+    /// [`Version::HTTP_2`] for bidi streaming.
+    ///
+    /// The version is passed to the transport, which may need to be
+    /// configured to support it (e.g. for HTTP/2 prior knowledge over
+    /// cleartext, or HTTP/3). Responses received over a different version
+    /// fail validation; see [`ConnectResponse::http_version`](crate::response::ConnectResponse::http_version).
+    pub fn http_version(mut self, version: Version) -> Self {
+        self.http_version = Some(version);
+        self
+    }
+
+    /// Attaches a per-call value (e.g. an auth principal or parent span) to
+    /// the request's [`http::Extensions`].
+    ///
+    /// Extensions aren't sent to the server. They are visible to
+    /// interceptors and transports, and
+    /// [`ConnectClient`](crate::client::ConnectClient) copies them to the
+    /// response.
+    pub fn extension<T: Clone + Send + Sync + 'static>(mut self, value: T) -> Self {
+        self.extensions.insert(value);
+        self
+    }
+
     /// Build logic common to all requests.
     fn common_request<T>(&mut self, method: Method, body: T) -> Result<http::Request<T>, Error> {
         let mut req = Request::new(body);
         *req.method_mut() = method;
         let mut headers: HeaderMap = std::mem::take(&mut self.metadata);
+        headers.extend(std::mem::take(&mut self.headers));
+        headers
+            .entry(header::USER_AGENT)
+            .or_insert(HeaderValue::from_static(DEFAULT_USER_AGENT));
         // Connect-Protocol-Version → "connect-protocol-version" "1"
         headers.insert(CONNECT_PROTOCOL_VERSION, PROTOCOL_VERSION_1);
         // Timeout → "connect-timeout-ms" Timeout-Milliseconds
@@ -208,11 +336,25 @@ impl RequestBuilder {
             headers.insert(CONNECT_TIMEOUT_MS, timeout);
         }
         *req.headers_mut() = headers;
+        *req.extensions_mut() = std::mem::take(&mut self.extensions);
+        if let Some(level) = self.idempotency_level {
+            req.extensions_mut().insert(level);
+        }
+        if let Some(max_bytes) = self.get_url_max_bytes {
+            req.extensions_mut().insert(GetUrlMaxBytes(max_bytes));
+        }
+        if let Some(version) = self.http_version {
+            *req.version_mut() = version;
+            req.extensions_mut().insert(RequiredHttpVersion(version));
+        }
         Ok(req)
     }
 
     /// Builds a [`UnaryRequest`].
     ///
+    /// Sets `accept` to the message codec's content type, unless set with
+    /// [`Self::header`].
+    ///
     /// See: https://connectrpc.com/docs/protocol/#unary-request
     pub fn unary<T>(mut self, body: T) -> Result<UnaryRequest<T>, Error> {
         let mut req = self.common_request(Method::POST, body)?;
@@ -220,10 +362,14 @@ impl RequestBuilder {
 
         // Unary-Content-Type → "content-type" "application/" Message-Codec
         if let Some(message_codec) = &self.message_codec {
-            req.headers_mut().insert(
-                header::CONTENT_TYPE,
-                (format!("{CONTENT_TYPE_PREFIX}{message_codec}")).try_into()?,
-            );
+            let content_type: HeaderValue =
+                (format!("{CONTENT_TYPE_PREFIX}{message_codec}")).try_into()?;
+            // Ask content-negotiating servers for the same codec, unless
+            // overridden with `Self::header`.
+            req.headers_mut()
+                .entry(header::ACCEPT)
+                .or_insert_with(|| content_type.clone());
+            req.headers_mut().insert(header::CONTENT_TYPE, content_type);
         }
         // Content-Encoding → "content-encoding" Content-Coding
         if let Some(content_encoding) = self.content_encoding.take() {
@@ -248,7 +394,7 @@ impl RequestBuilder {
         if let Some(message_codec) = &self.message_codec {
             req.headers_mut().insert(
                 header::CONTENT_TYPE,
-                (format!("{CONTENT_TYPE_PREFIX}{message_codec}")).try_into()?,
+                (format!("{STREAMING_CONTENT_TYPE_PREFIX}{message_codec}")).try_into()?,
             );
         }
         // Streaming-Content-Encoding → "connect-content-encoding" Content-Coding
@@ -263,36 +409,60 @@ impl RequestBuilder {
         Ok(req.into())
     }
 
+    /// Builds a [`StreamingRequest`] from a stream of messages.
+    ///
+    /// Each message is encoded with `encode` (which must match the
+    /// [message codec](Self::message_codec)) and enveloped; if a
+    /// [content encoding](Self::content_encoding) other than `identity` is
+    /// set, each frame is compressed with it.
+    pub fn streaming_messages<M, S, F>(
+        self,
+        messages: S,
+        mut encode: F,
+    ) -> Result<StreamingRequest<FramedBody>, Error>
+    where
+        S: Stream<Item = M> + Send + 'static,
+        F: FnMut(M) -> Result<Bytes, Error> + Send + 'static,
+    {
+        let compression = self
+            .content_encoding
+            .as_deref()
+            .map(|name| {
+                compression::lookup(name).ok_or_else(|| Error::UnacceptableEncoding(name.into()))
+            })
+            .transpose()?;
+        let frames = messages.map(move |message| {
+            let mut frame = ConnectFrame {
+                compressed: false,
+                end: false,
+                data: encode(message)?,
+            };
+            if let Some(compression) = &compression {
+                frame.data = compression.compress(&frame.data)?;
+                frame.compressed = true;
+            }
+            Ok(frame)
+        });
+        self.streaming(FramedBody::new(ConnectFrame::encode_stream(frames)))
+    }
+
     /// Builds a [`UnaryGetRequest`].
     ///
+    /// If a [content encoding](Self::content_encoding) is set, the message is
+    /// compressed with it before being base64-encoded.
+    ///
     // https://connectrpc.com/docs/protocol/#unary-get-request
     pub fn unary_get(mut self, message: impl AsRef<[u8]>) -> Result<UnaryGetRequest, Error> {
         let mut req = self.common_request(Method::GET, ())?;
-        *req.method_mut() = Method::GET;
 
         let path_and_query = {
             let path = self.path.ok_or(Error::invalid_request("path required"))?;
-            let query = {
-                let mut query = form_urlencoded::Serializer::new("?".to_string());
-                query
-                    // Message-Query → "message=" (*{percent-encoded octet})
-                    .append_pair("message", &BASE64_URL_SAFE.encode(message))
-                    // Base64-Query → "&base64=1"
-                    .append_pair("base64", "1")
-                    // Connect-Version-Query → "&connect=v1"
-                    .append_pair("connect", "v1");
-                if let Some(message_codec) = &self.message_codec {
-                    // Encoding-Query → "&encoding=" Message-Codec
-                    query.append_pair("encoding", message_codec);
-                } else {
-                    return Err(Error::invalid_request("message codec required"));
-                }
-                if let Some(content_encoding) = &self.content_encoding {
-                    // Compression-Query → "&compression=" Content-Coding
-                    query.append_pair("compression", content_encoding);
-                }
-                query.finish()
-            };
+            let message_codec = self
+                .message_codec
+                .as_deref()
+                .ok_or(Error::invalid_request("message codec required"))?;
+            let message = compress_message(message.as_ref(), self.content_encoding.as_deref())?;
+            let query = unary_get_query(&message, message_codec, self.content_encoding.as_deref());
             Some(format!("{path}?{query}"))
         };
         *req.uri_mut() = build_uri(self.scheme, self.authority, path_and_query)?;
@@ -303,6 +473,62 @@ impl RequestBuilder {
         }
         Ok(req.into())
     }
+
+    /// Builds a [`UnaryGetRequest`], falling back to a POST [`UnaryRequest`]
+    /// if the GET URL would exceed [`Self::get_url_max_bytes`].
+    ///
+    /// As with [`Self::unary_get`], the message is given uncompressed and
+    /// compressed per [`Self::content_encoding`] for either method.
+    ///
+    /// This matches connect-go's `WithHTTPGetMaxURLSize` with fallback.
+    pub fn unary_get_or_post(
+        self,
+        message: impl AsRef<[u8]>,
+    ) -> Result<UnaryGetOrPostRequest<Bytes>, Error> {
+        let max_bytes = self.get_url_max_bytes.unwrap_or(DEFAULT_GET_URL_MAX_BYTES);
+        let get = self.clone().unary_get(&message)?;
+        if get.url_len() <= max_bytes {
+            return Ok(UnaryGetOrPostRequest::Get(get));
+        }
+        let body = compress_message(message.as_ref(), self.content_encoding.as_deref())?;
+        Ok(UnaryGetOrPostRequest::Post(self.unary(body)?))
+    }
+}
+
+/// Compresses a message with the given content encoding, if any.
+fn compress_message(message: &[u8], content_encoding: Option<&str>) -> Result<Bytes, Error> {
+    match content_encoding {
+        Some(name) => compression::lookup(name)
+            .ok_or_else(|| Error::UnacceptableEncoding(name.into()))?
+            .compress(message),
+        None => Ok(Bytes::copy_from_slice(message)),
+    }
+}
+
+/// Builds the query string of a unary GET request.
+pub(crate) fn unary_get_query(
+    message: &[u8],
+    message_codec: &str,
+    content_encoding: Option<&str>,
+) -> String {
+    let mut query = form_urlencoded::Serializer::new(String::new());
+    query
+        // Message-Query → "message=" (*{percent-encoded octet})
+        .append_pair(
+            "message",
+            &crate::base64::config().get_message.encode(message),
+        )
+        // Base64-Query → "&base64=1"
+        .append_pair("base64", "1")
+        // Connect-Version-Query → "&connect=v1"
+        .append_pair("connect", "v1")
+        // Encoding-Query → "&encoding=" Message-Codec
+        .append_pair("encoding", message_codec);
+    if let Some(content_encoding) = content_encoding {
+        // Compression-Query → "&compression=" Content-Coding
+        query.append_pair("compression", content_encoding);
+    }
+    query.finish()
 }
 
 fn build_uri(
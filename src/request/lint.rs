@@ -0,0 +1,158 @@
+//! Reports all of a request's protocol violations at once, e.g. for gateway
+//! diagnostics and test tooling.
+//!
+//! ```no_run
+//! # use bytes::Bytes;
+//! # use connect_rpc::request::ConnectRequestType;
+//! # fn example(req: http::Request<Bytes>) {
+//! for violation in ConnectRequestType::from_http(req).lint() {
+//!     tracing::warn!(rule = ?violation.rule, "{}", violation.message);
+//! }
+//! # }
+//! ```
+
+use http::header;
+
+use crate::common::{
+    parse_timeout, CONNECT_CONTENT_ENCODING, CONNECT_TIMEOUT_MS, PROTOCOL_VERSION_1,
+};
+
+use super::{
+    ConnectRequestType, HttpConnectRequest, StreamingRequest, UnaryGetRequest, UnaryRequest,
+};
+
+/// A protocol rule checked by [`ConnectRequestType::lint`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum LintRule {
+    /// No `connect-protocol-version` header (or `connect` query param for
+    /// GET requests).
+    MissingProtocolVersion,
+    /// A protocol version other than `1`.
+    UnknownProtocolVersion,
+    /// A missing or invalid `content-type` (or `encoding` query param for
+    /// GET requests).
+    InvalidMessageCodec,
+    /// A `connect-timeout-ms` that isn't 1 to 10 ASCII digits.
+    InvalidTimeout,
+    /// A content encoding header for the other request type (unary vs
+    /// streaming).
+    ConflictingEncoding,
+    /// A GET request with a body.
+    GetWithBody,
+    /// A GET request without a `message` query param.
+    MissingGetMessage,
+    /// A GET request `message` query param that can't be decoded.
+    InvalidGetMessage,
+}
+
+/// A protocol violation found by [`ConnectRequestType::lint`].
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct LintViolation {
+    pub rule: LintRule,
+    pub message: String,
+}
+
+impl LintViolation {
+    fn new(rule: LintRule, message: impl Into<String>) -> Self {
+        Self {
+            rule,
+            message: message.into(),
+        }
+    }
+}
+
+impl<T> ConnectRequestType<T> {
+    /// Checks the request against the Connect protocol, returning all
+    /// violations found (rather than failing at the first, as
+    /// [`ConnectRequest::validate`](super::ConnectRequest::validate) does).
+    pub fn lint(&self) -> Vec<LintViolation> {
+        match self {
+            Self::Unary(req) => lint_unary(req),
+            Self::Streaming(req) => lint_streaming(req),
+            Self::UnaryGet(req) => lint_unary_get(req),
+        }
+    }
+}
+
+fn lint_common(req: &impl HttpConnectRequest) -> Vec<LintViolation> {
+    let mut violations = vec![];
+    match req.http_connect_protocol_version() {
+        None => violations.push(LintViolation::new(
+            LintRule::MissingProtocolVersion,
+            req.http_missing_protocol_version(),
+        )),
+        Some(version) if version == PROTOCOL_VERSION_1 => (),
+        Some(version) => violations.push(LintViolation::new(
+            LintRule::UnknownProtocolVersion,
+            format!("unknown protocol version {version:?}"),
+        )),
+    }
+    if let Err(err) = req.http_message_codec() {
+        violations.push(LintViolation::new(
+            LintRule::InvalidMessageCodec,
+            err.to_string(),
+        ));
+    }
+    if let Some(Err(err)) = req
+        .http_headers()
+        .get(CONNECT_TIMEOUT_MS)
+        .map(parse_timeout)
+    {
+        violations.push(LintViolation::new(
+            LintRule::InvalidTimeout,
+            err.to_string(),
+        ));
+    }
+    violations
+}
+
+fn lint_unary<T>(req: &UnaryRequest<T>) -> Vec<LintViolation> {
+    let mut violations = lint_common(req);
+    if req.http_headers().contains_key(CONNECT_CONTENT_ENCODING) {
+        violations.push(LintViolation::new(
+            LintRule::ConflictingEncoding,
+            format!("unary request with {CONNECT_CONTENT_ENCODING}"),
+        ));
+    }
+    violations
+}
+
+fn lint_streaming<T>(req: &StreamingRequest<T>) -> Vec<LintViolation> {
+    let mut violations = lint_common(req);
+    if req.http_headers().contains_key(header::CONTENT_ENCODING) {
+        violations.push(LintViolation::new(
+            LintRule::ConflictingEncoding,
+            format!("streaming request with {}", header::CONTENT_ENCODING),
+        ));
+    }
+    violations
+}
+
+fn lint_unary_get(req: &UnaryGetRequest) -> Vec<LintViolation> {
+    let mut violations = lint_common(req);
+    let headers = req.http_headers();
+    let has_body = headers.contains_key(header::TRANSFER_ENCODING)
+        || headers
+            .get(header::CONTENT_LENGTH)
+            .is_some_and(|len| len != "0");
+    if has_body {
+        violations.push(LintViolation::new(
+            LintRule::GetWithBody,
+            "GET request with a body",
+        ));
+    }
+    if !req.query.contains_key("message") {
+        violations.push(LintViolation::new(
+            LintRule::MissingGetMessage,
+            "missing 'message' param",
+        ));
+    } else if let Err(err) = req.encoded_message() {
+        violations.push(LintViolation::new(
+            LintRule::InvalidGetMessage,
+            err.to_string(),
+        ));
+    }
+    violations
+}
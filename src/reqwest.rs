@@ -1,28 +1,68 @@
+//! Connect RPCs over [`reqwest`].
+//!
+//! With the `http3` feature (which, like reqwest's, requires building with
+//! `RUSTFLAGS="--cfg reqwest_unstable"`), requests built with
+//! [`RequestBuilder::http_version`](crate::request::builder::RequestBuilder::http_version)
+//! set to [`Version::HTTP_3`](http::Version::HTTP_3) are sent over HTTP/3
+//! (QUIC), given a client built with e.g.
+//! `reqwest::ClientBuilder::http3_prior_knowledge`. The negotiated version is
+//! available from [`ConnectResponse::http_version`].
+
 use std::future::Future;
 
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
+use futures_util::future::BoxFuture;
+use http::{header, HeaderName, Version};
+use http_body_util::BodyExt;
 
 use crate::{
+    common::request_timeout,
+    compression,
+    instrument::CallInstrument,
     request::{ConnectRequest, UnaryGetRequest, UnaryRequest},
     response::{
         error::{ConnectCode, ConnectError},
-        UnaryResponse, ValidateOpts,
+        ConnectResponse, UnaryResponse, ValidateOpts,
     },
+    transport::{RequestBody, ResponseBody, Transport},
     Error,
 };
 
+/// The maximum size of a response body buffered by
+/// [`ReqwestClientExt::execute_unary`] and
+/// [`ReqwestClientExt::execute_unary_get`].
+pub const MAX_UNARY_RESPONSE_SIZE: usize = 4 * 1024 * 1024;
+
 pub trait ReqwestClientExt {
     /// Executes a Connect RPC [`UnaryRequest`].
+    ///
+    /// Response bodies larger than [`MAX_UNARY_RESPONSE_SIZE`] produce a
+    /// `resource_exhausted` error; use [`Self::execute_unary_raw`] to stream
+    /// larger responses.
     fn execute_unary(
         &self,
         req: UnaryRequest<impl Into<reqwest::Body>>,
     ) -> impl Future<Output = Result<UnaryResponse<Bytes>, Error>>;
 
     /// Executes a Connect RPC [`UnaryGetRequest`].
+    ///
+    /// Response bodies larger than [`MAX_UNARY_RESPONSE_SIZE`] produce a
+    /// `resource_exhausted` error.
     fn execute_unary_get(
         &self,
         req: UnaryGetRequest,
     ) -> impl Future<Output = Result<UnaryResponse<Bytes>, Error>>;
+
+    /// Executes a Connect RPC [`UnaryRequest`] without buffering the
+    /// response body, e.g. to stream a large download.
+    ///
+    /// The response headers are validated as for [`Self::execute_unary`].
+    /// Error responses are still buffered (up to [`MAX_UNARY_RESPONSE_SIZE`])
+    /// and returned as errors. The body is not decompressed.
+    fn execute_unary_raw(
+        &self,
+        req: UnaryRequest<impl Into<reqwest::Body>>,
+    ) -> impl Future<Output = Result<UnaryResponse<ResponseBody>, Error>>;
 }
 
 impl ReqwestClientExt for reqwest::Client {
@@ -31,27 +71,96 @@ impl ReqwestClientExt for reqwest::Client {
         req: UnaryRequest<impl Into<reqwest::Body>>,
     ) -> Result<UnaryResponse<Bytes>, Error> {
         let validate_opts = ValidateOpts::from_request(&req);
-        let resp = self.execute(req.try_into()?).await?;
-        let connect_resp: UnaryResponse<_> = response_to_http_bytes(resp).await?.into();
-        connect_resp.result(&validate_opts)
+        let instrument = CallInstrument::new(&req);
+        let extensions = req.extensions().clone();
+        instrument
+            .run(async {
+                let resp = self.execute(req.try_into()?).await?;
+                let mut http_resp = response_to_http_bytes(resp).await?;
+                http_resp.extensions_mut().extend(extensions);
+                UnaryResponse::from(http_resp).result(&validate_opts)
+            })
+            .await
     }
 
     async fn execute_unary_get(&self, req: UnaryGetRequest) -> Result<UnaryResponse<Bytes>, Error> {
         let validate_opts = ValidateOpts::from_request(&req);
-        let resp = self.execute(req.try_into()?).await?;
-        let connect_resp: UnaryResponse<_> = response_to_http_bytes(resp).await?.into();
-        connect_resp.result(&validate_opts)
+        let instrument = CallInstrument::new(&req);
+        let extensions = req.extensions().clone();
+        instrument
+            .run(async {
+                let resp = self.execute(req.try_into()?).await?;
+                let mut http_resp = response_to_http_bytes(resp).await?;
+                http_resp.extensions_mut().extend(extensions);
+                UnaryResponse::from(http_resp).result(&validate_opts)
+            })
+            .await
+    }
+
+    async fn execute_unary_raw(
+        &self,
+        req: UnaryRequest<impl Into<reqwest::Body>>,
+    ) -> Result<UnaryResponse<ResponseBody>, Error> {
+        let validate_opts = ValidateOpts::from_request(&req);
+        let instrument = CallInstrument::new(&req);
+        let extensions = req.extensions().clone();
+        instrument
+            .run(async {
+                let resp = self.execute(req.try_into()?).await?;
+                if !resp.status().is_success() {
+                    let resp = response_to_http_bytes(resp).await?;
+                    return Err(Error::ConnectError(resp.into()));
+                }
+                let mut resp = http::Response::<reqwest::Body>::from(resp);
+                resp.extensions_mut().extend(extensions);
+                let connect_resp =
+                    UnaryResponse::from(resp.map(|body| body.map_err(Error::from).boxed()));
+                connect_resp.validate(&validate_opts)?;
+                Ok(connect_resp)
+            })
+            .await
     }
 }
 
+impl Transport for reqwest::Client {
+    fn round_trip(
+        &self,
+        req: http::Request<RequestBody>,
+    ) -> BoxFuture<'_, Result<http::Response<ResponseBody>, Error>> {
+        Box::pin(async move {
+            let timeout = request_timeout(req.headers());
+            let mut req = reqwest::Request::try_from(req.map(reqwest::Body::wrap))?;
+            *req.timeout_mut() = timeout;
+            strip_connection_headers(&mut req);
+            let resp = http::Response::<reqwest::Body>::from(self.execute(req).await?);
+            Ok(resp.map(|body| body.map_err(Error::from).boxed()))
+        })
+    }
+}
+
+/// Reads a response, failing if its body exceeds [`MAX_UNARY_RESPONSE_SIZE`].
 async fn response_to_http_bytes(
     mut resp: reqwest::Response,
 ) -> Result<http::Response<Bytes>, Error> {
+    if resp
+        .content_length()
+        .is_some_and(|len| len > MAX_UNARY_RESPONSE_SIZE as u64)
+    {
+        return Err(compression::limit_exceeded());
+    }
     let status = resp.status();
+    let version = resp.version();
     let headers = std::mem::take(resp.headers_mut());
-    let body = resp.bytes().await?;
-    let mut http_resp = http::Response::new(body);
+    let mut body = BytesMut::new();
+    while let Some(chunk) = resp.chunk().await? {
+        if body.len() + chunk.len() > MAX_UNARY_RESPONSE_SIZE {
+            return Err(compression::limit_exceeded());
+        }
+        body.extend_from_slice(&chunk);
+    }
+    let mut http_resp = http::Response::new(body.freeze());
     *http_resp.status_mut() = status;
+    *http_resp.version_mut() = version;
     *http_resp.headers_mut() = headers;
     Ok(http_resp)
 }
@@ -63,6 +172,7 @@ impl<T: Into<reqwest::Body>> TryFrom<UnaryRequest<T>> for reqwest::Request {
         let timeout = req.timeout();
         let mut req = reqwest::Request::try_from(http::Request::from(req))?;
         *req.timeout_mut() = timeout;
+        strip_connection_headers(&mut req);
         Ok(req)
     }
 }
@@ -75,10 +185,42 @@ impl TryFrom<UnaryGetRequest> for reqwest::Request {
         let http_req = http::Request::from(req).map(|()| reqwest::Body::default());
         let mut req = reqwest::Request::try_from(http_req)?;
         *req.timeout_mut() = timeout;
+        strip_connection_headers(&mut req);
         Ok(req)
     }
 }
 
+/// Removes connection-specific headers, which are forbidden in HTTP/2 and
+/// HTTP/3 requests (RFC 9113 §8.2.2, RFC 9114 §4.2).
+fn strip_connection_headers(req: &mut reqwest::Request) {
+    if req.version() < Version::HTTP_2 {
+        return;
+    }
+    let headers = req.headers_mut();
+    let named: Vec<HeaderName> = headers
+        .get_all(header::CONNECTION)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|name| name.trim().parse().ok())
+        .collect();
+    for name in named {
+        headers.remove(name);
+    }
+    for name in [
+        header::CONNECTION,
+        header::TRANSFER_ENCODING,
+        header::UPGRADE,
+        HeaderName::from_static("keep-alive"),
+        HeaderName::from_static("proxy-connection"),
+    ] {
+        headers.remove(name);
+    }
+    if headers.get(header::TE).is_some_and(|te| te != "trailers") {
+        headers.remove(header::TE);
+    }
+}
+
 impl From<reqwest::Error> for Error {
     fn from(err: reqwest::Error) -> Self {
         if err.is_timeout() {
@@ -86,6 +228,9 @@ impl From<reqwest::Error> for Error {
                 ConnectCode::DeadlineExceeded,
                 "request timed out",
             ))
+        } else if err.is_connect() {
+            // Connection failures (including DNS and TLS) are `unavailable`.
+            Self::ConnectError(ConnectError::new(ConnectCode::Unavailable, &err).with_source(err))
         } else {
             Self::ReqwestError(err)
         }
@@ -1,14 +1,17 @@
 pub mod builder;
 pub mod error;
 
-use http::{header, HeaderMap, StatusCode};
+use std::fmt;
+
+use http::{header, HeaderMap, HeaderName, StatusCode, Version};
 
 use crate::{
     common::{
-        streaming_message_codec, unary_message_codec, CONNECT_CONTENT_ENCODING,
-        CONTENT_ENCODING_IDENTITY,
+        content_type_params, is_identity_encoding, streaming_message_codec, unary_message_codec,
+        CONNECT_CONTENT_ENCODING,
     },
-    metadata::Metadata,
+    compression::{self, AcceptEncoding},
+    metadata::{redact_metadata, split_trailers, Metadata, Redact},
     request::ConnectRequest,
     Error,
 };
@@ -18,15 +21,29 @@ pub trait ConnectResponse {
     /// Returns the status code.
     fn status(&self) -> StatusCode;
 
+    /// Returns the HTTP version the response was received over.
+    fn http_version(&self) -> Version;
+
     /// Returns the message codec.
     fn message_codec(&self) -> Result<&str, Error>;
 
+    /// Returns the `content-type` parameters (e.g. `charset`), which are
+    /// ignored when determining the message codec.
+    fn content_type_params(&self) -> impl Iterator<Item = (&str, &str)>;
+
     /// Returns the content encoding.
     fn content_encoding(&self) -> Option<&str>;
 
     /// Returns a reference to the metadata.
     fn metadata(&self) -> &impl Metadata;
 
+    /// Returns the leading headers and trailers separately.
+    ///
+    /// Unary trailers are sent as `trailer-` prefixed headers, which are
+    /// returned without the prefix. Streaming trailers are sent in the
+    /// end-stream frame instead, so are empty here.
+    fn headers_and_trailers(&self) -> (HeaderMap, HeaderMap);
+
     /// Validates the response.
     fn validate(&self, opts: &ValidateOpts) -> Result<(), Error>;
 }
@@ -38,6 +55,10 @@ pub struct ValidateOpts {
     pub message_codec: Option<String>,
     /// If given, the response content encoding must match (or be 'identity').
     pub accept_encoding: Option<Vec<String>>,
+    /// How strictly to validate the response.
+    pub strictness: Strictness,
+    /// If given, the response HTTP version must match.
+    pub http_version: Option<Version>,
 }
 
 impl ValidateOpts {
@@ -47,18 +68,89 @@ impl ValidateOpts {
         Self {
             message_codec,
             accept_encoding,
+            strictness: Default::default(),
+            http_version: req.http_version(),
         }
     }
+
+    /// Sets the validation [`Strictness`].
+    pub fn strictness(mut self, strictness: Strictness) -> Self {
+        self.strictness = strictness;
+        self
+    }
+}
+
+/// The strictness of [`ConnectResponse::validate`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Strictness {
+    /// Accepts responses that deviate from the protocol in ways that are
+    /// common in practice (e.g. through proxies).
+    #[default]
+    Lenient,
+    /// Additionally rejects responses with a missing `content-type`,
+    /// streaming responses with a status other than `200 OK`, and content
+    /// encodings not supported by this build. Intended for servers and
+    /// conformance testing.
+    Strict,
 }
 
 trait HttpConnectResponse {
     fn http_status(&self) -> StatusCode;
 
+    fn http_response_version(&self) -> Version;
+
     fn http_headers(&self) -> &HeaderMap;
 
     fn http_message_codec(&self) -> Result<&str, Error>;
 
     fn http_content_encoding(&self) -> Option<&str>;
+
+    /// Returns the content encoding header used by the _other_ response type
+    /// (unary vs streaming), which must not be present.
+    fn http_conflicting_encoding_header(&self) -> HeaderName;
+
+    fn http_is_streaming(&self) -> bool;
+}
+
+fn validate_headers(resp: &impl HttpConnectResponse) -> Result<(), Error> {
+    let headers = resp.http_headers();
+    if headers
+        .get_all(header::CONTENT_TYPE)
+        .iter()
+        .nth(1)
+        .is_some()
+    {
+        return Err(Error::ConflictingHeaders("multiple content-type values"));
+    }
+    // An explicit `identity` is harmless in either header.
+    if headers
+        .get_all(resp.http_conflicting_encoding_header())
+        .iter()
+        .any(|encoding| !is_identity_encoding(encoding))
+    {
+        return Err(Error::ConflictingHeaders(
+            "both unary and streaming content encodings",
+        ));
+    }
+    Ok(())
+}
+
+fn validate_strict(resp: &impl HttpConnectResponse) -> Result<(), Error> {
+    if !resp.http_headers().contains_key(header::CONTENT_TYPE) {
+        return Err(Error::InvalidResponse("missing content-type".into()));
+    }
+    if resp.http_is_streaming() && resp.http_status() != StatusCode::OK {
+        return Err(Error::InvalidResponse(format!(
+            "unexpected streaming response status {}",
+            resp.http_status()
+        )));
+    }
+    if let Some(encoding) = resp.http_content_encoding() {
+        if compression::lookup(encoding).is_none() {
+            return Err(Error::UnacceptableEncoding(encoding.into()));
+        }
+    }
+    Ok(())
 }
 
 impl<T: HttpConnectResponse> ConnectResponse for T {
@@ -66,10 +158,18 @@ impl<T: HttpConnectResponse> ConnectResponse for T {
         self.http_status()
     }
 
+    fn http_version(&self) -> Version {
+        self.http_response_version()
+    }
+
     fn message_codec(&self) -> Result<&str, Error> {
         self.http_message_codec()
     }
 
+    fn content_type_params(&self) -> impl Iterator<Item = (&str, &str)> {
+        content_type_params(self.http_headers())
+    }
+
     fn content_encoding(&self) -> Option<&str> {
         self.http_content_encoding()
     }
@@ -78,7 +178,26 @@ impl<T: HttpConnectResponse> ConnectResponse for T {
         self.http_headers()
     }
 
+    fn headers_and_trailers(&self) -> (HeaderMap, HeaderMap) {
+        if self.http_is_streaming() {
+            return (self.http_headers().clone(), HeaderMap::new());
+        }
+        split_trailers(self.http_headers())
+    }
+
     fn validate(&self, opts: &ValidateOpts) -> Result<(), Error> {
+        validate_headers(self)?;
+        if let Some(version) = opts.http_version {
+            if self.http_version() != version {
+                return Err(Error::InvalidResponse(format!(
+                    "expected {version:?} response, got {:?}",
+                    self.http_version()
+                )));
+            }
+        }
+        if opts.strictness == Strictness::Strict {
+            validate_strict(self)?;
+        }
         let codec = self.message_codec()?;
         if let Some(validate_codec) = &opts.message_codec {
             if codec != validate_codec {
@@ -86,9 +205,11 @@ impl<T: HttpConnectResponse> ConnectResponse for T {
             }
         }
         if let Some(encoding) = self.content_encoding() {
-            if encoding != CONTENT_ENCODING_IDENTITY {
+            if !is_identity_encoding(encoding) {
                 if let Some(accept_encoding) = &opts.accept_encoding {
-                    if !accept_encoding.iter().any(|accept| accept == encoding) {
+                    let accept_encoding =
+                        AcceptEncoding::parse(accept_encoding.iter().map(String::as_str));
+                    if !accept_encoding.accepts(encoding) {
                         return Err(Error::UnacceptableEncoding(encoding.into()));
                     }
                 }
@@ -105,6 +226,28 @@ impl<T> UnaryResponse<T> {
     pub fn body(&self) -> &T {
         self.0.body()
     }
+
+    /// Returns the response extensions, including the per-call extensions
+    /// of the request.
+    pub fn extensions(&self) -> &http::Extensions {
+        self.0.extensions()
+    }
+
+    /// Consumes the response, returning its head (status, metadata, etc.)
+    /// and body.
+    pub fn into_parts(self) -> (http::response::Parts, T) {
+        self.0.into_parts()
+    }
+
+    /// Consumes the response, returning its body.
+    pub fn into_body(self) -> T {
+        self.0.into_body()
+    }
+
+    /// Maps the body, keeping the status and metadata.
+    pub fn map_body<U>(self, f: impl FnOnce(T) -> U) -> UnaryResponse<U> {
+        UnaryResponse(self.0.map(f))
+    }
 }
 
 impl<T: AsRef<[u8]>> UnaryResponse<T> {
@@ -122,6 +265,10 @@ impl<T> HttpConnectResponse for UnaryResponse<T> {
         self.0.status()
     }
 
+    fn http_response_version(&self) -> Version {
+        self.0.version()
+    }
+
     fn http_headers(&self) -> &HeaderMap {
         self.0.headers()
     }
@@ -136,6 +283,14 @@ impl<T> HttpConnectResponse for UnaryResponse<T> {
             .to_str()
             .ok()
     }
+
+    fn http_conflicting_encoding_header(&self) -> HeaderName {
+        CONNECT_CONTENT_ENCODING
+    }
+
+    fn http_is_streaming(&self) -> bool {
+        false
+    }
 }
 
 impl<T> From<http::Response<T>> for UnaryResponse<T> {
@@ -153,11 +308,43 @@ impl<T> From<UnaryResponse<T>> for http::Response<T> {
 #[derive(Clone, Debug)]
 pub struct StreamingResponse<T>(http::Response<T>);
 
+impl<T> StreamingResponse<T> {
+    /// Returns the response extensions, including the per-call extensions
+    /// of the request.
+    pub fn extensions(&self) -> &http::Extensions {
+        self.0.extensions()
+    }
+
+    /// Consumes the response, returning its head (status, metadata, etc.)
+    /// and body.
+    pub fn into_parts(self) -> (http::response::Parts, T) {
+        self.0.into_parts()
+    }
+
+    /// Consumes the response, returning its body.
+    pub fn into_body(self) -> T {
+        self.0.into_body()
+    }
+
+    /// Maps the body, keeping the status and metadata.
+    pub fn map_body<U>(self, f: impl FnOnce(T) -> U) -> StreamingResponse<U> {
+        StreamingResponse(self.0.map(f))
+    }
+
+    pub(crate) fn headers(&self) -> &HeaderMap {
+        self.0.headers()
+    }
+}
+
 impl<T> HttpConnectResponse for StreamingResponse<T> {
     fn http_status(&self) -> StatusCode {
         self.0.status()
     }
 
+    fn http_response_version(&self) -> Version {
+        self.0.version()
+    }
+
     fn http_headers(&self) -> &HeaderMap {
         self.0.headers()
     }
@@ -172,6 +359,14 @@ impl<T> HttpConnectResponse for StreamingResponse<T> {
             .to_str()
             .ok()
     }
+
+    fn http_conflicting_encoding_header(&self) -> HeaderName {
+        header::CONTENT_ENCODING
+    }
+
+    fn http_is_streaming(&self) -> bool {
+        true
+    }
 }
 
 impl<T> From<http::Response<T>> for StreamingResponse<T> {
@@ -185,3 +380,28 @@ impl<T> From<StreamingResponse<T>> for http::Response<T> {
         resp.0
     }
 }
+
+impl<T> Redact for UnaryResponse<T> {
+    fn fmt_redacted(&self, keys: &[&str], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_redacted_response("UnaryResponse", &self.0, keys, f)
+    }
+}
+
+impl<T> Redact for StreamingResponse<T> {
+    fn fmt_redacted(&self, keys: &[&str], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_redacted_response("StreamingResponse", &self.0, keys, f)
+    }
+}
+
+fn fmt_redacted_response<T>(
+    name: &str,
+    resp: &http::Response<T>,
+    keys: &[&str],
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    f.debug_struct(name)
+        .field("status", &resp.status())
+        .field("version", &resp.version())
+        .field("headers", &redact_metadata(resp.headers(), keys))
+        .finish_non_exhaustive()
+}
@@ -1,12 +1,17 @@
+use bytes::{Bytes, BytesMut};
 use http::{header, HeaderMap, HeaderName, StatusCode};
 
 use crate::{
-    common::{is_valid_http_token, CONNECT_CONTENT_ENCODING, CONTENT_TYPE_PREFIX},
+    common::{
+        is_identity_encoding, is_valid_http_token, CONNECT_CONTENT_ENCODING, CONTENT_TYPE_PREFIX,
+        STREAMING_CONTENT_TYPE_PREFIX,
+    },
     metadata::Metadata,
+    stream::EndStreamResponse,
     Error,
 };
 
-use super::{StreamingResponse, UnaryResponse};
+use super::{error::ConnectError, StreamingResponse, UnaryResponse};
 
 #[derive(Debug, Default)]
 pub struct ResponseBuilder {
@@ -61,12 +66,16 @@ impl ResponseBuilder {
     }
 
     /// Sets the response content encoding (e.g. compression).
+    ///
+    /// `identity` (the default) clears any previous encoding; no header is
+    /// sent for it.
     pub fn content_encoding(mut self, content_encoding: impl Into<String>) -> Result<Self, Error> {
         let content_encoding = content_encoding.into();
         if !is_valid_http_token(&content_encoding) {
             return Err(Error::invalid_request("invalid content encoding"));
         }
-        self.content_encoding = Some(content_encoding);
+        self.content_encoding =
+            Some(content_encoding).filter(|encoding| !is_identity_encoding(encoding));
         Ok(self)
     }
 
@@ -103,7 +112,7 @@ impl ResponseBuilder {
         if let Some(message_codec) = &self.message_codec {
             resp.headers_mut().insert(
                 header::CONTENT_TYPE,
-                (format!("{CONTENT_TYPE_PREFIX}{message_codec}")).try_into()?,
+                (format!("{STREAMING_CONTENT_TYPE_PREFIX}{message_codec}")).try_into()?,
             );
         }
         // Streaming-Content-Encoding → "connect-content-encoding" Content-Coding
@@ -113,4 +122,21 @@ impl ResponseBuilder {
         }
         Ok(resp.into())
     }
+
+    /// Builds a [`StreamingResponse`] whose body is `messages` (enveloped
+    /// message frames) followed by an end-stream frame with the given error
+    /// and trailers.
+    ///
+    /// See: https://connectrpc.com/docs/protocol/#error-end-stream
+    pub fn end_stream(
+        self,
+        messages: impl Into<BytesMut>,
+        error: Option<ConnectError>,
+        trailers: &HeaderMap,
+    ) -> Result<StreamingResponse<Bytes>, Error> {
+        let mut body = messages.into();
+        let end = EndStreamResponse::new(error, trailers).to_frame()?;
+        body.extend_from_slice(&end.encode()?);
+        self.streaming(body.freeze())
+    }
 }
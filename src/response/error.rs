@@ -1,9 +1,29 @@
-use http::{header, HeaderMap, HeaderValue};
+use std::sync::Arc;
 
-use crate::{common::base64_decode, metadata::Metadata, Error};
+use bytes::Bytes;
+use http::{header, HeaderMap, HeaderName, HeaderValue};
+
+use crate::{base64::Base64Variant, codes, metadata::Metadata, BoxError, Error};
 
 const ERROR_CONTENT_TYPE: HeaderValue = HeaderValue::from_static("application/json");
 
+/// A result whose error is a [`ConnectError`], e.g. for application code
+/// matching on error codes.
+pub type ConnectResult<T> = Result<T, ConnectError>;
+
+/// Converts a crate [`Result`] into a [`ConnectResult`].
+pub trait IntoConnectResult<T> {
+    /// Converts the error into a [`ConnectError`], mapping non-Connect
+    /// errors (e.g. transport errors) to their codes.
+    fn into_connect(self) -> ConnectResult<T>;
+}
+
+impl<T> IntoConnectResult<T> for Result<T, Error> {
+    fn into_connect(self) -> ConnectResult<T> {
+        self.map_err(ConnectError::from)
+    }
+}
+
 /// A Connect error.
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct ConnectError {
@@ -13,8 +33,14 @@ pub struct ConnectError {
     pub message: String,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub details: Vec<ConnectErrorDetail>,
+    /// Unknown top-level fields (e.g. vendor extensions), kept so they can
+    /// be relayed.
+    #[serde(flatten, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+    #[serde(skip)]
+    headers: Box<HeaderMap>,
     #[serde(skip)]
-    headers: HeaderMap,
+    source: Option<Arc<dyn std::error::Error + Send + Sync>>,
 }
 
 impl ConnectError {
@@ -23,22 +49,159 @@ impl ConnectError {
             code: Some(code),
             message: message.to_string(),
             details: Default::default(),
+            extra: Default::default(),
             headers: Default::default(),
+            source: None,
         }
     }
 
+    /// Returns a [`ConnectErrorBuilder`] for an error with the given code.
+    pub fn builder(code: ConnectCode) -> ConnectErrorBuilder {
+        ConnectErrorBuilder(Self::new(code, ""))
+    }
+
+    /// Sets the underlying cause of this error, e.g. a transport error.
+    ///
+    /// The source is kept for local inspection (and logging) only; it is
+    /// never serialized onto the wire.
+    pub fn with_source(mut self, source: impl Into<BoxError>) -> Self {
+        self.source = Some(source.into().into());
+        self
+    }
+
+    /// Returns the underlying cause of this error, if any, e.g. to
+    /// [downcast](https://doc.rust-lang.org/std/error/trait.Error.html#method.downcast_ref) it.
+    ///
+    /// Unlike [`std::error::Error::source`], the cause is `Send + Sync`.
+    pub fn cause(&self) -> Option<&(dyn std::error::Error + Send + Sync + 'static)> {
+        self.source.as_deref()
+    }
+
     pub fn code(&self) -> ConnectCode {
         self.code.unwrap_or(ConnectCode::Unknown)
     }
 
     pub fn metadata(&self) -> &impl Metadata {
+        self.headers.as_ref()
+    }
+
+    /// Returns the raw headers of the error response, which for unary calls
+    /// include trailers as `trailer-` prefixed headers.
+    pub fn headers(&self) -> &HeaderMap {
         &self.headers
     }
+
+    #[cfg(feature = "tonic")]
+    pub(crate) fn headers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.headers
+    }
+
+    #[cfg(feature = "tonic")]
+    pub(crate) fn shared_source(&self) -> Option<Arc<dyn std::error::Error + Send + Sync>> {
+        self.source.clone()
+    }
+
+    /// Returns true if the RPC may succeed if retried (with backoff), i.e.
+    /// the code is `unavailable`.
+    pub fn is_retryable(&self) -> bool {
+        self.code() == ConnectCode::Unavailable
+    }
+
+    /// Returns true if the error was caused by the request itself (e.g.
+    /// `invalid_argument` or `not_found`) and won't succeed unchanged.
+    pub fn is_client_error(&self) -> bool {
+        matches!(
+            self.code(),
+            ConnectCode::InvalidArgument
+                | ConnectCode::NotFound
+                | ConnectCode::AlreadyExists
+                | ConnectCode::PermissionDenied
+                | ConnectCode::FailedPrecondition
+                | ConnectCode::OutOfRange
+                | ConnectCode::Unauthenticated
+        )
+    }
+
+    /// Returns true if the RPC's deadline was exceeded.
+    pub fn is_timeout(&self) -> bool {
+        self.code() == ConnectCode::DeadlineExceeded
+    }
+}
+
+/// Builds a [`ConnectError`], including the metadata sent with it in a
+/// server's error response.
+///
+/// ```no_run
+/// # use connect_rpc::response::error::{ConnectCode, ConnectError, ConnectErrorDetail};
+/// # fn example(info: &[u8], request_id: &str) -> Result<(), connect_rpc::Error> {
+/// let err = ConnectError::builder(ConnectCode::NotFound)
+///     .message("no such user")
+///     .detail(ConnectErrorDetail::new("example.v1.UserInfo", info))
+///     .metadata("x-request-id", request_id)?
+///     .build();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ConnectErrorBuilder(ConnectError);
+
+impl ConnectErrorBuilder {
+    /// Sets the error message.
+    pub fn message(mut self, message: impl std::fmt::Display) -> Self {
+        self.0.message = message.to_string();
+        self
+    }
+
+    /// Appends an error detail.
+    pub fn detail(mut self, detail: ConnectErrorDetail) -> Self {
+        self.0.details.push(detail);
+        self
+    }
+
+    /// Appends ASCII metadata, sent as response headers.
+    pub fn metadata(
+        mut self,
+        key: impl TryInto<HeaderName, Error: Into<Error>>,
+        val: impl Into<String>,
+    ) -> Result<Self, Error> {
+        self.0.headers.append_ascii(key, val)?;
+        Ok(self)
+    }
+
+    /// Appends binary metadata, sent as response headers.
+    pub fn binary_metadata(
+        mut self,
+        key: impl TryInto<HeaderName, Error: Into<Error>>,
+        val: impl AsRef<[u8]>,
+    ) -> Result<Self, Error> {
+        self.0.headers.append_binary(key, val)?;
+        Ok(self)
+    }
+
+    /// Appends ASCII trailer metadata, sent as a `trailer-` prefixed header
+    /// in unary error responses.
+    pub fn trailer(mut self, key: impl AsRef<str>, val: impl Into<String>) -> Result<Self, Error> {
+        self.0
+            .headers
+            .append_ascii(format!("trailer-{}", key.as_ref()), val)?;
+        Ok(self)
+    }
+
+    /// Sets the underlying cause of the error.
+    pub fn source(mut self, source: impl Into<BoxError>) -> Self {
+        self.0 = self.0.with_source(source);
+        self
+    }
+
+    /// Returns the built error.
+    pub fn build(self) -> ConnectError {
+        self.0
+    }
 }
 
 impl std::fmt::Display for ConnectError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(serde_json::to_value(self.code()).unwrap().as_str().unwrap())?;
+        f.write_str(self.code().as_str())?;
         if !self.message.is_empty() {
             write!(f, ": {}", self.message)?;
         }
@@ -46,6 +209,12 @@ impl std::fmt::Display for ConnectError {
     }
 }
 
+impl std::error::Error for ConnectError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source.as_deref().map(|source| source as _)
+    }
+}
+
 impl<T: AsRef<[u8]>> From<http::Response<T>> for ConnectError {
     fn from(resp: http::Response<T>) -> Self {
         let (parts, body) = resp.into_parts();
@@ -64,7 +233,7 @@ impl<T: AsRef<[u8]>> From<http::Response<T>> for ConnectError {
             None
         };
         let mut error = error.unwrap_or_else(|| Self::new(parts.status.into(), "request invalid"));
-        error.headers = parts.headers;
+        *error.headers = parts.headers;
         error
     }
 }
@@ -73,16 +242,41 @@ impl From<Error> for ConnectError {
     fn from(err: Error) -> Self {
         let code = match err {
             Error::ConnectError(connect_error) => return connect_error,
-            Error::InvalidResponse(_)
-            | Error::UnacceptableEncoding(_)
-            | Error::UnexpectedMessageCodec(_) => ConnectCode::Internal,
-            _ => ConnectCode::Unknown,
+            _ => err.connect_code(),
         };
         let message = match &err {
-            Error::UnacceptableEncoding(_) | Error::UnexpectedMessageCodec(_) => err.to_string(),
+            Error::ConflictingHeaders(_)
+            | Error::FrameTooLarge { .. }
+            | Error::TruncatedFrame { .. }
+            | Error::UnacceptableEncoding(_)
+            | Error::UnexpectedMessageCodec(_) => err.to_string(),
             _ => "".into(),
         };
-        Self::new(code, message)
+        Self::new(code, message).with_source(err)
+    }
+}
+
+/// Converts an error into a unary error response, with the error's
+/// [headers](ConnectError::headers) (including `trailer-` prefixed trailers).
+///
+/// Headers describing a parsed error's original body (e.g. `content-length`
+/// and `content-encoding`) and hop-by-hop headers are not copied, since the
+/// body is re-serialized.
+///
+/// See: https://connectrpc.com/docs/protocol/#unary-response
+impl From<ConnectError> for http::Response<Bytes> {
+    fn from(err: ConnectError) -> Self {
+        let body = serde_json::to_vec(&err).expect("error JSON");
+        let mut resp = http::Response::new(body.into());
+        *resp.status_mut() = codes::http_status(err.code());
+        *resp.headers_mut() = *err.headers;
+        let headers = resp.headers_mut();
+        crate::proxy::strip_hop_by_hop(headers);
+        for name in [header::CONTENT_LENGTH, header::CONTENT_ENCODING, header::TE] {
+            headers.remove(name);
+        }
+        headers.insert(header::CONTENT_TYPE, ERROR_CONTENT_TYPE);
+        resp
     }
 }
 
@@ -94,7 +288,7 @@ fn deserialize_error_code<'de, D: serde::Deserializer<'de>>(
 }
 
 /// ConnectCode represents categories of errors as codes.
-#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ConnectCode {
     /// The operation completed successfully.
@@ -133,22 +327,47 @@ pub enum ConnectCode {
     Unauthenticated,
 }
 
+impl ConnectCode {
+    /// Returns the code's string form, as used in error JSON.
+    pub fn as_str(&self) -> &'static str {
+        codes::lookup(*self).name
+    }
+
+    /// Returns the code with the given numeric gRPC status code.
+    pub fn from_i32(grpc_code: i32) -> Option<Self> {
+        codes::from_grpc_code(grpc_code.try_into().ok()?)
+    }
+
+    /// Returns the code's numeric gRPC status code.
+    pub fn as_grpc_code(&self) -> i32 {
+        codes::lookup(*self).grpc_code as i32
+    }
+}
+
+impl std::fmt::Display for ConnectCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for ConnectCode {
+    type Err = UnknownCodeError;
+
+    /// Parses a code's string form, e.g. `not_found`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        codes::from_name(s).ok_or_else(|| UnknownCodeError(s.into()))
+    }
+}
+
+/// The error returned when parsing an unknown [`ConnectCode`].
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("unknown Connect code {0:?}")]
+pub struct UnknownCodeError(pub String);
+
 // https://connectrpc.com/docs/protocol/#http-to-error-code
 impl From<http::StatusCode> for ConnectCode {
-    fn from(code: http::StatusCode) -> Self {
-        use http::StatusCode;
-        match code {
-            StatusCode::BAD_REQUEST => Self::Internal,
-            StatusCode::UNAUTHORIZED => Self::Unauthenticated,
-            StatusCode::FORBIDDEN => Self::PermissionDenied,
-            StatusCode::NOT_FOUND => Self::Unimplemented,
-            StatusCode::NOT_IMPLEMENTED => Self::Unimplemented,
-            StatusCode::TOO_MANY_REQUESTS
-            | StatusCode::BAD_GATEWAY
-            | StatusCode::SERVICE_UNAVAILABLE
-            | StatusCode::GATEWAY_TIMEOUT => Self::Unavailable,
-            _ => Self::Unknown,
-        }
+    fn from(status: http::StatusCode) -> Self {
+        codes::from_http_status(status)
     }
 }
 
@@ -161,12 +380,89 @@ pub struct ConnectErrorDetail {
     pub value_base64: String,
 }
 
+const DEFAULT_TYPE_URL_PREFIX: &str = "type.googleapis.com/";
+
 impl ConnectErrorDetail {
+    /// Returns a detail with the given type (a fully-qualified message name
+    /// or type URL) and serialized message.
+    ///
+    /// The default `type.googleapis.com/` prefix is stripped, as the
+    /// protocol expects; other prefixes are kept.
+    pub fn new(type_url: impl Into<String>, value: impl AsRef<[u8]>) -> Self {
+        let mut proto_type = type_url.into();
+        if let Some(name) = proto_type.strip_prefix(DEFAULT_TYPE_URL_PREFIX) {
+            proto_type = name.to_string();
+        }
+        Self {
+            proto_type,
+            value_base64: Base64Variant::StandardNoPad.encode(value),
+        }
+    }
+
+    /// Returns the type URL, e.g. for a `google.protobuf.Any`.
+    ///
+    /// A type that is already a URL (contains a `/`) is returned as is;
+    /// otherwise the `type.googleapis.com/` prefix is added.
     pub fn type_url(&self) -> String {
-        format!("type.googleapis.com/{}", self.proto_type)
+        self.type_url_with_prefix(DEFAULT_TYPE_URL_PREFIX)
+    }
+
+    /// Returns the type URL, adding `prefix` (e.g. `example.com/types/`) to
+    /// a type that isn't already a URL.
+    pub fn type_url_with_prefix(&self, prefix: &str) -> String {
+        if self.proto_type.contains('/') {
+            self.proto_type.clone()
+        } else {
+            format!("{prefix}{}", self.proto_type)
+        }
+    }
+
+    /// Returns the fully-qualified message name, without any type URL
+    /// prefix.
+    pub fn type_name(&self) -> &str {
+        self.proto_type
+            .rsplit_once('/')
+            .map_or(&self.proto_type, |(_, name)| name)
     }
 
     pub fn value(&self) -> Result<Vec<u8>, Error> {
-        base64_decode(&self.value_base64)
+        Base64Variant::StandardNoPad.decode(&self.value_base64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_response_drops_body_headers() {
+        // A compressed upstream error, as parsed after decompression.
+        let json = br#"{"code":"not_found","message":"no such user"}"#;
+        let upstream = http::Response::builder()
+            .status(http::StatusCode::NOT_FOUND)
+            .header(header::CONTENT_TYPE, ERROR_CONTENT_TYPE)
+            .header(header::CONTENT_ENCODING, "gzip")
+            .header(header::CONTENT_LENGTH, "27")
+            .header(header::CONNECTION, "x-hop")
+            .header("x-hop", "1")
+            .header(header::TRANSFER_ENCODING, "chunked")
+            .header("x-request-id", "abc")
+            .body(&json[..])
+            .unwrap();
+        let err = ConnectError::from(upstream);
+        assert_eq!(err.code(), ConnectCode::NotFound);
+
+        let resp = http::Response::<Bytes>::from(err);
+        let headers = resp.headers();
+        for name in ["content-encoding", "content-length", "connection", "x-hop"] {
+            assert!(!headers.contains_key(name), "{name} copied");
+        }
+        assert!(!headers.contains_key(header::TRANSFER_ENCODING));
+        assert_eq!(headers[header::CONTENT_TYPE], ERROR_CONTENT_TYPE);
+        assert_eq!(headers["x-request-id"], "abc");
+
+        let reparsed = ConnectError::from(resp);
+        assert_eq!(reparsed.code(), ConnectCode::NotFound);
+        assert_eq!(reparsed.message, "no such user");
     }
 }
@@ -1,9 +1,28 @@
-use bytes::{Buf, BufMut, Bytes, BytesMut};
-use futures_util::{stream, Stream, StreamExt, TryStream, TryStreamExt};
-use http_body::Body;
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use bytes::{buf::Chain, Buf, BufMut, Bytes, BytesMut};
+use futures_util::{stream, stream::BoxStream, Stream, StreamExt, TryStream, TryStreamExt};
+use http::{HeaderMap, HeaderName, HeaderValue};
+use http_body::{Body, Frame};
 use http_body_util::BodyExt;
 
-use crate::{BoxError, Error};
+use crate::{
+    compression::{self, Compression},
+    response::error::{ConnectCode, ConnectError},
+    BoxError, Error,
+};
+
+use pool::BufferPool;
+
+pub mod json;
+pub mod pool;
+pub mod read_ahead;
+pub mod sender;
 
 pub struct ConnectFrame {
     pub compressed: bool,
@@ -12,7 +31,7 @@ pub struct ConnectFrame {
 }
 
 const FLAGS_COMPRESSED: u8 = 0b1;
-const FLAGS_END: u8 = 0b01;
+const FLAGS_END: u8 = 0b10;
 
 impl ConnectFrame {
     pub fn body_stream<B>(body: B) -> impl Stream<Item = Result<Self, Error>>
@@ -26,21 +45,449 @@ impl ConnectFrame {
     where
         S: TryStream<Ok: Buf, Error: Into<BoxError>>,
     {
-        let mut parse_state = FrameParseState::default();
+        Self::parse_stream(s, FrameParseState::default())
+    }
+
+    /// Like [`Self::bytes_stream`], but with a parse buffer taken from (and
+    /// returned to) `pool`.
+    pub fn bytes_stream_pooled<S>(s: S, pool: BufferPool) -> impl Stream<Item = Result<Self, Error>>
+    where
+        S: TryStream<Ok: Buf, Error: Into<BoxError>>,
+    {
+        Self::parse_stream(s, FrameParseState::pooled(pool))
+    }
+
+    fn parse_stream<S>(
+        s: S,
+        mut parse_state: FrameParseState,
+    ) -> impl Stream<Item = Result<Self, Error>>
+    where
+        S: TryStream<Ok: Buf, Error: Into<BoxError>>,
+    {
         s.map_err(Error::body)
             .map(Some)
             .chain(stream::iter([None]))
             .flat_map(move |item| stream::iter(parse_state.feed(item)))
     }
+
+    /// Parses a fully-buffered body into frames.
+    pub fn parse_all(body: Bytes) -> Result<Vec<Self>, Error> {
+        let mut parse_state = FrameParseState::default();
+        parse_state
+            .feed(Some(Ok(body)))
+            .into_iter()
+            .chain(parse_state.feed(None::<Result<Bytes, Error>>))
+            .collect()
+    }
+
+    /// Reads the single request message of a unary-shaped RPC sent with a
+    /// streaming content type, e.g. a server-streaming request.
+    ///
+    /// Fails with `unimplemented` if there are zero or multiple messages
+    /// and `invalid_argument` on an end-stream frame, rather than taking the
+    /// first message. The returned frame may be compressed.
+    pub async fn single_request<S>(frames: S) -> Result<Self, Error>
+    where
+        S: Stream<Item = Result<Self, Error>>,
+    {
+        let mut frames = std::pin::pin!(frames);
+        let mut message = None;
+        while let Some(frame) = frames.next().await {
+            let frame = frame?;
+            if frame.end {
+                return Err(Error::ConnectError(ConnectError::new(
+                    ConnectCode::InvalidArgument,
+                    "end-stream frame in request",
+                )));
+            }
+            if message.replace(frame).is_some() {
+                return Err(Error::ConnectError(ConnectError::new(
+                    ConnectCode::Unimplemented,
+                    "unary request has multiple messages",
+                )));
+            }
+        }
+        message.ok_or_else(|| {
+            Error::ConnectError(ConnectError::new(
+                ConnectCode::Unimplemented,
+                "unary request has zero messages",
+            ))
+        })
+    }
+
+    /// Reads the single response message and end-stream frame of a
+    /// unary-shaped RPC sent with a streaming content type, e.g. a
+    /// client-streaming response.
+    ///
+    /// An error in the end-stream frame is returned as is; otherwise zero or
+    /// multiple messages fail with `unimplemented`.
+    pub async fn single_response<S>(frames: S) -> Result<(Bytes, EndStreamResponse), Error>
+    where
+        S: Stream<Item = Result<Self, Error>>,
+    {
+        let mut frames = std::pin::pin!(frames);
+        let mut message = None;
+        let mut multiple = false;
+        let mut end = loop {
+            match ResponseFrame::from_next(frames.next().await, None, usize::MAX)? {
+                ResponseFrame::Message(data) => multiple |= message.replace(data).is_some(),
+                ResponseFrame::End(end) => break end,
+            }
+        };
+        if let Some(err) = end.error.take() {
+            return Err(Error::ConnectError(err));
+        }
+        let count = match (message, multiple) {
+            (Some(message), false) => return Ok((message, end)),
+            (None, _) => "zero",
+            (Some(_), true) => "multiple",
+        };
+        Err(Error::ConnectError(ConnectError::new(
+            ConnectCode::Unimplemented,
+            format!("unary response has {count} messages"),
+        )))
+    }
+
+    /// Encodes a stream of frames, e.g. to be used as a request body.
+    ///
+    /// Each frame is yielded as its envelope prefix followed by its data,
+    /// which isn't copied (see [`Self::encode_vectored`]).
+    pub fn encode_stream<S>(frames: S) -> impl Stream<Item = Result<Bytes, Error>>
+    where
+        S: Stream<Item = Result<Self, Error>>,
+    {
+        frames.flat_map(|frame| {
+            let chunks = match frame.and_then(|frame| frame.encode_vectored()) {
+                Ok(encoded) => {
+                    let (prefix, data) = encoded.into_inner();
+                    vec![Ok(prefix), Ok(data)]
+                }
+                Err(err) => vec![Err(err)],
+            };
+            stream::iter(
+                chunks
+                    .into_iter()
+                    .filter(|chunk| !matches!(chunk, Ok(chunk) if chunk.is_empty())),
+            )
+        })
+    }
+
+    /// Re-envelopes a stream of frames with a different compression.
+    ///
+    /// Compressed frames (including a compressed end-stream frame) are
+    /// decompressed with `from`, then every frame is compressed with `to`;
+    /// a `to` of `None` (or `identity`) leaves frames uncompressed. Frames
+    /// are processed one at a time, and decompressed frames larger than
+    /// `max_message_size` produce a `resource_exhausted` error.
+    pub fn recompress_stream<S>(
+        frames: S,
+        from: Option<Arc<dyn Compression>>,
+        to: Option<Arc<dyn Compression>>,
+        max_message_size: usize,
+    ) -> impl Stream<Item = Result<Self, Error>>
+    where
+        S: Stream<Item = Result<Self, Error>>,
+    {
+        let to = to.filter(|to| to.name() != "identity");
+        frames.map(move |frame| {
+            let mut frame = frame?;
+            if frame.compressed {
+                let from = from.as_ref().ok_or_else(|| {
+                    Error::InvalidResponse("compressed frame without compression".into())
+                })?;
+                frame.data = from.decompress(&frame.data, max_message_size)?;
+                frame.compressed = false;
+            } else if frame.data.len() > max_message_size {
+                return Err(compression::limit_exceeded());
+            }
+            if let Some(to) = &to {
+                frame.data = to.compress(&frame.data)?;
+                frame.compressed = true;
+            }
+            Ok(frame)
+        })
+    }
+
+    /// Decodes this frame's data as a `proto` codec message. `index` is the
+    /// message's position in a stream, if any, for error reporting.
+    ///
+    /// Fails with [`Error::MessageDecode`] if the data isn't a valid `M`.
+    #[cfg(feature = "prost")]
+    pub fn decode_proto<M: prost::Message + Default>(
+        &self,
+        index: Option<usize>,
+    ) -> Result<M, Error> {
+        if self.compressed {
+            return Err(Error::InvalidResponse(
+                "compressed messages not supported".into(),
+            ));
+        }
+        M::decode(self.data.clone()).map_err(|err| Error::MessageDecode {
+            codec: "proto".into(),
+            index,
+            source: err.into(),
+        })
+    }
+
+    /// Encodes this frame, including its 5-byte envelope prefix.
+    pub fn encode(&self) -> Result<Bytes, Error> {
+        self.encode_into(BytesMut::with_capacity(5 + self.data.len()))
+    }
+
+    /// Like [`Self::encode`], but into a buffer from `pool`; pass the result
+    /// to [`BufferPool::recycle`] once it has been sent.
+    pub fn encode_pooled(&self, pool: &BufferPool) -> Result<Bytes, Error> {
+        self.encode_into(pool.get())
+    }
+
+    /// Encodes this frame as its 5-byte envelope prefix chained with its
+    /// data, without copying the data into a new buffer.
+    ///
+    /// The parts may be written with a vectored write, or sent as separate
+    /// body chunks.
+    pub fn encode_vectored(&self) -> Result<Chain<Bytes, Bytes>, Error> {
+        let prefix = Bytes::copy_from_slice(&self.prefix()?);
+        Ok(prefix.chain(self.data.clone()))
+    }
+
+    fn encode_into(&self, mut buf: BytesMut) -> Result<Bytes, Error> {
+        let prefix = self.prefix()?;
+        buf.reserve(5 + self.data.len());
+        buf.put_slice(&prefix);
+        buf.put_slice(&self.data);
+        Ok(buf.freeze())
+    }
+
+    fn prefix(&self) -> Result<[u8; 5], Error> {
+        let data_len: u32 = self
+            .data
+            .len()
+            .try_into()
+            .map_err(|_| Error::FrameTooLarge {
+                size: self.data.len() as u64,
+                limit: u32::MAX.into(),
+            })?;
+        let mut flags = 0;
+        if self.compressed {
+            flags |= FLAGS_COMPRESSED;
+        }
+        if self.end {
+            flags |= FLAGS_END;
+        }
+        let mut prefix = [flags, 0, 0, 0, 0];
+        prefix[1..].copy_from_slice(&data_len.to_be_bytes());
+        Ok(prefix)
+    }
+
+    /// Encodes an envelope with arbitrary flags and a declared length that
+    /// need not match `data` (defaulting to its actual length).
+    ///
+    /// This produces deliberately malformed frames, e.g. to test how a peer
+    /// handles unknown flags or truncated messages; use [`Self::encode`] for
+    /// valid frames.
+    pub fn encode_raw(flags: u8, length: Option<u32>, data: &[u8]) -> Bytes {
+        let mut buf = BytesMut::with_capacity(5 + data.len());
+        buf.put_u8(flags);
+        buf.put_u32(length.unwrap_or(data.len() as u32));
+        buf.put_slice(data);
+        buf.freeze()
+    }
+}
+
+/// A stream of encoded (enveloped) frames, e.g. a client-streaming request
+/// body.
+///
+/// Implements both [`Stream`] and [`Body`], and is `Sync` (as a
+/// [`RequestBody`](crate::transport::RequestBody) must be) even if the
+/// wrapped stream isn't.
+pub struct FramedBody(std::sync::Mutex<BoxStream<'static, Result<Bytes, Error>>>);
+
+impl FramedBody {
+    pub fn new(frames: impl Stream<Item = Result<Bytes, Error>> + Send + 'static) -> Self {
+        Self(std::sync::Mutex::new(frames.boxed()))
+    }
+
+    /// Polls the wrapped stream; a `Mutex` is only needed for `Sync`, so
+    /// polling through `&mut` never locks.
+    fn poll_frames(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes, Error>>> {
+        self.0
+            .get_mut()
+            .unwrap_or_else(|err| err.into_inner())
+            .poll_next_unpin(cx)
+    }
+}
+
+impl std::fmt::Debug for FramedBody {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("FramedBody").finish_non_exhaustive()
+    }
+}
+
+impl Stream for FramedBody {
+    type Item = Result<Bytes, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.poll_frames(cx)
+    }
+}
+
+impl Body for FramedBody {
+    type Data = Bytes;
+    type Error = Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        self.poll_frames(cx)
+            .map(|item| item.map(|res| res.map(Frame::data)))
+    }
+}
+
+/// The JSON payload of an end-of-stream frame.
+///
+/// See: https://connectrpc.com/docs/protocol/#error-end-stream
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct EndStreamResponse {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<ConnectError>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub metadata: HashMap<String, Vec<String>>,
+}
+
+impl EndStreamResponse {
+    /// Returns an end-stream response with the given error and trailers.
+    ///
+    /// Trailer values that aren't valid UTF-8 are skipped.
+    pub fn new(error: Option<ConnectError>, trailers: &HeaderMap) -> Self {
+        let mut metadata: HashMap<String, Vec<String>> = HashMap::new();
+        for (key, val) in trailers {
+            if let Ok(val) = val.to_str() {
+                metadata
+                    .entry(key.to_string())
+                    .or_default()
+                    .push(val.into());
+            }
+        }
+        Self { error, metadata }
+    }
+
+    /// Serializes this response as an end-of-stream frame.
+    pub fn to_frame(&self) -> Result<ConnectFrame, Error> {
+        let data = serde_json::to_vec(self)
+            .map_err(|err| Error::invalid_request(format!("invalid end-stream JSON: {err}")))?;
+        Ok(ConnectFrame {
+            compressed: false,
+            end: true,
+            data: data.into(),
+        })
+    }
+
+    /// Parses an end-of-stream frame.
+    pub fn from_frame(frame: &ConnectFrame) -> Result<Self, Error> {
+        if !frame.end {
+            return Err(Error::InvalidResponse("not an end-stream frame".into()));
+        }
+        serde_json::from_slice(&frame.data)
+            .map_err(|err| Error::InvalidResponse(format!("invalid end-stream JSON: {err}")))
+    }
+
+    /// Merges response headers and this end-stream metadata into a single
+    /// [`HeaderMap`].
+    ///
+    /// Fails with `resource_exhausted` if the merged names and values would
+    /// exceed `max_bytes`.
+    pub fn flatten_metadata(
+        &self,
+        headers: &HeaderMap,
+        max_bytes: usize,
+    ) -> Result<HeaderMap, Error> {
+        let mut size = 0;
+        let mut check_size = |key: &str, val: &[u8]| {
+            size += key.len() + val.len();
+            if size > max_bytes {
+                return Err(compression::limit_exceeded());
+            }
+            Ok(())
+        };
+        let mut flattened = HeaderMap::new();
+        for (key, val) in headers {
+            check_size(key.as_str(), val.as_bytes())?;
+            flattened.append(key, val.clone());
+        }
+        for (key, vals) in &self.metadata {
+            let key = HeaderName::try_from(key)?;
+            for val in vals {
+                check_size(key.as_str(), val.as_bytes())?;
+                flattened.append(&key, HeaderValue::try_from(val)?);
+            }
+        }
+        Ok(flattened)
+    }
+}
+
+/// A frame received on a response stream.
+pub(crate) enum ResponseFrame {
+    Message(Bytes),
+    End(EndStreamResponse),
+}
+
+impl ResponseFrame {
+    /// Interprets the next item of a response frame stream. The stream
+    /// ending without an end-stream frame is an error.
+    ///
+    /// Compressed frames are decompressed with `compression` (the response's
+    /// negotiated content encoding), failing with `resource_exhausted` if the
+    /// result would exceed `max_message_size`.
+    pub(crate) fn from_next(
+        next: Option<Result<ConnectFrame, Error>>,
+        compression: Option<&dyn Compression>,
+        max_message_size: usize,
+    ) -> Result<Self, Error> {
+        let mut frame = match next {
+            Some(Ok(frame)) => frame,
+            Some(Err(err)) => return Err(err),
+            None => return Err(Error::InvalidResponse("missing end-stream frame".into())),
+        };
+        if frame.compressed {
+            let compression = compression.ok_or_else(|| {
+                Error::InvalidResponse("compressed frame without content encoding".into())
+            })?;
+            frame.data = compression.decompress(&frame.data, max_message_size)?;
+            frame.compressed = false;
+        }
+        if frame.end {
+            Ok(Self::End(EndStreamResponse::from_frame(&frame)?))
+        } else {
+            Ok(Self::Message(frame.data))
+        }
+    }
 }
 
 #[derive(Default)]
 struct FrameParseState {
     buf: BytesMut,
     failed: bool,
+    pool: Option<BufferPool>,
+}
+
+impl Drop for FrameParseState {
+    fn drop(&mut self) {
+        if let Some(pool) = &self.pool {
+            pool.put(std::mem::take(&mut self.buf));
+        }
+    }
 }
 
 impl FrameParseState {
+    fn pooled(pool: BufferPool) -> Self {
+        Self {
+            buf: pool.get(),
+            failed: false,
+            pool: Some(pool),
+        }
+    }
+
     fn feed(&mut self, item: Option<Result<impl Buf, Error>>) -> Vec<Result<ConnectFrame, Error>> {
         if self.failed {
             return vec![];
@@ -53,7 +500,17 @@ impl FrameParseState {
             }
             None => {
                 if !self.buf.is_empty() {
-                    return vec![Err(Error::body("partial frame at end of stream"))];
+                    let expected = match self.buf.get(..5) {
+                        Some(mut prefix) => {
+                            prefix.advance(1);
+                            prefix.get_u32() as u64 + 5
+                        }
+                        None => 5,
+                    };
+                    return vec![Err(Error::TruncatedFrame {
+                        expected,
+                        received: self.buf.len() as u64,
+                    })];
                 }
                 return vec![];
             }
@@ -69,6 +526,7 @@ impl FrameParseState {
                 Err(err) => {
                     self.failed = true;
                     frames.push(Err(err));
+                    return frames;
                 }
             }
         }
@@ -80,7 +538,10 @@ impl FrameParseState {
         }
         let data_len = (&self.buf[1..]).get_u32();
         let Ok(frame_len) = ((data_len as u64) + 5).try_into() else {
-            return Err(Error::body("frame too large"));
+            return Err(Error::FrameTooLarge {
+                size: data_len as u64 + 5,
+                limit: usize::MAX as u64,
+            });
         };
         if self.buf.len() < frame_len {
             return Ok(None);
@@ -95,3 +556,45 @@ impl FrameParseState {
         }))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oversized_length_prefix() {
+        let mut state = FrameParseState::default();
+        let prefix: &[u8] = &[0, 0xff, 0xff, 0xff, 0xff];
+        let results = state.feed(Some(Ok(prefix)));
+        if usize::BITS < 64 {
+            assert!(matches!(
+                results.as_slice(),
+                [Err(Error::FrameTooLarge { .. })]
+            ));
+            assert!(state.feed(Some(Ok(prefix))).is_empty());
+            return;
+        }
+        assert!(results.is_empty());
+        let results = state.feed(None::<Result<&[u8], Error>>);
+        assert!(matches!(
+            results.as_slice(),
+            [Err(Error::TruncatedFrame {
+                expected: 0x1_0000_0004,
+                received: 5
+            })]
+        ));
+    }
+
+    #[test]
+    fn no_frames_after_error() {
+        let mut state = FrameParseState::default();
+        let results = state.feed(Some(Err::<&[u8], _>(Error::body("broken"))));
+        assert!(matches!(results.as_slice(), [Err(Error::BodyError(_))]));
+        let frame = ConnectFrame {
+            compressed: false,
+            end: false,
+            data: Bytes::from_static(b"x"),
+        };
+        assert!(state.feed(Some(Ok(frame.encode().unwrap()))).is_empty());
+    }
+}
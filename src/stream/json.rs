@@ -0,0 +1,179 @@
+//! Incremental decoding for the `json` streaming codec.
+
+use futures_util::{stream, Stream, StreamExt};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    compression,
+    response::error::{ConnectCode, ConnectError},
+    Error,
+};
+
+use super::{ConnectFrame, EndStreamResponse};
+
+const DEFAULT_MAX_MESSAGE_SIZE: usize = 4 * 1024 * 1024;
+const DEFAULT_MAX_DEPTH: usize = 64;
+
+/// Decodes a stream of `json` codec frames one message at a time.
+///
+/// Each frame is checked against a size and nesting depth limit before being
+/// parsed. With the `json-path-errors` feature, decode errors include the
+/// path to the offending value (e.g. `items[2].name`).
+#[derive(Clone, Debug)]
+pub struct JsonStreamDecoder {
+    max_message_size: usize,
+    max_depth: usize,
+}
+
+impl Default for JsonStreamDecoder {
+    fn default() -> Self {
+        Self {
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+            max_depth: DEFAULT_MAX_DEPTH,
+        }
+    }
+}
+
+impl JsonStreamDecoder {
+    /// Sets the maximum size of a single message.
+    ///
+    /// Defaults to 4 MiB. Larger messages produce a `resource_exhausted`
+    /// error.
+    pub fn max_message_size(mut self, max_message_size: usize) -> Self {
+        self.max_message_size = max_message_size;
+        self
+    }
+
+    /// Sets the maximum nesting depth of arrays and objects in a message.
+    ///
+    /// Defaults to 64. Note that `serde_json` also enforces its own limit of
+    /// 128.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Decodes a single message frame. `index` identifies the message in
+    /// error messages.
+    pub fn decode<T: DeserializeOwned>(
+        &self,
+        frame: &ConnectFrame,
+        index: usize,
+    ) -> Result<T, Error> {
+        if frame.compressed {
+            return Err(Error::InvalidResponse(
+                "compressed messages not supported".into(),
+            ));
+        }
+        if frame.data.len() > self.max_message_size {
+            return Err(compression::limit_exceeded());
+        }
+        if exceeds_depth(&frame.data, self.max_depth) {
+            return Err(Error::ConnectError(ConnectError::new(
+                ConnectCode::ResourceExhausted,
+                format!("message {index} exceeds maximum nesting depth"),
+            )));
+        }
+        decode_json(&frame.data, index)
+    }
+
+    /// Decodes a stream of frames into a stream of messages.
+    ///
+    /// The stream ends at the end-stream frame, yielding its error (if any).
+    /// Decoding stops at the first error. Frames are read as messages are
+    /// consumed; to read ahead, pass a [`read_ahead`](super::read_ahead)
+    /// stream.
+    pub fn decode_stream<T, S>(self, frames: S) -> impl Stream<Item = Result<T, Error>>
+    where
+        T: DeserializeOwned,
+        S: Stream<Item = Result<ConnectFrame, Error>>,
+    {
+        let mut index = 0;
+        let mut done = false;
+        frames
+            .map(Some)
+            .chain(stream::iter([None]))
+            .filter_map(move |frame| {
+                let result = if done {
+                    None
+                } else {
+                    match frame {
+                        Some(Ok(frame)) if frame.end => {
+                            done = true;
+                            match EndStreamResponse::from_frame(&frame) {
+                                Ok(end) => end.error.map(|err| Err(Error::ConnectError(err))),
+                                Err(err) => Some(Err(err)),
+                            }
+                        }
+                        Some(Ok(frame)) => {
+                            let result = self.decode(&frame, index);
+                            index += 1;
+                            done = result.is_err();
+                            Some(result)
+                        }
+                        Some(Err(err)) => {
+                            done = true;
+                            Some(Err(err))
+                        }
+                        None => {
+                            done = true;
+                            Some(Err(Error::InvalidResponse(
+                                "missing end-stream frame".into(),
+                            )))
+                        }
+                    }
+                };
+                std::future::ready(result)
+            })
+    }
+}
+
+#[cfg(feature = "json-path-errors")]
+fn decode_json<T: DeserializeOwned>(data: &[u8], index: usize) -> Result<T, Error> {
+    let de = &mut serde_json::Deserializer::from_slice(data);
+    serde_path_to_error::deserialize(de).map_err(|err| Error::InvalidJsonMessage {
+        index,
+        path: Some(err.path().to_string()),
+        source: err.into_inner(),
+    })
+}
+
+#[cfg(not(feature = "json-path-errors"))]
+fn decode_json<T: DeserializeOwned>(data: &[u8], index: usize) -> Result<T, Error> {
+    serde_json::from_slice(data).map_err(|source| Error::InvalidJsonMessage {
+        index,
+        path: None,
+        source,
+    })
+}
+
+/// Returns true if the JSON nests arrays or objects more than `max_depth`
+/// deep. Malformed JSON is left for the parser to reject.
+fn exceeds_depth(data: &[u8], max_depth: usize) -> bool {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for &b in data {
+        if in_string {
+            match b {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => (),
+            }
+            continue;
+        }
+        match b {
+            b'"' => in_string = true,
+            b'[' | b'{' => {
+                depth += 1;
+                if depth > max_depth {
+                    return true;
+                }
+            }
+            b']' | b'}' => depth = depth.saturating_sub(1),
+            _ => (),
+        }
+    }
+    false
+}
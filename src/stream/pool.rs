@@ -0,0 +1,93 @@
+//! Reuse of frame buffers across frames and streams, to reduce allocator
+//! churn in high-throughput proxies.
+//!
+//! ```no_run
+//! # use bytes::Bytes;
+//! # use connect_rpc::{stream::{pool::BufferPool, ConnectFrame}, Error};
+//! # use futures_util::{Stream, StreamExt};
+//! # async fn send(data: &[u8]) -> Result<(), Error> { unimplemented!() }
+//! # async fn example(
+//! #     body: impl Stream<Item = Result<Bytes, Error>>,
+//! #     frame: ConnectFrame,
+//! # ) -> Result<(), Error> {
+//! let pool = BufferPool::default();
+//! let frames = ConnectFrame::bytes_stream_pooled(body, pool.clone());
+//! // ...
+//! let encoded = frame.encode_pooled(&pool)?;
+//! send(&encoded).await?;
+//! pool.recycle(encoded);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::sync::{Arc, Mutex};
+
+use bytes::{Bytes, BytesMut};
+
+const DEFAULT_MAX_BUFFERS: usize = 64;
+const DEFAULT_BUFFER_CAPACITY: usize = 16 * 1024;
+
+/// A pool of reusable [`BytesMut`] buffers.
+///
+/// Clones share the same pool.
+#[derive(Clone, Debug)]
+pub struct BufferPool {
+    buffers: Arc<Mutex<Vec<BytesMut>>>,
+    max_buffers: usize,
+    buffer_capacity: usize,
+}
+
+impl Default for BufferPool {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_BUFFERS, DEFAULT_BUFFER_CAPACITY)
+    }
+}
+
+impl BufferPool {
+    /// Returns a pool keeping at most `max_buffers` idle buffers, each
+    /// allocated with `buffer_capacity` bytes.
+    ///
+    /// Returned buffers that have grown past 4 times `buffer_capacity` are
+    /// dropped rather than kept, so one large message doesn't pin memory.
+    pub fn new(max_buffers: usize, buffer_capacity: usize) -> Self {
+        Self {
+            buffers: Default::default(),
+            max_buffers,
+            buffer_capacity,
+        }
+    }
+
+    /// Takes an empty buffer from the pool, or allocates one.
+    pub fn get(&self) -> BytesMut {
+        self.buffers
+            .lock()
+            .unwrap()
+            .pop()
+            .unwrap_or_else(|| BytesMut::with_capacity(self.buffer_capacity))
+    }
+
+    /// Returns a buffer to the pool.
+    pub fn put(&self, mut buf: BytesMut) {
+        if buf.capacity() > self.buffer_capacity * 4 {
+            return;
+        }
+        buf.clear();
+        let mut buffers = self.buffers.lock().unwrap();
+        if buffers.len() < self.max_buffers {
+            buffers.push(buf);
+        }
+    }
+
+    /// Returns the buffer backing `bytes` to the pool, if it has no other
+    /// references.
+    pub fn recycle(&self, bytes: Bytes) {
+        if let Ok(buf) = bytes.try_into_mut() {
+            self.put(buf);
+        }
+    }
+
+    /// Returns the number of idle buffers in the pool.
+    pub fn idle(&self) -> usize {
+        self.buffers.lock().unwrap().len()
+    }
+}
@@ -0,0 +1,350 @@
+//! A bounded buffer for reading a response stream ahead of its consumer.
+//!
+//! A [`Reader`] future drives the underlying frame stream (and so the
+//! response body) into a buffer, independently of the consumer, which reads
+//! from the [`ReadAheadStream`]. Once the buffer holds
+//! [`ReadAhead::max_frames`] frames or [`ReadAhead::max_bytes`] bytes of
+//! message data, the reader stops reading until the consumer catches up,
+//! so a server pushing faster than the consumer reads is held back by flow
+//! control instead of being buffered without bound.
+//!
+//! [`ServerStreamCall::read_ahead`](crate::client::call::ServerStreamCall::read_ahead)
+//! uses this buffer for a call's response:
+//!
+//! ```no_run
+//! # use connect_rpc::{client::{call::ServerStreamCall}, stream::read_ahead::ReadAhead};
+//! # fn example(call: ServerStreamCall) {
+//! let limits = ReadAhead {
+//!     max_frames: 32,
+//!     max_bytes: 4 * 1024 * 1024,
+//! };
+//! let call = call.read_ahead(limits, |reader| {
+//!     tokio::spawn(reader);
+//! });
+//! # }
+//! ```
+
+use std::{
+    collections::VecDeque,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+use futures_util::{Stream, StreamExt};
+
+use crate::{
+    response::error::{ConnectCode, ConnectError},
+    Error,
+};
+
+use super::ConnectFrame;
+
+type FrameStream = Pin<Box<dyn Stream<Item = Result<ConnectFrame, Error>> + Send>>;
+
+/// Limits on how far a [`Reader`] reads ahead of its consumer.
+#[derive(Clone, Copy, Debug)]
+pub struct ReadAhead {
+    /// The maximum number of buffered frames.
+    pub max_frames: usize,
+    /// The number of buffered message bytes at which reading pauses.
+    ///
+    /// A single frame is always buffered, even if it's larger.
+    pub max_bytes: usize,
+}
+
+impl Default for ReadAhead {
+    /// Defaults to 16 frames and 1 MiB.
+    fn default() -> Self {
+        Self {
+            max_frames: 16,
+            max_bytes: 1024 * 1024,
+        }
+    }
+}
+
+/// Returns a [`ReadAheadStream`] yielding the frames of `frames`, and the
+/// [`Reader`] that reads them into its buffer, bounded by `limits`.
+///
+/// The reader must be polled (e.g. spawned) for the stream to make
+/// progress.
+pub fn buffer(
+    frames: impl Stream<Item = Result<ConnectFrame, Error>> + Send + 'static,
+    limits: ReadAhead,
+) -> (ReadAheadStream, Reader) {
+    let shared = Arc::new(Mutex::new(Shared {
+        queue: VecDeque::new(),
+        bytes: 0,
+        limits,
+        reader_waker: None,
+        stream_waker: None,
+        reader_done: false,
+        stream_closed: false,
+    }));
+    let reader = Reader {
+        shared: shared.clone(),
+        frames: Some(Box::pin(frames)),
+    };
+    (ReadAheadStream { shared }, reader)
+}
+
+struct Shared {
+    queue: VecDeque<Result<ConnectFrame, Error>>,
+    /// The message bytes in `queue`.
+    bytes: usize,
+    limits: ReadAhead,
+    reader_waker: Option<Waker>,
+    stream_waker: Option<Waker>,
+    reader_done: bool,
+    stream_closed: bool,
+}
+
+impl Shared {
+    fn is_full(&self) -> bool {
+        !self.queue.is_empty()
+            && (self.queue.len() >= self.limits.max_frames || self.bytes >= self.limits.max_bytes)
+    }
+
+    fn push(&mut self, item: Result<ConnectFrame, Error>) {
+        self.bytes += frame_len(&item);
+        self.queue.push_back(item);
+        if let Some(waker) = self.stream_waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn finish(&mut self) {
+        self.reader_done = true;
+        if let Some(waker) = self.stream_waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+fn frame_len(item: &Result<ConnectFrame, Error>) -> usize {
+    item.as_ref().map_or(0, |frame| frame.data.len())
+}
+
+/// Reads frames into the buffer of a [`ReadAheadStream`]; see [`buffer`].
+///
+/// Completes when the underlying stream ends or the [`ReadAheadStream`] is
+/// dropped, dropping the underlying stream. If the reader is dropped first,
+/// the stream ends with a `canceled` error.
+pub struct Reader {
+    shared: Arc<Mutex<Shared>>,
+    frames: Option<FrameStream>,
+}
+
+impl Future for Reader {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        loop {
+            {
+                let mut shared = self.shared.lock().unwrap();
+                if shared.stream_closed {
+                    drop(shared);
+                    self.frames = None;
+                    return Poll::Ready(());
+                }
+                if shared.is_full() {
+                    shared.reader_waker = Some(cx.waker().clone());
+                    return Poll::Pending;
+                }
+            }
+            let Some(frames) = self.frames.as_mut() else {
+                return Poll::Ready(());
+            };
+            let next = match frames.poll_next_unpin(cx) {
+                Poll::Ready(next) => next,
+                Poll::Pending => return Poll::Pending,
+            };
+            let mut shared = self.shared.lock().unwrap();
+            match next {
+                Some(item) => shared.push(item),
+                None => {
+                    shared.finish();
+                    drop(shared);
+                    self.frames = None;
+                    return Poll::Ready(());
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Reader {
+    fn drop(&mut self) {
+        if self.frames.take().is_some() {
+            let mut shared = self.shared.lock().unwrap();
+            shared.push(Err(Error::ConnectError(ConnectError::new(
+                ConnectCode::Canceled,
+                "read-ahead reader dropped",
+            ))));
+            shared.finish();
+        }
+    }
+}
+
+impl std::fmt::Debug for Reader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Reader")
+            .field("finished", &self.frames.is_none())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A stream of frames read ahead by a [`Reader`]; see [`buffer`].
+///
+/// Dropping the stream stops the reader, dropping the underlying stream.
+pub struct ReadAheadStream {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl ReadAheadStream {
+    /// Returns the number of frames currently buffered.
+    pub fn buffered_frames(&self) -> usize {
+        self.shared.lock().unwrap().queue.len()
+    }
+}
+
+impl Stream for ReadAheadStream {
+    type Item = Result<ConnectFrame, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut shared = self.shared.lock().unwrap();
+        if let Some(item) = shared.queue.pop_front() {
+            shared.bytes -= frame_len(&item);
+            if let Some(waker) = shared.reader_waker.take() {
+                waker.wake();
+            }
+            return Poll::Ready(Some(item));
+        }
+        if shared.reader_done {
+            return Poll::Ready(None);
+        }
+        shared.stream_waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Drop for ReadAheadStream {
+    fn drop(&mut self) {
+        let mut shared = self.shared.lock().unwrap();
+        shared.stream_closed = true;
+        if let Some(waker) = shared.reader_waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl std::fmt::Debug for ReadAheadStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let shared = self.shared.lock().unwrap();
+        f.debug_struct("ReadAheadStream")
+            .field("limits", &shared.limits)
+            .field("buffered", &shared.queue.len())
+            .field("buffered_bytes", &shared.bytes)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use bytes::Bytes;
+    use futures_util::{poll, stream};
+
+    use super::*;
+
+    /// Returns `count` 4-byte frames, and the number read so far.
+    fn frames(
+        count: usize,
+    ) -> (
+        impl Stream<Item = Result<ConnectFrame, Error>> + Send,
+        Arc<AtomicUsize>,
+    ) {
+        let read = Arc::new(AtomicUsize::new(0));
+        let frames = stream::iter(0..count).map({
+            let read = read.clone();
+            move |_| {
+                read.fetch_add(1, Ordering::SeqCst);
+                Ok(ConnectFrame {
+                    compressed: false,
+                    end: false,
+                    data: Bytes::from_static(b"data"),
+                })
+            }
+        });
+        (frames, read)
+    }
+
+    #[tokio::test]
+    async fn reads_ahead_up_to_max_frames() {
+        let (frames, read) = frames(10);
+        let limits = ReadAhead {
+            max_frames: 3,
+            max_bytes: usize::MAX,
+        };
+        let (mut stream, mut reader) = buffer(frames, limits);
+
+        // The reader fills the buffer without the stream being polled.
+        assert!(poll!(&mut reader).is_pending());
+        assert_eq!(read.load(Ordering::SeqCst), 3);
+        assert_eq!(stream.buffered_frames(), 3);
+
+        stream.next().await.unwrap().unwrap();
+        assert!(poll!(&mut reader).is_pending());
+        assert_eq!(read.load(Ordering::SeqCst), 4);
+
+        let (_, remaining) = futures_util::join!(reader, stream.count());
+        assert_eq!(remaining, 9);
+    }
+
+    #[tokio::test]
+    async fn reads_ahead_up_to_max_bytes() {
+        let (frames, read) = frames(10);
+        let limits = ReadAhead {
+            max_frames: usize::MAX,
+            max_bytes: 8,
+        };
+        let (stream, mut reader) = buffer(frames, limits);
+        assert!(poll!(&mut reader).is_pending());
+        assert_eq!(read.load(Ordering::SeqCst), 2);
+        assert_eq!(stream.buffered_frames(), 2);
+    }
+
+    #[tokio::test]
+    async fn dropping_stream_stops_reader() {
+        let (frames, read) = frames(10);
+        let limits = ReadAhead {
+            max_frames: 1,
+            max_bytes: usize::MAX,
+        };
+        let (stream, mut reader) = buffer(frames, limits);
+        assert!(poll!(&mut reader).is_pending());
+        drop(stream);
+        assert!(poll!(&mut reader).is_ready());
+        assert_eq!(read.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn dropping_reader_cancels_stream() {
+        let (frames, _) = frames(10);
+        let limits = ReadAhead {
+            max_frames: 3,
+            max_bytes: usize::MAX,
+        };
+        let (stream, mut reader) = buffer(frames, limits);
+        assert!(poll!(&mut reader).is_pending());
+        drop(reader);
+        let items: Vec<_> = stream.collect().await;
+        assert_eq!(items.len(), 4);
+        assert!(matches!(
+            items.last(),
+            Some(Err(Error::ConnectError(err))) if err.code() == ConnectCode::Canceled
+        ));
+    }
+}
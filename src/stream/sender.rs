@@ -0,0 +1,217 @@
+//! A bounded channel for producing a client-streaming request body while the
+//! request is in flight.
+//!
+//! [`ConnectClient::execute_bidi_stream`](crate::client::ConnectClient::execute_bidi_stream)
+//! uses this channel; it can also feed a request sent some other way:
+//!
+//! ```no_run
+//! # use std::time::Duration;
+//! # use bytes::Bytes;
+//! # use connect_rpc::{request::builder::RequestBuilder, stream::{sender, ConnectFrame}, Error};
+//! # async fn example(builder: RequestBuilder, data: Bytes) -> Result<(), Error> {
+//! let (sender, body) = sender::channel(8);
+//! let sender = sender.send_timeout(tokio::time::sleep, Duration::from_secs(10));
+//! let req = builder.streaming(body)?;
+//! // ...start the request, then:
+//! sender.send(ConnectFrame { compressed: false, end: false, data }).await?;
+//! sender.close_send(); // ends the request stream
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::VecDeque,
+    future::poll_fn,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+use bytes::Bytes;
+use futures_util::{
+    future::{select, Either},
+    Stream,
+};
+
+use crate::{
+    client::Sleep,
+    response::error::{ConnectCode, ConnectError},
+    Error,
+};
+
+use super::{ConnectFrame, FramedBody};
+
+/// Returns a [`FrameSender`] and the [`FramedBody`] that yields its frames,
+/// buffering at most `capacity` frames (at least 1).
+pub fn channel(capacity: usize) -> (FrameSender, FramedBody) {
+    let shared = Arc::new(Mutex::new(Shared {
+        queue: VecDeque::new(),
+        capacity: capacity.max(1),
+        sender_waker: None,
+        receiver_waker: None,
+        sender_closed: false,
+        receiver_closed: false,
+    }));
+    let sender = FrameSender {
+        shared: shared.clone(),
+        send_timeout: None,
+    };
+    let receiver = Receiver { shared, data: None };
+    (sender, FramedBody::new(receiver))
+}
+
+struct Shared {
+    /// Encoded frames, as envelope prefix and data.
+    queue: VecDeque<(Bytes, Bytes)>,
+    capacity: usize,
+    sender_waker: Option<Waker>,
+    receiver_waker: Option<Waker>,
+    sender_closed: bool,
+    receiver_closed: bool,
+}
+
+impl Shared {
+    fn close_send(&mut self) {
+        self.sender_closed = true;
+        if let Some(waker) = self.receiver_waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Sends frames to a [`FramedBody`] created with [`channel`].
+///
+/// Dropping the sender ends the body, as does [`Self::close_send`].
+pub struct FrameSender {
+    shared: Arc<Mutex<Shared>>,
+    send_timeout: Option<(Arc<dyn Sleep>, Duration)>,
+}
+
+impl FrameSender {
+    /// Fails [`Self::send`] with a `deadline_exceeded` error if the frame
+    /// can't be buffered within `timeout`, using `sleep` to wait (e.g.
+    /// `tokio::time::sleep`).
+    ///
+    /// The buffer only drains as the transport sends the body, so this
+    /// surfaces a peer that has stopped reading the request rather than
+    /// waiting indefinitely.
+    pub fn send_timeout(mut self, sleep: impl Sleep + 'static, timeout: Duration) -> Self {
+        self.send_timeout = Some((Arc::new(sleep), timeout));
+        self
+    }
+
+    /// Encodes and buffers a frame, waiting for buffer space if needed. The
+    /// frame's data is sent without being copied.
+    ///
+    /// Fails with a `canceled` error if the body has been dropped.
+    pub async fn send(&self, frame: ConnectFrame) -> Result<(), Error> {
+        let mut data = Some(frame.encode_vectored()?.into_inner());
+        let send = poll_fn(|cx| self.poll_send(cx, &mut data));
+        let Some((sleep, timeout)) = &self.send_timeout else {
+            return send.await;
+        };
+        match select(Box::pin(send), sleep.sleep(*timeout)).await {
+            Either::Left((result, _)) => result,
+            Either::Right(_) => Err(Error::ConnectError(ConnectError::new(
+                ConnectCode::DeadlineExceeded,
+                format!("request frame not sent in {timeout:?}"),
+            ))),
+        }
+    }
+
+    /// Half-closes the call: ends the body once buffered frames have been
+    /// sent, while the response continues to be read.
+    ///
+    /// Connect clients signal the end of a request stream by ending the
+    /// body, not with an end-stream frame (which only servers send). Later
+    /// sends fail with a `failed_precondition` error.
+    pub fn close_send(&self) {
+        self.shared.lock().unwrap().close_send();
+    }
+
+    fn poll_send(
+        &self,
+        cx: &mut Context<'_>,
+        data: &mut Option<(Bytes, Bytes)>,
+    ) -> Poll<Result<(), Error>> {
+        let mut shared = self.shared.lock().unwrap();
+        if shared.receiver_closed {
+            return Poll::Ready(Err(Error::ConnectError(ConnectError::new(
+                ConnectCode::Canceled,
+                "request body dropped",
+            ))));
+        }
+        if shared.sender_closed {
+            return Poll::Ready(Err(Error::ConnectError(ConnectError::new(
+                ConnectCode::FailedPrecondition,
+                "request stream closed",
+            ))));
+        }
+        if shared.queue.len() >= shared.capacity {
+            shared.sender_waker = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+        if let Some(data) = data.take() {
+            shared.queue.push_back(data);
+        }
+        if let Some(waker) = shared.receiver_waker.take() {
+            waker.wake();
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Drop for FrameSender {
+    fn drop(&mut self) {
+        self.shared.lock().unwrap().close_send();
+    }
+}
+
+impl std::fmt::Debug for FrameSender {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FrameSender")
+            .field("send_timeout", &self.send_timeout.as_ref().map(|(_, t)| t))
+            .finish_non_exhaustive()
+    }
+}
+
+struct Receiver {
+    shared: Arc<Mutex<Shared>>,
+    /// The data of the frame whose prefix was last yielded.
+    data: Option<Bytes>,
+}
+
+impl Stream for Receiver {
+    type Item = Result<Bytes, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(data) = self.data.take() {
+            return Poll::Ready(Some(Ok(data)));
+        }
+        let mut shared = self.shared.lock().unwrap();
+        if let Some((prefix, data)) = shared.queue.pop_front() {
+            if let Some(waker) = shared.sender_waker.take() {
+                waker.wake();
+            }
+            drop(shared);
+            self.data = Some(data).filter(|data| !data.is_empty());
+            return Poll::Ready(Some(Ok(prefix)));
+        }
+        if shared.sender_closed {
+            return Poll::Ready(None);
+        }
+        shared.receiver_waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Drop for Receiver {
+    fn drop(&mut self) {
+        let mut shared = self.shared.lock().unwrap();
+        shared.receiver_closed = true;
+        if let Some(waker) = shared.sender_waker.take() {
+            waker.wake();
+        }
+    }
+}
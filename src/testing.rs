@@ -0,0 +1,310 @@
+//! Test utilities.
+//!
+//! [`MockConnectServer`] serves canned responses over HTTP/1 on an ephemeral
+//! localhost port and records the requests it receives:
+//!
+//! ```no_run
+//! # use bytes::Bytes;
+//! # use connect_rpc::{
+//! #     client::ConnectClient,
+//! #     request::builder::RequestBuilder,
+//! #     testing::{MockConnectServer, MockResponse},
+//! # };
+//! # async fn example(client: ConnectClient, resp_bytes: Bytes) -> Result<(), Box<dyn std::error::Error>> {
+//! let server = MockConnectServer::start().await?;
+//! server.mock("/acme.foo.v1.FooService/Get", MockResponse::unary(resp_bytes));
+//! let req = RequestBuilder::default()
+//!     .uri(format!("{}/acme.foo.v1.FooService/Get", server.base_url()))?
+//!     .message_codec("proto")?
+//!     .unary(Bytes::from_static(b""))?;
+//! client.execute_unary(req).await?;
+//! assert_eq!(server.requests().len(), 1);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    net::{Ipv4Addr, SocketAddr},
+    sync::{Arc, Mutex},
+};
+
+use ::hyper::{body::Incoming, server::conn::http1, service::service_fn};
+use bytes::Bytes;
+use http::{header, HeaderMap, HeaderName, HeaderValue, Method, Uri};
+use http_body_util::{BodyExt, Full};
+use hyper_util::rt::TokioIo;
+use tokio::{net::TcpListener, task::JoinHandle};
+
+use crate::{
+    consts::STREAMING_CONTENT_TYPE_PREFIX,
+    response::error::{ConnectCode, ConnectError},
+    stream::{ConnectFrame, EndStreamResponse},
+};
+
+/// A request received by a [`MockConnectServer`].
+#[derive(Clone, Debug)]
+pub struct RecordedRequest {
+    pub method: Method,
+    pub uri: Uri,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+impl RecordedRequest {
+    /// Returns the request's content type; for unary GET requests this is
+    /// derived from the `encoding` query parameter.
+    fn content_type(&self) -> Option<String> {
+        if let Some(content_type) = self.headers.get(header::CONTENT_TYPE) {
+            return content_type.to_str().ok().map(Into::into);
+        }
+        let query = self.uri.query()?;
+        form_urlencoded::parse(query.as_bytes())
+            .find(|(key, _)| key == "encoding")
+            .map(|(_, encoding)| format!("application/{encoding}"))
+    }
+}
+
+/// A canned response served by a [`MockConnectServer`].
+///
+/// The response is encoded to match the request: streaming requests get
+/// enveloped messages followed by an end-stream frame carrying any error and
+/// trailers; unary requests get a single message (or error JSON) with
+/// trailers sent as `trailer-` prefixed headers. Unless set explicitly, the
+/// response `content-type` mirrors the request's.
+#[derive(Clone, Debug, Default)]
+pub struct MockResponse {
+    headers: HeaderMap,
+    messages: Vec<Bytes>,
+    error: Option<ConnectError>,
+    trailers: HashMap<String, Vec<String>>,
+}
+
+impl MockResponse {
+    /// Returns a response with a single message.
+    pub fn unary(message: impl Into<Bytes>) -> Self {
+        Self::stream([message])
+    }
+
+    /// Returns a response with a sequence of messages.
+    pub fn stream(messages: impl IntoIterator<Item = impl Into<Bytes>>) -> Self {
+        Self {
+            messages: messages.into_iter().map(Into::into).collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Returns an error response.
+    pub fn error(error: ConnectError) -> Self {
+        Self {
+            error: Some(error),
+            ..Default::default()
+        }
+    }
+
+    /// Sets an error to be sent after any messages (streaming only; unary
+    /// responses with an error carry no message).
+    pub fn with_error(mut self, error: ConnectError) -> Self {
+        self.error = Some(error);
+        self
+    }
+
+    /// Appends a response header.
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.append(name, value);
+        self
+    }
+
+    /// Appends a trailer.
+    pub fn trailer(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.trailers
+            .entry(name.into())
+            .or_default()
+            .push(value.into());
+        self
+    }
+
+    fn into_response(self, req: &RecordedRequest) -> http::Response<Bytes> {
+        let content_type = req.content_type().unwrap_or_default();
+        let mut resp = if content_type.starts_with(STREAMING_CONTENT_TYPE_PREFIX) {
+            self.streaming_response()
+        } else {
+            self.unary_response()
+        };
+        if !resp.headers().contains_key(header::CONTENT_TYPE) {
+            if let Ok(content_type) = HeaderValue::try_from(content_type) {
+                resp.headers_mut()
+                    .insert(header::CONTENT_TYPE, content_type);
+            }
+        }
+        resp
+    }
+
+    fn streaming_response(self) -> http::Response<Bytes> {
+        let end = EndStreamResponse {
+            error: self.error,
+            metadata: self.trailers,
+        };
+        let end_data = serde_json::to_vec(&end).expect("end-stream JSON");
+        let frames = self
+            .messages
+            .into_iter()
+            .map(|data| (false, data))
+            .chain([(true, end_data.into())]);
+        let mut body = Vec::new();
+        for (end, data) in frames {
+            let frame = ConnectFrame {
+                compressed: false,
+                end,
+                data,
+            };
+            body.extend_from_slice(&frame.encode().expect("frame too large"));
+        }
+        let mut resp = http::Response::new(body.into());
+        *resp.headers_mut() = self.headers;
+        resp
+    }
+
+    fn unary_response(self) -> http::Response<Bytes> {
+        let mut resp = match self.error {
+            Some(error) => error.into(),
+            None => http::Response::new(self.messages.into_iter().next().unwrap_or_default()),
+        };
+        resp.headers_mut().extend(self.headers);
+        for (name, values) in self.trailers {
+            let Ok(name) = HeaderName::try_from(format!("trailer-{name}")) else {
+                continue;
+            };
+            for value in values {
+                if let Ok(value) = HeaderValue::try_from(value) {
+                    resp.headers_mut().append(&name, value);
+                }
+            }
+        }
+        resp
+    }
+}
+
+#[derive(Default)]
+struct MockState {
+    responses: HashMap<String, MockResponse>,
+    requests: Vec<RecordedRequest>,
+}
+
+/// A Connect server for tests that serves canned [`MockResponse`]s.
+///
+/// The server stops when dropped.
+pub struct MockConnectServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+    task: JoinHandle<()>,
+}
+
+impl MockConnectServer {
+    /// Binds an ephemeral localhost port and starts serving.
+    ///
+    /// Must be called within a Tokio runtime.
+    pub async fn start() -> std::io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+        let addr = listener.local_addr()?;
+        let state = Arc::<Mutex<MockState>>::default();
+        let task = tokio::spawn(serve(listener, state.clone()));
+        Ok(Self { addr, state, task })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Returns the server's base URL, e.g. `http://127.0.0.1:12345`.
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Registers the response for a procedure path (e.g.
+    /// `/acme.foo.v1.FooService/Get`), replacing any existing one.
+    ///
+    /// Requests to procedures without a registered response fail with
+    /// `unimplemented`.
+    pub fn mock(&self, procedure: impl Into<String>, response: MockResponse) {
+        self.state
+            .lock()
+            .unwrap()
+            .responses
+            .insert(procedure.into(), response);
+    }
+
+    /// Returns the requests received so far, in order.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+}
+
+impl Drop for MockConnectServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl std::fmt::Debug for MockConnectServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MockConnectServer")
+            .field("addr", &self.addr)
+            .finish_non_exhaustive()
+    }
+}
+
+async fn serve(listener: TcpListener, state: Arc<Mutex<MockState>>) {
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(err) => {
+                tracing::debug!(?err, "Mock server accept failed");
+                continue;
+            }
+        };
+        let state = state.clone();
+        tokio::spawn(async move {
+            let service = service_fn(move |req| handle(state.clone(), req));
+            if let Err(err) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                tracing::debug!(?err, "Mock server connection failed");
+            }
+        });
+    }
+}
+
+async fn handle(
+    state: Arc<Mutex<MockState>>,
+    req: http::Request<Incoming>,
+) -> Result<http::Response<Full<Bytes>>, Infallible> {
+    let (parts, body) = req.into_parts();
+    let body = match body.collect().await {
+        Ok(collected) => collected.to_bytes(),
+        Err(err) => {
+            tracing::debug!(?err, "Mock server failed to read request body");
+            Bytes::new()
+        }
+    };
+    let req = RecordedRequest {
+        method: parts.method,
+        uri: parts.uri,
+        headers: parts.headers,
+        body,
+    };
+    let mock = {
+        let mut state = state.lock().unwrap();
+        state.requests.push(req.clone());
+        state.responses.get(req.uri.path()).cloned()
+    };
+    let mock = mock.unwrap_or_else(|| {
+        MockResponse::error(ConnectError::new(
+            ConnectCode::Unimplemented,
+            format!("no mock for {}", req.uri.path()),
+        ))
+    });
+    Ok(mock.into_response(&req).map(Full::new))
+}
@@ -0,0 +1,107 @@
+//! Conversions to and from [`tonic`](::tonic) types.
+//!
+//! Connect and gRPC share status codes, so errors convert losslessly apart
+//! from details: tonic carries details as an encoded `google.rpc.Status`,
+//! which isn't decoded here.
+
+use ::tonic::{metadata::MetadataMap, Code, Status};
+use http::{header, HeaderMap, HeaderName};
+
+use crate::{
+    common::{CONNECT_ACCEPT_ENCODING, CONNECT_CONTENT_ENCODING, CONNECT_PROTOCOL_VERSION},
+    response::error::{ConnectCode, ConnectError},
+};
+
+const TRAILER_PREFIX: &str = "trailer-";
+
+/// Returns true for headers that belong to the Connect protocol rather than
+/// the application.
+fn is_connect_reserved(name: &HeaderName) -> bool {
+    name == header::CONTENT_TYPE
+        || name == header::CONTENT_LENGTH
+        || name == header::CONTENT_ENCODING
+        || name == header::ACCEPT_ENCODING
+        || name == CONNECT_PROTOCOL_VERSION
+        || name == CONNECT_CONTENT_ENCODING
+        || name == CONNECT_ACCEPT_ENCODING
+}
+
+/// Headers that belong to the gRPC protocol rather than the application.
+const GRPC_RESERVED: &[&str] = &[
+    "content-type",
+    "te",
+    "grpc-status",
+    "grpc-message",
+    "grpc-status-details-bin",
+    "grpc-encoding",
+    "grpc-accept-encoding",
+];
+
+/// Converts Connect metadata to a tonic [`MetadataMap`].
+///
+/// Connect protocol headers are dropped and unary trailers (`trailer-`
+/// prefixed headers) are unprefixed.
+pub fn to_tonic_metadata(headers: &HeaderMap) -> MetadataMap {
+    let mut metadata = HeaderMap::new();
+    for (key, val) in headers {
+        if is_connect_reserved(key) {
+            continue;
+        }
+        let key = match key.as_str().strip_prefix(TRAILER_PREFIX) {
+            Some(name) => match HeaderName::try_from(name) {
+                Ok(name) => name,
+                Err(_) => continue,
+            },
+            None => key.clone(),
+        };
+        metadata.append(key, val.clone());
+    }
+    MetadataMap::from_headers(metadata)
+}
+
+/// Converts a tonic [`MetadataMap`] to Connect metadata, dropping gRPC
+/// protocol headers.
+pub fn from_tonic_metadata(metadata: MetadataMap) -> HeaderMap {
+    let mut headers = metadata.into_headers();
+    for name in GRPC_RESERVED {
+        headers.remove(*name);
+    }
+    headers
+}
+
+impl From<ConnectCode> for Code {
+    fn from(code: ConnectCode) -> Self {
+        Code::from(code.as_grpc_code())
+    }
+}
+
+impl From<Code> for ConnectCode {
+    fn from(code: Code) -> Self {
+        ConnectCode::from_i32(code as i32).unwrap_or(ConnectCode::Unknown)
+    }
+}
+
+impl From<ConnectError> for Status {
+    fn from(err: ConnectError) -> Self {
+        let metadata = to_tonic_metadata(err.headers());
+        let mut status = Status::with_metadata(err.code().into(), err.message.clone(), metadata);
+        if let Some(source) = err.shared_source() {
+            status.set_source(source);
+        }
+        status
+    }
+}
+
+impl From<Status> for ConnectError {
+    fn from(status: Status) -> Self {
+        let code = match status.code() {
+            // A status of `Ok` isn't an error; treat it as unknown, as
+            // Connect clients do for an error without a code.
+            Code::Ok => ConnectCode::Unknown,
+            code => code.into(),
+        };
+        let mut err = ConnectError::new(code, status.message());
+        *err.headers_mut() = from_tonic_metadata(status.metadata().clone());
+        err
+    }
+}
@@ -0,0 +1,239 @@
+//! JSON ↔ protobuf transcoding using [`prost_reflect`] descriptors.
+//!
+//! A [`Transcoder`] sits between JSON clients and a proto-only backend:
+//! [`Transcoder::request`] rewrites `application/json` (and
+//! `application/connect+json`) requests to proto, and
+//! [`Transcoder::response`] rewrites the backend's proto responses back to
+//! JSON. Requests that are already proto pass through unchanged.
+//!
+//! Transcoded messages are sent uncompressed; compressed input (including
+//! compressed backend responses) is decompressed first. Error responses and
+//! end-stream frames are JSON in both codecs and aren't modified.
+
+use bytes::{Bytes, BytesMut};
+use http::{header, HeaderMap, HeaderValue, StatusCode};
+use prost::Message;
+use prost_reflect::{DescriptorPool, DynamicMessage, MessageDescriptor, MethodDescriptor};
+
+use crate::{
+    common::CONNECT_CONTENT_ENCODING,
+    compression,
+    response::error::{ConnectCode, ConnectError},
+    stream::ConnectFrame,
+    Error,
+};
+
+/// The default maximum (decompressed) size of a transcoded message.
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 4 * 1024 * 1024;
+
+const JSON: &str = "json";
+const PROTO: &str = "proto";
+
+/// Transcodes Connect requests and responses between the `json` and `proto`
+/// codecs.
+#[derive(Clone, Debug)]
+pub struct Transcoder {
+    pool: DescriptorPool,
+    max_message_size: usize,
+}
+
+impl Transcoder {
+    /// Returns a transcoder for the services in the given descriptor pool.
+    pub fn new(pool: DescriptorPool) -> Self {
+        Self {
+            pool,
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+        }
+    }
+
+    /// Sets the maximum (decompressed) size of a transcoded message.
+    ///
+    /// Defaults to [`DEFAULT_MAX_MESSAGE_SIZE`].
+    pub fn max_message_size(mut self, max_message_size: usize) -> Self {
+        self.max_message_size = max_message_size;
+        self
+    }
+
+    /// Rewrites a JSON request to proto, for forwarding to a proto-only
+    /// backend.
+    ///
+    /// The method is looked up by the request path (e.g.
+    /// `/acme.foo.v1.FooService/Get`); unknown methods fail with
+    /// `unimplemented` and invalid JSON with `invalid_argument`.
+    pub fn request(&self, req: http::Request<Bytes>) -> Result<http::Request<Bytes>, Error> {
+        let Some(codec) = Codec::from_headers(req.headers()) else {
+            return Ok(req);
+        };
+        if codec.name != JSON {
+            return Ok(req);
+        }
+        let input = self.method(req.uri().path())?.input();
+        let (mut parts, body) = req.into_parts();
+        let body = self.transcode_body(&mut parts.headers, codec, body, |data| {
+            json_to_proto(&input, data)
+        })?;
+        Ok(http::Request::from_parts(parts, body))
+    }
+
+    /// Rewrites a proto response to JSON, for a request that was rewritten
+    /// with [`Self::request`]. `path` is the original request path.
+    pub fn response(
+        &self,
+        path: &str,
+        resp: http::Response<Bytes>,
+    ) -> Result<http::Response<Bytes>, Error> {
+        let Some(codec) = Codec::from_headers(resp.headers()) else {
+            return Ok(resp);
+        };
+        if codec.name != PROTO || (!codec.streaming && resp.status() != StatusCode::OK) {
+            return Ok(resp);
+        }
+        let output = self.method(path)?.output();
+        let (mut parts, body) = resp.into_parts();
+        let body = self.transcode_body(&mut parts.headers, codec, body, |data| {
+            proto_to_json(&output, data)
+        })?;
+        Ok(http::Response::from_parts(parts, body))
+    }
+
+    /// Transcodes a single `proto` request message for the method at `path`
+    /// to JSON, for a JSON client that holds binary messages (e.g. a test
+    /// harness).
+    pub fn request_message_to_json(&self, path: &str, message: &[u8]) -> Result<Bytes, Error> {
+        proto_to_json(&self.method(path)?.input(), message)
+    }
+
+    /// Transcodes a single JSON response message for the method at `path` to
+    /// `proto`; the inverse of [`Self::request_message_to_json`] for
+    /// responses.
+    pub fn response_message_to_proto(&self, path: &str, json: &[u8]) -> Result<Bytes, Error> {
+        json_to_proto(&self.method(path)?.output(), json)
+    }
+
+    fn method(&self, path: &str) -> Result<MethodDescriptor, Error> {
+        let unimplemented = || {
+            Error::ConnectError(ConnectError::new(
+                ConnectCode::Unimplemented,
+                format!("unknown method {path:?}"),
+            ))
+        };
+        let (service, method) = path
+            .strip_prefix('/')
+            .and_then(|path| path.split_once('/'))
+            .ok_or_else(unimplemented)?;
+        self.pool
+            .get_service_by_name(service)
+            .and_then(|service| service.methods().find(|m| m.name() == method))
+            .ok_or_else(unimplemented)
+    }
+
+    /// Transcodes a unary body or each message frame of a streaming body,
+    /// updating the content headers to match.
+    fn transcode_body(
+        &self,
+        headers: &mut HeaderMap,
+        codec: Codec,
+        body: Bytes,
+        transcode: impl Fn(&[u8]) -> Result<Bytes, Error>,
+    ) -> Result<Bytes, Error> {
+        let to = if codec.name == JSON { PROTO } else { JSON };
+        let body = if codec.streaming {
+            let encoding = headers.remove(CONNECT_CONTENT_ENCODING);
+            let mut transcoded = BytesMut::new();
+            for mut frame in ConnectFrame::parse_all(body)? {
+                if !frame.end {
+                    let data = if frame.compressed {
+                        self.decompress(encoding.as_ref(), &frame.data)?
+                    } else {
+                        frame.data
+                    };
+                    frame = ConnectFrame {
+                        compressed: false,
+                        end: false,
+                        data: transcode(&data)?,
+                    };
+                }
+                transcoded.extend_from_slice(&frame.encode()?);
+            }
+            headers.insert(
+                header::CONTENT_TYPE,
+                format!("application/connect+{to}").try_into()?,
+            );
+            transcoded.freeze()
+        } else {
+            let body = match headers.remove(header::CONTENT_ENCODING) {
+                Some(encoding) => self.decompress(Some(&encoding), &body)?,
+                None => body,
+            };
+            headers.insert(
+                header::CONTENT_TYPE,
+                format!("application/{to}").try_into()?,
+            );
+            transcode(&body)?
+        };
+        headers.remove(header::CONTENT_LENGTH);
+        Ok(body)
+    }
+
+    fn decompress(&self, encoding: Option<&HeaderValue>, data: &[u8]) -> Result<Bytes, Error> {
+        let name = encoding
+            .and_then(|encoding| encoding.to_str().ok())
+            .unwrap_or("identity");
+        let compression =
+            compression::lookup(name).ok_or_else(|| Error::UnacceptableEncoding(name.into()))?;
+        compression.decompress(data, self.max_message_size)
+    }
+}
+
+/// The message codec of a request or response.
+#[derive(Clone, Copy)]
+struct Codec {
+    name: &'static str,
+    streaming: bool,
+}
+
+impl Codec {
+    fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let content_type = headers.get(header::CONTENT_TYPE)?.to_str().ok()?;
+        let media_type = content_type
+            .split_once(';')
+            .map_or(content_type, |(media_type, _)| media_type)
+            .trim();
+        let subtype = media_type.strip_prefix("application/")?;
+        let (streaming, name) = match subtype.strip_prefix("connect+") {
+            Some(name) => (true, name),
+            None => (false, subtype),
+        };
+        let name = [JSON, PROTO].into_iter().find(|codec| *codec == name)?;
+        Some(Self { name, streaming })
+    }
+}
+
+fn json_to_proto(desc: &MessageDescriptor, json: &[u8]) -> Result<Bytes, Error> {
+    let mut deserializer = serde_json::Deserializer::from_slice(json);
+    let message = DynamicMessage::deserialize(desc.clone(), &mut deserializer)
+        .and_then(|message| deserializer.end().map(|()| message))
+        .map_err(|err| {
+            Error::ConnectError(ConnectError::new(
+                ConnectCode::InvalidArgument,
+                format!("invalid {} JSON: {err}", desc.full_name()),
+            ))
+        })?;
+    Ok(message.encode_to_vec().into())
+}
+
+fn proto_to_json(desc: &MessageDescriptor, data: &[u8]) -> Result<Bytes, Error> {
+    let message = DynamicMessage::decode(desc.clone(), data).map_err(|err| {
+        Error::ConnectError(ConnectError::new(
+            ConnectCode::Internal,
+            format!("invalid {} message: {err}", desc.full_name()),
+        ))
+    })?;
+    let json = serde_json::to_vec(&message).map_err(|err| {
+        Error::ConnectError(ConnectError::new(
+            ConnectCode::Internal,
+            format!("failed to encode {} JSON: {err}", desc.full_name()),
+        ))
+    })?;
+    Ok(json.into())
+}
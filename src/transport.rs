@@ -0,0 +1,270 @@
+//! HTTP transports.
+//!
+//! A [`Transport`] sends finalized HTTP requests for a
+//! [`ConnectClient`](crate::client::ConnectClient). Implementations are
+//! provided for:
+//!
+//! - [`reqwest::Client`](::reqwest::Client), with the `reqwest` feature
+//! - [`hyper_util`]'s legacy client and
+//!   [`UnixSocketTransport`](crate::hyper::UnixSocketTransport), with the
+//!   `hyper` feature
+//! - [`WasiClient`](crate::wasi::WasiClient), with the `wasi` feature
+//!   (request bodies are buffered before sending)
+//! - [`MemoryTransport`], which routes requests to in-process handlers, for
+//!   tests
+//!
+//! In the browser, [`WebClient`](crate::web::WebClient) (with the `web`
+//! feature) executes requests directly, as `fetch` futures aren't `Send`.
+
+use std::{collections::HashMap, future::Future, sync::Arc};
+
+use bytes::Bytes;
+use futures_util::{future::BoxFuture, FutureExt};
+use http_body_util::{combinators::BoxBody, BodyExt, Full};
+
+use crate::{
+    response::error::{ConnectCode, ConnectError},
+    Error,
+};
+
+/// A request body sent by a [`Transport`].
+///
+/// Bodies of client- and bidi-streaming calls are produced while the call is
+/// in progress.
+pub type RequestBody = BoxBody<Bytes, Error>;
+
+/// A response body returned by a [`Transport`].
+pub type ResponseBody = BoxBody<Bytes, Error>;
+
+/// Returns a [`RequestBody`] with the given (buffered) contents.
+pub fn full_body(body: impl Into<Bytes>) -> RequestBody {
+    Full::new(body.into())
+        .map_err(|never| match never {})
+        .boxed()
+}
+
+/// Sends HTTP requests.
+pub trait Transport: Send + Sync {
+    /// Sends a request, returning once response headers are received.
+    ///
+    /// The request body may still be streaming when response headers
+    /// arrive, so transports should keep sending it while the response is
+    /// read.
+    ///
+    /// Transports should enforce the request timeout given by the
+    /// `connect-timeout-ms` header (if any) on the entire exchange, including
+    /// reading the response body, returning a `deadline_exceeded` error.
+    fn round_trip(
+        &self,
+        req: http::Request<RequestBody>,
+    ) -> BoxFuture<'_, Result<http::Response<ResponseBody>, Error>>;
+}
+
+type MemoryHandler = dyn Fn(
+        http::Request<RequestBody>,
+    ) -> BoxFuture<'static, Result<http::Response<ResponseBody>, Error>>
+    + Send
+    + Sync;
+
+/// A [`Transport`] that passes requests directly to in-process handlers, so
+/// tests can exercise full client flows (including streaming) without
+/// opening sockets.
+///
+/// Handlers receive finalized requests (after interceptors and signing) and
+/// are picked by request path, e.g. `/example.v1.Service/Method`; requests
+/// with no matching route go to the [fallback](Self::new), or fail with an
+/// `unimplemented` error response. Streaming request and response bodies
+/// contain enveloped frames (see [`ConnectFrame`](crate::stream::ConnectFrame)).
+///
+/// ```no_run
+/// # use connect_rpc::{stream::{ConnectFrame, FramedBody}, transport::MemoryTransport};
+/// # use http_body_util::BodyExt;
+/// // Echoes each request message back as it arrives.
+/// let transport = MemoryTransport::default().streaming_route(
+///     "/example.v1.EchoService/Echo",
+///     |req| async move {
+///         let frames = ConnectFrame::body_stream(req.into_body());
+///         let body = FramedBody::new(ConnectFrame::encode_stream(frames));
+///         Ok(http::Response::new(BodyExt::boxed(body)))
+///     },
+/// );
+/// ```
+#[derive(Clone, Default)]
+pub struct MemoryTransport {
+    routes: HashMap<String, Arc<MemoryHandler>>,
+    fallback: Option<Arc<MemoryHandler>>,
+}
+
+impl MemoryTransport {
+    /// Returns a transport that sends all requests to `handler`, with
+    /// buffered request and response bodies.
+    pub fn new<F, Fut>(handler: F) -> Self
+    where
+        F: Fn(http::Request<Bytes>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<http::Response<Bytes>, Error>> + Send + 'static,
+    {
+        Self {
+            routes: HashMap::new(),
+            fallback: Some(buffered_handler(handler)),
+        }
+    }
+
+    /// Routes requests for `path` to `handler`, with buffered request and
+    /// response bodies.
+    pub fn route<F, Fut>(mut self, path: impl Into<String>, handler: F) -> Self
+    where
+        F: Fn(http::Request<Bytes>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<http::Response<Bytes>, Error>> + Send + 'static,
+    {
+        self.routes.insert(path.into(), buffered_handler(handler));
+        self
+    }
+
+    /// Routes requests for `path` to `handler`, which receives the request
+    /// body as it is sent and may respond before it ends (e.g. for
+    /// client-streaming and bidi calls).
+    pub fn streaming_route<F, Fut>(mut self, path: impl Into<String>, handler: F) -> Self
+    where
+        F: Fn(http::Request<RequestBody>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<http::Response<ResponseBody>, Error>> + Send + 'static,
+    {
+        self.routes
+            .insert(path.into(), Arc::new(move |req| handler(req).boxed()));
+        self
+    }
+}
+
+fn buffered_handler<F, Fut>(handler: F) -> Arc<MemoryHandler>
+where
+    F: Fn(http::Request<Bytes>) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<http::Response<Bytes>, Error>> + Send + 'static,
+{
+    let handler = Arc::new(handler);
+    Arc::new(move |req| {
+        let handler = handler.clone();
+        Box::pin(async move {
+            let resp = handler(buffer_request(req).await?).await?;
+            Ok(resp.map(full_body))
+        })
+    })
+}
+
+impl Transport for MemoryTransport {
+    fn round_trip(
+        &self,
+        req: http::Request<RequestBody>,
+    ) -> BoxFuture<'_, Result<http::Response<ResponseBody>, Error>> {
+        let handler = self.routes.get(req.uri().path()).or(self.fallback.as_ref());
+        match handler {
+            Some(handler) => handler(req),
+            None => {
+                let err = ConnectError::new(
+                    ConnectCode::Unimplemented,
+                    format!("no route for {}", req.uri().path()),
+                );
+                let resp = http::Response::<Bytes>::from(err).map(full_body);
+                Box::pin(std::future::ready(Ok(resp)))
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for MemoryTransport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MemoryTransport")
+            .field("routes", &self.routes.keys().collect::<Vec<_>>())
+            .field("fallback", &self.fallback.is_some())
+            .finish()
+    }
+}
+
+/// Reads a request body to the end.
+pub(crate) async fn buffer_request(
+    req: http::Request<RequestBody>,
+) -> Result<http::Request<Bytes>, Error> {
+    let (parts, body) = req.into_parts();
+    let body = body.collect().await?.to_bytes();
+    Ok(http::Request::from_parts(parts, body))
+}
+
+/// Reads a response body to the end.
+pub(crate) async fn buffer_response(
+    resp: http::Response<ResponseBody>,
+) -> Result<http::Response<Bytes>, Error> {
+    let (parts, body) = resp.into_parts();
+    let body = body.collect().await?.to_bytes();
+    Ok(http::Response::from_parts(parts, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::StreamExt;
+
+    use crate::stream::{sender, ConnectFrame, FramedBody};
+
+    use super::*;
+
+    fn request(path: &str, body: RequestBody) -> http::Request<RequestBody> {
+        let mut req = http::Request::new(body);
+        *req.uri_mut() = format!("http://example.com{path}").parse().unwrap();
+        req
+    }
+
+    fn frame(data: &'static [u8]) -> ConnectFrame {
+        ConnectFrame {
+            compressed: false,
+            end: false,
+            data: Bytes::from_static(data),
+        }
+    }
+
+    #[tokio::test]
+    async fn routes_by_path() {
+        let transport = MemoryTransport::default()
+            .route("/a.Service/A", |_| async {
+                Ok(http::Response::new("a".into()))
+            })
+            .route("/a.Service/B", |_| async {
+                Ok(http::Response::new("b".into()))
+            });
+        for (path, expected) in [("/a.Service/A", "a"), ("/a.Service/B", "b")] {
+            let resp = transport
+                .round_trip(request(path, full_body(Bytes::new())))
+                .await
+                .unwrap();
+            assert_eq!(buffer_response(resp).await.unwrap().body(), expected);
+        }
+
+        let resp = transport
+            .round_trip(request("/a.Service/C", full_body(Bytes::new())))
+            .await
+            .unwrap();
+        let err = ConnectError::from(buffer_response(resp).await.unwrap());
+        assert_eq!(err.code(), ConnectCode::Unimplemented);
+    }
+
+    #[tokio::test]
+    async fn streams_bodies() {
+        let transport =
+            MemoryTransport::default().streaming_route("/a.Service/Echo", |req| async {
+                let frames = ConnectFrame::body_stream(req.into_body());
+                let body = FramedBody::new(ConnectFrame::encode_stream(frames));
+                Ok(http::Response::new(BodyExt::boxed(body)))
+            });
+        let (sender, body) = sender::channel(1);
+        let resp = transport
+            .round_trip(request("/a.Service/Echo", BodyExt::boxed(body)))
+            .await
+            .unwrap();
+        let mut frames = ConnectFrame::body_stream(resp.into_body());
+
+        // Each message is echoed before the request body ends.
+        for data in [&b"one"[..], b"two"] {
+            sender.send(frame(data)).await.unwrap();
+            let echoed = frames.next().await.unwrap().unwrap();
+            assert_eq!(echoed.data, data);
+        }
+        sender.close_send();
+        assert!(frames.next().await.is_none());
+    }
+}
@@ -0,0 +1,192 @@
+//! A client backend using `wasi:http/outgoing-handler`, for use inside WASI
+//! components (e.g. Spin, wasmCloud, `wasmtime serve`).
+//!
+//! Typically used with `default-features = false` as `reqwest` is not
+//! available on WASI targets.
+
+use bytes::{Bytes, BytesMut};
+use futures_util::future::BoxFuture;
+use http::{HeaderName, HeaderValue, Method, StatusCode};
+use http_body_util::{BodyExt, Full};
+use wasi::{
+    http::{
+        outgoing_handler,
+        types::{
+            ErrorCode, Fields, IncomingBody, Method as WasiMethod, OutgoingBody, OutgoingRequest,
+            RequestOptions, Scheme as WasiScheme,
+        },
+    },
+    io::streams::StreamError,
+};
+
+use crate::{
+    common::request_timeout,
+    request::{UnaryGetRequest, UnaryRequest},
+    response::{
+        error::{ConnectCode, ConnectError},
+        UnaryResponse, ValidateOpts,
+    },
+    transport::{buffer_request, RequestBody, ResponseBody, Transport},
+    Error,
+};
+
+const WRITE_CHUNK_SIZE: usize = 4096;
+const READ_CHUNK_SIZE: u64 = 64 * 1024;
+
+/// A Connect client backed by `wasi:http`.
+///
+/// Calls block the component until the response is fully received. Also a
+/// [`Transport`], for use with a [`ConnectClient`](crate::client::ConnectClient).
+#[derive(Clone, Debug, Default)]
+pub struct WasiClient {}
+
+impl WasiClient {
+    /// Executes a Connect RPC [`UnaryRequest`].
+    pub async fn execute_unary(
+        &self,
+        req: UnaryRequest<impl AsRef<[u8]>>,
+    ) -> Result<UnaryResponse<Bytes>, Error> {
+        let validate_opts = ValidateOpts::from_request(&req);
+        let connect_resp: UnaryResponse<_> = send(http::Request::from(req))?.into();
+        connect_resp.result(&validate_opts)
+    }
+
+    /// Executes a Connect RPC [`UnaryGetRequest`].
+    pub async fn execute_unary_get(
+        &self,
+        req: UnaryGetRequest,
+    ) -> Result<UnaryResponse<Bytes>, Error> {
+        let validate_opts = ValidateOpts::from_request(&req);
+        let connect_resp: UnaryResponse<_> =
+            send(http::Request::from(req).map(|()| Bytes::new()))?.into();
+        connect_resp.result(&validate_opts)
+    }
+}
+
+impl Transport for WasiClient {
+    fn round_trip(
+        &self,
+        req: http::Request<RequestBody>,
+    ) -> BoxFuture<'_, Result<http::Response<ResponseBody>, Error>> {
+        Box::pin(async move {
+            let req = buffer_request(req).await?;
+            send(req).map(|resp| resp.map(|body| Full::new(body).map_err(Error::from).boxed()))
+        })
+    }
+}
+
+fn send(req: http::Request<impl AsRef<[u8]>>) -> Result<http::Response<Bytes>, Error> {
+    let timeout = request_timeout(req.headers());
+    let (parts, body) = req.into_parts();
+
+    let entries: Vec<(String, Vec<u8>)> = parts
+        .headers
+        .iter()
+        .map(|(key, val)| (key.as_str().to_string(), val.as_bytes().to_vec()))
+        .collect();
+    let headers = Fields::from_list(&entries).map_err(|err| wasi_error(format!("{err:?}")))?;
+    let outgoing = OutgoingRequest::new(headers);
+    let method = match parts.method {
+        Method::GET => WasiMethod::Get,
+        Method::POST => WasiMethod::Post,
+        other => WasiMethod::Other(other.to_string()),
+    };
+    let scheme = match parts.uri.scheme_str() {
+        None | Some("https") => WasiScheme::Https,
+        Some("http") => WasiScheme::Http,
+        Some(other) => WasiScheme::Other(other.to_string()),
+    };
+    outgoing
+        .set_method(&method)
+        .and_then(|()| outgoing.set_scheme(Some(&scheme)))
+        .and_then(|()| outgoing.set_authority(parts.uri.authority().map(|a| a.as_str())))
+        .and_then(|()| {
+            outgoing.set_path_with_query(parts.uri.path_and_query().map(|paq| paq.as_str()))
+        })
+        .map_err(|()| Error::invalid_request("request rejected by host"))?;
+
+    let options = timeout.map(|timeout| {
+        let options = RequestOptions::new();
+        // Not all hosts support timeouts; ignore errors.
+        let _ = options.set_first_byte_timeout(Some(timeout.as_nanos() as u64));
+        options
+    });
+
+    let outgoing_body = outgoing
+        .body()
+        .map_err(|()| wasi_error("request body already taken"))?;
+    let future_resp = outgoing_handler::handle(outgoing, options).map_err(error_code)?;
+    {
+        let stream = outgoing_body
+            .write()
+            .map_err(|()| wasi_error("request body stream already taken"))?;
+        for chunk in body.as_ref().chunks(WRITE_CHUNK_SIZE) {
+            stream
+                .blocking_write_and_flush(chunk)
+                .map_err(stream_error)?;
+        }
+    }
+    OutgoingBody::finish(outgoing_body, None).map_err(error_code)?;
+
+    let incoming = loop {
+        match future_resp.get() {
+            Some(result) => {
+                break result
+                    .map_err(|()| wasi_error("response already taken"))?
+                    .map_err(error_code)?
+            }
+            None => future_resp.subscribe().block(),
+        }
+    };
+
+    let mut resp = http::Response::new(Bytes::new());
+    *resp.status_mut() = StatusCode::from_u16(incoming.status())
+        .map_err(|_| Error::InvalidResponse("invalid status code".into()))?;
+    for (key, val) in incoming.headers().entries() {
+        resp.headers_mut()
+            .append(HeaderName::try_from(key)?, HeaderValue::try_from(val)?);
+    }
+
+    let incoming_body = incoming
+        .consume()
+        .map_err(|()| wasi_error("response body already taken"))?;
+    let mut buf = BytesMut::new();
+    {
+        let stream = incoming_body
+            .stream()
+            .map_err(|()| wasi_error("response body stream already taken"))?;
+        loop {
+            match stream.blocking_read(READ_CHUNK_SIZE) {
+                Ok(chunk) => buf.extend_from_slice(&chunk),
+                Err(StreamError::Closed) => break,
+                Err(err) => return Err(stream_error(err)),
+            }
+        }
+    }
+    drop(IncomingBody::finish(incoming_body));
+    *resp.body_mut() = buf.freeze();
+    Ok(resp)
+}
+
+fn wasi_error(msg: impl Into<String>) -> Error {
+    Error::WasiHttpError(msg.into())
+}
+
+fn stream_error(err: StreamError) -> Error {
+    match err {
+        StreamError::LastOperationFailed(err) => wasi_error(err.to_debug_string()),
+        StreamError::Closed => wasi_error("stream closed"),
+    }
+}
+
+fn error_code(code: ErrorCode) -> Error {
+    match code {
+        ErrorCode::ConnectionTimeout
+        | ErrorCode::ConnectionReadTimeout
+        | ErrorCode::HttpResponseTimeout => Error::ConnectError(ConnectError::new(
+            ConnectCode::DeadlineExceeded,
+            "request timed out",
+        )),
+        other => wasi_error(format!("{other:?}")),
+    }
+}
@@ -0,0 +1,277 @@
+//! A client backend using the browser Fetch API, for use in
+//! `wasm32-unknown-unknown` apps (and web workers).
+//!
+//! Typically used with `default-features = false`. Calls are not `Send`, and
+//! must be run on a single-threaded executor such as `wasm-bindgen-futures`.
+
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    sync::Arc,
+    task::{ready, Context, Poll},
+};
+
+use bytes::Bytes;
+use futures_util::{stream::LocalBoxStream, Stream, StreamExt, TryStreamExt};
+use http::{HeaderMap, HeaderName, HeaderValue, StatusCode};
+use js_sys::{Array, Uint8Array};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{AbortController, Headers, RequestInit, Response, Window, WorkerGlobalScope};
+
+use crate::{
+    compression::{self, Compression},
+    request::{StreamingRequest, UnaryGetRequest, UnaryRequest},
+    response::{ConnectResponse, StreamingResponse, UnaryResponse, ValidateOpts},
+    stream::{ConnectFrame, EndStreamResponse, ResponseFrame},
+    Error,
+};
+
+const DEFAULT_MAX_MESSAGE_SIZE: usize = 4 * 1024 * 1024;
+
+/// A Connect client backed by `fetch`.
+///
+/// Request timeouts are sent to the server (`connect-timeout-ms`) but not
+/// enforced locally.
+#[derive(Clone, Debug, Default)]
+pub struct WebClient {}
+
+impl WebClient {
+    /// Executes a Connect RPC [`UnaryRequest`].
+    pub async fn execute_unary(
+        &self,
+        req: UnaryRequest<impl AsRef<[u8]>>,
+    ) -> Result<UnaryResponse<Bytes>, Error> {
+        let validate_opts = ValidateOpts::from_request(&req);
+        let resp = fetch(
+            http::Request::from(req),
+            &AbortController::new().map_err(js_error)?,
+        )
+        .await?;
+        let connect_resp: UnaryResponse<_> = read_body(resp).await?.into();
+        connect_resp.result(&validate_opts)
+    }
+
+    /// Executes a Connect RPC [`UnaryGetRequest`].
+    pub async fn execute_unary_get(
+        &self,
+        req: UnaryGetRequest,
+    ) -> Result<UnaryResponse<Bytes>, Error> {
+        let validate_opts = ValidateOpts::from_request(&req);
+        let http_req = http::Request::from(req).map(|()| Bytes::new());
+        let resp = fetch(http_req, &AbortController::new().map_err(js_error)?).await?;
+        let connect_resp: UnaryResponse<_> = read_body(resp).await?.into();
+        connect_resp.result(&validate_opts)
+    }
+
+    /// Executes a server-streaming Connect RPC.
+    ///
+    /// The request body must be a single enveloped message (see
+    /// [`ConnectFrame::encode`]). Returns once response headers are received.
+    pub async fn execute_server_stream(
+        &self,
+        req: StreamingRequest<impl AsRef<[u8]>>,
+    ) -> Result<WebServerStream, Error> {
+        let validate_opts = ValidateOpts::from_request(&req);
+        let abort = AbortController::new().map_err(js_error)?;
+        let resp = fetch(http::Request::from(req), &abort).await?;
+        if !resp.ok() {
+            return Err(Error::ConnectError(read_body(resp).await?.into()));
+        }
+        let response = StreamingResponse::from(http_response(&resp, ())?);
+        response.validate(&validate_opts)?;
+
+        let body = resp
+            .body()
+            .ok_or_else(|| Error::InvalidResponse("missing response body".into()))?;
+        let chunks = wasm_streams::ReadableStream::from_raw(body.unchecked_into())
+            .into_stream()
+            .map_ok(|chunk| Bytes::from(Uint8Array::new(&chunk).to_vec()))
+            .map_err(|err| format!("{err:?}"));
+        let compression = compression::lookup(response.content_encoding().unwrap_or("identity"));
+        Ok(WebServerStream {
+            response,
+            frames: Some(ConnectFrame::bytes_stream(chunks).boxed_local()),
+            trailers: None,
+            flatten_limit: None,
+            metadata_snapshot: None,
+            compression,
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+            abort,
+        })
+    }
+}
+
+/// A handle to an in-progress server-streaming call made by a [`WebClient`].
+///
+/// Yields each response message. Dropping the handle before the call
+/// completes aborts the underlying `fetch`.
+pub struct WebServerStream {
+    response: StreamingResponse<()>,
+    frames: Option<LocalBoxStream<'static, Result<ConnectFrame, Error>>>,
+    trailers: Option<HashMap<String, Vec<String>>>,
+    flatten_limit: Option<usize>,
+    metadata_snapshot: Option<HeaderMap>,
+    compression: Option<Arc<dyn Compression>>,
+    max_message_size: usize,
+    abort: AbortController,
+}
+
+impl WebServerStream {
+    /// Returns the response headers.
+    pub fn response(&self) -> &StreamingResponse<()> {
+        &self.response
+    }
+
+    /// Returns the trailing metadata from the end-stream frame, once the
+    /// call has completed successfully.
+    pub fn trailers(&self) -> Option<&HashMap<String, Vec<String>>> {
+        self.trailers.as_ref()
+    }
+
+    /// Enables flattening of response headers and trailers into a single
+    /// metadata snapshot; see [`ServerStreamCall::flatten_metadata`].
+    ///
+    /// [`ServerStreamCall::flatten_metadata`]: crate::client::call::ServerStreamCall::flatten_metadata
+    pub fn flatten_metadata(mut self, max_bytes: usize) -> Self {
+        self.flatten_limit = Some(max_bytes);
+        self
+    }
+
+    /// Returns the flattened metadata snapshot, if enabled with
+    /// [`Self::flatten_metadata`] and the call has completed.
+    pub fn metadata_snapshot(&self) -> Option<&HeaderMap> {
+        self.metadata_snapshot.as_ref()
+    }
+
+    /// Sets the maximum size of a decompressed response message; see
+    /// [`ServerStreamCall::max_message_size`].
+    ///
+    /// [`ServerStreamCall::max_message_size`]: crate::client::call::ServerStreamCall::max_message_size
+    pub fn max_message_size(mut self, max_message_size: usize) -> Self {
+        self.max_message_size = max_message_size;
+        self
+    }
+
+    fn end_stream(&mut self, end: EndStreamResponse) -> Result<(), Error> {
+        if let Some(max_bytes) = self.flatten_limit {
+            let snapshot = end.flatten_metadata(self.response.headers(), max_bytes)?;
+            self.metadata_snapshot = Some(snapshot);
+        }
+        self.trailers = Some(end.metadata);
+        match end.error {
+            Some(err) => Err(Error::ConnectError(err)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Stream for WebServerStream {
+    type Item = Result<Bytes, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let Some(frames) = this.frames.as_mut() else {
+            return Poll::Ready(None);
+        };
+        let next = ready!(frames.poll_next_unpin(cx));
+        let result = match ResponseFrame::from_next(
+            next,
+            this.compression.as_deref(),
+            this.max_message_size,
+        ) {
+            Ok(ResponseFrame::Message(data)) => return Poll::Ready(Some(Ok(data))),
+            Ok(ResponseFrame::End(end)) => this.end_stream(end).err(),
+            Err(err) => Some(err),
+        };
+        this.frames = None;
+        Poll::Ready(result.map(Err))
+    }
+}
+
+impl Drop for WebServerStream {
+    fn drop(&mut self) {
+        if self.frames.take().is_some() {
+            self.abort.abort();
+        }
+    }
+}
+
+impl std::fmt::Debug for WebServerStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebServerStream")
+            .field("response", &self.response)
+            .field("trailers", &self.trailers)
+            .finish()
+    }
+}
+
+async fn fetch(
+    req: http::Request<impl AsRef<[u8]>>,
+    abort: &AbortController,
+) -> Result<Response, Error> {
+    let (parts, body) = req.into_parts();
+
+    let headers = Headers::new().map_err(js_error)?;
+    for (key, val) in &parts.headers {
+        let val = val
+            .to_str()
+            .map_err(|_| Error::invalid_request("non-ASCII header value"))?;
+        headers.append(key.as_str(), val).map_err(js_error)?;
+    }
+
+    let init = RequestInit::new();
+    init.set_method(parts.method.as_str());
+    init.set_headers(&headers);
+    init.set_signal(Some(&abort.signal()));
+    let body = body.as_ref();
+    if !body.is_empty() {
+        init.set_body(&Uint8Array::from(body));
+    }
+    let req =
+        web_sys::Request::new_with_str_and_init(&parts.uri.to_string(), &init).map_err(js_error)?;
+
+    let global = js_sys::global();
+    let promise = if let Some(window) = global.dyn_ref::<Window>() {
+        window.fetch_with_request(&req)
+    } else if let Some(worker) = global.dyn_ref::<WorkerGlobalScope>() {
+        worker.fetch_with_request(&req)
+    } else {
+        return Err(js_error("fetch not available".into()));
+    };
+    JsFuture::from(promise)
+        .await
+        .map_err(js_error)?
+        .dyn_into()
+        .map_err(js_error)
+}
+
+async fn read_body(resp: Response) -> Result<http::Response<Bytes>, Error> {
+    let buf = JsFuture::from(resp.array_buffer().map_err(js_error)?)
+        .await
+        .map_err(js_error)?;
+    http_response(&resp, Uint8Array::new(&buf).to_vec().into())
+}
+
+fn http_response<T>(resp: &Response, body: T) -> Result<http::Response<T>, Error> {
+    let mut http_resp = http::Response::new(body);
+    *http_resp.status_mut() = StatusCode::from_u16(resp.status())
+        .map_err(|_| Error::InvalidResponse("invalid status code".into()))?;
+    let entries = js_sys::try_iter(&resp.headers())
+        .map_err(js_error)?
+        .ok_or_else(|| js_error("headers not iterable".into()))?;
+    for entry in entries {
+        let entry: Array = entry.map_err(js_error)?.unchecked_into();
+        let (Some(key), Some(val)) = (entry.get(0).as_string(), entry.get(1).as_string()) else {
+            continue;
+        };
+        http_resp
+            .headers_mut()
+            .append(HeaderName::try_from(key)?, HeaderValue::try_from(val)?);
+    }
+    Ok(http_resp)
+}
+
+fn js_error(val: JsValue) -> Error {
+    Error::FetchError(format!("{val:?}"))
+}
@@ -0,0 +1,177 @@
+//! Wire-level logging, for debugging interop with other Connect
+//! implementations.
+//!
+//! [`WireLogTransport`] logs (with `tracing` at trace level) the headers of
+//! each request and response, and the flags, length, and a hexdump prefix of
+//! each enveloped frame (or unary body). Sensitive metadata is redacted as by
+//! [`Redact::redacted`](crate::metadata::Redact::redacted). Enable it per
+//! client with [`ClientBuilder::wire_log`](crate::client::builder::ClientBuilder::wire_log).
+
+use std::{fmt::Write, sync::Arc};
+
+use futures_util::future::BoxFuture;
+use http::{header, HeaderMap};
+use http_body_util::BodyExt;
+
+use crate::{
+    common::STREAMING_CONTENT_TYPE_PREFIX,
+    metadata::{redact_metadata, DEFAULT_REDACTED_KEYS},
+    transport::{RequestBody, ResponseBody, Transport},
+    Error,
+};
+
+/// The number of bytes of each frame included in logs.
+const HEXDUMP_PREFIX_LEN: usize = 32;
+
+/// A [`Transport`] that logs requests and responses at the wire level.
+pub struct WireLogTransport {
+    inner: Arc<dyn Transport>,
+}
+
+impl WireLogTransport {
+    pub fn new(inner: impl Transport + 'static) -> Self {
+        Self::wrap(Arc::new(inner))
+    }
+
+    pub(crate) fn wrap(inner: Arc<dyn Transport>) -> Self {
+        Self { inner }
+    }
+}
+
+impl Transport for WireLogTransport {
+    fn round_trip(
+        &self,
+        req: http::Request<RequestBody>,
+    ) -> BoxFuture<'_, Result<http::Response<ResponseBody>, Error>> {
+        Box::pin(async move {
+            tracing::trace!(
+                method = %req.method(),
+                uri = %req.uri(),
+                headers = ?redact_metadata(req.headers(), DEFAULT_REDACTED_KEYS),
+                "> request",
+            );
+            let mut inspector = FrameInspector::new(">", req.headers());
+            let req = req.map(|body| {
+                body.map_frame(move |frame| {
+                    if let Some(data) = frame.data_ref() {
+                        inspector.inspect(data);
+                    }
+                    frame
+                })
+                .boxed()
+            });
+
+            let resp = self.inner.round_trip(req).await.inspect_err(|err| {
+                tracing::trace!(%err, "< transport error");
+            })?;
+            tracing::trace!(
+                status = %resp.status(),
+                headers = ?redact_metadata(resp.headers(), DEFAULT_REDACTED_KEYS),
+                "< response",
+            );
+            let mut inspector = FrameInspector::new("<", resp.headers());
+            Ok(resp.map(|body| {
+                body.map_frame(move |frame| {
+                    if let Some(data) = frame.data_ref() {
+                        inspector.inspect(data);
+                    } else if let Some(trailers) = frame.trailers_ref() {
+                        tracing::trace!(
+                            trailers = ?redact_metadata(trailers, DEFAULT_REDACTED_KEYS),
+                            "< trailers",
+                        );
+                    }
+                    frame
+                })
+                .boxed()
+            }))
+        })
+    }
+}
+
+impl std::fmt::Debug for WireLogTransport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WireLogTransport").finish_non_exhaustive()
+    }
+}
+
+/// Logs frames incrementally as body chunks pass through, without
+/// buffering.
+struct FrameInspector {
+    direction: &'static str,
+    streaming: bool,
+    /// A partially-received envelope prefix.
+    prefix: Vec<u8>,
+    /// The remaining (unlogged) length of the current frame's data.
+    remaining: usize,
+}
+
+impl FrameInspector {
+    fn new(direction: &'static str, headers: &HeaderMap) -> Self {
+        let streaming = headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|content_type| content_type.starts_with(STREAMING_CONTENT_TYPE_PREFIX));
+        Self {
+            direction,
+            streaming,
+            prefix: Vec::with_capacity(5),
+            remaining: 0,
+        }
+    }
+
+    fn inspect(&mut self, mut data: &[u8]) {
+        if !self.streaming {
+            if !data.is_empty() {
+                tracing::trace!(
+                    direction = self.direction,
+                    len = data.len(),
+                    data = hexdump_prefix(data),
+                    "body chunk",
+                );
+            }
+            return;
+        }
+        while !data.is_empty() {
+            if self.remaining > 0 {
+                let skip = self.remaining.min(data.len());
+                self.remaining -= skip;
+                data = &data[skip..];
+                continue;
+            }
+            let take = (5 - self.prefix.len()).min(data.len());
+            self.prefix.extend_from_slice(&data[..take]);
+            data = &data[take..];
+            if self.prefix.len() < 5 {
+                break;
+            }
+            let flags = self.prefix[0];
+            let len = u32::from_be_bytes(self.prefix[1..5].try_into().unwrap()) as usize;
+            tracing::trace!(
+                direction = self.direction,
+                flags = format!("{flags:#04x}"),
+                compressed = flags & 0b1 != 0,
+                end = flags & 0b10 != 0,
+                len,
+                data = hexdump_prefix(&data[..len.min(data.len())]),
+                "frame",
+            );
+            self.prefix.clear();
+            self.remaining = len;
+        }
+    }
+}
+
+/// Formats up to [`HEXDUMP_PREFIX_LEN`] bytes as space-separated hex.
+fn hexdump_prefix(data: &[u8]) -> String {
+    let mut hex = String::with_capacity(HEXDUMP_PREFIX_LEN * 3 + 3);
+    for (i, byte) in data.iter().take(HEXDUMP_PREFIX_LEN).enumerate() {
+        if i > 0 {
+            hex.push(' ');
+        }
+        write!(hex, "{byte:02x}").unwrap();
+    }
+    if data.len() > HEXDUMP_PREFIX_LEN {
+        hex.push_str(" ..");
+    }
+    hex
+}
@@ -0,0 +1,50 @@
+use connect_rpc::{
+    base64::{self, Base64Config, Base64Variant},
+    metadata::Metadata,
+    request::builder::RequestBuilder,
+};
+use http::HeaderMap;
+
+#[test]
+fn encodes_with_configured_variants() {
+    let data = [0xfb, 0xff];
+    assert_eq!(Base64Variant::Standard.encode(data), "+/8=");
+    assert_eq!(Base64Variant::StandardNoPad.encode(data), "+/8");
+    assert_eq!(Base64Variant::UrlSafe.encode(data), "-_8=");
+    assert_eq!(Base64Variant::UrlSafeNoPad.encode(data), "-_8");
+    assert_eq!(Base64Variant::UrlSafeNoPad.decode("-_8=").unwrap(), data);
+    assert_eq!(Base64Variant::Standard.decode("+/8").unwrap(), data);
+
+    // The configuration is global, so it's only changed in this test.
+    let get_message = || {
+        let req = RequestBuilder::default()
+            .uri("https://example.com/example.v1.Service/Get")
+            .unwrap()
+            .message_codec("proto")
+            .unwrap()
+            .unary_get(data)
+            .unwrap();
+        assert_eq!(req.message().unwrap().as_ref(), data);
+        let query = http::Request::from(req).uri().query().unwrap().to_string();
+        query
+            .split('&')
+            .find_map(|param| param.strip_prefix("message="))
+            .unwrap()
+            .to_string()
+    };
+    let mut headers = HeaderMap::new();
+    headers.append_binary("key-bin", data).unwrap();
+    assert_eq!(headers["key-bin"], "+/8");
+    assert_eq!(get_message(), "-_8");
+
+    base64::set_config(Base64Config {
+        metadata: Base64Variant::Standard,
+        get_message: Base64Variant::UrlSafe,
+    });
+    let mut headers = HeaderMap::new();
+    headers.append_binary("key-bin", data).unwrap();
+    assert_eq!(headers["key-bin"], "+/8=");
+    assert_eq!(headers.get_binary("key-bin").unwrap(), data);
+    assert_eq!(get_message(), "-_8%3D");
+    base64::set_config(Base64Config::default());
+}
@@ -0,0 +1,341 @@
+use bytes::Bytes;
+use connect_rpc::{
+    client::{builder::ClientBuilder, ConnectClient},
+    request::builder::RequestBuilder,
+    response::error::ConnectCode,
+};
+
+fn client(builder: ClientBuilder) -> ConnectClient {
+    builder.build().unwrap()
+}
+
+fn builder(base_url: &str, method: &str) -> RequestBuilder {
+    RequestBuilder::default()
+        .uri(format!("{base_url}/example.v1.Service/{method}"))
+        .unwrap()
+        .message_codec("proto")
+        .unwrap()
+}
+
+#[cfg(feature = "reqwest")]
+#[tokio::test]
+async fn maps_connection_failures_to_unavailable() {
+    use connect_rpc::reqwest::ReqwestClientExt;
+
+    // Bind and drop a listener to find a port that refuses connections.
+    let addr = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let req = || {
+        builder(&format!("http://{addr}"), "Get")
+            .unary(Bytes::new())
+            .unwrap()
+    };
+
+    let err = reqwest::Client::new()
+        .execute_unary(req())
+        .await
+        .unwrap_err();
+    assert_eq!(err.connect_code(), ConnectCode::Unavailable);
+    let err = client(ConnectClient::builder())
+        .execute_unary(req())
+        .await
+        .unwrap_err();
+    assert_eq!(err.connect_code(), ConnectCode::Unavailable);
+}
+
+const BASE_URL: &str = "http://example.com";
+
+#[tokio::test]
+async fn resumes_interrupted_server_streams() {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+        time::Duration,
+    };
+
+    use connect_rpc::{
+        client::resilient::{Backoff, ResilientServerStream},
+        stream::{ConnectFrame, EndStreamResponse},
+        transport::{full_body, MemoryTransport},
+    };
+    use futures_util::StreamExt;
+
+    let message = |data: &'static [u8]| ConnectFrame {
+        compressed: false,
+        end: false,
+        data: Bytes::from_static(data),
+    };
+    // The first attempt breaks off mid-frame; the second completes.
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let transport = MemoryTransport::default().streaming_route("/example.v1.Service/List", {
+        let attempts = attempts.clone();
+        move |_| {
+            let mut body = message(b"one").encode().unwrap().to_vec();
+            if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                body.extend_from_slice(&ConnectFrame::encode_raw(0, Some(10), b"tw"));
+            } else {
+                body = message(b"two").encode().unwrap().to_vec();
+                let end = EndStreamResponse::default().to_frame().unwrap();
+                body.extend_from_slice(&end.encode().unwrap());
+            }
+            let resp = http::Response::builder()
+                .header("content-type", "application/connect+proto")
+                .body(full_body(body))
+                .unwrap();
+            std::future::ready(Ok(resp))
+        }
+    });
+    let client = client(ConnectClient::builder().transport(transport));
+    let backoff = Backoff {
+        initial: Duration::from_millis(1),
+        ..Default::default()
+    };
+    let resumed_from = Arc::new(Mutex::new(vec![]));
+    let stream = ResilientServerStream::new(client, backoff, tokio::time::sleep, {
+        let resumed_from = resumed_from.clone();
+        move |last| {
+            resumed_from.lock().unwrap().push(last.cloned());
+            builder(BASE_URL, "List").streaming(message(b"request").encode()?)
+        }
+    });
+
+    let messages: Vec<_> = stream.map(Result::unwrap).collect().await;
+    assert_eq!(messages, ["one", "two"]);
+    assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    assert_eq!(
+        *resumed_from.lock().unwrap(),
+        [None, Some(Bytes::from_static(b"one"))]
+    );
+}
+
+#[cfg(feature = "request-id")]
+#[tokio::test]
+async fn injects_request_ids() {
+    use connect_rpc::{interceptor::request_id::RequestIdInterceptor, transport::MemoryTransport};
+
+    // Echoes the request ID.
+    let transport = MemoryTransport::new(|req: http::Request<Bytes>| async move {
+        let request_id = req.headers()["x-request-id"].as_bytes().to_vec();
+        let resp = http::Response::builder()
+            .header("content-type", "application/proto")
+            .body(request_id.into())
+            .unwrap();
+        Ok(resp)
+    });
+    let client = client(
+        ConnectClient::builder()
+            .transport(transport)
+            .interceptor(RequestIdInterceptor::new()),
+    );
+
+    let req = builder(BASE_URL, "Get").unary(Bytes::new()).unwrap();
+    let resp = http::Response::from(client.execute_unary(req).await.unwrap());
+    assert_eq!(resp.body().len(), 36);
+    assert_eq!(resp.headers()["x-request-id"], resp.body().as_ref());
+
+    let req = builder(BASE_URL, "Get")
+        .ascii_metadata("x-request-id", "given")
+        .unwrap()
+        .unary(Bytes::new())
+        .unwrap();
+    let resp = client.execute_unary(req).await.unwrap();
+    assert_eq!(resp.body().as_ref(), b"given");
+}
+
+/// Returns an `application/proto` response with the given body.
+fn proto_response(body: impl Into<Bytes>) -> http::Response<Bytes> {
+    http::Response::builder()
+        .header("content-type", "application/proto")
+        .body(body.into())
+        .unwrap()
+}
+
+#[tokio::test]
+async fn reports_transfer_progress() {
+    use std::sync::{Arc, Mutex};
+
+    use connect_rpc::{
+        client::progress::ProgressListener, metrics::RpcInfo, transport::MemoryTransport,
+    };
+
+    #[derive(Clone, Default)]
+    struct Progress(Arc<Mutex<Vec<(&'static str, u64)>>>);
+
+    impl ProgressListener for Progress {
+        fn on_upload(&self, rpc: &RpcInfo, sent: u64) {
+            assert_eq!(rpc.method, "Echo");
+            self.0.lock().unwrap().push(("upload", sent));
+        }
+
+        fn on_download(&self, _rpc: &RpcInfo, received: u64, _total: Option<u64>) {
+            self.0.lock().unwrap().push(("download", received));
+        }
+    }
+
+    let transport = MemoryTransport::new(|req: http::Request<Bytes>| async move {
+        Ok(proto_response(req.into_body()))
+    });
+    let progress = Progress::default();
+    let client = client(
+        ConnectClient::builder()
+            .transport(transport)
+            .progress_listener(progress.clone()),
+    );
+    let req = builder(BASE_URL, "Echo")
+        .unary(Bytes::from_static(b"message"))
+        .unwrap();
+    client.execute_unary(req).await.unwrap();
+
+    let events = progress.0.lock().unwrap();
+    assert_eq!(events.first(), Some(&("upload", 7)));
+    assert_eq!(events.last(), Some(&("download", 7)));
+}
+
+#[tokio::test]
+async fn propagates_request_extensions() {
+    use connect_rpc::transport::MemoryTransport;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Principal(&'static str);
+
+    let transport = MemoryTransport::new(|req: http::Request<Bytes>| async move {
+        let principal = req.extensions().get::<Principal>().unwrap().0;
+        Ok(proto_response(principal))
+    });
+    let client = client(ConnectClient::builder().transport(transport));
+    let req = builder(BASE_URL, "Get")
+        .extension(Principal("user"))
+        .unary(Bytes::new())
+        .unwrap();
+    let resp = client.execute_unary(req).await.unwrap();
+    assert_eq!(resp.body().as_ref(), b"user");
+    assert_eq!(resp.extensions().get(), Some(&Principal("user")));
+}
+
+#[tokio::test]
+async fn manual_clock_controls_sleeps() {
+    use std::time::Duration;
+
+    use connect_rpc::{
+        client::Sleep,
+        clock::{Clock, ManualClock},
+    };
+    use futures_util::poll;
+
+    let clock = ManualClock::new();
+    let start = clock.now();
+    let mut sleep = clock.sleep(Duration::from_secs(2));
+    assert!(poll!(&mut sleep).is_pending());
+    clock.advance(Duration::from_secs(1));
+    assert!(poll!(&mut sleep).is_pending());
+    clock.advance(Duration::from_secs(1));
+    assert!(poll!(&mut sleep).is_ready());
+    assert_eq!(clock.now() - start, Duration::from_secs(2));
+}
+
+#[tokio::test]
+async fn times_out_idle_streams() {
+    use std::time::Duration;
+
+    use connect_rpc::{
+        stream::{ConnectFrame, FramedBody},
+        transport::MemoryTransport,
+    };
+    use futures_util::{stream, StreamExt};
+    use http_body_util::BodyExt;
+
+    let message = || ConnectFrame {
+        compressed: false,
+        end: false,
+        data: Bytes::from_static(b"message"),
+    };
+    // Sends one message, then hangs.
+    let transport =
+        MemoryTransport::default().streaming_route("/example.v1.Service/List", move |_| {
+            let frames = stream::iter([Ok(message())]).chain(stream::pending());
+            let body = FramedBody::new(ConnectFrame::encode_stream(frames));
+            let resp = http::Response::builder()
+                .header("content-type", "application/connect+proto")
+                .body(BodyExt::boxed(body))
+                .unwrap();
+            std::future::ready(Ok(resp))
+        });
+    let client = client(ConnectClient::builder().transport(transport));
+    let req = builder(BASE_URL, "List")
+        .streaming(message().encode().unwrap())
+        .unwrap();
+    let mut call = client
+        .execute_server_stream(req)
+        .await
+        .unwrap()
+        .idle_timeout(tokio::time::sleep, Duration::from_millis(10));
+
+    assert_eq!(call.next().await.unwrap().unwrap().as_ref(), b"message");
+    let err = call.next().await.unwrap().unwrap_err();
+    assert_eq!(err.connect_code(), ConnectCode::DeadlineExceeded);
+    assert!(call.next().await.is_none());
+}
+
+#[tokio::test]
+async fn returns_connect_errors() {
+    use connect_rpc::{transport::MemoryTransport, Error};
+
+    let req = || builder(BASE_URL, "Get").unary(Bytes::new()).unwrap();
+
+    let unrouted = client(ConnectClient::builder().transport(MemoryTransport::default()));
+    let err = unrouted.execute_unary_connect(req()).await.unwrap_err();
+    assert_eq!(err.code(), ConnectCode::Unimplemented);
+
+    let transport = MemoryTransport::new(|_| async {
+        Err::<http::Response<Bytes>, _>(Error::InvalidResponse("bad".into()))
+    });
+    let failing = client(ConnectClient::builder().transport(transport));
+    let err = failing.execute_unary_connect(req()).await.unwrap_err();
+    assert_eq!(err.code(), ConnectCode::Internal);
+}
+
+#[tokio::test]
+async fn selects_request_profiles() {
+    use connect_rpc::transport::MemoryTransport;
+
+    // Echoes the authorization header.
+    let transport = MemoryTransport::new(|req: http::Request<Bytes>| async move {
+        let auth = req
+            .headers()
+            .get("authorization")
+            .map(|auth| auth.as_bytes().to_vec())
+            .unwrap_or_default();
+        Ok(proto_response(auth))
+    });
+    let client = client(
+        ConnectClient::builder()
+            .transport(transport)
+            .profile(
+                "internal",
+                RequestBuilder::default().bearer_auth("token").unwrap(),
+            )
+            .profile("anonymous", RequestBuilder::default()),
+    );
+    let call = |profile| {
+        let req = client
+            .profile(profile)
+            .unwrap()
+            .uri(format!("{BASE_URL}/example.v1.Service/Get"))
+            .unwrap()
+            .message_codec("proto")
+            .unwrap()
+            .unary(Bytes::new())
+            .unwrap();
+        client.execute_unary(req)
+    };
+
+    let resp = call("internal").await.unwrap();
+    assert_eq!(resp.body().as_ref(), b"Bearer token");
+    let resp = call("anonymous").await.unwrap();
+    assert_eq!(resp.body().as_ref(), b"");
+    assert!(client.profile("missing").is_err());
+}
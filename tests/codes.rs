@@ -0,0 +1,85 @@
+use connect_rpc::{
+    codes::{self, CODES},
+    response::error::ConnectCode,
+};
+use http::StatusCode;
+
+// https://connectrpc.com/docs/protocol/#error-codes
+const SPEC_CODES: [(&str, u32, u16); 17] = [
+    ("ok", 0, 200),
+    ("canceled", 1, 499),
+    ("unknown", 2, 500),
+    ("invalid_argument", 3, 400),
+    ("deadline_exceeded", 4, 504),
+    ("not_found", 5, 404),
+    ("already_exists", 6, 409),
+    ("permission_denied", 7, 403),
+    ("resource_exhausted", 8, 429),
+    ("failed_precondition", 9, 400),
+    ("aborted", 10, 409),
+    ("out_of_range", 11, 400),
+    ("unimplemented", 12, 501),
+    ("internal", 13, 500),
+    ("unavailable", 14, 503),
+    ("data_loss", 15, 500),
+    ("unauthenticated", 16, 401),
+];
+
+// https://connectrpc.com/docs/protocol/#http-to-error-code
+fn spec_http_to_code(status: u16) -> ConnectCode {
+    match status {
+        400 => ConnectCode::Internal,
+        401 => ConnectCode::Unauthenticated,
+        403 => ConnectCode::PermissionDenied,
+        404 => ConnectCode::Unimplemented,
+        429 | 502 | 503 | 504 => ConnectCode::Unavailable,
+        _ => ConnectCode::Unknown,
+    }
+}
+
+#[test]
+fn codes_match_spec() {
+    assert_eq!(CODES.len(), SPEC_CODES.len());
+    for (mapping, (name, grpc_code, http_status)) in CODES.iter().zip(SPEC_CODES) {
+        assert_eq!(mapping.name, name);
+        assert_eq!(mapping.grpc_code, grpc_code, "{name}");
+        assert_eq!(mapping.http_status, http_status, "{name}");
+    }
+}
+
+#[test]
+fn names_match_serialization() {
+    for mapping in CODES {
+        let json = serde_json::to_value(mapping.code).unwrap();
+        assert_eq!(json, mapping.name);
+    }
+}
+
+#[test]
+fn lookups_round_trip() {
+    for mapping in &CODES {
+        let code = mapping.code;
+        assert_eq!(codes::lookup(code), mapping);
+        assert_eq!(codes::from_name(mapping.name), Some(code));
+        assert_eq!(codes::from_grpc_code(mapping.grpc_code), Some(code));
+        assert_eq!(codes::http_status(code).as_u16(), mapping.http_status);
+        assert_eq!(code.to_string(), mapping.name);
+        assert_eq!(mapping.name.parse::<ConnectCode>(), Ok(code));
+        assert_eq!(ConnectCode::from_i32(code.as_grpc_code()), Some(code));
+    }
+    assert_eq!(codes::from_name("bogus"), None);
+    assert_eq!(codes::from_grpc_code(17), None);
+    assert!("bogus".parse::<ConnectCode>().is_err());
+    assert_eq!(ConnectCode::from_i32(-1), None);
+    assert_eq!(ConnectCode::from_i32(17), None);
+}
+
+#[test]
+fn http_status_to_code_matches_spec() {
+    for status in 100..1000 {
+        let status = StatusCode::from_u16(status).unwrap();
+        let code = spec_http_to_code(status.as_u16());
+        assert_eq!(codes::from_http_status(status), code, "{status}");
+        assert_eq!(ConnectCode::from(status), code, "{status}");
+    }
+}
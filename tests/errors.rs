@@ -0,0 +1,80 @@
+use connect_rpc::response::error::ConnectCode;
+
+#[test]
+fn parses_and_formats_codes() {
+    assert_eq!(ConnectCode::NotFound.to_string(), "not_found");
+    assert_eq!("not_found".parse(), Ok(ConnectCode::NotFound));
+    let err = "not-found".parse::<ConnectCode>().unwrap_err();
+    assert_eq!(err.0, "not-found");
+
+    assert_eq!(ConnectCode::from_i32(5), Some(ConnectCode::NotFound));
+    assert_eq!(ConnectCode::NotFound.as_grpc_code(), 5);
+    assert_eq!(ConnectCode::from_i32(17), None);
+    assert_eq!(ConnectCode::from_i32(-1), None);
+}
+
+#[test]
+fn classifies_errors() {
+    use connect_rpc::{response::error::ConnectError, Error};
+
+    let err = |code| ConnectError::new(code, "");
+    assert!(err(ConnectCode::Unavailable).is_retryable());
+    assert!(!err(ConnectCode::Internal).is_retryable());
+    assert!(err(ConnectCode::InvalidArgument).is_client_error());
+    assert!(!err(ConnectCode::Unavailable).is_client_error());
+    assert!(err(ConnectCode::DeadlineExceeded).is_timeout());
+
+    let err = Error::ConnectError(err(ConnectCode::NotFound));
+    assert_eq!(err.connect_code(), ConnectCode::NotFound);
+    let err = Error::InvalidResponse("bad".into());
+    assert_eq!(err.connect_code(), ConnectCode::Internal);
+}
+
+#[test]
+fn keeps_unknown_error_fields() {
+    use connect_rpc::response::error::ConnectError;
+
+    let json = r#"{"code":"aborted","message":"retry","x-vendor":{"hint":1}}"#;
+    let err: ConnectError = serde_json::from_str(json).unwrap();
+    assert_eq!(err.code(), ConnectCode::Aborted);
+    assert_eq!(err.extra["x-vendor"], serde_json::json!({"hint": 1}));
+    let expected: serde_json::Value = serde_json::from_str(json).unwrap();
+    assert_eq!(serde_json::to_value(&err).unwrap(), expected);
+
+    let unknown: ConnectError = serde_json::from_str(r#"{"code":"bogus"}"#).unwrap();
+    assert_eq!(unknown.code(), ConnectCode::Unknown);
+}
+
+#[test]
+fn handles_detail_type_urls() {
+    use connect_rpc::response::error::ConnectErrorDetail;
+
+    let detail = ConnectErrorDetail::new("type.googleapis.com/example.v1.Info", b"\x01");
+    assert_eq!(detail.proto_type, "example.v1.Info");
+    assert_eq!(detail.type_url(), "type.googleapis.com/example.v1.Info");
+    assert_eq!(detail.type_name(), "example.v1.Info");
+    assert_eq!(detail.value().unwrap(), b"\x01");
+
+    let detail = ConnectErrorDetail::new("example.com/types/example.v1.Info", b"");
+    assert_eq!(detail.type_url(), "example.com/types/example.v1.Info");
+    assert_eq!(
+        detail.type_url_with_prefix("other.com/"),
+        "example.com/types/example.v1.Info"
+    );
+    assert_eq!(detail.type_name(), "example.v1.Info");
+}
+
+#[test]
+fn keeps_converted_errors_as_causes() {
+    use connect_rpc::{response::error::ConnectError, Error};
+
+    let err = ConnectError::from(Error::InvalidResponse("bad frame".into()));
+    assert_eq!(err.code(), ConnectCode::Internal);
+    let cause = err.cause().unwrap().downcast_ref::<Error>().unwrap();
+    assert!(matches!(cause, Error::InvalidResponse(message) if message == "bad frame"));
+    assert!(std::error::Error::source(&err).is_some());
+
+    // Causes aren't sent.
+    let json = serde_json::to_string(&err).unwrap();
+    assert!(!json.contains("bad frame"));
+}
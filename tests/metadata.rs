@@ -0,0 +1,98 @@
+use connect_rpc::metadata::Metadata;
+use http::HeaderMap;
+
+fn headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.append_ascii("x-b", "1").unwrap();
+    headers.append_ascii("x-a", "2").unwrap();
+    headers.append_ascii("x-b", "3").unwrap();
+    headers.append_binary("x-c-bin", b"\xff").unwrap();
+    headers.append_ascii("trailer-x-d", "4").unwrap();
+    headers
+}
+
+#[test]
+fn groups_metadata_in_wire_order() {
+    let headers = headers();
+    assert_eq!(
+        headers.group_ascii(),
+        [
+            ("x-b", vec!["1", "3"]),
+            ("x-a", vec!["2"]),
+            ("x-d", vec!["4"])
+        ]
+    );
+    assert_eq!(headers.group_binary(), [("x-c-bin", vec![vec![0xff]])]);
+}
+
+#[test]
+fn joins_values_and_iterates_entries() {
+    let headers = headers();
+    assert_eq!(headers.get_joined_ascii("x-b").as_deref(), Some("1, 3"));
+    assert_eq!(headers.get_joined_ascii("x-e"), None);
+    let entries: Vec<_> = headers.iter_entries().collect();
+    assert_eq!(
+        entries,
+        [
+            ("x-b", vec!["1", "3"]),
+            ("x-a", vec!["2"]),
+            ("x-c-bin", vec!["/w"]),
+            ("x-d", vec!["4"]),
+        ]
+    );
+}
+
+#[test]
+fn iterates_raw_values() {
+    let mut headers = headers();
+    let latin1 = http::HeaderValue::from_bytes(b"caf\xe9").unwrap();
+    headers.append("x-e", latin1);
+    let raw: Vec<_> = headers.iter_raw().collect();
+    assert_eq!(raw.len(), 6);
+    assert!(raw.contains(&("x-c-bin", b"/w".as_slice())));
+    assert!(raw.contains(&("x-e", b"caf\xe9".as_slice())));
+    assert!(!headers.iter_ascii().any(|(key, _)| key == "x-e"));
+}
+
+#[test]
+fn redacts_sensitive_metadata() {
+    use connect_rpc::metadata::{redact_metadata, Redact};
+
+    let mut headers = headers();
+    headers
+        .append_ascii("authorization", "Bearer secret")
+        .unwrap();
+    let redacted = redact_metadata(&headers, &["Authorization", "x-a"]);
+    assert_eq!(redacted["authorization"], "<redacted>");
+    assert_eq!(redacted["x-a"], "<redacted>");
+    assert_eq!(redacted["x-c-bin"], "<redacted>");
+    assert_eq!(redacted["x-b"], "1");
+    let debug = format!("{:?}", headers.redacted());
+    assert!(!debug.contains("secret"));
+    assert!(debug.contains("x-a"));
+}
+
+#[test]
+fn splits_trailers() {
+    let (leading, trailers) = connect_rpc::metadata::split_trailers(&headers());
+    assert_eq!(leading.len(), 4);
+    assert!(!leading.contains_key("trailer-x-d"));
+    assert_eq!(trailers.len(), 1);
+    assert_eq!(trailers["x-d"], "4");
+}
+
+#[test]
+fn round_trips_metadata_json() {
+    use connect_rpc::metadata::{metadata_from_json, metadata_to_json};
+
+    let headers = headers();
+    let json = metadata_to_json(&headers);
+    assert_eq!(json["x-b"], serde_json::json!(["1", "3"]));
+    assert_eq!(json["x-c-bin"], serde_json::json!(["/w"]));
+    assert_eq!(metadata_from_json(&json).unwrap(), headers);
+
+    let single = serde_json::json!({"x-a": "2"});
+    assert_eq!(metadata_from_json(&single).unwrap()["x-a"], "2");
+    assert!(metadata_from_json(&serde_json::json!({"x-a-bin": "!"})).is_err());
+    assert!(metadata_from_json(&serde_json::json!(["x-a"])).is_err());
+}
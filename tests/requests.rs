@@ -0,0 +1,245 @@
+use bytes::Bytes;
+use connect_rpc::request::{
+    builder::RequestBuilder, ConnectRequest, ConnectRequestType, UnaryGetOrPostRequest,
+};
+
+fn builder() -> RequestBuilder {
+    RequestBuilder::default()
+        .uri("https://example.com/example.v1.Service/Get")
+        .unwrap()
+        .message_codec("proto")
+        .unwrap()
+}
+
+#[test]
+fn falls_back_to_post_for_long_urls() {
+    let req = builder().unary_get_or_post(b"message").unwrap();
+    assert!(matches!(req, UnaryGetOrPostRequest::Get(_)));
+
+    let req = builder()
+        .get_url_max_bytes(32)
+        .unary_get_or_post(b"message")
+        .unwrap();
+    let UnaryGetOrPostRequest::Post(req) = req else {
+        panic!("expected POST");
+    };
+    assert_eq!(req.body().as_ref(), b"message");
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn compresses_get_messages() {
+    let req = builder()
+        .content_encoding("gzip")
+        .unwrap()
+        .unary_get(b"message")
+        .unwrap();
+    assert_eq!(req.content_encoding(), Some("gzip"));
+    assert!(req.to_curl().contains("compression=gzip"));
+    assert_eq!(req.message().unwrap().as_ref(), b"message");
+    assert!(req.message_with_limit(3).is_err());
+}
+
+fn parse(req: http::request::Builder) -> ConnectRequestType<Bytes> {
+    ConnectRequestType::from_http(req.body(Bytes::new()).unwrap())
+}
+
+#[test]
+fn ignores_content_type_params() {
+    let req = http::Request::post("/example.v1.Service/Get")
+        .header("content-type", "application/json; charset=utf-8");
+    let ConnectRequestType::Unary(req) = parse(req) else {
+        panic!("expected unary request");
+    };
+    assert_eq!(req.message_codec().unwrap(), "json");
+    let params: Vec<_> = req.content_type_params().collect();
+    assert_eq!(params, [("charset", "utf-8")]);
+}
+
+#[test]
+fn requires_protocol_version_when_configured() {
+    use connect_rpc::request::RequestValidateOpts;
+
+    let req =
+        http::Request::post("/example.v1.Service/Get").header("content-type", "application/proto");
+    let ConnectRequestType::Unary(req) = parse(req) else {
+        panic!("expected unary request");
+    };
+    req.validate().unwrap();
+    let opts = RequestValidateOpts {
+        require_protocol_version: true,
+        ..Default::default()
+    };
+    assert!(req.validate_with(&opts).is_err());
+    builder()
+        .unary(Bytes::new())
+        .unwrap()
+        .validate_with(&opts)
+        .unwrap();
+}
+
+#[test]
+fn sets_headers_and_default_user_agent() {
+    use connect_rpc::request::builder::DEFAULT_USER_AGENT;
+
+    let req: http::Request<_> = builder().unary(Bytes::new()).unwrap().into();
+    assert_eq!(req.headers()["user-agent"], DEFAULT_USER_AGENT);
+
+    let req: http::Request<_> = builder()
+        .header("user-agent", "custom/1.0")
+        .unwrap()
+        .header("x-forwarded-for", "10.0.0.1")
+        .unwrap()
+        .unary(Bytes::new())
+        .unwrap()
+        .into();
+    assert_eq!(req.headers()["user-agent"], "custom/1.0");
+    assert_eq!(req.headers()["x-forwarded-for"], "10.0.0.1");
+}
+
+#[test]
+fn accepts_the_message_codec() {
+    let req: http::Request<_> = builder().unary(Bytes::new()).unwrap().into();
+    assert_eq!(req.headers()["accept"], "application/proto");
+
+    let req: http::Request<_> = builder()
+        .header("accept", "application/json")
+        .unwrap()
+        .unary(Bytes::new())
+        .unwrap()
+        .into();
+    assert_eq!(req.headers()["accept"], "application/json");
+}
+
+#[test]
+fn renders_curl_commands() {
+    let req = builder()
+        .bearer_auth("secret")
+        .unwrap()
+        .unary(Bytes::from_static(b"message"))
+        .unwrap();
+    let curl = req.to_curl();
+    assert!(curl.contains("curl -X POST"));
+    assert!(curl.contains("https://example.com/example.v1.Service/Get"));
+    assert!(curl.contains("body.bin"));
+    assert!(!curl.contains("secret"));
+
+    let curl = builder().unary_get(b"message").unwrap().to_curl();
+    assert!(curl.contains("encoding=proto"));
+}
+
+#[test]
+fn requires_http_version() {
+    let req = builder()
+        .http_version(http::Version::HTTP_2)
+        .unary(Bytes::new())
+        .unwrap();
+    assert_eq!(req.http_version(), Some(http::Version::HTTP_2));
+    let req: http::Request<_> = req.into();
+    assert_eq!(req.version(), http::Version::HTTP_2);
+
+    assert_eq!(builder().unary(Bytes::new()).unwrap().http_version(), None);
+}
+
+#[test]
+fn modifies_built_requests() {
+    use connect_rpc::metadata::Metadata;
+
+    let mut req = builder().unary(Bytes::new()).unwrap();
+    req.metadata_mut().insert_ascii("x-tenant", "a").unwrap();
+    *req.uri_mut() = "https://example.org/example.v1.Service/Put"
+        .parse()
+        .unwrap();
+    *req.body_mut() = Bytes::from_static(b"message");
+    assert_eq!(req.metadata().get_ascii("x-tenant"), Some("a"));
+    assert_eq!(req.path(), "/example.v1.Service/Put");
+    assert_eq!(req.body().as_ref(), b"message");
+
+    let mut req = builder().unary_get(b"message").unwrap();
+    req.set_uri(
+        "/example.v1.Service/Get?encoding=json&message=%7B%7D"
+            .parse()
+            .unwrap(),
+    );
+    assert_eq!(req.message_codec().unwrap(), "json");
+    assert_eq!(req.message().unwrap().as_ref(), b"{}");
+}
+
+#[test]
+fn lints_all_violations() {
+    use connect_rpc::request::lint::LintRule;
+
+    let req = http::Request::post("/example.v1.Service/Get")
+        .header("connect-timeout-ms", "soon")
+        .header("connect-content-encoding", "gzip");
+    let rules: Vec<_> = parse(req).lint().into_iter().map(|v| v.rule).collect();
+    assert_eq!(
+        rules,
+        [
+            LintRule::MissingProtocolVersion,
+            LintRule::InvalidMessageCodec,
+            LintRule::InvalidTimeout,
+            LintRule::ConflictingEncoding,
+        ]
+    );
+
+    let req: http::Request<_> = builder().unary(Bytes::new()).unwrap().into();
+    assert!(ConnectRequestType::from_http(req).lint().is_empty());
+}
+
+#[test]
+fn validates_and_clamps_timeouts() {
+    use std::time::Duration;
+
+    use connect_rpc::request::RequestValidateOpts;
+
+    let opts = RequestValidateOpts {
+        max_timeout: Some(Duration::from_secs(1)),
+        ..Default::default()
+    };
+    let req = builder()
+        .timeout_ms(5000)
+        .unwrap()
+        .unary(Bytes::new())
+        .unwrap();
+    assert_eq!(req.timeout(), Some(Duration::from_secs(5)));
+    assert_eq!(
+        req.timeout_with(&opts).unwrap(),
+        Some(Duration::from_secs(1))
+    );
+    let req = builder().unary(Bytes::new()).unwrap();
+    assert_eq!(
+        req.timeout_with(&opts).unwrap(),
+        Some(Duration::from_secs(1))
+    );
+
+    let req = http::Request::post("/example.v1.Service/Get")
+        .header("content-type", "application/proto")
+        .header("connect-timeout-ms", "12345678901");
+    let ConnectRequestType::Unary(req) = parse(req) else {
+        panic!("expected unary request");
+    };
+    assert_eq!(req.timeout(), None);
+    assert!(req.validate().is_err());
+    assert!(req.timeout_with(&opts).is_err());
+}
+
+#[test]
+fn treats_identity_as_no_encoding() {
+    let req = builder()
+        .content_encoding("gzip")
+        .unwrap()
+        .content_encoding("identity")
+        .unwrap()
+        .unary(Bytes::new())
+        .unwrap();
+    assert_eq!(req.content_encoding(), None);
+
+    let req = http::Request::post("/example.v1.Service/Get")
+        .header("content-type", "application/proto")
+        .header("content-encoding", "identity");
+    let ConnectRequestType::Unary(req) = parse(req) else {
+        panic!("expected unary request");
+    };
+    req.validate().unwrap();
+}
@@ -0,0 +1,137 @@
+use connect_rpc::{response::error::ConnectCode, stream::EndStreamResponse, Error};
+use http::HeaderMap;
+
+#[test]
+fn flattens_headers_and_trailers() {
+    let mut headers = HeaderMap::new();
+    headers.insert("header", "one".parse().unwrap());
+    let end = EndStreamResponse {
+        error: None,
+        metadata: [("trailer".into(), vec!["two".into(), "three".into()])].into(),
+    };
+
+    let flattened = end.flatten_metadata(&headers, 1024).unwrap();
+    assert_eq!(flattened["header"], "one");
+    let values: Vec<_> = flattened.get_all("trailer").iter().collect();
+    assert_eq!(values, ["two", "three"]);
+
+    let err = end.flatten_metadata(&headers, 20).unwrap_err();
+    assert!(matches!(
+        err,
+        Error::ConnectError(err) if err.code() == ConnectCode::ResourceExhausted
+    ));
+}
+
+fn unary_response(
+    status: u16,
+    content_type: Option<&str>,
+) -> connect_rpc::response::UnaryResponse<()> {
+    let mut resp = http::Response::builder().status(status);
+    if let Some(content_type) = content_type {
+        resp = resp.header("content-type", content_type);
+    }
+    resp.body(()).unwrap().into()
+}
+
+#[test]
+fn validates_strictly_when_configured() {
+    use connect_rpc::response::{ConnectResponse, Strictness, ValidateOpts};
+
+    let strict = ValidateOpts::default().strictness(Strictness::Strict);
+
+    let resp = unary_response(200, None);
+    assert!(matches!(
+        resp.validate(&strict),
+        Err(connect_rpc::Error::InvalidResponse(_))
+    ));
+
+    let resp = unary_response(200, Some("application/proto"));
+    resp.validate(&strict).unwrap();
+
+    let resp: connect_rpc::response::StreamingResponse<()> = http::Response::builder()
+        .status(202)
+        .header("content-type", "application/connect+proto")
+        .body(())
+        .unwrap()
+        .into();
+    resp.validate(&ValidateOpts::default()).unwrap();
+    assert!(resp.validate(&strict).is_err());
+
+    let resp: connect_rpc::response::StreamingResponse<()> = http::Response::builder()
+        .header("content-type", "application/connect+proto")
+        .header("connect-content-encoding", "unknown")
+        .body(())
+        .unwrap()
+        .into();
+    resp.validate(&ValidateOpts::default()).unwrap();
+    assert!(resp.validate(&strict).is_err());
+}
+
+#[test]
+fn takes_apart_responses() {
+    let resp = unary_response(200, Some("application/proto")).map_body(|()| "body");
+    assert_eq!(*resp.body(), "body");
+    let (parts, body) = resp.into_parts();
+    assert_eq!(parts.headers["content-type"], "application/proto");
+    assert_eq!(body, "body");
+
+    let resp: connect_rpc::response::StreamingResponse<()> = http::Response::new(()).into();
+    assert_eq!(resp.map_body(|()| 1).into_body(), 1);
+}
+
+#[test]
+fn builds_responses_with_protocol_constants() {
+    use connect_rpc::{consts, response::builder::ResponseBuilder};
+
+    let resp: http::Response<()> = ResponseBuilder::default()
+        .message_codec("json")
+        .unwrap()
+        .content_encoding("gzip")
+        .unwrap()
+        .streaming(())
+        .unwrap()
+        .into();
+    let content_type = resp.headers()["content-type"].to_str().unwrap();
+    assert_eq!(
+        content_type.strip_prefix(consts::STREAMING_CONTENT_TYPE_PREFIX),
+        Some("json")
+    );
+    assert_eq!(resp.headers()[consts::CONNECT_CONTENT_ENCODING], "gzip");
+}
+
+#[test]
+fn parses_weighted_accept_encodings() {
+    use connect_rpc::compression::AcceptEncoding;
+
+    let accept = AcceptEncoding::parse(["gzip;q=0.8, br;q=1.0", "*;q=0.1, deflate;q=0"]);
+    let encodings: Vec<_> = accept.iter().map(|w| w.encoding.as_str()).collect();
+    assert_eq!(encodings, ["br", "gzip", "*", "deflate"]);
+    assert_eq!(accept.weight("gzip"), 0.8);
+    assert_eq!(accept.weight("zstd"), 0.1);
+    assert!(!accept.accepts("deflate"));
+    assert_eq!(accept.preferred(["gzip", "zstd"]), Some("gzip"));
+    assert_eq!(accept.preferred(["deflate"]), None);
+
+    let accept = AcceptEncoding::parse(["gzip, invalid;q=2"]);
+    assert!(accept.accepts("identity"));
+    assert!(!accept.accepts("invalid"));
+}
+
+#[test]
+fn validates_weighted_accept_encodings() {
+    use connect_rpc::response::{ConnectResponse, ValidateOpts};
+
+    let opts = |accept: &str| ValidateOpts {
+        accept_encoding: Some(vec![accept.into()]),
+        ..Default::default()
+    };
+    let resp: connect_rpc::response::UnaryResponse<()> = http::Response::builder()
+        .header("content-type", "application/proto")
+        .header("content-encoding", "gzip")
+        .body(())
+        .unwrap()
+        .into();
+    resp.validate(&opts("br, gzip;q=0.5")).unwrap();
+    resp.validate(&opts("*")).unwrap();
+    assert!(resp.validate(&opts("gzip;q=0, *")).is_err());
+}
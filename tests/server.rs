@@ -0,0 +1,219 @@
+use bytes::Bytes;
+use connect_rpc::response::error::{ConnectCode, ConnectError};
+
+#[cfg(feature = "tonic")]
+#[test]
+fn converts_tonic_statuses() {
+    use tonic::{Code, Status};
+
+    assert_eq!(Code::from(ConnectCode::NotFound), Code::NotFound);
+    assert_eq!(
+        ConnectCode::from(Code::Unavailable),
+        ConnectCode::Unavailable
+    );
+
+    let mut headers = http::HeaderMap::new();
+    headers.insert("content-type", "application/json".parse().unwrap());
+    headers.insert("trailer-key", "value".parse().unwrap());
+    let metadata = connect_rpc::tonic::to_tonic_metadata(&headers);
+    assert_eq!(metadata.get("key").unwrap(), "value");
+    assert!(metadata.get("content-type").is_none());
+
+    let status = Status::from(ConnectError::new(ConnectCode::PermissionDenied, "denied"));
+    assert_eq!(status.code(), Code::PermissionDenied);
+    assert_eq!(status.message(), "denied");
+
+    let err = ConnectError::from(Status::ok("not an error"));
+    assert_eq!(err.code(), ConnectCode::Unknown);
+    let err = ConnectError::from(Status::aborted("stop"));
+    assert_eq!(err.code(), ConnectCode::Aborted);
+    assert_eq!(err.message, "stop");
+}
+
+fn parse(
+    req: http::request::Builder,
+    body: &'static [u8],
+) -> connect_rpc::request::ConnectRequestType<Bytes> {
+    connect_rpc::request::ConnectRequestType::from_http(req.body(Bytes::from_static(body)).unwrap())
+}
+
+#[test]
+fn translates_requests_to_grpc() {
+    use connect_rpc::{grpc_bridge, stream::ConnectFrame};
+
+    let req = http::Request::post("http://example.com/example.v1.Service/Get")
+        .header("content-type", "application/proto")
+        .header("connect-protocol-version", "1")
+        .header("connect-timeout-ms", "1500")
+        .header("x-custom", "value");
+    let req = grpc_bridge::grpc_request(parse(req, b"message")).unwrap();
+    assert_eq!(req.headers()["content-type"], "application/grpc+proto");
+    assert_eq!(req.headers()["te"], "trailers");
+    assert_eq!(req.headers()["grpc-timeout"], "1500m");
+    assert_eq!(req.headers()["x-custom"], "value");
+    assert!(!req.headers().contains_key("connect-protocol-version"));
+    let frames = ConnectFrame::parse_all(req.into_body()).unwrap();
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].data.as_ref(), b"message");
+
+    let req = http::Request::get(
+        "http://example.com/example.v1.Service/Get?encoding=json&message=%7B%7D&connect=v1",
+    );
+    let req = grpc_bridge::grpc_request(parse(req, b"")).unwrap();
+    assert_eq!(req.method(), http::Method::POST);
+    assert_eq!(req.uri().query(), None);
+    assert_eq!(req.headers()["content-type"], "application/grpc+json");
+    let frames = ConnectFrame::parse_all(req.into_body()).unwrap();
+    assert_eq!(frames[0].data.as_ref(), b"{}");
+}
+
+#[tokio::test]
+async fn translates_grpc_responses() {
+    use connect_rpc::{grpc_bridge, stream::ConnectFrame};
+    use http_body_util::Full;
+
+    let grpc_response = |status: &str, body: Bytes| {
+        http::Response::builder()
+            .header("content-type", "application/grpc+proto")
+            .header("grpc-status", status)
+            .header("grpc-message", "not%20found")
+            .body(Full::new(body))
+            .unwrap()
+    };
+    let message = ConnectFrame {
+        compressed: false,
+        end: false,
+        data: Bytes::from_static(b"message"),
+    }
+    .encode()
+    .unwrap();
+
+    let resp = grpc_bridge::connect_response(grpc_response("0", message.clone()), false)
+        .await
+        .unwrap();
+    assert_eq!(resp.headers()["content-type"], "application/proto");
+    assert_eq!(resp.body().as_ref(), b"message");
+
+    let resp = grpc_bridge::connect_response(grpc_response("5", Bytes::new()), false)
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+    let err: ConnectError = serde_json::from_slice(resp.body()).unwrap();
+    assert_eq!(err.code(), ConnectCode::NotFound);
+    assert_eq!(err.message, "not found");
+
+    let resp = grpc_bridge::connect_response(grpc_response("5", message), true)
+        .await
+        .unwrap();
+    assert_eq!(resp.headers()["content-type"], "application/connect+proto");
+    let frames = ConnectFrame::parse_all(resp.into_body()).unwrap();
+    assert_eq!(frames.len(), 2);
+    assert!(frames[1].end);
+}
+
+#[test]
+fn forwards_requests_upstream() {
+    use std::time::{Duration, Instant};
+
+    use connect_rpc::proxy::{self, ReceivedAt};
+
+    let req = || {
+        http::Request::post("https://example.com/example.v1.Service/Get?x=1")
+            .header("host", "example.com")
+            .header("connection", "keep-alive, x-hop")
+            .header("x-hop", "value")
+            .header("te", "trailers")
+            .header("connect-timeout-ms", "1000")
+            .header("x-custom", "value")
+            .body(())
+            .unwrap()
+    };
+    let forwarded = proxy::forward(req(), "backend.internal:8080").unwrap();
+    assert_eq!(
+        forwarded.uri(),
+        "https://backend.internal:8080/example.v1.Service/Get?x=1"
+    );
+    for name in ["host", "connection", "x-hop"] {
+        assert!(!forwarded.headers().contains_key(name), "{name}");
+    }
+    assert_eq!(forwarded.headers()["te"], "trailers");
+    assert_eq!(forwarded.headers()["x-custom"], "value");
+    assert_eq!(forwarded.headers()["connect-timeout-ms"], "1000");
+
+    let mut delayed = req();
+    let received_at = Instant::now() - Duration::from_millis(400);
+    delayed.extensions_mut().insert(ReceivedAt(received_at));
+    let forwarded = proxy::forward(delayed, "backend.internal:8080").unwrap();
+    let timeout: u64 = forwarded.headers()["connect-timeout-ms"]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(timeout <= 600, "{timeout}");
+
+    let mut expired = req();
+    let received_at = Instant::now() - Duration::from_secs(2);
+    expired.extensions_mut().insert(ReceivedAt(received_at));
+    let err = proxy::forward(expired, "backend.internal:8080").unwrap_err();
+    assert_eq!(err.connect_code(), ConnectCode::DeadlineExceeded);
+}
+
+#[test]
+fn answers_cors_preflights() {
+    use connect_rpc::cors::Cors;
+
+    let cors = Cors::allow_origins(["https://app.example.com"])
+        .unwrap()
+        .allow_header("x-token")
+        .unwrap();
+    let preflight = |origin: &str| {
+        http::Request::options("/example.v1.Service/Get")
+            .header("origin", origin)
+            .header("access-control-request-method", "POST")
+            .body(())
+            .unwrap()
+    };
+
+    let req = preflight("https://app.example.com");
+    assert!(Cors::is_preflight(&req));
+    let resp = cors.preflight_response(&req);
+    assert_eq!(resp.status(), http::StatusCode::NO_CONTENT);
+    let headers = resp.headers();
+    assert_eq!(
+        headers["access-control-allow-origin"],
+        "https://app.example.com"
+    );
+    let allowed = headers["access-control-allow-headers"].to_str().unwrap();
+    assert!(allowed.contains("connect-protocol-version"));
+    assert!(allowed.contains("x-token"));
+
+    let resp = cors.preflight_response(&preflight("https://other.example.com"));
+    assert!(!resp.headers().contains_key("access-control-allow-origin"));
+}
+
+#[test]
+fn adds_cors_headers_to_responses() {
+    use connect_rpc::cors::Cors;
+    use http::HeaderMap;
+
+    let mut req_headers = HeaderMap::new();
+    req_headers.insert("origin", "https://app.example.com".parse().unwrap());
+
+    let mut resp_headers = HeaderMap::new();
+    Cors::allow_any_origin().apply(&req_headers, &mut resp_headers);
+    assert_eq!(resp_headers["access-control-allow-origin"], "*");
+    let exposed = resp_headers["access-control-expose-headers"]
+        .to_str()
+        .unwrap();
+    assert!(exposed.contains("connect-content-encoding"));
+
+    let mut resp_headers = HeaderMap::new();
+    Cors::allow_any_origin()
+        .allow_credentials(true)
+        .apply(&req_headers, &mut resp_headers);
+    assert_eq!(
+        resp_headers["access-control-allow-origin"],
+        "https://app.example.com"
+    );
+    assert_eq!(resp_headers["access-control-allow-credentials"], "true");
+}
@@ -0,0 +1,207 @@
+use bytes::Bytes;
+use connect_rpc::{
+    response::{
+        builder::ResponseBuilder,
+        error::{ConnectCode, ConnectError},
+    },
+    stream::{ConnectFrame, EndStreamResponse},
+};
+use http::HeaderMap;
+
+fn message(data: &'static [u8]) -> ConnectFrame {
+    ConnectFrame {
+        compressed: false,
+        end: false,
+        data: Bytes::from_static(data),
+    }
+}
+
+#[test]
+fn appends_end_stream_frame() {
+    let messages = message(b"message").encode().unwrap();
+    let mut trailers = HeaderMap::new();
+    trailers.insert("trailer", "value".parse().unwrap());
+    let error = ConnectError::new(ConnectCode::Aborted, "stop");
+    let resp = ResponseBuilder::default()
+        .message_codec("proto")
+        .unwrap()
+        .end_stream(&messages[..], Some(error), &trailers)
+        .unwrap();
+
+    let frames = ConnectFrame::parse_all(resp.into_body()).unwrap();
+    assert_eq!(frames.len(), 2);
+    assert!(!frames[0].end);
+    assert_eq!(frames[0].data.as_ref(), b"message");
+    let end = EndStreamResponse::from_frame(&frames[1]).unwrap();
+    assert_eq!(end.error.unwrap().code(), ConnectCode::Aborted);
+    assert_eq!(end.metadata["trailer"], ["value"]);
+}
+
+async fn encoded_frames(body: connect_rpc::stream::FramedBody) -> Vec<ConnectFrame> {
+    use futures_util::TryStreamExt;
+
+    let chunks: Vec<Bytes> = body.try_collect().await.unwrap();
+    ConnectFrame::parse_all(chunks.concat().into()).unwrap()
+}
+
+#[tokio::test]
+async fn builds_streaming_requests_from_messages() {
+    use connect_rpc::request::builder::RequestBuilder;
+    use futures_util::stream;
+
+    let req = RequestBuilder::default()
+        .uri("https://example.com/example.v1.Service/Upload")
+        .unwrap()
+        .message_codec("proto")
+        .unwrap()
+        .streaming_messages(stream::iter(["one", "two"]), |message| {
+            Ok(Bytes::from(message))
+        })
+        .unwrap();
+    let req: http::Request<_> = req.into();
+    assert_eq!(req.headers()["content-type"], "application/connect+proto");
+
+    let frames = encoded_frames(req.into_body()).await;
+    let data: Vec<_> = frames.iter().map(|frame| frame.data.as_ref()).collect();
+    assert_eq!(data, [b"one".as_ref(), b"two"]);
+    assert!(frames.iter().all(|frame| !frame.compressed && !frame.end));
+}
+
+#[tokio::test]
+async fn sends_frames_through_channel() {
+    use connect_rpc::stream::sender;
+
+    let (sender, body) = sender::channel(2);
+    sender.send(message(b"one")).await.unwrap();
+    sender.send(message(b"two")).await.unwrap();
+    sender.close_send();
+    assert!(sender.send(message(b"three")).await.is_err());
+
+    let frames = encoded_frames(body).await;
+    let data: Vec<_> = frames.iter().map(|frame| frame.data.as_ref()).collect();
+    assert_eq!(data, [b"one".as_ref(), b"two"]);
+}
+
+#[tokio::test]
+async fn times_out_sends_to_a_full_channel() {
+    use std::time::Duration;
+
+    use connect_rpc::stream::sender;
+
+    let (sender, _body) = sender::channel(1);
+    let sender = sender.send_timeout(tokio::time::sleep, Duration::from_millis(10));
+    sender.send(message(b"one")).await.unwrap();
+    let err = sender.send(message(b"two")).await.unwrap_err();
+    assert_eq!(err.connect_code(), ConnectCode::DeadlineExceeded);
+}
+
+#[tokio::test]
+async fn requires_a_single_request_message() {
+    use futures_util::stream;
+
+    let single = |frames: Vec<ConnectFrame>| {
+        ConnectFrame::single_request(stream::iter(frames.into_iter().map(Ok)))
+    };
+
+    let frame = single(vec![message(b"one")]).await.unwrap();
+    assert_eq!(frame.data.as_ref(), b"one");
+
+    let err = single(vec![]).await.err().unwrap();
+    assert_eq!(err.connect_code(), ConnectCode::Unimplemented);
+    let err = single(vec![message(b"one"), message(b"two")])
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(err.connect_code(), ConnectCode::Unimplemented);
+    let end = EndStreamResponse::default().to_frame().unwrap();
+    let err = single(vec![end]).await.err().unwrap();
+    assert_eq!(err.connect_code(), ConnectCode::InvalidArgument);
+}
+
+#[tokio::test]
+async fn requires_a_single_response_message() {
+    use futures_util::stream;
+
+    let end = || EndStreamResponse::default().to_frame().unwrap();
+    let single = |frames: Vec<ConnectFrame>| {
+        ConnectFrame::single_response(stream::iter(frames.into_iter().map(Ok)))
+    };
+
+    let (data, _) = single(vec![message(b"one"), end()]).await.unwrap();
+    assert_eq!(data.as_ref(), b"one");
+
+    let err = single(vec![end()]).await.unwrap_err();
+    assert_eq!(err.connect_code(), ConnectCode::Unimplemented);
+    let err = single(vec![message(b"one"), message(b"two"), end()])
+        .await
+        .unwrap_err();
+    assert_eq!(err.connect_code(), ConnectCode::Unimplemented);
+
+    let error = ConnectError::new(ConnectCode::NotFound, "missing");
+    let end = EndStreamResponse::new(Some(error), &HeaderMap::new())
+        .to_frame()
+        .unwrap();
+    let err = single(vec![end]).await.unwrap_err();
+    assert_eq!(err.connect_code(), ConnectCode::NotFound);
+}
+
+#[tokio::test]
+async fn reuses_pooled_buffers() {
+    use connect_rpc::stream::pool::BufferPool;
+    use futures_util::{stream, TryStreamExt};
+
+    let pool = BufferPool::new(1, 64);
+    let encoded = message(b"message").encode_pooled(&pool).unwrap();
+    assert_eq!(pool.idle(), 0);
+    pool.recycle(encoded.clone());
+    assert_eq!(pool.idle(), 0, "shared buffers aren't recycled");
+    pool.recycle(encoded);
+    assert_eq!(pool.idle(), 1);
+
+    let chunks = [message(b"one").encode(), message(b"two").encode()];
+    let frames: Vec<_> = ConnectFrame::bytes_stream_pooled(stream::iter(chunks), pool.clone())
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[1].data.as_ref(), b"two");
+}
+
+#[tokio::test]
+async fn encodes_frames_without_copying() {
+    use bytes::Buf;
+    use futures_util::{stream, TryStreamExt};
+
+    let frame = message(b"message");
+    let encoded = frame.encode_vectored().unwrap();
+    let (prefix, data) = encoded.into_inner();
+    assert_eq!(prefix.as_ref(), [0, 0, 0, 0, 7]);
+    assert_eq!(data.as_ptr(), frame.data.as_ptr());
+
+    let mut encoded = frame.encode_vectored().unwrap();
+    assert_eq!(encoded.chunk(), prefix.as_ref());
+    assert_eq!(
+        encoded.copy_to_bytes(12).as_ref(),
+        frame.encode().unwrap().as_ref()
+    );
+
+    let chunks: Vec<_> = ConnectFrame::encode_stream(stream::iter([Ok(frame)]))
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(chunks, [prefix, data]);
+}
+
+#[cfg(feature = "prost")]
+#[test]
+fn reports_message_decode_errors() {
+    let err = message(b"\xff")
+        .decode_proto::<String>(Some(3))
+        .unwrap_err();
+    let connect_rpc::Error::MessageDecode { codec, index, .. } = &err else {
+        panic!("expected decode error, got {err:?}");
+    };
+    assert_eq!(codec, "proto");
+    assert_eq!(*index, Some(3));
+    assert_eq!(err.connect_code(), ConnectCode::Internal);
+}
@@ -0,0 +1,112 @@
+#![cfg(all(feature = "reqwest", feature = "testing"))]
+
+use bytes::Bytes;
+use connect_rpc::{
+    client::{builder::ClientBuilder, ConnectClient},
+    request::builder::RequestBuilder,
+    response::error::ConnectCode,
+};
+
+fn client(builder: ClientBuilder) -> ConnectClient {
+    builder.build().unwrap()
+}
+
+fn builder(base_url: &str, method: &str) -> RequestBuilder {
+    RequestBuilder::default()
+        .uri(format!("{base_url}/example.v1.Service/{method}"))
+        .unwrap()
+        .message_codec("proto")
+        .unwrap()
+}
+
+#[tokio::test]
+async fn serves_mock_responses() {
+    use connect_rpc::testing::{MockConnectServer, MockResponse};
+
+    let server = MockConnectServer::start().await.unwrap();
+    server.mock("/example.v1.Service/Get", MockResponse::unary("response"));
+    let client = client(ConnectClient::builder());
+
+    let req = builder(&server.base_url(), "Get")
+        .unary(Bytes::from_static(b"request"))
+        .unwrap();
+    let resp = client.execute_unary(req).await.unwrap();
+    assert_eq!(resp.body().as_ref(), b"response");
+
+    let req = builder(&server.base_url(), "Missing")
+        .unary(Bytes::new())
+        .unwrap();
+    let err = client.execute_unary(req).await.unwrap_err();
+    assert_eq!(err.connect_code(), ConnectCode::Unimplemented);
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].uri.path(), "/example.v1.Service/Get");
+    assert_eq!(requests[0].body.as_ref(), b"request");
+}
+
+#[tokio::test]
+async fn serves_mock_streams() {
+    use connect_rpc::{
+        response::error::ConnectError,
+        stream::ConnectFrame,
+        testing::{MockConnectServer, MockResponse},
+    };
+    use futures_util::StreamExt;
+
+    let server = MockConnectServer::start().await.unwrap();
+    let error = ConnectError::new(ConnectCode::Aborted, "stop");
+    server.mock(
+        "/example.v1.Service/List",
+        MockResponse::stream(["one", "two"]).with_error(error),
+    );
+    let client = client(ConnectClient::builder());
+
+    let message = ConnectFrame {
+        compressed: false,
+        end: false,
+        data: Bytes::from_static(b"request"),
+    };
+    let req = builder(&server.base_url(), "List")
+        .streaming(message.encode().unwrap())
+        .unwrap();
+    let mut call = client.execute_server_stream(req).await.unwrap();
+    assert_eq!(call.next().await.unwrap().unwrap().as_ref(), b"one");
+    assert_eq!(call.next().await.unwrap().unwrap().as_ref(), b"two");
+    let err = call.next().await.unwrap().unwrap_err();
+    assert_eq!(err.connect_code(), ConnectCode::Aborted);
+    assert!(call.next().await.is_none());
+
+    let requests = server.requests();
+    assert_eq!(
+        requests[0].headers["content-type"],
+        "application/connect+proto"
+    );
+}
+
+#[tokio::test]
+async fn limits_buffered_unary_responses() {
+    use connect_rpc::{
+        reqwest::{ReqwestClientExt, MAX_UNARY_RESPONSE_SIZE},
+        testing::{MockConnectServer, MockResponse},
+    };
+    use http_body_util::BodyExt;
+
+    let server = MockConnectServer::start().await.unwrap();
+    let message = vec![0; MAX_UNARY_RESPONSE_SIZE + 1];
+    server.mock("/example.v1.Service/Get", MockResponse::unary(message));
+    let req = || {
+        builder(&server.base_url(), "Get")
+            .unary(Bytes::new())
+            .unwrap()
+    };
+    let client = reqwest::Client::new();
+
+    let err = client.execute_unary(req()).await.unwrap_err();
+    assert_eq!(err.connect_code(), ConnectCode::ResourceExhausted);
+
+    let resp = client.execute_unary_raw(req()).await.unwrap();
+    let body = http::Response::from(resp).into_body();
+    let body = body.collect().await.unwrap().to_bytes();
+    assert_eq!(body.len(), MAX_UNARY_RESPONSE_SIZE + 1);
+}